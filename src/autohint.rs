@@ -0,0 +1,378 @@
+use std::cmp::Ordering;
+use FontInfo;
+use outline::{Outline, OutlineBuilder, Segment};
+
+// Characters measured for blue zones, split into those with flat and
+// round extrema.
+const CAP_HEIGHT_FLAT: &str = "THEZ";
+const CAP_HEIGHT_ROUND: &str = "OCQS";
+const X_HEIGHT_FLAT: &str = "xz";
+const X_HEIGHT_ROUND: &str = "oesc";
+const BASELINE_FLAT: &str = "HEZLxz";
+const BASELINE_ROUND: &str = "OCSoesc";
+
+/// A kind of vertical alignment zone.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BlueZoneKind {
+    Baseline,
+    XHeight,
+    CapHeight,
+}
+
+/// A vertical alignment zone shared by glyphs of a font.
+///
+/// Positions are expressed in unscaled font units.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BlueZone {
+    pub kind: BlueZoneKind,
+    /// Position of flat edges, e.g. the top of `x`.
+    pub reference: i32,
+    /// Position of round edges, e.g. the top of `o`.
+    pub overshoot: i32,
+}
+
+impl BlueZone {
+    // Top zones align top edges of strokes, the baseline aligns bottom ones.
+    fn is_top(&self) -> bool {
+        self.kind != BlueZoneKind::Baseline
+    }
+}
+
+/// A hinter that grid-fits outlines without instructions of the font.
+///
+/// Blue zones are measured from outlines of reference characters. When an
+/// outline is hinted, its horizontal edges are detected, edges inside of
+/// blue zones are aligned to the zones and stems get a whole number of
+/// pixels, then the remaining points are interpolated between the edges.
+/// Only vertical positions are changed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AutoHinter {
+    blue_zones: Vec<BlueZone>,
+    units_per_em: f32,
+}
+
+impl AutoHinter {
+    /// Creates an autohinter for `font`.
+    ///
+    /// Zones of characters missing in the font are taken from the `OS/2`
+    /// table or left out.
+    pub fn new(font: &FontInfo) -> AutoHinter {
+        let os2 = font.os2();
+        let cap_height = os2.and_then(|os2| os2.cap_height()).filter(|&h| h > 0);
+        let x_height = os2.and_then(|os2| os2.x_height()).filter(|&h| h > 0);
+        let zones = [(BlueZoneKind::Baseline, BASELINE_FLAT, BASELINE_ROUND, Some(0)),
+                     (BlueZoneKind::XHeight, X_HEIGHT_FLAT, X_HEIGHT_ROUND, x_height),
+                     (BlueZoneKind::CapHeight, CAP_HEIGHT_FLAT, CAP_HEIGHT_ROUND, cap_height)];
+        let blue_zones = zones.iter().filter_map(|&(kind, flat, round, fallback)| {
+            let top = kind != BlueZoneKind::Baseline;
+            let flat = measure(font, flat, top);
+            let round = measure(font, round, top);
+            flat.or(fallback).or(round).map(|reference| BlueZone {
+                kind: kind,
+                reference: reference,
+                overshoot: round.unwrap_or(reference),
+            })
+        }).collect();
+        AutoHinter::with_blue_zones(blue_zones, font.head.units_per_em())
+    }
+
+    /// Creates an autohinter with the given zones for a font with
+    /// `units_per_em` units per em.
+    pub fn with_blue_zones(blue_zones: Vec<BlueZone>, units_per_em: f32) -> AutoHinter {
+        AutoHinter {
+            blue_zones: blue_zones,
+            units_per_em: units_per_em,
+        }
+    }
+
+    /// Returns the blue zones of the font.
+    pub fn blue_zones(&self) -> &[BlueZone] {
+        &self.blue_zones
+    }
+
+    /// Returns `outline` scaled by `scale` into pixels, with horizontal
+    /// edges placed at whole pixels. Y increases up.
+    pub fn hint_outline(&self, outline: &Outline, scale: f32) -> Outline {
+        let map = self.edge_map(outline, scale);
+        let mut hinted = Outline::new();
+        for segment in outline {
+            match *segment {
+                Segment::MoveTo { x, y } => hinted.move_to(x * scale, map.apply(y * scale)),
+                Segment::LineTo { x, y } => hinted.line_to(x * scale, map.apply(y * scale)),
+                Segment::QuadTo { cx, cy, x, y } => {
+                    hinted.quad_to(cx * scale, map.apply(cy * scale), x * scale, map.apply(y * scale));
+                },
+                Segment::CurveTo { cx1, cy1, cx2, cy2, x, y } => {
+                    hinted.curve_to(cx1 * scale, map.apply(cy1 * scale), cx2 * scale, map.apply(cy2 * scale),
+                                    x * scale, map.apply(y * scale));
+                },
+            }
+        }
+        hinted
+    }
+
+    // Computes hinted positions of the horizontal edges of `outline`.
+    fn edge_map(&self, outline: &Outline, scale: f32) -> EdgeMap {
+        let edges = find_edges(outline, self.units_per_em / 256.0);
+        let mut hinted: Vec<Option<f32>> = vec![None; edges.len()];
+
+        // Edges in blue zones.
+        let fuzz = self.units_per_em / 40.0;
+        for (edge, position) in edges.iter().zip(&mut hinted) {
+            let zone = self.blue_zones.iter().filter(|zone| {
+                let (low, high) = if zone.reference < zone.overshoot {
+                    (zone.reference, zone.overshoot)
+                } else {
+                    (zone.overshoot, zone.reference)
+                };
+                zone.is_top() == edge.top && edge.y >= low as f32 - fuzz && edge.y <= high as f32 + fuzz
+            }).min_by(|a, b| {
+                let distance = |zone: &BlueZone| (zone.reference as f32 - edge.y).abs();
+                distance(a).partial_cmp(&distance(b)).unwrap_or(Ordering::Equal)
+            });
+            if let Some(zone) = zone {
+                let reference = (zone.reference as f32 * scale).round();
+                // Overshoots are suppressed while they are under half a pixel.
+                let overshoot = (zone.overshoot - zone.reference) as f32 * scale;
+                let overshoot = if overshoot.abs() < 0.5 { 0.0 } else { overshoot.round() };
+                *position = Some(if edge.round { reference + overshoot } else { reference });
+            }
+        }
+
+        // Stems keep a whole number of pixels, at least one.
+        let max_stem = self.units_per_em / 5.0;
+        for b in 0..edges.len() {
+            if edges[b].top {
+                continue;
+            }
+            let t = (0..edges.len()).filter(|&t| {
+                let (bottom, top) = (&edges[b], &edges[t]);
+                top.top && top.y > bottom.y && top.y - bottom.y <= max_stem &&
+                    top.x0.max(bottom.x0) < top.x1.min(bottom.x1)
+            }).min_by(|&t1, &t2| edges[t1].y.partial_cmp(&edges[t2].y).unwrap_or(Ordering::Equal));
+            let t = match t {
+                Some(t) => t,
+                None => continue,
+            };
+            let width = ((edges[t].y - edges[b].y) * scale).round().max(1.0);
+            match (hinted[b], hinted[t]) {
+                (Some(_), Some(_)) => {},
+                (Some(bottom), None) => hinted[t] = Some(bottom + width),
+                (None, Some(top)) => hinted[b] = Some(top - width),
+                (None, None) => {
+                    let center = (edges[b].y + edges[t].y) * scale / 2.0;
+                    let bottom = (center - width / 2.0).round();
+                    hinted[b] = Some(bottom);
+                    hinted[t] = Some(bottom + width);
+                },
+            }
+        }
+
+        let mut points: Vec<(f32, f32)> = edges.iter().zip(&hinted).map(|(edge, position)| {
+            let y = edge.y * scale;
+            (y, position.unwrap_or_else(|| y.round()))
+        }).collect();
+        points.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        // Edges must not cross each other.
+        let mut map = EdgeMap { points: vec![] };
+        for point in points {
+            match map.points.last() {
+                Some(last) if point.0 <= last.0 || point.1 < last.1 => {},
+                _ => map.points.push(point),
+            }
+        }
+        map
+    }
+}
+
+// Returns the average top or bottom of the characters present in the font.
+fn measure(font: &FontInfo, chars: &str, top: bool) -> Option<i32> {
+    let values: Vec<i32> = chars.chars().filter_map(|c| {
+        match font.glyph_index_for_code(c as usize) {
+            0 => None,
+            i => font.glyph_bounding_box(i).map(|bbox| if top { bbox.y1 } else { bbox.y0 }),
+        }
+    }).collect();
+    if values.is_empty() {
+        None
+    } else {
+        Some((values.iter().sum::<i32>() as f32 / values.len() as f32).round() as i32)
+    }
+}
+
+// A horizontal edge of an outline in font units.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Edge {
+    y: f32,
+    x0: f32,
+    x1: f32,
+    // `true` if the ink is below the edge.
+    top: bool,
+    // `true` if the edge is an extremum of a curve.
+    round: bool,
+}
+
+// Finds horizontal lines and horizontal tangents at ends of curves. Edges
+// closer than `fuzz` in the same direction are merged.
+fn find_edges(outline: &Outline, fuzz: f32) -> Vec<Edge> {
+    // Contours of TrueType outlines are clockwise, while PostScript ones
+    // are counterclockwise.
+    let mut area = 0.0;
+    let mut current = (0.0, 0.0);
+    for segment in outline {
+        let end = segment.end();
+        if let Segment::MoveTo { .. } = *segment {} else {
+            area += current.0 * end.1 - end.0 * current.1;
+        }
+        current = end;
+    }
+    let clockwise = area <= 0.0;
+
+    let mut edges = vec![];
+    {
+        let mut add = |x0: f32, x1: f32, y: f32, round: bool| {
+            let dx = x1 - x0;
+            if dx != 0.0 {
+                edges.push(Edge { y: y, x0: x0.min(x1), x1: x0.max(x1), top: (dx > 0.0) == clockwise, round: round });
+            }
+        };
+        let flat = |dx: f32, dy: f32| dy.abs() * 12.0 <= dx.abs();
+        let mut current = (0.0, 0.0);
+        for segment in outline {
+            let (px, py) = current;
+            match *segment {
+                Segment::MoveTo { .. } => {},
+                Segment::LineTo { x, y } => if flat(x - px, y - py) && (x - px).abs() >= fuzz {
+                    add(px, x, (py + y) / 2.0, false);
+                },
+                Segment::QuadTo { cx, cy, x, y } => {
+                    if flat(cx - px, cy - py) {
+                        add(px, cx, py, true);
+                    }
+                    if flat(x - cx, y - cy) {
+                        add(cx, x, y, true);
+                    }
+                },
+                Segment::CurveTo { cx1, cy1, cx2, cy2, x, y } => {
+                    if flat(cx1 - px, cy1 - py) {
+                        add(px, cx1, py, true);
+                    }
+                    if flat(x - cx2, y - cy2) {
+                        add(cx2, x, y, true);
+                    }
+                },
+            }
+            current = segment.end();
+        }
+    }
+
+    edges.sort_by(|a, b| a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal));
+    let mut merged: Vec<Edge> = vec![];
+    for edge in edges {
+        let found = merged.iter_mut().rev().take_while(|other| edge.y - other.y <= fuzz).find(|other| {
+            other.top == edge.top && edge.x0 <= other.x1 && other.x0 <= edge.x1
+        });
+        match found {
+            Some(other) => {
+                other.x0 = other.x0.min(edge.x0);
+                other.x1 = other.x1.max(edge.x1);
+                other.round = other.round && edge.round;
+            },
+            None => merged.push(edge),
+        }
+    }
+    merged
+}
+
+// A piecewise linear mapping of vertical positions in pixels.
+struct EdgeMap {
+    // Original and hinted positions sorted by both.
+    points: Vec<(f32, f32)>,
+}
+
+impl EdgeMap {
+    fn apply(&self, y: f32) -> f32 {
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return y,
+        };
+        if y <= first.0 {
+            return first.1 + (y - first.0);
+        }
+        if y >= last.0 {
+            return last.1 + (y - last.0);
+        }
+        let i = self.points.iter().position(|p| p.0 > y).unwrap_or(self.points.len() - 1);
+        let (a, b) = (self.points[i - 1], self.points[i]);
+        a.1 + (y - a.0) * (b.1 - a.1) / (b.0 - a.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+    use outline::{Outline, OutlineBuilder};
+
+    fn hinter() -> AutoHinter {
+        AutoHinter::with_blue_zones(vec![
+            BlueZone { kind: BlueZoneKind::Baseline, reference: 0, overshoot: -12 },
+            BlueZone { kind: BlueZoneKind::XHeight, reference: 500, overshoot: 512 },
+        ], 1000.0)
+    }
+
+    // A clockwise rectangle, or a counterclockwise one if `reversed`.
+    fn rectangle(y0: f32, y1: f32, reversed: bool) -> Outline {
+        let mut outline = Outline::new();
+        let points = [(0.0, y0), (0.0, y1), (400.0, y1), (400.0, y0), (0.0, y0)];
+        let mut iter: Vec<_> = points.iter().collect();
+        if reversed {
+            iter.reverse();
+        }
+        outline.move_to(iter[0].0, iter[0].1);
+        for p in &iter[1..] {
+            outline.line_to(p.0, p.1);
+        }
+        outline
+    }
+
+    fn vertical_extent(outline: &Outline) -> (f32, f32) {
+        let ys: Vec<f32> = outline.iter().map(|s| s.end().1).collect();
+        (ys.iter().cloned().fold(f32::MAX, f32::min), ys.iter().cloned().fold(f32::MIN, f32::max))
+    }
+
+    #[test]
+    fn edges() {
+        let edges = find_edges(&rectangle(0.0, 500.0, false), 4.0);
+        expect!(edges.len()).to(be_equal_to(2));
+        expect!((edges[0].y, edges[0].top)).to(be_equal_to((0.0, false)));
+        expect!((edges[1].y, edges[1].top)).to(be_equal_to((500.0, true)));
+        expect!(find_edges(&rectangle(0.0, 500.0, true), 4.0)).to(be_equal_to(edges));
+    }
+
+    #[test]
+    fn blue_zones() {
+        // 5.5 pixels of x height are aligned to the zone.
+        let hinted = hinter().hint_outline(&rectangle(0.0, 500.0, false), 0.011);
+        expect!(vertical_extent(&hinted)).to(be_equal_to((0.0, 6.0)));
+
+        // Round edges overshoot from half a pixel.
+        let mut outline = Outline::new();
+        outline.move_to(0.0, 250.0);
+        outline.quad_to(0.0, 512.0, 200.0, 512.0);
+        outline.quad_to(400.0, 512.0, 400.0, 250.0);
+        outline.quad_to(400.0, -12.0, 200.0, -12.0);
+        outline.quad_to(0.0, -12.0, 0.0, 250.0);
+        expect!(vertical_extent(&hinter().hint_outline(&outline, 0.011))).to(be_equal_to((0.0, 6.0)));
+        expect!(vertical_extent(&hinter().hint_outline(&outline, 0.05))).to(be_equal_to((-1.0, 26.0)));
+    }
+
+    #[test]
+    fn stems() {
+        // A bar of 0.88 pixels gets one pixel around its center.
+        let hinted = hinter().hint_outline(&rectangle(230.0, 310.0, false), 0.011);
+        expect!(vertical_extent(&hinted)).to(be_equal_to((2.0, 3.0)));
+        let hinted = hinter().hint_outline(&rectangle(230.0, 310.0, true), 0.011);
+        expect!(vertical_extent(&hinted)).to(be_equal_to((2.0, 3.0)));
+    }
+}
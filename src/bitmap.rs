@@ -0,0 +1,208 @@
+use std::borrow::Cow;
+
+/// An owned single-channel 8bpp bitmap of a rendered glyph.
+///
+/// Pixels are stored left-to-right, top-to-bottom, 0 is no coverage
+/// (transparent) and 255 is fully covered (opaque).
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct GlyphBitmap {
+    /// Coverage values of the bitmap.
+    pub pixels: Vec<u8>,
+    /// Width of the bitmap in pixels.
+    pub width: usize,
+    /// Height of the bitmap in pixels.
+    pub height: usize,
+    /// Distance in bytes between the starts of two consecutive rows.
+    pub stride: usize,
+    /// Offset in pixel space from the glyph origin to the left of the bitmap.
+    pub x_offset: i32,
+    /// Offset in pixel space from the glyph origin to the top of the bitmap.
+    pub y_offset: i32,
+}
+
+impl GlyphBitmap {
+    /// Creates a blank bitmap of the given size.
+    pub fn new(width: usize, height: usize, x_offset: i32, y_offset: i32) -> GlyphBitmap {
+        GlyphBitmap {
+            pixels: vec![0; width * height],
+            width: width,
+            height: height,
+            stride: width,
+            x_offset: x_offset,
+            y_offset: y_offset,
+        }
+    }
+
+    /// Returns `true` if the bitmap has no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Returns the coverage of the pixel at `x`, `y`.
+    ///
+    /// # Panics
+    /// Panics if the pixel is outside of the bitmap.
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        assert!(x < self.width && y < self.height);
+        self.pixels[y * self.stride + x]
+    }
+
+    /// Returns a row of the bitmap.
+    ///
+    /// # Panics
+    /// Panics if `y` is outside of the bitmap.
+    pub fn row(&self, y: usize) -> &[u8] {
+        assert!(y < self.height);
+        &self.pixels[y * self.stride..y * self.stride + self.width]
+    }
+}
+
+/// An owned three-channel 24bpp bitmap of a rendered glyph.
+///
+/// Pixels are stored left-to-right, top-to-bottom, each as red, green and
+/// blue bytes.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct RgbBitmap {
+    /// Channel values of the bitmap.
+    pub pixels: Vec<u8>,
+    /// Width of the bitmap in pixels.
+    pub width: usize,
+    /// Height of the bitmap in pixels.
+    pub height: usize,
+    /// Distance in bytes between the starts of two consecutive rows.
+    pub stride: usize,
+    /// Offset in pixel space from the glyph origin to the left of the bitmap.
+    pub x_offset: i32,
+    /// Offset in pixel space from the glyph origin to the top of the bitmap.
+    pub y_offset: i32,
+}
+
+impl RgbBitmap {
+    /// Creates a blank bitmap of the given size.
+    pub fn new(width: usize, height: usize, x_offset: i32, y_offset: i32) -> RgbBitmap {
+        RgbBitmap {
+            pixels: vec![0; width * height * 3],
+            width: width,
+            height: height,
+            stride: width * 3,
+            x_offset: x_offset,
+            y_offset: y_offset,
+        }
+    }
+
+    /// Returns `true` if the bitmap has no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Returns the red, green and blue values of the pixel at `x`, `y`.
+    ///
+    /// # Panics
+    /// Panics if the pixel is outside of the bitmap.
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        assert!(x < self.width && y < self.height);
+        let i = y * self.stride + x * 3;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+    }
+
+    /// Returns a row of the bitmap.
+    ///
+    /// # Panics
+    /// Panics if `y` is outside of the bitmap.
+    pub fn row(&self, y: usize) -> &[u8] {
+        assert!(y < self.height);
+        &self.pixels[y * self.stride..y * self.stride + self.width * 3]
+    }
+}
+
+/// An owned four-channel 32bpp bitmap of a rendered color glyph.
+///
+/// Pixels are stored left-to-right, top-to-bottom, each as red, green, blue
+/// and alpha bytes. Alpha is straight, i.e. colors are not multiplied by it.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct RgbaBitmap {
+    /// Channel values of the bitmap.
+    pub pixels: Vec<u8>,
+    /// Width of the bitmap in pixels.
+    pub width: usize,
+    /// Height of the bitmap in pixels.
+    pub height: usize,
+    /// Distance in bytes between the starts of two consecutive rows.
+    pub stride: usize,
+    /// Offset in pixel space from the glyph origin to the left of the bitmap.
+    pub x_offset: i32,
+    /// Offset in pixel space from the glyph origin to the top of the bitmap.
+    pub y_offset: i32,
+}
+
+impl RgbaBitmap {
+    /// Creates a blank bitmap of the given size.
+    pub fn new(width: usize, height: usize, x_offset: i32, y_offset: i32) -> RgbaBitmap {
+        RgbaBitmap {
+            pixels: vec![0; width * height * 4],
+            width: width,
+            height: height,
+            stride: width * 4,
+            x_offset: x_offset,
+            y_offset: y_offset,
+        }
+    }
+
+    /// Returns `true` if the bitmap has no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Returns the red, green, blue and alpha values of the pixel at `x`, `y`.
+    ///
+    /// # Panics
+    /// Panics if the pixel is outside of the bitmap.
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        assert!(x < self.width && y < self.height);
+        let i = y * self.stride + x * 4;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+
+    /// Returns a row of the bitmap.
+    ///
+    /// # Panics
+    /// Panics if `y` is outside of the bitmap.
+    pub fn row(&self, y: usize) -> &[u8] {
+        assert!(y < self.height);
+        &self.pixels[y * self.stride..y * self.stride + self.width * 4]
+    }
+}
+
+/// Encoding of the data of an embedded image.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ImageFormat {
+    /// A PNG file.
+    Png,
+    /// A JPEG file.
+    Jpeg,
+    /// A TIFF file.
+    Tiff,
+    /// Coverage values of pixels like in `GlyphBitmap`, rows follow each
+    /// other without padding.
+    Coverage,
+}
+
+/// An image of a glyph embedded into the font for a size.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EmbeddedImage<'a> {
+    pub format: ImageFormat,
+    /// The encoded file or decoded coverage values, depending on the format.
+    pub data: Cow<'a, [u8]>,
+    /// Size in pixels per em of the strike of the image.
+    pub ppem: u16,
+    /// Width of the image in pixels, 0 if unknown.
+    pub width: u32,
+    /// Height of the image in pixels, 0 if unknown.
+    pub height: u32,
+    /// Offset in pixels from the glyph origin to the left of the image.
+    pub bearing_x: i32,
+    /// Offset in pixels from the baseline up to the top of the image.
+    pub bearing_y: i32,
+    /// Horizontal advance in pixels, if the font stores it with the image.
+    pub advance: Option<u32>,
+}
@@ -0,0 +1,610 @@
+use std::cell::Cell;
+use bitmap::{GlyphBitmap, RgbaBitmap};
+use outline::{Outline, OutlineBuilder, Transform};
+use tables::{COLR, ColorLine, CompositeMode, Extend, Paint};
+use types::{BBox, Color};
+use rasterize_pixel_outline;
+use Error;
+use Result;
+
+/// The palette index of the text color.
+const FOREGROUND: u16 = 0xffff;
+
+/// The maximum nesting of paints while rendering, references to layers and
+/// color glyphs may form cycles.
+const MAX_PAINT_DEPTH: usize = 64;
+
+/// The maximum number of paints visited while rendering, paints shared by
+/// several parents are visited once for each parent.
+const MAX_PAINT_VISITS: usize = 4096;
+
+/// The maximum number of pixels of a color glyph bitmap.
+const MAX_BITMAP_PIXELS: usize = 1 << 22;
+
+/// A color with premultiplied alpha and channels from 0 to 1.
+type Rgba = [f32; 4];
+
+const TRANSPARENT: Rgba = [0.0; 4];
+
+/// Returns the box of pixels touched by the color glyph, or `None` if it
+/// is not a color glyph or paints nothing.
+///
+/// `transform` maps font units to pixels with y increasing up, `outline`
+/// returns outlines of glyphs in font units. The box of a version 1 glyph
+/// is its clip box if the font defines it. Y increases down like in bitmaps.
+pub fn bitmap_box<F>(colr: &COLR, glyph: u16, transform: &Transform, outline: &F) -> Result<Option<BBox>>
+    where F: Fn(u16) -> Result<Outline>
+{
+    let mut bounds = None;
+    if let Some(paint) = colr.glyph_paint(glyph) {
+        match colr.clip_box(glyph) {
+            Some(clip) => bounds = transform.apply_to_outline(&rectangle(clip)).bounding_box(),
+            None => try!(paint_bounds(colr, paint, transform, outline, 0, &mut 0, &mut bounds)),
+        }
+    } else if let Some(layers) = colr.layers(glyph) {
+        for layer in layers {
+            let layer = transform.apply_to_outline(&try!(outline(layer.glyph)));
+            add_bounds(&mut bounds, layer.bounding_box());
+        }
+    }
+    Ok(bounds.map(|bbox| BBox { x0: bbox.x0, y0: -bbox.y1, x1: bbox.x1, y1: -bbox.y0 }))
+}
+
+/// Renders the color glyph into an RGBA bitmap. The bitmap is empty if
+/// the glyph paints nothing.
+///
+/// Colors are taken from `palette`, the palette index `0xffff` and indices
+/// outside of the palette are the `foreground` color and transparent
+/// respectively. `transform` and `outline` are like in `bitmap_box`.
+pub fn render<F>(colr: &COLR, glyph: u16, palette: &[Color], foreground: Color,
+    transform: &Transform, outline: &F) -> Result<RgbaBitmap>
+    where F: Fn(u16) -> Result<Outline>
+{
+    let bbox = match try!(bitmap_box(colr, glyph, transform, outline)) {
+        Some(bbox) => bbox,
+        None => return Ok(RgbaBitmap::default()),
+    };
+    let (width, height) = ((bbox.x1 - bbox.x0) as usize, (bbox.y1 - bbox.y0) as usize);
+    // Clip boxes come from the font and may be arbitrarily large.
+    match width.checked_mul(height) {
+        Some(pixels) if pixels <= MAX_BITMAP_PIXELS => {},
+        _ => return Err(Error::Malformed),
+    }
+    let renderer = Renderer {
+        colr: colr,
+        palette: palette,
+        foreground: foreground,
+        bbox: bbox,
+        width: width,
+        height: height,
+        outline: outline,
+        visits: Cell::new(0),
+    };
+
+    let pixels = match colr.glyph_paint(glyph) {
+        Some(paint) => {
+            let mut pixels = try!(renderer.paint(paint, transform, 0));
+            if let Some(clip) = colr.clip_box(glyph) {
+                let mask = renderer.mask(&transform.apply_to_outline(&rectangle(clip)));
+                apply_mask(&mut pixels, &mask);
+            }
+            pixels
+        },
+        None => {
+            let mut pixels = renderer.blank();
+            for layer in colr.layers(glyph).unwrap_or(&[]) {
+                let mut source = vec![renderer.color(layer.palette_index, 1.0); pixels.len()];
+                apply_mask(&mut source, &try!(renderer.glyph_mask(layer.glyph, transform)));
+                compose_into(&mut pixels, &source, CompositeMode::SourceOver);
+            }
+            pixels
+        },
+    };
+
+    let mut bitmap = RgbaBitmap::new(renderer.width, renderer.height, bbox.x0, bbox.y0);
+    for (out, pixel) in bitmap.pixels.chunks_mut(4).zip(&pixels) {
+        let alpha = pixel[3];
+        if alpha > 0.0 {
+            for c in 0..3 {
+                out[c] = to_byte(pixel[c] / alpha);
+            }
+            out[3] = to_byte(alpha);
+        }
+    }
+    Ok(bitmap)
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+}
+
+fn rectangle(bbox: BBox) -> Outline {
+    let mut outline = Outline::new();
+    outline.move_to(bbox.x0 as f32, bbox.y0 as f32);
+    outline.line_to(bbox.x1 as f32, bbox.y0 as f32);
+    outline.line_to(bbox.x1 as f32, bbox.y1 as f32);
+    outline.line_to(bbox.x0 as f32, bbox.y1 as f32);
+    outline.line_to(bbox.x0 as f32, bbox.y0 as f32);
+    outline
+}
+
+fn add_bounds(bounds: &mut Option<BBox>, other: Option<BBox>) {
+    *bounds = match (*bounds, other) {
+        (Some(a), Some(b)) => Some(BBox {
+            x0: a.x0.min(b.x0),
+            y0: a.y0.min(b.y0),
+            x1: a.x1.max(b.x1),
+            y1: a.y1.max(b.y1),
+        }),
+        (a, b) => a.or(b),
+    };
+}
+
+/// Adds the box of glyphs painted by the paint at `index` to `bounds`.
+/// Fills outside of glyphs are unbounded and don't extend the box.
+fn paint_bounds<F>(colr: &COLR, index: usize, transform: &Transform, outline: &F,
+    depth: usize, visits: &mut usize, bounds: &mut Option<BBox>) -> Result<()>
+    where F: Fn(u16) -> Result<Outline>
+{
+    *visits += 1;
+    if depth > MAX_PAINT_DEPTH || *visits > MAX_PAINT_VISITS {
+        return Err(Error::Malformed);
+    }
+    match colr.paint(index) {
+        Some(&Paint::ColrLayers { first_layer, num_layers }) => {
+            for layer in first_layer..first_layer + num_layers {
+                let paint = try!(colr.layer_paint(layer).ok_or(Error::Malformed));
+                try!(paint_bounds(colr, paint, transform, outline, depth + 1, visits, bounds));
+            }
+        },
+        Some(&Paint::Glyph { glyph, .. }) => {
+            add_bounds(bounds, transform.apply_to_outline(&try!(outline(glyph))).bounding_box());
+        },
+        Some(&Paint::ColrGlyph { glyph }) => {
+            if let Some(paint) = colr.glyph_paint(glyph) {
+                try!(paint_bounds(colr, paint, transform, outline, depth + 1, visits, bounds));
+            }
+        },
+        Some(&Paint::Transform { transform: ref inner, paint }) => {
+            try!(paint_bounds(colr, paint, &transform.combine(inner), outline, depth + 1, visits, bounds));
+        },
+        Some(&Paint::Composite { source, backdrop, .. }) => {
+            try!(paint_bounds(colr, source, transform, outline, depth + 1, visits, bounds));
+            try!(paint_bounds(colr, backdrop, transform, outline, depth + 1, visits, bounds));
+        },
+        Some(_) => {},
+        None => return Err(Error::Malformed),
+    }
+    Ok(())
+}
+
+/// Multiplies pixels by the coverage of a mask of the same size.
+fn apply_mask(pixels: &mut [Rgba], mask: &GlyphBitmap) {
+    for (pixel, &coverage) in pixels.iter_mut().zip(&mask.pixels) {
+        let coverage = coverage as f32 / 255.0;
+        for c in pixel.iter_mut() {
+            *c *= coverage;
+        }
+    }
+}
+
+/// Composes `source` pixels with `backdrop` pixels in place.
+fn compose_into(backdrop: &mut [Rgba], source: &[Rgba], mode: CompositeMode) {
+    for (b, s) in backdrop.iter_mut().zip(source) {
+        *b = compose(mode, *s, *b);
+    }
+}
+
+/// Renders paints into buffers of premultiplied pixels of the bitmap box.
+struct Renderer<'a, F: 'a> {
+    colr: &'a COLR,
+    palette: &'a [Color],
+    foreground: Color,
+    bbox: BBox,
+    width: usize,
+    height: usize,
+    outline: &'a F,
+    /// The number of paints visited so far.
+    visits: Cell<usize>,
+}
+
+impl<'a, F> Renderer<'a, F> where F: Fn(u16) -> Result<Outline> {
+    fn blank(&self) -> Vec<Rgba> {
+        vec![TRANSPARENT; self.width * self.height]
+    }
+
+    fn color(&self, palette_index: u16, alpha: f32) -> Rgba {
+        let color = if palette_index == FOREGROUND {
+            self.foreground
+        } else {
+            match self.palette.get(palette_index as usize) {
+                Some(&color) => color,
+                None => return TRANSPARENT,
+            }
+        };
+        let alpha = color.alpha as f32 / 255.0 * alpha.clamp(0.0, 1.0);
+        [color.red as f32 / 255.0 * alpha, color.green as f32 / 255.0 * alpha,
+         color.blue as f32 / 255.0 * alpha, alpha]
+    }
+
+    /// Rasterizes an outline expressed in pixels.
+    fn mask(&self, outline: &Outline) -> GlyphBitmap {
+        let mut mask = GlyphBitmap::new(self.width, self.height, self.bbox.x0, self.bbox.y0);
+        if !outline.is_empty() {
+            rasterize_pixel_outline(outline, &mut mask);
+        }
+        mask
+    }
+
+    fn glyph_mask(&self, glyph: u16, transform: &Transform) -> Result<GlyphBitmap> {
+        let outline = try!((self.outline)(glyph));
+        Ok(self.mask(&transform.apply_to_outline(&outline)))
+    }
+
+    fn paint(&self, index: usize, transform: &Transform, depth: usize) -> Result<Vec<Rgba>> {
+        self.visits.set(self.visits.get() + 1);
+        if depth > MAX_PAINT_DEPTH || self.visits.get() > MAX_PAINT_VISITS {
+            return Err(Error::Malformed);
+        }
+        let paint = try!(self.colr.paint(index).ok_or(Error::Malformed));
+        match *paint {
+            Paint::ColrLayers { first_layer, num_layers } => {
+                let mut pixels = self.blank();
+                for layer in first_layer..first_layer + num_layers {
+                    let paint = try!(self.colr.layer_paint(layer).ok_or(Error::Malformed));
+                    let source = try!(self.paint(paint, transform, depth + 1));
+                    compose_into(&mut pixels, &source, CompositeMode::SourceOver);
+                }
+                Ok(pixels)
+            },
+            Paint::Solid { palette_index, alpha } => {
+                Ok(vec![self.color(palette_index, alpha); self.width * self.height])
+            },
+            Paint::LinearGradient { ref color_line, p0, p1, p2 } => {
+                // The gradient vector is the projection of p0p1 onto
+                // the perpendicular to p0p2.
+                let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+                let (nx, ny) = (p2.1 - p0.1, p0.0 - p2.0);
+                let n2 = nx * nx + ny * ny;
+                let (vx, vy) = if n2 == 0.0 {
+                    (dx, dy)
+                } else {
+                    let k = (dx * nx + dy * ny) / n2;
+                    (nx * k, ny * k)
+                };
+                let length2 = vx * vx + vy * vy;
+                Ok(self.gradient(color_line, transform, |x, y| {
+                    if length2 == 0.0 { None } else { Some(((x - p0.0) * vx + (y - p0.1) * vy) / length2) }
+                }))
+            },
+            Paint::RadialGradient { ref color_line, c0, r0, c1, r1 } => {
+                Ok(self.gradient(color_line, transform, |x, y| radial_position(c0, r0, c1, r1, x, y)))
+            },
+            Paint::SweepGradient { ref color_line, center, start_angle, end_angle } => {
+                Ok(self.gradient(color_line, transform, |x, y| {
+                    if start_angle == end_angle {
+                        return None;
+                    }
+                    let angle = (y - center.1).atan2(x - center.0).to_degrees().rem_euclid(360.0);
+                    Some((angle - start_angle) / (end_angle - start_angle))
+                }))
+            },
+            Paint::Glyph { glyph, paint } => {
+                let mask = try!(self.glyph_mask(glyph, transform));
+                if mask.pixels.iter().all(|&coverage| coverage == 0) {
+                    return Ok(self.blank());
+                }
+                let mut pixels = try!(self.paint(paint, transform, depth + 1));
+                apply_mask(&mut pixels, &mask);
+                Ok(pixels)
+            },
+            Paint::ColrGlyph { glyph } => match self.colr.glyph_paint(glyph) {
+                Some(paint) => self.paint(paint, transform, depth + 1),
+                None => Ok(self.blank()),
+            },
+            Paint::Transform { transform: ref inner, paint } => {
+                self.paint(paint, &transform.combine(inner), depth + 1)
+            },
+            Paint::Composite { source, mode, backdrop } => {
+                let source = try!(self.paint(source, transform, depth + 1));
+                let mut pixels = try!(self.paint(backdrop, transform, depth + 1));
+                compose_into(&mut pixels, &source, mode);
+                Ok(pixels)
+            },
+        }
+    }
+
+    /// Fills pixels with a gradient, `position` returns the position on
+    /// the color line of a point in the paint space.
+    fn gradient<G>(&self, color_line: &ColorLine, transform: &Transform, position: G) -> Vec<Rgba>
+        where G: Fn(f32, f32) -> Option<f32>
+    {
+        let inverse = match transform.invert() {
+            Some(inverse) => inverse,
+            None => return self.blank(),
+        };
+        let stops: Vec<_> = color_line.stops.iter()
+            .map(|stop| (stop.offset, self.color(stop.palette_index, stop.alpha)))
+            .collect();
+        let mut pixels = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                // Centers of pixels with y increasing up.
+                let (px, py) = inverse.apply(self.bbox.x0 as f32 + x as f32 + 0.5,
+                                             -(self.bbox.y0 as f32 + y as f32 + 0.5));
+                pixels.push(match position(px, py) {
+                    Some(t) => color_at(&stops, color_line.extend, t),
+                    None => TRANSPARENT,
+                });
+            }
+        }
+        pixels
+    }
+}
+
+/// Returns the position on the color line of a radial gradient at the point
+/// `x`, `y`, i.e. the largest `t` for which the point is on the circle
+/// interpolated between the two circles with a non-negative radius.
+fn radial_position(c0: (f32, f32), r0: f32, c1: (f32, f32), r1: f32, x: f32, y: f32) -> Option<f32> {
+    let (cdx, cdy, dr) = (c1.0 - c0.0, c1.1 - c0.1, r1 - r0);
+    let (pdx, pdy) = (x - c0.0, y - c0.1);
+    // Solves a t^2 - 2 b t + c = 0.
+    let a = cdx * cdx + cdy * cdy - dr * dr;
+    let b = pdx * cdx + pdy * cdy + r0 * dr;
+    let c = pdx * pdx + pdy * pdy - r0 * r0;
+    let valid = |t: f32| if r0 + t * dr >= 0.0 { Some(t) } else { None };
+    if a == 0.0 {
+        return if b == 0.0 { None } else { valid(c / (2.0 * b)) };
+    }
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let (t1, t2) = ((b + root) / a, (b - root) / a);
+    let (high, low) = if t1 > t2 { (t1, t2) } else { (t2, t1) };
+    valid(high).or_else(|| valid(low))
+}
+
+/// Returns the color at the position `t` of sorted stops.
+fn color_at(stops: &[(f32, Rgba)], extend: Extend, t: f32) -> Rgba {
+    if stops.is_empty() || !t.is_finite() {
+        return TRANSPARENT;
+    }
+    let (first, last) = (stops[0].0, stops[stops.len() - 1].0);
+    let length = last - first;
+    let t = if length > 0.0 {
+        match extend {
+            Extend::Pad => t,
+            Extend::Repeat => first + (t - first).rem_euclid(length),
+            Extend::Reflect => {
+                let u = (t - first).rem_euclid(2.0 * length);
+                first + if u > length { 2.0 * length - u } else { u }
+            },
+        }
+    } else {
+        t
+    };
+
+    if t < first {
+        return stops[0].1;
+    }
+    match stops.iter().position(|stop| stop.0 > t) {
+        Some(i) => {
+            let ((o0, c0), (o1, c1)) = (stops[i - 1], stops[i]);
+            let f = (t - o0) / (o1 - o0);
+            [c0[0] + (c1[0] - c0[0]) * f, c0[1] + (c1[1] - c0[1]) * f,
+             c0[2] + (c1[2] - c0[2]) * f, c0[3] + (c1[3] - c0[3]) * f]
+        },
+        None => stops[stops.len() - 1].1,
+    }
+}
+
+/// Composes the source color `s` with the backdrop color `d`.
+fn compose(mode: CompositeMode, s: Rgba, d: Rgba) -> Rgba {
+    use tables::CompositeMode::*;
+    let (sa, da) = (s[3], d[3]);
+    let porter_duff = |fs: f32, fd: f32| {
+        [s[0] * fs + d[0] * fd, s[1] * fs + d[1] * fd, s[2] * fs + d[2] * fd, sa * fs + da * fd]
+    };
+    match mode {
+        Clear => TRANSPARENT,
+        Source => s,
+        Destination => d,
+        SourceOver => porter_duff(1.0, 1.0 - sa),
+        DestinationOver => porter_duff(1.0 - da, 1.0),
+        SourceIn => porter_duff(da, 0.0),
+        DestinationIn => porter_duff(0.0, sa),
+        SourceOut => porter_duff(1.0 - da, 0.0),
+        DestinationOut => porter_duff(0.0, 1.0 - sa),
+        SourceAtop => porter_duff(da, 1.0 - sa),
+        DestinationAtop => porter_duff(1.0 - da, sa),
+        Xor => porter_duff(1.0 - da, 1.0 - sa),
+        Plus => [(s[0] + d[0]).min(1.0), (s[1] + d[1]).min(1.0), (s[2] + d[2]).min(1.0), (sa + da).min(1.0)],
+        _ => {
+            let unpremultiply = |c: Rgba| {
+                if c[3] > 0.0 { [c[0] / c[3], c[1] / c[3], c[2] / c[3]] } else { [0.0; 3] }
+            };
+            let blended = blend(mode, unpremultiply(s), unpremultiply(d));
+            let mut result = [0.0, 0.0, 0.0, sa + da - sa * da];
+            for c in 0..3 {
+                result[c] = (1.0 - da) * s[c] + (1.0 - sa) * d[c] + sa * da * blended[c];
+            }
+            result
+        },
+    }
+}
+
+/// Blends the source color `cs` with the backdrop color `cb`, both with
+/// straight alpha.
+fn blend(mode: CompositeMode, cs: [f32; 3], cb: [f32; 3]) -> [f32; 3] {
+    use tables::CompositeMode::*;
+    let separable = |f: &dyn Fn(f32, f32) -> f32| [f(cs[0], cb[0]), f(cs[1], cb[1]), f(cs[2], cb[2])];
+    let multiply = |s: f32, b: f32| s * b;
+    let screen = |s: f32, b: f32| s + b - s * b;
+    let hard_light = |s: f32, b: f32| if s <= 0.5 { multiply(2.0 * s, b) } else { screen(2.0 * s - 1.0, b) };
+    match mode {
+        Screen => separable(&screen),
+        Overlay => separable(&|s, b| hard_light(b, s)),
+        Darken => separable(&|s: f32, b| s.min(b)),
+        Lighten => separable(&|s: f32, b| s.max(b)),
+        ColorDodge => separable(&|s, b| {
+            if b == 0.0 { 0.0 } else if s >= 1.0 { 1.0 } else { (b / (1.0 - s)).min(1.0) }
+        }),
+        ColorBurn => separable(&|s, b| {
+            if b >= 1.0 { 1.0 } else if s <= 0.0 { 0.0 } else { 1.0 - ((1.0 - b) / s).min(1.0) }
+        }),
+        HardLight => separable(&hard_light),
+        SoftLight => separable(&|s, b| {
+            if s <= 0.5 {
+                b - (1.0 - 2.0 * s) * b * (1.0 - b)
+            } else {
+                let d = if b <= 0.25 { ((16.0 * b - 12.0) * b + 4.0) * b } else { b.sqrt() };
+                b + (2.0 * s - 1.0) * (d - b)
+            }
+        }),
+        Difference => separable(&|s: f32, b: f32| (s - b).abs()),
+        Exclusion => separable(&|s, b| s + b - 2.0 * s * b),
+        Hue => set_luminosity(set_saturation(cs, saturation(cb)), luminosity(cb)),
+        Saturation => set_luminosity(set_saturation(cb, saturation(cs)), luminosity(cb)),
+        Color => set_luminosity(cs, luminosity(cb)),
+        Luminosity => set_luminosity(cb, luminosity(cs)),
+        _ => separable(&multiply),
+    }
+}
+
+fn luminosity(c: [f32; 3]) -> f32 {
+    0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2]
+}
+
+fn saturation(c: [f32; 3]) -> f32 {
+    c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+}
+
+fn set_luminosity(c: [f32; 3], l: f32) -> [f32; 3] {
+    let d = l - luminosity(c);
+    let c = [c[0] + d, c[1] + d, c[2] + d];
+    // Clips the color into the range keeping its luminosity.
+    let l = luminosity(c);
+    let (min, max) = (c[0].min(c[1]).min(c[2]), c[0].max(c[1]).max(c[2]));
+    let mut result = c;
+    for value in &mut result {
+        if min < 0.0 {
+            *value = l + (*value - l) * l / (l - min);
+        }
+        if max > 1.0 {
+            *value = l + (*value - l) * (1.0 - l) / (max - l);
+        }
+    }
+    result
+}
+
+fn set_saturation(c: [f32; 3], s: f32) -> [f32; 3] {
+    let mut order = [0, 1, 2];
+    order.sort_by(|&a, &b| c[a].partial_cmp(&c[b]).unwrap());
+    let (min, mid, max) = (order[0], order[1], order[2]);
+    let mut result = [0.0; 3];
+    if c[max] > c[min] {
+        result[mid] = (c[mid] - c[min]) * s / (c[max] - c[min]);
+        result[max] = s;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+
+    fn close(a: [f32; 4], b: [f32; 4]) -> bool {
+        a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-4)
+    }
+
+    // Returns a table where glyph 1 is a chain of `depth` composites of
+    // their child with itself over a solid paint, clipped to a box from
+    // the origin to `size`, `size`.
+    fn composite_chain(depth: usize, size: u16) -> COLR {
+        let mut data = vec![0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                            0, 0, 0, 34, 0, 0, 0, 0, 0, 0, 0, 44, 0, 0, 0, 0, 0, 0, 0, 0];
+        data.extend_from_slice(&[0, 0, 0, 1, 0, 1, 0, 0, 0, 31]);
+        data.extend_from_slice(&[1, 0, 0, 0, 1, 0, 1, 0, 1, 0, 0, 12, 1, 0, 0, 0, 0]);
+        data.extend_from_slice(&[(size >> 8) as u8, size as u8, (size >> 8) as u8, size as u8]);
+        for _ in 0..depth {
+            data.extend_from_slice(&[32, 0, 0, 8, 3, 0, 0, 8]);
+        }
+        data.extend_from_slice(&[2, 0, 0, 0x40, 0]);
+        COLR::from_data(&data, 0).unwrap()
+    }
+
+    #[test]
+    fn hostile_paint_graphs() {
+        let outline = |_| Ok(Outline::new());
+        let transform = Transform::identity();
+        let black = Color { red: 0, green: 0, blue: 0, alpha: 255 };
+        let bitmap = render(&composite_chain(8, 10), 1, &[], black, &transform, &outline).unwrap();
+        expect!((bitmap.width, bitmap.height)).to(be_equal_to((10, 10)));
+
+        // Shared children would be visited 2^40 times.
+        let colr = composite_chain(40, 10);
+        expect!(render(&colr, 1, &[], black, &transform, &outline)).to(be_err().value(Error::Malformed));
+        let paint = colr.glyph_paint(1).unwrap();
+        expect!(paint_bounds(&colr, paint, &transform, &outline, 0, &mut 0, &mut None))
+            .to(be_err().value(Error::Malformed));
+
+        let colr = composite_chain(1, 30000);
+        expect!(render(&colr, 1, &[], black, &transform, &outline)).to(be_err().value(Error::Malformed));
+    }
+
+    #[test]
+    fn compose_modes() {
+        let red = [0.5, 0.0, 0.0, 0.5];
+        let blue = [0.0, 0.0, 1.0, 1.0];
+        expect!(close(compose(CompositeMode::SourceOver, red, blue), [0.5, 0.0, 0.5, 1.0])).to(be_true());
+        expect!(close(compose(CompositeMode::DestinationOver, red, blue), blue)).to(be_true());
+        expect!(close(compose(CompositeMode::SourceIn, red, blue), red)).to(be_true());
+        expect!(close(compose(CompositeMode::SourceOut, red, blue), [0.0; 4])).to(be_true());
+        expect!(close(compose(CompositeMode::DestinationOut, red, blue), [0.0, 0.0, 0.5, 0.5])).to(be_true());
+        expect!(close(compose(CompositeMode::Xor, red, blue), [0.0, 0.0, 0.5, 0.5])).to(be_true());
+        expect!(close(compose(CompositeMode::Plus, red, blue), [0.5, 0.0, 1.0, 1.0])).to(be_true());
+        expect!(close(compose(CompositeMode::Clear, red, blue), [0.0; 4])).to(be_true());
+
+        let gray = [0.5, 0.5, 0.5, 1.0];
+        let white = [1.0, 1.0, 1.0, 1.0];
+        expect!(close(compose(CompositeMode::Multiply, gray, gray), [0.25, 0.25, 0.25, 1.0])).to(be_true());
+        expect!(close(compose(CompositeMode::Screen, gray, gray), [0.75, 0.75, 0.75, 1.0])).to(be_true());
+        expect!(close(compose(CompositeMode::Difference, white, gray), gray)).to(be_true());
+        expect!(close(compose(CompositeMode::Darken, white, blue), blue)).to(be_true());
+        expect!(close(compose(CompositeMode::Lighten, gray, blue), [0.5, 0.5, 1.0, 1.0])).to(be_true());
+        // Only the luminosity of the gray source is taken.
+        let luminosity = compose(CompositeMode::Luminosity, gray, [1.0, 0.0, 0.0, 1.0]);
+        expect!((0.3 * luminosity[0] + 0.59 * luminosity[1] + 0.11 * luminosity[2] - 0.5).abs() < 1e-4).to(be_true());
+        expect!(luminosity[0] > luminosity[1] && luminosity[1] == luminosity[2]).to(be_true());
+        // Blending with a transparent backdrop leaves the source.
+        expect!(close(compose(CompositeMode::Hue, red, [0.0; 4]), red)).to(be_true());
+    }
+
+    #[test]
+    fn color_lines() {
+        let stops = [(0.0, [0.0, 0.0, 0.0, 0.0]), (0.5, [1.0, 0.0, 0.0, 1.0]), (1.0, [0.0, 0.0, 1.0, 1.0])];
+        expect!(color_at(&stops, Extend::Pad, 0.25)).to(be_equal_to([0.5, 0.0, 0.0, 0.5]));
+        expect!(color_at(&stops, Extend::Pad, 0.75)).to(be_equal_to([0.5, 0.0, 0.5, 1.0]));
+        expect!(color_at(&stops, Extend::Pad, -1.0)).to(be_equal_to([0.0; 4]));
+        expect!(color_at(&stops, Extend::Pad, 2.0)).to(be_equal_to([0.0, 0.0, 1.0, 1.0]));
+        expect!(color_at(&stops, Extend::Repeat, 1.25)).to(be_equal_to([0.5, 0.0, 0.0, 0.5]));
+        expect!(color_at(&stops, Extend::Reflect, 1.25)).to(be_equal_to([0.5, 0.0, 0.5, 1.0]));
+        expect!(color_at(&stops, Extend::Reflect, -0.25)).to(be_equal_to([0.5, 0.0, 0.0, 0.5]));
+        expect!(color_at(&stops[1..2], Extend::Repeat, 3.0)).to(be_equal_to([1.0, 0.0, 0.0, 1.0]));
+        expect!(color_at(&[], Extend::Pad, 0.0)).to(be_equal_to([0.0; 4]));
+    }
+
+    #[test]
+    fn radial_positions() {
+        // Concentric circles.
+        expect!(radial_position((0.0, 0.0), 10.0, (0.0, 0.0), 20.0, 15.0, 0.0)).to(be_some().value(0.5));
+        expect!(radial_position((0.0, 0.0), 10.0, (0.0, 0.0), 20.0, 0.0, 0.0)).to(be_some().value(-1.0));
+        // A cone from a point, outside of it nothing is painted.
+        let t = radial_position((0.0, 0.0), 0.0, (10.0, 0.0), 5.0, 10.0, 5.0).unwrap();
+        expect!((t - 5.0 / 3.0).abs() < 1e-5).to(be_true());
+        expect!(radial_position((0.0, 0.0), 0.0, (10.0, 0.0), 5.0, -10.0, 0.0)).to(be_none());
+    }
+}
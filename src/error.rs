@@ -8,11 +8,29 @@ pub enum Error {
     Malformed,
     MissingTable,
     HHEAVersionIsNotSupported,
+    VHEAVersionIsNotSupported,
     HEADVersionIsNotSupported,
     MAXPVersionIsNotSupported,
     CMAPEncodingSubtableIsNotSupported,
     CMAPFormatIsNotSupported,
     UnknownLocationFormat,
+    NAMEFormatIsNotSupported,
+    OS2VersionIsNotSupported,
+    POSTVersionIsNotSupported,
+    KERNVersionIsNotSupported,
+    GPOSVersionIsNotSupported,
+    GSUBVersionIsNotSupported,
+    CFFVersionIsNotSupported,
+    COLRVersionIsNotSupported,
+    CPALVersionIsNotSupported,
+    EBLCVersionIsNotSupported,
+    EBDTVersionIsNotSupported,
+    SBIXVersionIsNotSupported,
+    SVGVersionIsNotSupported,
+    FVARVersionIsNotSupported,
+    AVARVersionIsNotSupported,
+    GVARVersionIsNotSupported,
+    HintingFailed,
 }
 
 impl fmt::Display for Error {
@@ -28,11 +46,29 @@ impl ::std::error::Error for Error {
             Error::Malformed => "malformed data",
             Error::MissingTable => "missing table",
             Error::HHEAVersionIsNotSupported => "hhea version is not supported",
+            Error::VHEAVersionIsNotSupported => "vhea version is not supported",
             Error::HEADVersionIsNotSupported => "head version is not supported",
             Error::MAXPVersionIsNotSupported => "maxp version is not supported",
             Error::CMAPEncodingSubtableIsNotSupported => "cmap encoding subtable is not supported",
             Error::CMAPFormatIsNotSupported => "cmap format is not supported",
             Error::UnknownLocationFormat => "unknown index to glyph map format",
+            Error::NAMEFormatIsNotSupported => "name format is not supported",
+            Error::OS2VersionIsNotSupported => "OS/2 version is not supported",
+            Error::POSTVersionIsNotSupported => "post version is not supported",
+            Error::KERNVersionIsNotSupported => "kern version is not supported",
+            Error::GPOSVersionIsNotSupported => "GPOS version is not supported",
+            Error::GSUBVersionIsNotSupported => "GSUB version is not supported",
+            Error::CFFVersionIsNotSupported => "CFF version is not supported",
+            Error::COLRVersionIsNotSupported => "COLR version is not supported",
+            Error::CPALVersionIsNotSupported => "CPAL version is not supported",
+            Error::EBLCVersionIsNotSupported => "EBLC or CBLC version is not supported",
+            Error::EBDTVersionIsNotSupported => "EBDT or CBDT version is not supported",
+            Error::SBIXVersionIsNotSupported => "sbix version is not supported",
+            Error::SVGVersionIsNotSupported => "SVG version is not supported",
+            Error::FVARVersionIsNotSupported => "fvar version is not supported",
+            Error::AVARVersionIsNotSupported => "avar version is not supported",
+            Error::GVARVersionIsNotSupported => "gvar version is not supported",
+            Error::HintingFailed => "execution of TrueType instructions failed",
         }
     }
 }
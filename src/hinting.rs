@@ -0,0 +1,1663 @@
+use std::cmp;
+use Error;
+use FontInfo;
+use MAX_COMPONENT_DEPTH;
+use Result;
+use outline::{ContourBuilder, Outline, OutlineBuilder};
+use tables::ComponentOffset;
+
+// Coordinates are 26.6 fixed point pixels and vectors are 2.14 fixed point.
+type Point = (i32, i32);
+
+const ONE: i32 = 0x4000;
+const X_AXIS: Point = (ONE, 0);
+const Y_AXIS: Point = (0, ONE);
+
+const TOUCHED_X: u8 = 1;
+const TOUCHED_Y: u8 = 2;
+
+const TWILIGHT: usize = 0;
+const GLYPH: usize = 1;
+
+const FONT_PROGRAM: usize = 0;
+const CONTROL_VALUE_PROGRAM: usize = 1;
+const GLYPH_PROGRAM: usize = 2;
+
+// Limits protecting from broken or malicious fonts.
+const MAX_CALL_DEPTH: usize = 64;
+const MAX_STEPS: usize = 1_000_000;
+
+/// A glyph grid-fitted by TrueType instructions.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct HintedGlyph {
+    /// The outline in pixels with y increasing up.
+    pub outline: Outline,
+    /// The hinted advance width in pixels.
+    pub advance_width: f32,
+}
+
+/// An interpreter of TrueType instructions for one size of a font.
+///
+/// The font program is executed when the hinter is created, followed by
+/// the control value program which scales the control value table to
+/// the size. Each glyph is then grid-fitted by its own instructions.
+#[derive(Debug, Clone)]
+pub struct Hinter {
+    ppem: u16,
+    // 26.6 pixels per font unit.
+    scale: f32,
+    font_program: Vec<u8>,
+    control_value_program: Vec<u8>,
+    memory: Memory,
+    max_stack: usize,
+}
+
+impl Hinter {
+    /// Creates a hinter for `font` with `ppem` pixels per em.
+    ///
+    /// # Errors
+    /// Returns error if the font program or the control value program
+    /// fail.
+    pub fn new(font: &FontInfo, ppem: u16) -> Result<Hinter> {
+        let scale = ppem as f32 * 64.0 / font.head.units_per_em();
+        let cvt = font.cvt.as_ref().map_or(vec![], |cvt| {
+            cvt.values().iter().map(|&value| (value as f32 * scale).round() as i32).collect()
+        });
+        let mut hinter = Hinter {
+            ppem: ppem,
+            scale: scale,
+            font_program: font.fpgm.as_ref().map_or(vec![], |fpgm| fpgm.instructions().to_vec()),
+            control_value_program: font.prep.as_ref().map_or(vec![], |prep| prep.instructions().to_vec()),
+            memory: Memory {
+                functions: vec![None; font.maxp.max_function_defs()],
+                instructions: vec![None; 256],
+                cvt: cvt,
+                storage: vec![0; font.maxp.max_storage()],
+                twilight: Zone::new(font.maxp.max_twilight_points()),
+                state: GraphicsState::default(),
+            },
+            // Some fonts underestimate the depth.
+            max_stack: font.maxp.max_stack_elements() + 32,
+        };
+
+        let memory = {
+            let mut context = hinter.context(&[], Zone::default());
+            try!(context.execute(FONT_PROGRAM, 0, hinter.font_program.len(), 0));
+            context.memory.state = GraphicsState::default();
+            try!(context.execute(CONTROL_VALUE_PROGRAM, 0, hinter.control_value_program.len(), 0));
+            context.memory.twilight = context.zones[TWILIGHT].clone();
+            context.memory
+        };
+        hinter.memory = memory;
+        Ok(hinter)
+    }
+
+    /// Returns the number of pixels per em the hinter was created for.
+    pub fn ppem(&self) -> u16 {
+        self.ppem
+    }
+
+    /// Grid-fits the glyph at index `i` of `font`.
+    ///
+    /// `font` must be the font the hinter was created for. The origin of
+    /// the outline is the hinted left phantom point. Glyphs with PostScript
+    /// outlines are only scaled.
+    ///
+    /// # Errors
+    /// Returns error if the glyph data is malformed or its instructions
+    /// fail.
+    pub fn hint_glyph(&self, font: &FontInfo, i: usize) -> Result<HintedGlyph> {
+        if font.cff.is_some() {
+            let mut outline = Outline::new();
+            let scale = self.scale / 64.0;
+            let mut scaled = Scaled { builder: &mut outline, scale: scale };
+            try!(font.build_glyph_outline(i, &mut scaled));
+            let advance = font.hmtx.hmetric_for_glyph_at_index(i).advance_width as f32 * scale;
+            return Ok(HintedGlyph { outline: outline, advance_width: advance.round() });
+        }
+
+        let glyph = try!(self.load_glyph(font, i, 0));
+        let origin = glyph.phantom[0].0;
+        let mut outline = Outline::new();
+        let mut contour = ContourBuilder::new();
+        let mut start = 0;
+        for &end in &glyph.zone.contour_ends {
+            for p in start..cmp::min(end + 1, glyph.zone.current.len()) {
+                let (x, y) = glyph.zone.current[p];
+                contour.push(&mut outline, (x - origin) as f32 / 64.0, y as f32 / 64.0,
+                             glyph.zone.on_curve[p]);
+            }
+            contour.close(&mut outline);
+            start = end + 1;
+        }
+        Ok(HintedGlyph {
+            outline: outline,
+            advance_width: (glyph.phantom[1].0 - origin) as f32 / 64.0,
+        })
+    }
+
+    fn context<'a>(&'a self, glyph_program: &'a [u8], zone: Zone) -> Context<'a> {
+        Context {
+            programs: [&self.font_program[..], &self.control_value_program[..], glyph_program],
+            memory: self.memory.clone(),
+            zones: [self.memory.twilight.clone(), zone],
+            stack: vec![],
+            max_stack: self.max_stack,
+            ppem: self.ppem,
+            scale: self.scale,
+            steps: 0,
+        }
+    }
+
+    fn load_glyph(&self, font: &FontInfo, i: usize, depth: usize) -> Result<Glyph> {
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(Error::Malformed);
+        }
+        let glyph_data = font.loca.offset_for_glyph_at_index(i).map(|offset| font.glyf.glyph_data(offset));
+        let bbox = glyph_data.as_ref().and_then(|data| data.bounding_box()).unwrap_or_default();
+
+        // Phantom points of the horizontal and vertical metrics.
+        let metric = font.hmtx.hmetric_for_glyph_at_index(i);
+        let vertical = font.vertical_metrics(i);
+        let left = (bbox.x0 - metric.left_side_bearing as i32) as f32;
+        let top = (bbox.y1 + vertical.top_side_bearing) as f32;
+        let phantom = [(self.scaled(left), 0),
+                       (self.scaled(left + metric.advance_width as f32), 0),
+                       (0, self.scaled(top)),
+                       (0, self.scaled(top - vertical.advance_height as f32))];
+
+        let glyph_data = match glyph_data {
+            Some(glyph_data) => glyph_data,
+            None => return Ok(Glyph::new(Zone::default(), phantom)),
+        };
+
+        let mut zone = Zone::default();
+        if glyph_data.is_composite() {
+            for component in glyph_data.components() {
+                let component = try!(component);
+                let mut glyph = try!(self.load_glyph(font, component.glyph_index, depth + 1));
+                let m = component.matrix;
+                if m != [1.0, 0.0, 0.0, 1.0] {
+                    for point in &mut glyph.zone.current {
+                        let (x, y) = (point.0 as f32, point.1 as f32);
+                        *point = ((m[0] * x + m[2] * y).round() as i32, (m[1] * x + m[3] * y).round() as i32);
+                    }
+                }
+                let (dx, dy) = match component.offset {
+                    ComponentOffset::Offset { x, y, scaled, round_to_grid } => {
+                        let (x, y) = if scaled { (m[0] * x + m[2] * y, m[1] * x + m[3] * y) } else { (x, y) };
+                        let (x, y) = (self.scaled(x), self.scaled(y));
+                        if round_to_grid { (round(x), round(y)) } else { (x, y) }
+                    },
+                    ComponentOffset::MatchingPoints { parent, child } => {
+                        match (zone.current.get(parent), glyph.zone.current.get(child)) {
+                            (Some(p), Some(c)) => (p.0 - c.0, p.1 - c.1),
+                            _ => return Err(Error::Malformed),
+                        }
+                    },
+                };
+                let placed = zone.current.len();
+                zone.contour_ends.extend(glyph.zone.contour_ends.iter().map(|end| end + placed));
+                zone.current.extend(glyph.zone.current.iter().map(|p| (p.0 + dx, p.1 + dy)));
+                zone.on_curve.extend_from_slice(&glyph.zone.on_curve);
+            }
+            zone.original = zone.current.clone();
+        } else {
+            for (point, end) in try!(glyph_data.point_iter()) {
+                if end {
+                    zone.contour_ends.push(zone.current.len());
+                }
+                zone.current.push((self.scaled(point.x), self.scaled(point.y)));
+                zone.on_curve.push(point.on_curve);
+            }
+            zone.original = zone.current.clone();
+        }
+        zone.touched = vec![0; zone.current.len()];
+
+        let instructions = glyph_data.instructions();
+        let count = zone.current.len();
+        zone.push_phantom_points(&phantom);
+        if instructions.is_empty() || self.memory.state.instruct_control & 1 != 0 {
+            return Ok(Glyph::new(zone, phantom));
+        }
+
+        let mut context = self.context(instructions, zone);
+        context.reset_state();
+        try!(context.execute(GLYPH_PROGRAM, 0, instructions.len(), 0));
+        let mut zone = context.zones[GLYPH].clone();
+        let phantom = [zone.current[count], zone.current[count + 1],
+                       zone.current[count + 2], zone.current[count + 3]];
+        zone.truncate(count);
+        Ok(Glyph { zone: zone, phantom: phantom })
+    }
+
+    fn scaled(&self, value: f32) -> i32 {
+        (value * self.scale).round() as i32
+    }
+}
+
+// Scales an outline on the way to another builder.
+struct Scaled<'a, B: 'a> {
+    builder: &'a mut B,
+    scale: f32,
+}
+
+impl<'a, B: OutlineBuilder> OutlineBuilder for Scaled<'a, B> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.builder.move_to(x * self.scale, y * self.scale);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.builder.line_to(x * self.scale, y * self.scale);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let s = self.scale;
+        self.builder.quad_to(x1 * s, y1 * s, x * s, y * s);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let s = self.scale;
+        self.builder.curve_to(x1 * s, y1 * s, x2 * s, y2 * s, x * s, y * s);
+    }
+
+    fn close(&mut self) {
+        self.builder.close();
+    }
+}
+
+// Hinted points of a glyph without phantom points.
+struct Glyph {
+    zone: Zone,
+    phantom: [Point; 4],
+}
+
+impl Glyph {
+    // A glyph that wasn't instructed gets rounded phantom points.
+    fn new(mut zone: Zone, phantom: [Point; 4]) -> Glyph {
+        let count = zone.current.len();
+        zone.push_phantom_points(&phantom);
+        let phantom = [zone.current[count], zone.current[count + 1],
+                       zone.current[count + 2], zone.current[count + 3]];
+        zone.truncate(count);
+        Glyph { zone: zone, phantom: phantom }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+struct Zone {
+    original: Vec<Point>,
+    current: Vec<Point>,
+    touched: Vec<u8>,
+    on_curve: Vec<bool>,
+    contour_ends: Vec<usize>,
+}
+
+impl Zone {
+    fn new(count: usize) -> Zone {
+        Zone {
+            original: vec![(0, 0); count],
+            current: vec![(0, 0); count],
+            touched: vec![0; count],
+            on_curve: vec![false; count],
+            contour_ends: vec![],
+        }
+    }
+
+    // Phantom points are placed at whole pixels before instructions run.
+    fn push_phantom_points(&mut self, phantom: &[Point; 4]) {
+        for (i, &point) in phantom.iter().enumerate() {
+            self.original.push(point);
+            self.current.push(if i < 2 { (round(point.0), point.1) } else { (point.0, round(point.1)) });
+            self.touched.push(0);
+            self.on_curve.push(true);
+        }
+    }
+
+    fn truncate(&mut self, count: usize) {
+        self.original.truncate(count);
+        self.current.truncate(count);
+        self.touched.truncate(count);
+        self.on_curve.truncate(count);
+    }
+
+    fn len(&self) -> usize {
+        self.current.len()
+    }
+}
+
+// A function or an instruction defined by FDEF or IDEF.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Function {
+    program: usize,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Round {
+    ToHalfGrid,
+    ToGrid,
+    ToDoubleGrid,
+    DownToGrid,
+    UpToGrid,
+    Off,
+    Super { period: i32, phase: i32, threshold: i32 },
+    Super45 { period: i32, phase: i32, threshold: i32 },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct GraphicsState {
+    projection: Point,
+    freedom: Point,
+    dual: Point,
+    rp: [usize; 3],
+    zp: [usize; 3],
+    loop_count: i32,
+    minimum_distance: i32,
+    control_value_cut_in: i32,
+    single_width_cut_in: i32,
+    single_width: i32,
+    round: Round,
+    delta_base: i32,
+    delta_shift: i32,
+    auto_flip: bool,
+    instruct_control: u8,
+}
+
+impl Default for GraphicsState {
+    fn default() -> GraphicsState {
+        GraphicsState {
+            projection: X_AXIS,
+            freedom: X_AXIS,
+            dual: X_AXIS,
+            rp: [0; 3],
+            zp: [GLYPH; 3],
+            loop_count: 1,
+            minimum_distance: 64,
+            control_value_cut_in: 68,
+            single_width_cut_in: 0,
+            single_width: 0,
+            round: Round::ToGrid,
+            delta_base: 9,
+            delta_shift: 3,
+            auto_flip: true,
+            instruct_control: 0,
+        }
+    }
+}
+
+// State kept from the font and control value programs.
+#[derive(Debug, Clone)]
+struct Memory {
+    functions: Vec<Option<Function>>,
+    instructions: Vec<Option<Function>>,
+    cvt: Vec<i32>,
+    storage: Vec<i32>,
+    twilight: Zone,
+    state: GraphicsState,
+}
+
+struct Context<'a> {
+    programs: [&'a [u8]; 3],
+    memory: Memory,
+    zones: [Zone; 2],
+    stack: Vec<i32>,
+    max_stack: usize,
+    ppem: u16,
+    scale: f32,
+    steps: usize,
+}
+
+impl<'a> Context<'a> {
+    // Glyph programs start with the state left by the control value
+    // program, except for the following.
+    fn reset_state(&mut self) {
+        if self.memory.state.instruct_control & 2 != 0 {
+            self.memory.state = GraphicsState::default();
+        }
+        let state = &mut self.memory.state;
+        state.projection = X_AXIS;
+        state.freedom = X_AXIS;
+        state.dual = X_AXIS;
+        state.rp = [0; 3];
+        state.zp = [GLYPH; 3];
+        state.loop_count = 1;
+        state.round = Round::ToGrid;
+    }
+
+    fn execute(&mut self, program: usize, start: usize, end: usize, depth: usize) -> Result<()> {
+        if depth > MAX_CALL_DEPTH {
+            return Err(Error::HintingFailed);
+        }
+        let code = self.programs[program];
+        let mut ip = start;
+        while ip < end {
+            self.steps += 1;
+            if self.steps > MAX_STEPS {
+                return Err(Error::HintingFailed);
+            }
+            let opcode = code[ip];
+            let length = try!(instruction_length(code, ip));
+            let mut next = ip + length;
+            if next > end {
+                return Err(Error::HintingFailed);
+            }
+            match opcode {
+                // ELSE is reached after the IF branch.
+                0x1b => next = try!(skip_branch(code, next, end, false)),
+                // JMPR, JROT, JROF.
+                0x1c | 0x78 | 0x79 => {
+                    let (offset, jump) = match opcode {
+                        0x1c => (try!(self.pop()), true),
+                        _ => {
+                            let condition = try!(self.pop());
+                            (try!(self.pop()), (condition != 0) == (opcode == 0x78))
+                        },
+                    };
+                    if jump {
+                        let target = ip as i64 + offset as i64;
+                        if offset == 0 || target < start as i64 || target > end as i64 {
+                            return Err(Error::HintingFailed);
+                        }
+                        next = target as usize;
+                    }
+                },
+                // FDEF, IDEF.
+                0x2c | 0x89 => {
+                    if program == GLYPH_PROGRAM {
+                        return Err(Error::HintingFailed);
+                    }
+                    let index = try!(self.pop());
+                    let body_end = try!(find_function_end(code, next, end));
+                    let function = Function { program: program, start: next, end: body_end };
+                    let table = if opcode == 0x2c { &mut self.memory.functions } else { &mut self.memory.instructions };
+                    if index < 0 || index as usize >= cmp::max(table.len(), 256) {
+                        return Err(Error::HintingFailed);
+                    }
+                    if index as usize >= table.len() {
+                        table.resize(index as usize + 1, None);
+                    }
+                    table[index as usize] = Some(function);
+                    next = body_end + 1;
+                },
+                // CALL, LOOPCALL.
+                0x2b | 0x2a => {
+                    let index = try!(self.pop());
+                    let count = if opcode == 0x2a { try!(self.pop()) } else { 1 };
+                    let function = match self.memory.functions.get(index as usize) {
+                        Some(&Some(function)) if index >= 0 => function,
+                        _ => return Err(Error::HintingFailed),
+                    };
+                    for _ in 0..count {
+                        try!(self.execute(function.program, function.start, function.end, depth + 1));
+                    }
+                },
+                // IF.
+                0x58 => if try!(self.pop()) == 0 {
+                    next = try!(skip_branch(code, next, end, true));
+                },
+                // EIF.
+                0x59 => {},
+                // ENDF outside of a function.
+                0x2d => return Err(Error::HintingFailed),
+                // NPUSHB, NPUSHW, PUSHB, PUSHW.
+                0x40 => for &byte in &code[ip + 2..next] {
+                    try!(self.push(byte as i32));
+                },
+                0x41 => for word in code[ip + 2..next].chunks(2) {
+                    try!(self.push((word[0] as i8 as i32) << 8 | word[1] as i32));
+                },
+                0xb0..=0xb7 => for &byte in &code[ip + 1..next] {
+                    try!(self.push(byte as i32));
+                },
+                0xb8..=0xbf => for word in code[ip + 1..next].chunks(2) {
+                    try!(self.push((word[0] as i8 as i32) << 8 | word[1] as i32));
+                },
+                _ => {
+                    match self.memory.instructions[opcode as usize] {
+                        Some(function) => {
+                            try!(self.execute(function.program, function.start, function.end, depth + 1));
+                        },
+                        None => try!(self.instruction(opcode)),
+                    }
+                },
+            }
+            ip = next;
+        }
+        Ok(())
+    }
+
+    // Executes an instruction that doesn't change the flow of the program.
+    fn instruction(&mut self, opcode: u8) -> Result<()> {
+        match opcode {
+            // SVTCA, SPVTCA, SFVTCA.
+            0x00..=0x05 => {
+                let axis = if opcode & 1 == 0 { Y_AXIS } else { X_AXIS };
+                if opcode < 0x04 {
+                    self.memory.state.projection = axis;
+                    self.memory.state.dual = axis;
+                }
+                if !(0x02..0x04).contains(&opcode) {
+                    self.memory.state.freedom = axis;
+                }
+            },
+            // SPVTL, SFVTL, SDPVTL.
+            0x06..=0x09 | 0x86 | 0x87 => {
+                let p1 = try!(self.pop_point());
+                let p2 = try!(self.pop_point());
+                let (zp1, zp2) = (self.memory.state.zp[1], self.memory.state.zp[2]);
+                let vector = |a: Point, b: Point| {
+                    let (x, y) = (a.0 - b.0, a.1 - b.1);
+                    let (x, y) = if opcode & 1 == 1 { (-y, x) } else { (x, y) };
+                    normalize(x, y)
+                };
+                let a = try!(self.point(zp1, p1, false));
+                let b = try!(self.point(zp2, p2, false));
+                match opcode {
+                    0x06 | 0x07 => {
+                        self.memory.state.projection = vector(a, b);
+                        self.memory.state.dual = self.memory.state.projection;
+                    },
+                    0x08 | 0x09 => self.memory.state.freedom = vector(a, b),
+                    _ => {
+                        let oa = try!(self.point(zp1, p1, true));
+                        let ob = try!(self.point(zp2, p2, true));
+                        self.memory.state.projection = vector(a, b);
+                        self.memory.state.dual = vector(oa, ob);
+                    },
+                }
+            },
+            // SPVFS, SFVFS.
+            0x0a | 0x0b => {
+                let y = try!(self.pop()) as i16 as i32;
+                let x = try!(self.pop()) as i16 as i32;
+                let vector = normalize(x, y);
+                if opcode == 0x0a {
+                    self.memory.state.projection = vector;
+                    self.memory.state.dual = vector;
+                } else {
+                    self.memory.state.freedom = vector;
+                }
+            },
+            // GPV, GFV.
+            0x0c | 0x0d => {
+                let vector = if opcode == 0x0c { self.memory.state.projection } else { self.memory.state.freedom };
+                try!(self.push(vector.0));
+                try!(self.push(vector.1));
+            },
+            // SFVTPV.
+            0x0e => self.memory.state.freedom = self.memory.state.projection,
+            // ISECT.
+            0x0f => try!(self.intersect()),
+            // SRP0, SRP1, SRP2.
+            0x10..=0x12 => self.memory.state.rp[opcode as usize - 0x10] = try!(self.pop_point()),
+            // SZP0, SZP1, SZP2, SZPS.
+            0x13..=0x16 => {
+                let zone = match try!(self.pop()) {
+                    0 => TWILIGHT,
+                    1 => GLYPH,
+                    _ => return Err(Error::HintingFailed),
+                };
+                if opcode == 0x16 {
+                    self.memory.state.zp = [zone; 3];
+                } else {
+                    self.memory.state.zp[opcode as usize - 0x13] = zone;
+                }
+            },
+            // SLOOP.
+            0x17 => {
+                let count = try!(self.pop());
+                if count < 0 {
+                    return Err(Error::HintingFailed);
+                }
+                self.memory.state.loop_count = cmp::min(count, 0xffff);
+            },
+            // RTG, RTHG.
+            0x18 => self.memory.state.round = Round::ToGrid,
+            0x19 => self.memory.state.round = Round::ToHalfGrid,
+            // SMD, SCVTCI, SSWCI.
+            0x1a => self.memory.state.minimum_distance = try!(self.pop()),
+            0x1d => self.memory.state.control_value_cut_in = try!(self.pop()),
+            0x1e => self.memory.state.single_width_cut_in = try!(self.pop()),
+            // SSW takes font units.
+            0x1f => {
+                let value = try!(self.pop());
+                self.memory.state.single_width = (value as f32 * self.scale).round() as i32;
+            },
+            // DUP, POP, CLEAR, SWAP, DEPTH.
+            0x20 => {
+                let value = try!(self.pop());
+                try!(self.push(value));
+                try!(self.push(value));
+            },
+            0x21 => { try!(self.pop()); },
+            0x22 => self.stack.clear(),
+            0x23 => {
+                let b = try!(self.pop());
+                let a = try!(self.pop());
+                try!(self.push(b));
+                try!(self.push(a));
+            },
+            0x24 => {
+                let depth = self.stack.len() as i32;
+                try!(self.push(depth));
+            },
+            // CINDEX, MINDEX.
+            0x25 | 0x26 => {
+                let k = try!(self.pop());
+                if k <= 0 || k as usize > self.stack.len() {
+                    return Err(Error::HintingFailed);
+                }
+                let index = self.stack.len() - k as usize;
+                let value = if opcode == 0x25 { self.stack[index] } else { self.stack.remove(index) };
+                try!(self.push(value));
+            },
+            // ALIGNPTS.
+            0x27 => {
+                let p2 = try!(self.pop_point());
+                let p1 = try!(self.pop_point());
+                let (zp0, zp1) = (self.memory.state.zp[0], self.memory.state.zp[1]);
+                let a = try!(self.point(zp0, p2, false));
+                let b = try!(self.point(zp1, p1, false));
+                let distance = self.project(a, b) / 2;
+                try!(self.move_point(zp1, p1, distance, true));
+                try!(self.move_point(zp0, p2, -distance, true));
+            },
+            // UTP.
+            0x29 => {
+                let p = try!(self.pop_point());
+                let zp0 = self.memory.state.zp[0];
+                let freedom = self.memory.state.freedom;
+                try!(self.point(zp0, p, false));
+                let touched = &mut self.zones[zp0].touched[p];
+                if freedom.0 != 0 {
+                    *touched &= !TOUCHED_X;
+                }
+                if freedom.1 != 0 {
+                    *touched &= !TOUCHED_Y;
+                }
+            },
+            // MDAP.
+            0x2e | 0x2f => {
+                let p = try!(self.pop_point());
+                let zp0 = self.memory.state.zp[0];
+                let distance = if opcode == 0x2f {
+                    let d = self.project(try!(self.point(zp0, p, false)), (0, 0));
+                    self.round(d) - d
+                } else {
+                    0
+                };
+                try!(self.move_point(zp0, p, distance, true));
+                self.memory.state.rp[0] = p;
+                self.memory.state.rp[1] = p;
+            },
+            // IUP.
+            0x30 | 0x31 => self.interpolate_untouched(opcode == 0x31),
+            // SHP, SHC, SHZ.
+            0x32..=0x37 => try!(self.shift(opcode)),
+            // SHPIX.
+            0x38 => {
+                let amount = try!(self.pop());
+                let zp2 = self.memory.state.zp[2];
+                let (fx, fy) = self.memory.state.freedom;
+                let (dx, dy) = (mul_div(amount, fx, ONE), mul_div(amount, fy, ONE));
+                for _ in 0..self.take_loop() {
+                    let p = try!(self.pop_point());
+                    try!(self.shift_point(zp2, p, dx, dy, true));
+                }
+            },
+            // IP.
+            0x39 => try!(self.interpolate_points()),
+            // MSIRP.
+            0x3a | 0x3b => {
+                let distance = try!(self.pop());
+                let p = try!(self.pop_point());
+                let state = self.memory.state;
+                let reference = try!(self.point(state.zp[0], state.rp[0], false));
+                try!(self.point(state.zp[1], p, false));
+                if state.zp[1] == TWILIGHT {
+                    let original = try!(self.point(state.zp[0], state.rp[0], true));
+                    self.zones[TWILIGHT].original[p] = original;
+                    self.move_original(TWILIGHT, p, distance);
+                    self.zones[TWILIGHT].current[p] = self.zones[TWILIGHT].original[p];
+                }
+                let current = self.project(try!(self.point(state.zp[1], p, false)), reference);
+                try!(self.move_point(state.zp[1], p, distance - current, true));
+                self.memory.state.rp[1] = state.rp[0];
+                self.memory.state.rp[2] = p;
+                if opcode == 0x3b {
+                    self.memory.state.rp[0] = p;
+                }
+            },
+            // ALIGNRP.
+            0x3c => {
+                let state = self.memory.state;
+                let reference = try!(self.point(state.zp[0], state.rp[0], false));
+                for _ in 0..self.take_loop() {
+                    let p = try!(self.pop_point());
+                    let distance = self.project(try!(self.point(state.zp[1], p, false)), reference);
+                    try!(self.move_point(state.zp[1], p, -distance, true));
+                }
+            },
+            // RTDG.
+            0x3d => self.memory.state.round = Round::ToDoubleGrid,
+            // MIAP.
+            0x3e | 0x3f => {
+                let index = try!(self.pop());
+                let p = try!(self.pop_point());
+                let zp0 = self.memory.state.zp[0];
+                let mut distance = try!(self.cvt(index));
+                try!(self.point(zp0, p, false));
+                if zp0 == TWILIGHT {
+                    let (fx, fy) = self.memory.state.freedom;
+                    let point = (mul_div(distance, fx, ONE), mul_div(distance, fy, ONE));
+                    self.zones[TWILIGHT].original[p] = point;
+                    self.zones[TWILIGHT].current[p] = point;
+                }
+                let current = self.project(self.zones[zp0].current[p], (0, 0));
+                if opcode == 0x3f {
+                    if (distance - current).abs() > self.memory.state.control_value_cut_in {
+                        distance = current;
+                    }
+                    distance = self.round(distance);
+                }
+                try!(self.move_point(zp0, p, distance - current, true));
+                self.memory.state.rp[0] = p;
+                self.memory.state.rp[1] = p;
+            },
+            // WS, RS.
+            0x42 => {
+                let value = try!(self.pop());
+                let index = try!(self.pop());
+                match self.memory.storage.get_mut(index as usize) {
+                    Some(location) if index >= 0 => *location = value,
+                    _ => return Err(Error::HintingFailed),
+                }
+            },
+            0x43 => {
+                let index = try!(self.pop());
+                let value = match self.memory.storage.get(index as usize) {
+                    Some(&value) if index >= 0 => value,
+                    _ => return Err(Error::HintingFailed),
+                };
+                try!(self.push(value));
+            },
+            // WCVTP, WCVTF.
+            0x44 | 0x70 => {
+                let value = try!(self.pop());
+                let index = try!(self.pop());
+                let value = if opcode == 0x70 { (value as f32 * self.scale).round() as i32 } else { value };
+                match self.memory.cvt.get_mut(index as usize) {
+                    Some(entry) if index >= 0 => *entry = value,
+                    _ => return Err(Error::HintingFailed),
+                }
+            },
+            // RCVT.
+            0x45 => {
+                let index = try!(self.pop());
+                let value = try!(self.cvt(index));
+                try!(self.push(value));
+            },
+            // GC.
+            0x46 | 0x47 => {
+                let p = try!(self.pop_point());
+                let zp2 = self.memory.state.zp[2];
+                let value = if opcode == 0x46 {
+                    self.project(try!(self.point(zp2, p, false)), (0, 0))
+                } else {
+                    self.dual_project(try!(self.point(zp2, p, true)), (0, 0))
+                };
+                try!(self.push(value));
+            },
+            // SCFS.
+            0x48 => {
+                let value = try!(self.pop());
+                let p = try!(self.pop_point());
+                let zp2 = self.memory.state.zp[2];
+                let current = self.project(try!(self.point(zp2, p, false)), (0, 0));
+                try!(self.move_point(zp2, p, value - current, true));
+                if zp2 == TWILIGHT {
+                    self.zones[TWILIGHT].original[p] = self.zones[TWILIGHT].current[p];
+                }
+            },
+            // MD.
+            0x49 | 0x4a => {
+                let k = try!(self.pop_point());
+                let l = try!(self.pop_point());
+                let (zp0, zp1) = (self.memory.state.zp[0], self.memory.state.zp[1]);
+                let distance = if opcode == 0x49 {
+                    self.project(try!(self.point(zp0, l, false)), try!(self.point(zp1, k, false)))
+                } else {
+                    self.dual_project(try!(self.point(zp0, l, true)), try!(self.point(zp1, k, true)))
+                };
+                try!(self.push(distance));
+            },
+            // MPPEM, MPS.
+            0x4b | 0x4c => {
+                let ppem = self.ppem as i32;
+                try!(self.push(ppem));
+            },
+            // FLIPON, FLIPOFF.
+            0x4d => self.memory.state.auto_flip = true,
+            0x4e => self.memory.state.auto_flip = false,
+            // DEBUG.
+            0x4f => { try!(self.pop()); },
+            // LT, LTEQ, GT, GTEQ, EQ, NEQ.
+            0x50..=0x55 => {
+                let b = try!(self.pop());
+                let a = try!(self.pop());
+                let result = match opcode {
+                    0x50 => a < b,
+                    0x51 => a <= b,
+                    0x52 => a > b,
+                    0x53 => a >= b,
+                    0x54 => a == b,
+                    _ => a != b,
+                };
+                try!(self.push(result as i32));
+            },
+            // ODD, EVEN.
+            0x56 | 0x57 => {
+                let value = try!(self.pop());
+                let value = self.round(value) & 127;
+                try!(self.push((value == if opcode == 0x56 { 64 } else { 0 }) as i32));
+            },
+            // AND, OR.
+            0x5a | 0x5b => {
+                let b = try!(self.pop()) != 0;
+                let a = try!(self.pop()) != 0;
+                try!(self.push(if opcode == 0x5a { a && b } else { a || b } as i32));
+            },
+            // NOT.
+            0x5c => {
+                let value = try!(self.pop());
+                try!(self.push((value == 0) as i32));
+            },
+            // DELTAP1, DELTAP2, DELTAP3, DELTAC1, DELTAC2, DELTAC3.
+            0x5d | 0x71 | 0x72 => try!(self.delta(opcode, true)),
+            0x73..=0x75 => try!(self.delta(opcode, false)),
+            // SDB, SDS.
+            0x5e => self.memory.state.delta_base = try!(self.pop()),
+            0x5f => self.memory.state.delta_shift = try!(self.pop()).clamp(0, 6),
+            // ADD, SUB, DIV, MUL.
+            0x60..=0x63 => {
+                let b = try!(self.pop());
+                let a = try!(self.pop());
+                let result = match opcode {
+                    0x60 => a.wrapping_add(b),
+                    0x61 => a.wrapping_sub(b),
+                    0x62 => {
+                        if b == 0 {
+                            return Err(Error::HintingFailed);
+                        }
+                        (a as i64 * 64 / b as i64) as i32
+                    },
+                    _ => mul_div(a, b, 64),
+                };
+                try!(self.push(result));
+            },
+            // ABS, NEG, FLOOR, CEILING.
+            0x64..=0x67 => {
+                let value = try!(self.pop());
+                let result = match opcode {
+                    0x64 => value.wrapping_abs(),
+                    0x65 => value.wrapping_neg(),
+                    0x66 => value & !63,
+                    _ => value.wrapping_add(63) & !63,
+                };
+                try!(self.push(result));
+            },
+            // ROUND, NROUND.
+            0x68..=0x6f => {
+                let value = try!(self.pop());
+                let result = if opcode < 0x6c { self.round(value) } else { value };
+                try!(self.push(result));
+            },
+            // SROUND, S45ROUND.
+            0x76 | 0x77 => {
+                let selector = try!(self.pop());
+                self.memory.state.round = super_round(selector, opcode == 0x77);
+            },
+            // ROFF, RUTG, RDTG.
+            0x7a => self.memory.state.round = Round::Off,
+            0x7c => self.memory.state.round = Round::UpToGrid,
+            0x7d => self.memory.state.round = Round::DownToGrid,
+            // SANGW, AA, SCANCTRL, SCANTYPE.
+            0x7e | 0x7f | 0x85 | 0x8d => { try!(self.pop()); },
+            // FLIPPT.
+            0x80 => for _ in 0..self.take_loop() {
+                let p = try!(self.pop_point());
+                try!(self.point(GLYPH, p, false));
+                self.zones[GLYPH].on_curve[p] = !self.zones[GLYPH].on_curve[p];
+            },
+            // FLIPRGON, FLIPRGOFF.
+            0x81 | 0x82 => {
+                let high = try!(self.pop_point());
+                let low = try!(self.pop_point());
+                if low > high || high >= self.zones[GLYPH].len() {
+                    return Err(Error::HintingFailed);
+                }
+                for on_curve in &mut self.zones[GLYPH].on_curve[low..high + 1] {
+                    *on_curve = opcode == 0x81;
+                }
+            },
+            // GETINFO, the interpreter is like version 35 of FreeType
+            // rendering in grayscale.
+            0x88 => {
+                let selector = try!(self.pop());
+                let mut result = 0;
+                if selector & 1 != 0 {
+                    result |= 35;
+                }
+                if selector & 32 != 0 {
+                    result |= 1 << 12;
+                }
+                try!(self.push(result));
+            },
+            // ROLL.
+            0x8a => {
+                let c = try!(self.pop());
+                let b = try!(self.pop());
+                let a = try!(self.pop());
+                try!(self.push(b));
+                try!(self.push(c));
+                try!(self.push(a));
+            },
+            // MAX, MIN.
+            0x8b | 0x8c => {
+                let b = try!(self.pop());
+                let a = try!(self.pop());
+                try!(self.push(if opcode == 0x8b { cmp::max(a, b) } else { cmp::min(a, b) }));
+            },
+            // INSTCTRL.
+            0x8e => {
+                let selector = try!(self.pop());
+                let value = try!(self.pop());
+                if (1..=2).contains(&selector) {
+                    let flag = 1 << (selector - 1);
+                    let state = &mut self.memory.state;
+                    state.instruct_control = if value != 0 {
+                        state.instruct_control | flag
+                    } else {
+                        state.instruct_control & !flag
+                    };
+                }
+            },
+            // MDRP.
+            0xc0..=0xdf => try!(self.move_direct_relative(opcode)),
+            // MIRP.
+            0xe0..=0xff => try!(self.move_indirect_relative(opcode)),
+            _ => return Err(Error::HintingFailed),
+        }
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<i32> {
+        self.stack.pop().ok_or(Error::HintingFailed)
+    }
+
+    fn pop_point(&mut self) -> Result<usize> {
+        let value = try!(self.pop());
+        if value < 0 {
+            return Err(Error::HintingFailed);
+        }
+        Ok(value as usize)
+    }
+
+    fn push(&mut self, value: i32) -> Result<()> {
+        if self.stack.len() >= self.max_stack {
+            return Err(Error::HintingFailed);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn cvt(&self, index: i32) -> Result<i32> {
+        match self.memory.cvt.get(index as usize) {
+            Some(&value) if index >= 0 => Ok(value),
+            _ => Err(Error::HintingFailed),
+        }
+    }
+
+    // Consumes the loop counter set by SLOOP.
+    fn take_loop(&mut self) -> i32 {
+        let count = self.memory.state.loop_count;
+        self.memory.state.loop_count = 1;
+        count
+    }
+
+    fn point(&self, zone: usize, i: usize, original: bool) -> Result<Point> {
+        let zone = &self.zones[zone];
+        let points = if original { &zone.original } else { &zone.current };
+        points.get(i).cloned().ok_or(Error::HintingFailed)
+    }
+
+    // Distance from `b` to `a` along the projection vector.
+    fn project(&self, a: Point, b: Point) -> i32 {
+        dot(a.0 - b.0, a.1 - b.1, self.memory.state.projection)
+    }
+
+    // Distance from `b` to `a` along the dual projection vector.
+    fn dual_project(&self, a: Point, b: Point) -> i32 {
+        dot(a.0 - b.0, a.1 - b.1, self.memory.state.dual)
+    }
+
+    // The dot product of the freedom and projection vectors.
+    fn freedom_dot_projection(&self) -> i32 {
+        let (f, p) = (self.memory.state.freedom, self.memory.state.projection);
+        let product = ((f.0 as i64 * p.0 as i64 + f.1 as i64 * p.1 as i64) >> 14) as i32;
+        // Nearly perpendicular vectors would move points too far.
+        if product.abs() < 0x400 { ONE } else { product }
+    }
+
+    // Moves the point along the freedom vector, so that its projection
+    // changes by `distance`.
+    fn move_point(&mut self, zone: usize, i: usize, distance: i32, touch: bool) -> Result<()> {
+        try!(self.point(zone, i, false));
+        let (fx, fy) = self.memory.state.freedom;
+        let product = self.freedom_dot_projection();
+        let zone = &mut self.zones[zone];
+        if fx != 0 {
+            zone.current[i].0 += mul_div(distance, fx, product);
+            if touch {
+                zone.touched[i] |= TOUCHED_X;
+            }
+        }
+        if fy != 0 {
+            zone.current[i].1 += mul_div(distance, fy, product);
+            if touch {
+                zone.touched[i] |= TOUCHED_Y;
+            }
+        }
+        Ok(())
+    }
+
+    // Moves the original position of a point like `move_point`.
+    fn move_original(&mut self, zone: usize, i: usize, distance: i32) {
+        let (fx, fy) = self.memory.state.freedom;
+        let product = self.freedom_dot_projection();
+        let point = &mut self.zones[zone].original[i];
+        point.0 += mul_div(distance, fx, product);
+        point.1 += mul_div(distance, fy, product);
+    }
+
+    fn shift_point(&mut self, zone: usize, i: usize, dx: i32, dy: i32, touch: bool) -> Result<()> {
+        try!(self.point(zone, i, false));
+        let (fx, fy) = self.memory.state.freedom;
+        let zone = &mut self.zones[zone];
+        if fx != 0 {
+            zone.current[i].0 += dx;
+            if touch {
+                zone.touched[i] |= TOUCHED_X;
+            }
+        }
+        if fy != 0 {
+            zone.current[i].1 += dy;
+            if touch {
+                zone.touched[i] |= TOUCHED_Y;
+            }
+        }
+        Ok(())
+    }
+
+    fn round(&self, distance: i32) -> i32 {
+        let (d, sign) = if distance >= 0 { (distance, 1) } else { (distance.saturating_neg(), -1) };
+        let rounded = match self.memory.state.round {
+            Round::ToHalfGrid => (d & !63) + 32,
+            Round::ToGrid => d.saturating_add(32) & !63,
+            Round::ToDoubleGrid => d.saturating_add(16) & !31,
+            Round::DownToGrid => d & !63,
+            Round::UpToGrid => d.saturating_add(63) & !63,
+            Round::Off => d,
+            Round::Super { period, phase, threshold } => {
+                let value = (d.saturating_sub(phase).saturating_add(threshold) & -period) + phase;
+                if value < 0 { phase } else { value }
+            },
+            Round::Super45 { period, phase, threshold } => {
+                let value = (d.saturating_sub(phase).saturating_add(threshold) / period * period).saturating_add(phase);
+                if value < 0 { phase } else { value }
+            },
+        };
+        sign * rounded
+    }
+
+    fn intersect(&mut self) -> Result<()> {
+        let b1 = try!(self.pop_point());
+        let b0 = try!(self.pop_point());
+        let a1 = try!(self.pop_point());
+        let a0 = try!(self.pop_point());
+        let p = try!(self.pop_point());
+        let zp = self.memory.state.zp;
+        let (pa0, pa1) = (try!(self.point(zp[1], a0, false)), try!(self.point(zp[1], a1, false)));
+        let (pb0, pb1) = (try!(self.point(zp[0], b0, false)), try!(self.point(zp[0], b1, false)));
+        try!(self.point(zp[2], p, false));
+
+        let (dbx, dby) = (pb1.0 - pb0.0, pb1.1 - pb0.1);
+        let (dax, day) = (pa1.0 - pa0.0, pa1.1 - pa0.1);
+        let (dx, dy) = (pb0.0 - pa0.0, pb0.1 - pa0.1);
+        let discriminant = mul_div(dax, -dby, 64) + mul_div(day, dbx, 64);
+        let product = mul_div(dax, dbx, 64) + mul_div(day, dby, 64);
+        // Nearly parallel lines meet in the middle of the points.
+        let point = if 19 * discriminant.abs() > product.abs() {
+            let value = mul_div(dx, -dby, 64) + mul_div(dy, dbx, 64);
+            (pa0.0 + mul_div(value, dax, discriminant), pa0.1 + mul_div(value, day, discriminant))
+        } else {
+            ((pa0.0 + pa1.0 + pb0.0 + pb1.0) / 4, (pa0.1 + pa1.1 + pb0.1 + pb1.1) / 4)
+        };
+        let zone = &mut self.zones[zp[2]];
+        zone.current[p] = point;
+        zone.touched[p] |= TOUCHED_X | TOUCHED_Y;
+        Ok(())
+    }
+
+    // The displacement of the reference point along the freedom vector.
+    fn displacement(&self, opcode: u8) -> Result<(usize, usize, i32, i32)> {
+        let state = self.memory.state;
+        let (zone, p) = if opcode & 1 == 0 { (state.zp[1], state.rp[2]) } else { (state.zp[0], state.rp[1]) };
+        let distance = self.project(try!(self.point(zone, p, false)), try!(self.point(zone, p, true)));
+        let product = self.freedom_dot_projection();
+        Ok((zone, p, mul_div(distance, state.freedom.0, product), mul_div(distance, state.freedom.1, product)))
+    }
+
+    fn shift(&mut self, opcode: u8) -> Result<()> {
+        let (zone, reference, dx, dy) = try!(self.displacement(opcode));
+        let zp2 = self.memory.state.zp[2];
+        match opcode {
+            // SHP.
+            0x32 | 0x33 => for _ in 0..self.take_loop() {
+                let p = try!(self.pop_point());
+                try!(self.shift_point(zp2, p, dx, dy, true));
+            },
+            // SHC.
+            0x34 | 0x35 => {
+                let contour = try!(self.pop_point());
+                let ends = &self.zones[zp2].contour_ends;
+                if contour >= ends.len() {
+                    return Err(Error::HintingFailed);
+                }
+                let start = if contour == 0 { 0 } else { ends[contour - 1] + 1 };
+                let end = ends[contour];
+                for p in start..end + 1 {
+                    if zone != zp2 || p != reference {
+                        try!(self.shift_point(zp2, p, dx, dy, true));
+                    }
+                }
+            },
+            // SHZ.
+            _ => {
+                let target = match try!(self.pop()) {
+                    0 => TWILIGHT,
+                    1 => GLYPH,
+                    _ => return Err(Error::HintingFailed),
+                };
+                // Phantom points are not shifted.
+                let count = match self.zones[target].contour_ends.last() {
+                    Some(&end) if target == GLYPH => end + 1,
+                    _ => self.zones[target].len(),
+                };
+                for p in 0..count {
+                    if zone != target || p != reference {
+                        try!(self.shift_point(target, p, dx, dy, false));
+                    }
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn interpolate_points(&mut self) -> Result<()> {
+        let state = self.memory.state;
+        let original_base = try!(self.point(state.zp[0], state.rp[1], true));
+        let current_base = try!(self.point(state.zp[0], state.rp[1], false));
+        let original_range = self.dual_project(try!(self.point(state.zp[1], state.rp[2], true)), original_base);
+        let current_range = self.project(try!(self.point(state.zp[1], state.rp[2], false)), current_base);
+        for _ in 0..self.take_loop() {
+            let p = try!(self.pop_point());
+            let original = self.dual_project(try!(self.point(state.zp[2], p, true)), original_base);
+            let current = self.project(try!(self.point(state.zp[2], p, false)), current_base);
+            let distance = if original == 0 {
+                0
+            } else if original_range != 0 {
+                mul_div(original, current_range, original_range)
+            } else {
+                original
+            };
+            try!(self.move_point(state.zp[2], p, distance - current, true));
+        }
+        Ok(())
+    }
+
+    // Moves untouched points of each contour like the touched points
+    // around them.
+    fn interpolate_untouched(&mut self, x: bool) {
+        let flag = if x { TOUCHED_X } else { TOUCHED_Y };
+        let zone = &mut self.zones[GLYPH];
+        let coordinate = |p: Point| if x { p.0 } else { p.1 };
+        let mut start = 0;
+        for contour in 0..zone.contour_ends.len() {
+            let end = cmp::min(zone.contour_ends[contour], zone.len().saturating_sub(1));
+            if start > end {
+                continue;
+            }
+            let touched: Vec<usize> = (start..end + 1).filter(|&p| zone.touched[p] & flag != 0).collect();
+            if touched.len() == 1 {
+                let p = touched[0];
+                let delta = coordinate(zone.current[p]) - coordinate(zone.original[p]);
+                for q in (start..end + 1).filter(|&q| q != p) {
+                    let value = coordinate(zone.original[q]) + delta;
+                    set_coordinate(&mut zone.current[q], x, value);
+                }
+            } else if touched.len() > 1 {
+                for (k, &p1) in touched.iter().enumerate() {
+                    let p2 = touched[(k + 1) % touched.len()];
+                    // Points between `p1` and `p2`, wrapping around the contour.
+                    let mut q = if p1 == end { start } else { p1 + 1 };
+                    while q != p2 {
+                        let value = interpolate(coordinate(zone.original[q]),
+                                                coordinate(zone.original[p1]), coordinate(zone.current[p1]),
+                                                coordinate(zone.original[p2]), coordinate(zone.current[p2]));
+                        set_coordinate(&mut zone.current[q], x, value);
+                        q = if q == end { start } else { q + 1 };
+                    }
+                }
+            }
+            start = zone.contour_ends[contour] + 1;
+        }
+    }
+
+    fn delta(&mut self, opcode: u8, points: bool) -> Result<()> {
+        let count = try!(self.pop());
+        let base = self.memory.state.delta_base.wrapping_add(match opcode {
+            0x71 | 0x74 => 16,
+            0x72 | 0x75 => 32,
+            _ => 0,
+        });
+        for _ in 0..count {
+            let target = try!(self.pop());
+            let argument = try!(self.pop());
+            if base.wrapping_add(argument >> 4 & 15) != self.ppem as i32 {
+                continue;
+            }
+            let mut steps = (argument & 15) - 8;
+            if steps >= 0 {
+                steps += 1;
+            }
+            let delta = steps * 64 / (1 << self.memory.state.delta_shift);
+            if points {
+                let zp0 = self.memory.state.zp[0];
+                if target < 0 {
+                    return Err(Error::HintingFailed);
+                }
+                try!(self.move_point(zp0, target as usize, delta, true));
+            } else {
+                let value = try!(self.cvt(target));
+                self.memory.cvt[target as usize] = value.wrapping_add(delta);
+            }
+        }
+        Ok(())
+    }
+
+    // Applies the single width cut-in to a distance.
+    fn single_width(&self, distance: i32) -> i32 {
+        let state = &self.memory.state;
+        if (distance.abs() - state.single_width).abs() < state.single_width_cut_in {
+            if distance >= 0 { state.single_width } else { -state.single_width }
+        } else {
+            distance
+        }
+    }
+
+    fn minimum_distance(&self, opcode: u8, original: i32, distance: i32) -> i32 {
+        let minimum = self.memory.state.minimum_distance;
+        if opcode & 0x08 == 0 {
+            distance
+        } else if original >= 0 {
+            cmp::max(distance, minimum)
+        } else {
+            cmp::min(distance, -minimum)
+        }
+    }
+
+    fn move_direct_relative(&mut self, opcode: u8) -> Result<()> {
+        let p = try!(self.pop_point());
+        let state = self.memory.state;
+        let original = self.dual_project(try!(self.point(state.zp[1], p, true)),
+                                         try!(self.point(state.zp[0], state.rp[0], true)));
+        let original = self.single_width(original);
+        let distance = if opcode & 0x04 != 0 { self.round(original) } else { original };
+        let distance = self.minimum_distance(opcode, original, distance);
+        let current = self.project(try!(self.point(state.zp[1], p, false)),
+                                   try!(self.point(state.zp[0], state.rp[0], false)));
+        try!(self.move_point(state.zp[1], p, distance - current, true));
+        self.finish_relative_move(opcode, p);
+        Ok(())
+    }
+
+    fn move_indirect_relative(&mut self, opcode: u8) -> Result<()> {
+        let index = try!(self.pop());
+        let p = try!(self.pop_point());
+        let state = self.memory.state;
+        let mut distance = if index == -1 { 0 } else { self.single_width(try!(self.cvt(index))) };
+        let reference = try!(self.point(state.zp[0], state.rp[0], true));
+        try!(self.point(state.zp[1], p, false));
+        if state.zp[1] == TWILIGHT {
+            self.zones[TWILIGHT].original[p] = reference;
+            self.move_original(TWILIGHT, p, distance);
+            self.zones[TWILIGHT].current[p] = self.zones[TWILIGHT].original[p];
+        }
+        let original = self.dual_project(try!(self.point(state.zp[1], p, true)), reference);
+        let current = self.project(try!(self.point(state.zp[1], p, false)),
+                                   try!(self.point(state.zp[0], state.rp[0], false)));
+        if state.auto_flip && (original ^ distance) < 0 {
+            distance = -distance;
+        }
+        if opcode & 0x04 != 0 {
+            if state.zp[0] == state.zp[1] && (distance - original).abs() > state.control_value_cut_in {
+                distance = original;
+            }
+            distance = self.round(distance);
+        }
+        let distance = self.minimum_distance(opcode, original, distance);
+        try!(self.move_point(state.zp[1], p, distance - current, true));
+        self.finish_relative_move(opcode, p);
+        Ok(())
+    }
+
+    fn finish_relative_move(&mut self, opcode: u8, p: usize) {
+        let state = &mut self.memory.state;
+        state.rp[1] = state.rp[0];
+        state.rp[2] = p;
+        if opcode & 0x10 != 0 {
+            state.rp[0] = p;
+        }
+    }
+}
+
+// Returns the length of the instruction at `ip` with its inline data.
+fn instruction_length(code: &[u8], ip: usize) -> Result<usize> {
+    let length = match code[ip] {
+        0x40 => 2 + *try!(code.get(ip + 1).ok_or(Error::HintingFailed)) as usize,
+        0x41 => 2 + *try!(code.get(ip + 1).ok_or(Error::HintingFailed)) as usize * 2,
+        opcode @ 0xb0..=0xb7 => 2 + (opcode - 0xb0) as usize,
+        opcode @ 0xb8..=0xbf => 3 + (opcode - 0xb8) as usize * 2,
+        _ => 1,
+    };
+    if ip + length > code.len() {
+        return Err(Error::HintingFailed);
+    }
+    Ok(length)
+}
+
+// Skips to the ELSE or EIF that ends the current branch and returns the
+// position after it. ELSE is ignored if `stop_at_else` is `false`.
+fn skip_branch(code: &[u8], mut ip: usize, end: usize, stop_at_else: bool) -> Result<usize> {
+    let mut nesting = 0;
+    while ip < end {
+        let opcode = code[ip];
+        ip += try!(instruction_length(code, ip));
+        match opcode {
+            0x58 => nesting += 1,
+            0x1b if nesting == 0 && stop_at_else => return Ok(ip),
+            0x59 if nesting == 0 => return Ok(ip),
+            0x59 => nesting -= 1,
+            _ => {},
+        }
+    }
+    Err(Error::HintingFailed)
+}
+
+// Returns the position of ENDF of the function starting at `ip`.
+fn find_function_end(code: &[u8], mut ip: usize, end: usize) -> Result<usize> {
+    while ip < end {
+        match code[ip] {
+            0x2d => return Ok(ip),
+            0x2c | 0x89 => return Err(Error::HintingFailed),
+            _ => ip += try!(instruction_length(code, ip)),
+        }
+    }
+    Err(Error::HintingFailed)
+}
+
+fn super_round(selector: i32, diagonal: bool) -> Round {
+    let grid = if diagonal { 45 } else { 64 };
+    let period = match selector & 0xc0 {
+        0x00 => grid / 2,
+        0x40 => grid,
+        _ => grid * 2,
+    };
+    let phase = match selector & 0x30 {
+        0x00 => 0,
+        0x10 => period / 4,
+        0x20 => period / 2,
+        _ => period * 3 / 4,
+    };
+    let threshold = match selector & 0x0f {
+        0 => period - 1,
+        n => (n - 4) * period / 8,
+    };
+    if diagonal {
+        Round::Super45 { period: period, phase: phase, threshold: threshold }
+    } else {
+        Round::Super { period: period, phase: phase, threshold: threshold }
+    }
+}
+
+fn normalize(x: i32, y: i32) -> Point {
+    if x == 0 && y == 0 {
+        return X_AXIS;
+    }
+    let length = ((x as f64).powi(2) + (y as f64).powi(2)).sqrt();
+    ((x as f64 * ONE as f64 / length).round() as i32, (y as f64 * ONE as f64 / length).round() as i32)
+}
+
+fn dot(x: i32, y: i32, vector: Point) -> i32 {
+    ((x as i64 * vector.0 as i64 + y as i64 * vector.1 as i64 + 0x2000) >> 14) as i32
+}
+
+// Computes `a * b / c` rounded to the nearest integer.
+fn mul_div(a: i32, b: i32, c: i32) -> i32 {
+    if c == 0 {
+        return if (a >= 0) == (b >= 0) { i32::MAX } else { -i32::MAX };
+    }
+    let product = a as i64 * b as i64;
+    let sign = product.signum() * (c as i64).signum();
+    let value = (product.abs() + (c as i64).abs() / 2) / (c as i64).abs();
+    (sign * value).clamp(-i32::MAX as i64, i32::MAX as i64) as i32
+}
+
+fn round(value: i32) -> i32 {
+    (value + 32) & !63
+}
+
+// Interpolates a coordinate between two reference points, points outside
+// of them are shifted like the nearest one.
+fn interpolate(original: i32, original1: i32, current1: i32, original2: i32, current2: i32) -> i32 {
+    let (original1, current1, original2, current2) = if original1 > original2 {
+        (original2, current2, original1, current1)
+    } else {
+        (original1, current1, original2, current2)
+    };
+    if original <= original1 {
+        original + current1 - original1
+    } else if original >= original2 {
+        original + current2 - original2
+    } else {
+        current1 + mul_div(original - original1, current2 - current1, original2 - original1)
+    }
+}
+
+fn set_coordinate(point: &mut Point, x: bool, value: i32) {
+    if x {
+        point.0 = value;
+    } else {
+        point.1 = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+
+    // A square of 4 points from (0, 0) to (100, 100) in 26.6 with
+    // 4 phantom points.
+    fn square() -> Zone {
+        let points = vec![(10, 10), (110, 10), (110, 110), (10, 110), (0, 0), (700, 0), (0, 0), (0, 0)];
+        Zone {
+            original: points.clone(),
+            current: points,
+            touched: vec![0; 8],
+            on_curve: vec![true; 8],
+            contour_ends: vec![3],
+        }
+    }
+
+    fn run(program: &[u8], zone: Zone, cvt: Vec<i32>) -> (Context, Result<()>) {
+        let memory = Memory {
+            functions: vec![None; 4],
+            instructions: vec![None; 256],
+            cvt: cvt,
+            storage: vec![0; 4],
+            twilight: Zone::new(2),
+            state: GraphicsState::default(),
+        };
+        let mut context = Context {
+            programs: [&[], &[], program],
+            zones: [memory.twilight.clone(), zone],
+            memory: memory,
+            stack: vec![],
+            max_stack: 32,
+            ppem: 12,
+            scale: 1.0,
+            steps: 0,
+        };
+        let result = context.execute(GLYPH_PROGRAM, 0, program.len(), 0);
+        (context, result)
+    }
+
+    #[test]
+    fn stack_and_arithmetic() {
+        // PUSHB 64 128 32, ADD, PUSHW -64, MUL, DUP, NEG, MAX.
+        let (context, result) = run(&[0xb2, 64, 128, 32, 0x60, 0xb8, 0xff, 0xc0, 0x63, 0x20, 0x65, 0x8b],
+                                    Zone::default(), vec![]);
+        expect!(result).to(be_ok());
+        expect!(context.stack).to(be_equal_to(vec![64, 160]));
+
+        // Division by zero and stack underflow fail.
+        expect!(run(&[0xb1, 1, 0, 0x62], Zone::default(), vec![]).1).to(be_err());
+        expect!(run(&[0x21], Zone::default(), vec![]).1).to(be_err());
+    }
+
+    #[test]
+    fn branches_and_functions() {
+        // IF 0 { PUSHB 1 } ELSE { PUSHB 2 } EIF, then a loop on a counter
+        // of 2 which pushes 1 below the counter and jumps back by JROT.
+        let program = [0xb0, 0, 0x58, 0xb0, 1, 0x1b, 0xb0, 2, 0x59,
+                       0xb0, 2,
+                       0xb0, 1, 0x23,
+                       0xb0, 1, 0x61, 0x20,
+                       0xb8, 0xff, 0xf5, 0x23, 0x78];
+        let (context, result) = run(&program, Zone::default(), vec![]);
+        expect!(result).to(be_ok());
+        expect!(context.stack).to(be_equal_to(vec![2, 1, 1, 0]));
+
+        // FDEF is not allowed in glyph programs.
+        expect!(run(&[0xb0, 0, 0x2c, 0x2d], Zone::default(), vec![]).1).to(be_err());
+    }
+
+    #[test]
+    fn rounding() {
+        let (mut context, _) = run(&[], Zone::default(), vec![]);
+        expect!(context.round(95)).to(be_equal_to(64));
+        expect!(context.round(-96)).to(be_equal_to(-128));
+        context.memory.state.round = Round::ToHalfGrid;
+        expect!(context.round(10)).to(be_equal_to(32));
+        context.memory.state.round = Round::DownToGrid;
+        expect!(context.round(127)).to(be_equal_to(64));
+        context.memory.state.round = super_round(0x48, false);
+        expect!(context.round(90)).to(be_equal_to(64));
+        expect!(context.round(129)).to(be_equal_to(128));
+    }
+
+    #[test]
+    fn direct_moves() {
+        // SVTCA[x], MDAP[round] 0, MDRP[min, round, rp0] 1, IUP[x].
+        let (context, result) = run(&[0x01, 0xb0, 0, 0x2f, 0xb0, 1, 0xdc, 0x31], square(), vec![]);
+        expect!(result).to(be_ok());
+        let zone = &context.zones[GLYPH];
+        expect!(zone.current[0]).to(be_equal_to((0, 10)));
+        expect!(zone.current[1]).to(be_equal_to((128, 10)));
+        // Untouched points follow the nearest touched points.
+        expect!(zone.current[2]).to(be_equal_to((128, 110)));
+        expect!(zone.current[3]).to(be_equal_to((0, 110)));
+        expect!(zone.touched[2]).to(be_equal_to(0));
+    }
+
+    #[test]
+    fn indirect_moves_and_interpolation() {
+        // SVTCA[y], MIAP[round] 0 to cvt 0, MIRP[min, round, rp0] 3 by
+        // cvt 1, then interpolate the mid point 5.
+        let mut zone = square();
+        zone.original.insert(4, (60, 60));
+        zone.current.insert(4, (60, 60));
+        zone.touched.insert(4, 0);
+        zone.on_curve.insert(4, true);
+        zone.contour_ends = vec![4];
+        let program = [0x00, 0xb1, 0, 0, 0x3f, 0xb1, 3, 1, 0xfc,
+                       0xb2, 4, 0, 3, 0x11, 0x12, 0x39];
+        let (context, result) = run(&program, zone, vec![0, 120]);
+        expect!(result).to(be_ok());
+        let zone = &context.zones[GLYPH];
+        // The distance of the CVT is within the cut-in and rounds to 128.
+        expect!(zone.current[0]).to(be_equal_to((10, 0)));
+        expect!(zone.current[3]).to(be_equal_to((10, 128)));
+        expect!(zone.current[4]).to(be_equal_to((60, 64)));
+    }
+
+    #[test]
+    fn twilight_zone() {
+        // SZP0 0, SVTCA[x], MIAP 0 to cvt 0 places a twilight point, then
+        // MDRP[round] moves glyph point 1 by the rounded original distance.
+        let program = [0xb0, 0, 0x13, 0x01, 0xb1, 0, 0, 0x3e, 0xb0, 1, 0xc4];
+        let (context, result) = run(&program, square(), vec![200]);
+        expect!(result).to(be_ok());
+        expect!(context.zones[TWILIGHT].current[0]).to(be_equal_to((200, 0)));
+        expect!(context.zones[TWILIGHT].original[0]).to(be_equal_to((200, 0)));
+        expect!(context.zones[GLYPH].current[1]).to(be_equal_to((136, 10)));
+    }
+
+    #[test]
+    fn deltas() {
+        // DELTAP1 moves point 0 by 1/8 pixel steps at 12 ppem: delta base
+        // 9 + 3 and step 15 is +8 steps.
+        let (context, result) = run(&[0x01, 0xb2, 0x3f, 0, 1, 0x5d], square(), vec![]);
+        expect!(result).to(be_ok());
+        expect!(context.zones[GLYPH].current[0]).to(be_equal_to((74, 10)));
+        let (context, _) = run(&[0x01, 0xb2, 0x2f, 0, 1, 0x5d], square(), vec![]);
+        expect!(context.zones[GLYPH].current[0]).to(be_equal_to((10, 10)));
+    }
+
+    #[test]
+    fn hostile_values() {
+        // SDS -1 is clamped to 0, then DELTAC1 moves cvt 0 by -8 pixels.
+        let (context, result) = run(&[0xb8, 0xff, 0xff, 0x5f, 0xb2, 0x30, 0, 1, 0x73], square(), vec![0]);
+        expect!(result).to(be_ok());
+        expect!(context.memory.cvt[0]).to(be_equal_to(-512));
+
+        // SDB and DELTAC1 with extreme cvt values wrap around.
+        let (_, result) = run(&[0xb0, 0, 0x45, 0x5e, 0xb2, 0x30, 0, 1, 0x73], square(), vec![i32::MAX]);
+        expect!(result).to(be_ok());
+        let (context, result) = run(&[0xb2, 0x3f, 0, 1, 0x73], square(), vec![i32::MAX]);
+        expect!(result).to(be_ok());
+        expect!(context.memory.cvt[0]).to(be_equal_to(i32::MIN + 63));
+
+        let (mut context, _) = run(&[], Zone::default(), vec![]);
+        for &round in &[Round::ToGrid, Round::ToDoubleGrid, Round::UpToGrid, super_round(0x48, false),
+                        super_round(0x4f, true)] {
+            context.memory.state.round = round;
+            context.round(i32::MIN);
+            context.round(i32::MAX);
+        }
+    }
+}
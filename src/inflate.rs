@@ -0,0 +1,375 @@
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{LittleEndian, ReadBytesExt};
+
+/// The maximum length of a Huffman code in bits.
+const MAX_CODE_LENGTH: usize = 15;
+/// The maximum length of decompressed data, a few bytes of matches may
+/// expand to any length.
+const MAX_OUTPUT_LENGTH: usize = 64 << 20;
+
+/// Base lengths of matches for length codes from 257 on.
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+                                35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA_BITS: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+                                     3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+/// Base distances of matches for distance codes.
+const DISTANCE_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+                                  257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+                                  8193, 12289, 16385, 24577];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+                                       7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+/// The order of lengths of the code length code in dynamic blocks.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Reads bits of data starting from the least significant bit of each byte.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader { data: data, position: 0, buffer: 0, count: 0 }
+    }
+
+    fn bits(&mut self, count: u32) -> Result<u32> {
+        while self.count < count {
+            let byte = try!(self.data.get(self.position).ok_or(Error::Malformed));
+            self.position += 1;
+            self.buffer |= (*byte as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1 << count) - 1);
+        self.buffer >>= count;
+        self.count -= count;
+        Ok(value)
+    }
+
+    /// Skips bits up to the next byte. Less than a byte is ever buffered, so
+    /// the buffer is dropped.
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        if self.position + count > self.data.len() {
+            return Err(Error::Malformed);
+        }
+        let bytes = &self.data[self.position..self.position + count];
+        self.position += count;
+        Ok(bytes)
+    }
+}
+
+/// A canonical Huffman code given by numbers of codes of each length and
+/// symbols ordered by their codes.
+struct Huffman {
+    counts: [u16; MAX_CODE_LENGTH + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Builds the code from code lengths of symbols, zero for unused
+    /// symbols. Incomplete codes are allowed, since a code of a single
+    /// symbol is.
+    fn new(lengths: &[u8]) -> Result<Huffman> {
+        let mut counts = [0; MAX_CODE_LENGTH + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(Error::Malformed);
+            }
+        }
+
+        let mut offsets = [0; MAX_CODE_LENGTH + 1];
+        for length in 1..MAX_CODE_LENGTH {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts: counts, symbols: symbols })
+    }
+
+    /// Decodes a symbol reading the code bit by bit.
+    fn decode(&self, reader: &mut BitReader) -> Result<u16> {
+        // The first code of the current length and the index of its symbol.
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= try!(reader.bits(1)) as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(Error::Malformed)
+    }
+}
+
+/// Decompresses `data` in the gzip format. Only the first member is read.
+///
+/// # Errors
+/// Returns error if the data is malformed, decompresses to more than
+/// `MAX_OUTPUT_LENGTH` bytes or its checksum or size don't match the
+/// decompressed data.
+pub fn gunzip(data: &[u8]) -> Result<Vec<u8>> {
+    const FLAG_HEADER_CRC: u8 = 2;
+    const FLAG_EXTRA: u8 = 4;
+    const FLAG_NAME: u8 = 8;
+    const FLAG_COMMENT: u8 = 16;
+
+    // The signature, the DEFLATE method, flags, time, extra flags and system.
+    if data.len() < 10 || data[0] != 0x1f || data[1] != 0x8b || data[2] != 8 {
+        return Err(Error::Malformed);
+    }
+    let flags = data[3];
+    let mut reader = BitReader::new(data);
+    try!(reader.bytes(10));
+    if flags & FLAG_EXTRA != 0 {
+        let length = try!(Cursor::new(try!(reader.bytes(2))).read_u16::<LittleEndian>());
+        try!(reader.bytes(length as usize));
+    }
+    for &flag in &[FLAG_NAME, FLAG_COMMENT] {
+        if flags & flag != 0 {
+            while try!(reader.bytes(1))[0] != 0 {}
+        }
+    }
+    if flags & FLAG_HEADER_CRC != 0 {
+        try!(reader.bytes(2));
+    }
+
+    let mut output = vec![];
+    try!(inflate_blocks(&mut reader, &mut output, MAX_OUTPUT_LENGTH));
+    reader.align();
+    let mut trailer = Cursor::new(try!(reader.bytes(8)));
+    if try!(trailer.read_u32::<LittleEndian>()) != crc32(&output) ||
+       try!(trailer.read_u32::<LittleEndian>()) != output.len() as u32 {
+        return Err(Error::Malformed);
+    }
+    Ok(output)
+}
+
+/// Decompresses blocks to `output`, failing if it would grow longer than
+/// `limit`.
+fn inflate_blocks(reader: &mut BitReader, output: &mut Vec<u8>, limit: usize) -> Result<()> {
+    loop {
+        let last = try!(reader.bits(1)) == 1;
+        match try!(reader.bits(2)) {
+            0 => {
+                reader.align();
+                let mut header = Cursor::new(try!(reader.bytes(4)));
+                let length = try!(header.read_u16::<LittleEndian>());
+                if length != !try!(header.read_u16::<LittleEndian>()) {
+                    return Err(Error::Malformed);
+                }
+                if output.len() + length as usize > limit {
+                    return Err(Error::Malformed);
+                }
+                output.extend_from_slice(try!(reader.bytes(length as usize)));
+            },
+            1 => {
+                let mut lengths = [0; 288 + 30];
+                for (symbol, length) in lengths.iter_mut().enumerate() {
+                    *length = match symbol {
+                        0..=143 => 8,
+                        144..=255 => 9,
+                        256..=279 => 7,
+                        280..=287 => 8,
+                        _ => 5,
+                    };
+                }
+                let literals = try!(Huffman::new(&lengths[..288]));
+                let distances = try!(Huffman::new(&lengths[288..]));
+                try!(inflate_block(reader, output, limit, &literals, &distances));
+            },
+            2 => {
+                let (literals, distances) = try!(read_dynamic_codes(reader));
+                try!(inflate_block(reader, output, limit, &literals, &distances));
+            },
+            _ => return Err(Error::Malformed),
+        }
+        if last {
+            return Ok(());
+        }
+    }
+}
+
+/// Reads literal and length, and distance codes of a dynamic block.
+fn read_dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman)> {
+    let num_literals = try!(reader.bits(5)) as usize + 257;
+    let num_distances = try!(reader.bits(5)) as usize + 1;
+    let num_code_lengths = try!(reader.bits(4)) as usize + 4;
+    if num_literals > 286 || num_distances > 30 {
+        return Err(Error::Malformed);
+    }
+
+    let mut code_lengths = [0; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..num_code_lengths] {
+        code_lengths[symbol] = try!(reader.bits(3)) as u8;
+    }
+    let code_lengths = try!(Huffman::new(&code_lengths));
+
+    let mut lengths = Vec::with_capacity(num_literals + num_distances);
+    while lengths.len() < num_literals + num_distances {
+        let (length, repeat) = match try!(code_lengths.decode(reader)) {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = try!(lengths.last().cloned().ok_or(Error::Malformed));
+                (previous, 3 + try!(reader.bits(2)))
+            },
+            17 => (0, 3 + try!(reader.bits(3))),
+            _ => (0, 11 + try!(reader.bits(7))),
+        };
+        if lengths.len() + repeat as usize > num_literals + num_distances {
+            return Err(Error::Malformed);
+        }
+        for _ in 0..repeat {
+            lengths.push(length);
+        }
+    }
+    // A block without the end of block code can't end.
+    if lengths[256] == 0 {
+        return Err(Error::Malformed);
+    }
+
+    Ok((try!(Huffman::new(&lengths[..num_literals])), try!(Huffman::new(&lengths[num_literals..]))))
+}
+
+fn inflate_block(reader: &mut BitReader, output: &mut Vec<u8>, limit: usize,
+                 literals: &Huffman, distances: &Huffman) -> Result<()> {
+    loop {
+        let symbol = try!(literals.decode(reader)) as usize;
+        if symbol < 256 {
+            if output.len() == limit {
+                return Err(Error::Malformed);
+            }
+            output.push(symbol as u8);
+        } else if symbol == 256 {
+            return Ok(());
+        } else {
+            let symbol = symbol - 257;
+            if symbol >= LENGTH_BASE.len() {
+                return Err(Error::Malformed);
+            }
+            let length = LENGTH_BASE[symbol] as usize + try!(reader.bits(LENGTH_EXTRA_BITS[symbol] as u32)) as usize;
+            let symbol = try!(distances.decode(reader)) as usize;
+            if symbol >= DISTANCE_BASE.len() {
+                return Err(Error::Malformed);
+            }
+            let distance = DISTANCE_BASE[symbol] as usize +
+                           try!(reader.bits(DISTANCE_EXTRA_BITS[symbol] as u32)) as usize;
+            if distance > output.len() || output.len() + length > limit {
+                return Err(Error::Malformed);
+            }
+            // Matches may overlap the bytes they produce.
+            let start = output.len() - distance;
+            for i in 0..length {
+                let byte = output[start + i];
+                output.push(byte);
+            }
+        }
+    }
+}
+
+/// Returns the CRC-32 checksum of `data` used by gzip.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    fn inflate(data: &[u8]) -> Result<Vec<u8>> {
+        let mut output = vec![];
+        try!(inflate_blocks(&mut BitReader::new(data), &mut output, MAX_OUTPUT_LENGTH));
+        Ok(output)
+    }
+
+    #[test]
+    fn blocks() {
+        // A stored block followed by a block with fixed codes.
+        let data = [0x00, 0x03, 0x00, 0xfc, 0xff, 0x61, 0x62, 0x63, 0x4b, 0x4c, 0x4a, 0x4e, 0x04, 0x23, 0x00];
+        expect!(inflate(&data)).to(be_ok().value(b"abcabcabcabc".to_vec()));
+        expect!(inflate(&data[..10])).to(be_err().value(Malformed));
+        expect!(inflate(&[0x00, 0x03, 0x00, 0xfc, 0xfe])).to(be_err().value(Malformed));
+        expect!(inflate(&[0x07])).to(be_err().value(Malformed));
+    }
+
+    #[test]
+    fn output_limit() {
+        let data = [0x00, 0x03, 0x00, 0xfc, 0xff, 0x61, 0x62, 0x63, 0x4b, 0x4c, 0x4a, 0x4e, 0x04, 0x23, 0x00];
+        let mut output = vec![];
+        expect!(inflate_blocks(&mut BitReader::new(&data), &mut output, 12)).to(be_ok());
+        for &limit in &[2, 3, 11] {
+            let mut output = vec![];
+            expect!(inflate_blocks(&mut BitReader::new(&data), &mut output, limit)).to(be_err().value(Malformed));
+            expect!(output.len() <= limit).to(be_true());
+        }
+    }
+
+    #[test]
+    fn dynamic_codes() {
+        let data = [
+            0x6d, 0xce, 0xcb, 0x09, 0x80, 0x30, 0x10, 0x84, 0xe1, 0x56, 0x86, 0x34, 0x60, 0x36, 0xef, 0x40,
+            0xb4, 0x03, 0x8b, 0x10, 0x04, 0x23, 0x78, 0xc8, 0xc1, 0x8b, 0x56, 0xaf, 0xb8, 0xb9, 0x08, 0x7b,
+            0x1b, 0xf8, 0x18, 0xf8, 0x4b, 0x5b, 0xce, 0x8a, 0x7d, 0x1d, 0xd5, 0x76, 0x5c, 0xad, 0x6a, 0x85,
+            0x77, 0xce, 0x1a, 0xfa, 0x56, 0xc3, 0x54, 0xfe, 0x48, 0x8c, 0x11, 0x24, 0xa0, 0x61, 0x24, 0x38,
+            0x01, 0x2d, 0x63, 0x42, 0x16, 0xd0, 0x31, 0x1a, 0x50, 0x10, 0xd4, 0xb3, 0x66, 0x18, 0x2f, 0x68,
+            0x60, 0xb5, 0x90, 0x30, 0xf6, 0x24, 0x0d, 0x4a, 0x02, 0x27, 0x66, 0x07, 0x23, 0x60, 0xee, 0x5f,
+            0x02, 0x7d, 0xcd, 0x0f,
+        ];
+        let text: String = (0..10).map(|i| format!("<path id=\"glyph{}\" d=\"M{} {}z\"/>", i, i * 7 % 13, i * i % 31))
+            .collect();
+        expect!(inflate(&data)).to(be_ok().value(text.into_bytes()));
+        expect!(inflate(&data[..50])).to(be_err().value(Malformed));
+    }
+
+    #[test]
+    fn gzip() {
+        let mut data = vec![0x1f, 0x8b, 8, 8, 0, 0, 0, 0, 0, 3, b'a', 0];
+        data.extend_from_slice(&[0x4b, 0x4c, 0x4a, 0x4e, 0x04, 0x23, 0x00]);
+        data.extend_from_slice(&[0x18, 0x48, 0x2d, 0x46, 9, 0, 0, 0]);
+        expect!(gunzip(&data)).to(be_ok().value(b"abcabcabc".to_vec()));
+
+        data[20] = 10;
+        expect!(gunzip(&data)).to(be_err().value(Malformed));
+        data[20] = 9;
+        data[16] ^= 1;
+        expect!(gunzip(&data)).to(be_err().value(Malformed));
+        expect!(gunzip(&data[1..])).to(be_err().value(Malformed));
+    }
+
+    #[test]
+    fn checksum() {
+        expect!(crc32(b"")).to(be_equal_to(0));
+        expect!(crc32(b"123456789")).to(be_equal_to(0xcbf4_3926));
+    }
+}
@@ -0,0 +1,150 @@
+use bitmap::RgbBitmap;
+use Result;
+use types::BBox;
+
+/// A filter reducing color fringes that is close to a Gaussian.
+pub const LCD_DEFAULT_FILTER: [u8; 5] = [8, 77, 86, 77, 8];
+
+/// A filter that is sharper than the default one, but has more color fringes.
+pub const LCD_LIGHT_FILTER: [u8; 5] = [0, 85, 86, 85, 0];
+
+/// Order of subpixels in a pixel of a display.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SubpixelOrder {
+    /// Red is the first subpixel.
+    Rgb,
+    /// Blue is the first subpixel.
+    Bgr,
+}
+
+/// Direction in which subpixels of a display follow each other.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SubpixelLayout {
+    /// Subpixels are stripes placed left to right.
+    Horizontal,
+    /// Subpixels are stripes placed top to bottom.
+    Vertical,
+}
+
+/// Settings of subpixel rendering for LCDs.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LcdOptions {
+    pub order: SubpixelOrder,
+    pub layout: SubpixelLayout,
+    /// Weights of the FIR filter applied to subpixels, centered on
+    /// the filtered subpixel. Weights are normalized by their sum, zero
+    /// weights disable the filtering.
+    pub filter: [u8; 5],
+}
+
+impl Default for LcdOptions {
+    fn default() -> LcdOptions {
+        LcdOptions {
+            order: SubpixelOrder::Rgb,
+            layout: SubpixelLayout::Horizontal,
+            filter: LCD_DEFAULT_FILTER,
+        }
+    }
+}
+
+impl LcdOptions {
+    /// Returns `true` if subpixels are placed left to right.
+    pub fn is_horizontal(&self) -> bool {
+        self.layout == SubpixelLayout::Horizontal
+    }
+}
+
+/// Renders subpixel coverage into an RGB bitmap.
+///
+/// `bbox` is the bitmap box of the glyph with the subpixel axis scaled by 3.
+/// `rasterize` renders coverage into a buffer of the given width and height
+/// with the glyph origin at the given position, like
+/// `FontInfo::render_glyph_into`.
+pub fn render<F>(bbox: BBox, options: &LcdOptions, rasterize: F) -> Result<RgbBitmap>
+    where F: FnOnce(&mut [u8], usize, usize, i32, i32) -> Result<()>
+{
+    // Pixels get wider by the radius of the filter, so that nothing
+    // is cut off.
+    let pixels = |from: i32, to: i32| ((from - 2).div_euclid(3), -(-(to + 2)).div_euclid(3));
+    let bbox = if options.is_horizontal() {
+        let (x0, x1) = pixels(bbox.x0, bbox.x1);
+        BBox { x0: x0, y0: bbox.y0, x1: x1, y1: bbox.y1 }
+    } else {
+        let (y0, y1) = pixels(bbox.y0, bbox.y1);
+        BBox { x0: bbox.x0, y0: y0, x1: bbox.x1, y1: y1 }
+    };
+    let width = (bbox.x1 - bbox.x0) as usize;
+    let height = (bbox.y1 - bbox.y0) as usize;
+    let mut bitmap = RgbBitmap::new(width, height, bbox.x0, bbox.y0);
+
+    let (cw, ch, step) = if options.is_horizontal() {
+        (width * 3, height, 1)
+    } else {
+        (width, height * 3, width)
+    };
+    let mut coverage = vec![0; cw * ch];
+    if options.is_horizontal() {
+        try!(rasterize(&mut coverage, cw, ch, -bbox.x0 * 3, -bbox.y0));
+    } else {
+        try!(rasterize(&mut coverage, cw, ch, -bbox.x0, -bbox.y0 * 3));
+    }
+
+    let sum: u32 = options.filter.iter().map(|&w| w as u32).sum();
+    let (filter, sum) = if sum == 0 { ([0, 0, 1, 0, 0], 1) } else { (options.filter, sum) };
+    let (length, count) = if options.is_horizontal() { (cw, height) } else { (ch, width) };
+    for line in 0..count {
+        // Start and distance between subpixels of the line in `coverage`.
+        let start = if options.is_horizontal() { line * cw } else { line };
+        let subpixel = |i: isize| {
+            if i >= 0 && (i as usize) < length { coverage[start + i as usize * step] as u32 } else { 0 }
+        };
+        for i in 0..length {
+            let total: u32 = filter.iter().enumerate()
+                .map(|(j, &w)| w as u32 * subpixel(i as isize + j as isize - 2)).sum();
+            let value = (total + sum / 2) / sum;
+            let (pixel, channel) = (i / 3, i % 3);
+            let channel = if options.order == SubpixelOrder::Rgb { channel } else { 2 - channel };
+            let (x, y) = if options.is_horizontal() { (pixel, line) } else { (line, pixel) };
+            bitmap.pixels[y * bitmap.stride + x * 3 + channel] = value.min(255) as u8;
+        }
+    }
+    Ok(bitmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+
+    // Covers a single subpixel at the origin.
+    fn dot(buffer: &mut [u8], width: usize, _: usize, x: i32, y: i32) -> Result<()> {
+        buffer[y as usize * width + x as usize] = 255;
+        Ok(())
+    }
+
+    #[test]
+    fn filter_and_order() {
+        let bbox = BBox { x0: 0, y0: 0, x1: 1, y1: 1 };
+        let options = LcdOptions { filter: [0, 0, 1, 0, 0], ..LcdOptions::default() };
+        let bitmap = render(bbox, &options, dot).unwrap();
+        expect!((bitmap.width, bitmap.height, bitmap.x_offset, bitmap.y_offset)).to(be_equal_to((2, 1, -1, 0)));
+        expect!(bitmap.row(0)).to(be_equal_to(&[0, 0, 0, 255, 0, 0][..]));
+
+        let bitmap = render(bbox, &LcdOptions::default(), dot).unwrap();
+        expect!(bitmap.row(0)).to(be_equal_to(&[0, 8, 77, 86, 77, 8][..]));
+
+        let options = LcdOptions { order: SubpixelOrder::Bgr, ..LcdOptions::default() };
+        let bitmap = render(bbox, &options, dot).unwrap();
+        expect!(bitmap.row(0)).to(be_equal_to(&[77, 8, 0, 8, 77, 86][..]));
+    }
+
+    #[test]
+    fn vertical_layout() {
+        let bbox = BBox { x0: 0, y0: 0, x1: 1, y1: 1 };
+        let options = LcdOptions { layout: SubpixelLayout::Vertical, ..LcdOptions::default() };
+        let bitmap = render(bbox, &options, dot).unwrap();
+        expect!((bitmap.width, bitmap.height, bitmap.x_offset, bitmap.y_offset)).to(be_equal_to((1, 2, 0, -1)));
+        expect!(bitmap.pixel(0, 0)).to(be_equal_to([0, 8, 77]));
+        expect!(bitmap.pixel(0, 1)).to(be_equal_to([86, 77, 8]));
+    }
+}
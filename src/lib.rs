@@ -251,16 +251,42 @@ extern crate expectest;
 use std::ptr::{ null, null_mut };
 use std::mem::size_of;
 use std::slice;
-use byteorder::{BigEndian, ByteOrder};
+use std::cmp;
+use std::borrow::Cow;
+use utils::{read_u16, read_u32, read_i32};
 use libc::{ c_void, free, malloc, size_t, c_char };
-use tables::{HHEA, HEAD, MAXP, HMTX, LOCA, CMAP, GLYF, GlyphData};
+use tables::{HHEA, HEAD, MAXP, HMTX, LOCA, CMAP, GLYF, GlyphData, Component, ComponentOffset};
+use outline::ContourBuilder;
 
+mod autohint;
+mod bitmap;
+mod color;
 mod error;
+mod hinting;
+mod inflate;
+mod lcd;
+mod msdf;
+mod outline;
+mod sdf;
+mod shaping;
+mod stroke;
 mod tables;
 mod types;
 mod utils;
 
+pub use autohint::{AutoHinter, BlueZone, BlueZoneKind};
+pub use bitmap::{GlyphBitmap, RgbBitmap, RgbaBitmap, EmbeddedImage, ImageFormat};
 pub use error::Error;
+pub use hinting::{Hinter, HintedGlyph};
+pub use lcd::{LcdOptions, SubpixelLayout, SubpixelOrder, LCD_DEFAULT_FILTER, LCD_LIGHT_FILTER};
+pub use outline::{Outline, OutlineBuilder, Segment, Transform};
+pub use shaping::{shape, PositionedGlyph};
+pub use stroke::{embolden_outline, stroke_outline, LineCap, LineJoin, StrokeStyle, Stroker};
+pub use tables::{NAME, NameRecord, OS2, POST, KERN, GPOS, ValueRecord, GSUB, VHEA, VMTX, LongVerticalMetric, CFF, CVT, FPGM, PREP,
+                 COLR, LayerRecord, Paint, ColorLine, ColorStop, Extend, CompositeMode, CPAL,
+                 EBLC, BitmapLocation, BitmapMetrics, EBDT, SBIX, SVG,
+                 FVAR, VariationAxis, NamedInstance, AVAR, GVAR};
+pub use types::{BBox, Color, LineMetrics, VerticalMetrics};
 
 pub type Result<T> = ::std::result::Result<T, Error>;
 
@@ -297,8 +323,6 @@ use libc::strlen as STBTT_strlen;
 
 //   #define STBTT_strlen(x)    strlen(x)
 
-use std::ptr::copy as STBTT_memcpy;
-
 //   #define STBTT_memcpy       memcpy
 
 //   #define STBTT_memset       memset
@@ -400,6 +424,7 @@ pub struct PackContext {
    v_oversample: usize,
    pixels: *mut u8,
    nodes: *mut c_void,
+   msdf_range: f32,
 }
 
 //////////////////////////////////////////////////////////////////////////////
@@ -408,6 +433,96 @@ pub struct PackContext {
 //
 //
 
+// The maximum nesting of composite glyphs.
+const MAX_COMPONENT_DEPTH: usize = 8;
+
+// If one of the scales is zero, the other one is used for both axes.
+fn effective_scale(scale_x: f32, scale_y: f32) -> Option<(f32, f32)> {
+    match (scale_x == 0.0, scale_y == 0.0) {
+        (true, true) => None,
+        (true, false) => Some((scale_y, scale_y)),
+        (false, true) => Some((scale_x, scale_x)),
+        (false, false) => Some((scale_x, scale_y)),
+    }
+}
+
+// Maximum units per pixel of outlines expressed in pixels, the rasterizer
+// takes whole units that fit into 16 bits.
+const PIXEL_OUTLINE_UNITS: f32 = 64.0;
+
+// Returns units per pixel for an outline expressed in pixels.
+fn pixel_outline_units(outline: &Outline) -> f32 {
+    let extent = outline.bounding_box().map_or(1, |bbox| {
+        cmp::max(cmp::max(bbox.x0.abs(), bbox.x1.abs()), cmp::max(bbox.y0.abs(), bbox.y1.abs()))
+    });
+    (i16::MAX as f32 / cmp::max(extent, 1) as f32).floor().clamp(1.0, PIXEL_OUTLINE_UNITS)
+}
+
+// Renders an outline expressed in pixels into an owned bitmap.
+fn render_pixel_outline(outline: &Outline, shift_x: f32, shift_y: f32) -> GlyphBitmap {
+    let units = pixel_outline_units(outline);
+    render_outline(&Transform::scale(units, units).apply_to_outline(outline),
+                   1.0 / units, 1.0 / units, shift_x, shift_y)
+}
+
+// Rasterizes an outline expressed in pixels into a bitmap placed by its
+// offsets, parts of the outline outside of the bitmap are clipped.
+fn rasterize_pixel_outline(outline: &Outline, bitmap: &mut GlyphBitmap) {
+    let units = pixel_outline_units(outline);
+    let mut gbm = Bitmap {
+        w: bitmap.width as isize,
+        h: bitmap.height as isize,
+        stride: bitmap.stride as isize,
+        pixels: bitmap.pixels.as_mut_ptr(),
+    };
+    unsafe {
+        rasterize_outline(&mut gbm, &Transform::scale(units, units).apply_to_outline(outline),
+                          1.0 / units, 1.0 / units, 0.0, 0.0,
+                          bitmap.x_offset as isize, bitmap.y_offset as isize);
+    }
+}
+
+/// Renders an outline expressed in font units, e.g. a stroked or
+/// emboldened glyph outline, into an owned bitmap with antialiasing.
+///
+/// Scales and shifts are the same as for `FontInfo::render_glyph`.
+/// Coordinates are truncated to whole font units.
+pub fn render_outline(outline: &Outline, scale_x: f32, scale_y: f32,
+    shift_x: f32, shift_y: f32) -> GlyphBitmap
+{
+    let (scale_x, scale_y) = match effective_scale(scale_x, scale_y) {
+        Some(scale) => scale,
+        None => return GlyphBitmap::default(),
+    };
+    let bbox = match outline.bounding_box() {
+        Some(bbox) => BBox {
+            x0: (bbox.x0 as f32 * scale_x + shift_x).floor() as i32,
+            y0: (-bbox.y1 as f32 * scale_y + shift_y).floor() as i32,
+            x1: (bbox.x1 as f32 * scale_x + shift_x).ceil() as i32,
+            y1: (-bbox.y0 as f32 * scale_y + shift_y).ceil() as i32,
+        },
+        None => return GlyphBitmap::default(),
+    };
+
+    let width = (bbox.x1 - bbox.x0) as usize;
+    let height = (bbox.y1 - bbox.y0) as usize;
+    let mut bitmap = GlyphBitmap::new(width, height, bbox.x0, bbox.y0);
+    if bitmap.is_empty() {
+        return bitmap;
+    }
+    let mut gbm = Bitmap {
+        w: width as isize,
+        h: height as isize,
+        stride: width as isize,
+        pixels: bitmap.pixels.as_mut_ptr(),
+    };
+    unsafe {
+        rasterize_outline(&mut gbm, outline, scale_x, scale_y, shift_x, shift_y,
+                          bbox.x0 as isize, bbox.y0 as isize);
+    }
+    bitmap
+}
+
 // The following structure is defined publically so you can declare one on
 // the stack or as a global or etc, but you should treat it as opaque.
 pub struct FontInfo<'a> {
@@ -418,21 +533,46 @@ pub struct FontInfo<'a> {
 
    hhea: HHEA,
    head: HEAD,
+   maxp: MAXP,
    hmtx: HMTX,
    loca: LOCA,
    cmap: CMAP,
    glyf: GLYF,
+   name: Option<NAME>,
+   os2: Option<OS2>,
+   post: Option<POST>,
+   kern: Option<KERN>,
+   gpos: Option<GPOS>,
+   gsub: Option<GSUB>,
+   vhea: Option<VHEA>,
+   vmtx: Option<VMTX>,
+   cff: Option<CFF>,
+   cvt: Option<CVT>,
+   fpgm: Option<FPGM>,
+   prep: Option<PREP>,
+   colr: Option<COLR>,
+   cpal: Option<CPAL>,
+   cblc: Option<EBLC>,
+   cbdt: Option<EBDT>,
+   eblc: Option<EBLC>,
+   ebdt: Option<EBDT>,
+   sbix: Option<SBIX>,
+   svg: Option<SVG>,
+   fvar: Option<FVAR>,
+   avar: Option<AVAR>,
+   gvar: Option<GVAR>,
+   // normalized variation coordinates, one for each axis
+   coordinates: Vec<f32>,
 
    // table locations as offset from start of .ttf
    _glyf: usize,
-   kern: usize,
 }
 
 impl<'a> FontInfo<'a> {
     // Given an offset into the file that defines a font, this function builds
     // the necessary cached info for the rest of the system.
     pub fn new_with_offset(data: &[u8], fontstart: usize) -> Result<FontInfo> {
-        use utils::{find_table_offset, find_required_table_offset};
+        use utils::{find_table_offset, find_table_range, find_required_table_offset};
 
         let hhea = try!(HHEA::from_data(&data,
                         try!(find_required_table_offset(data, fontstart, b"hhea"))));
@@ -448,30 +588,138 @@ impl<'a> FontInfo<'a> {
                         hhea.num_of_long_hor_metrics(),
                         maxp.num_glyphs()));
 
-        let loca = try!(LOCA::from_data(&data,
-                        try!(find_required_table_offset(data, fontstart, b"loca")),
-                        maxp.num_glyphs(),
-                        head.location_format()));
-
         let cmap = try!(CMAP::from_data(&data,
                         try!(find_required_table_offset(data, fontstart, b"cmap"))));
 
-        let _glyf = try!(find_required_table_offset(data, fontstart, b"glyf"));
-        let glyf = try!(GLYF::from_data(&data, _glyf, loca.size_of_glyf_table()));
+        let cff = match try!(find_table_range(data, fontstart, b"CFF ")) {
+            Some((offset, size)) => Some(try!(CFF::from_data(&data, offset, size))),
+            None => match try!(find_table_range(data, fontstart, b"CFF2")) {
+                Some((offset, size)) => Some(try!(CFF::from_data_cff2(&data, offset, size))),
+                None => None,
+            },
+        };
+
+        // Fonts with PostScript outlines have no `loca` and `glyf` tables.
+        let (loca, glyf, _glyf) = if cff.is_some() {
+            (LOCA::default(), GLYF::default(), 0)
+        } else {
+            let loca = try!(LOCA::from_data(&data,
+                            try!(find_required_table_offset(data, fontstart, b"loca")),
+                            maxp.num_glyphs(),
+                            head.location_format()));
+
+            let _glyf = try!(find_required_table_offset(data, fontstart, b"glyf"));
+            let glyf = try!(GLYF::from_data(&data, _glyf, loca.size_of_glyf_table()));
+            (loca, glyf, _glyf)
+        };
+
+        // Optional tables that are malformed or of unsupported versions are
+        // ignored, the font is still usable without them.
+        let name = try!(find_table_offset(data, fontstart, b"name"))
+            .and_then(|offset| NAME::from_data(&data, offset).ok());
+
+        let os2 = try!(find_table_offset(data, fontstart, b"OS/2"))
+            .and_then(|offset| OS2::from_data(&data, offset).ok());
+
+        let post = try!(find_table_offset(data, fontstart, b"post"))
+            .and_then(|offset| POST::from_data(&data, offset).ok());
+
+        let kern = try!(find_table_offset(data, fontstart, b"kern"))
+            .and_then(|offset| KERN::from_data(&data, offset).ok());
 
-        let kern = try!(find_table_offset(data, fontstart, b"kern")).unwrap_or(0);
+        let gpos = try!(find_table_offset(data, fontstart, b"GPOS"))
+            .and_then(|offset| GPOS::from_data(&data, offset).ok());
+
+        let gsub = try!(find_table_offset(data, fontstart, b"GSUB"))
+            .and_then(|offset| GSUB::from_data(&data, offset).ok());
+
+        let vhea = try!(find_table_offset(data, fontstart, b"vhea"))
+            .and_then(|offset| VHEA::from_data(&data, offset).ok());
+
+        let vmtx = match (vhea.as_ref(), try!(find_table_offset(data, fontstart, b"vmtx"))) {
+            (Some(vhea), Some(offset)) => VMTX::from_data(&data, offset, vhea.num_of_long_ver_metrics(),
+                                                          maxp.num_glyphs()).ok(),
+            _ => None,
+        };
+
+        let cvt = try!(find_table_range(data, fontstart, b"cvt "))
+            .and_then(|(offset, size)| CVT::from_data(&data, offset, size).ok());
+
+        let fpgm = try!(find_table_range(data, fontstart, b"fpgm"))
+            .and_then(|(offset, size)| FPGM::from_data(&data, offset, size).ok());
+
+        let prep = try!(find_table_range(data, fontstart, b"prep"))
+            .and_then(|(offset, size)| PREP::from_data(&data, offset, size).ok());
+
+        let colr = try!(find_table_offset(data, fontstart, b"COLR"))
+            .and_then(|offset| COLR::from_data(&data, offset).ok());
+
+        let cpal = try!(find_table_offset(data, fontstart, b"CPAL"))
+            .and_then(|offset| CPAL::from_data(&data, offset).ok());
+
+        let cblc = try!(find_table_offset(data, fontstart, b"CBLC"))
+            .and_then(|offset| EBLC::from_data(&data, offset).ok());
+
+        let cbdt = try!(find_table_range(data, fontstart, b"CBDT"))
+            .and_then(|(offset, size)| EBDT::from_data(&data, offset, size).ok());
+
+        let eblc = try!(find_table_offset(data, fontstart, b"EBLC"))
+            .and_then(|offset| EBLC::from_data(&data, offset).ok());
+
+        let ebdt = try!(find_table_range(data, fontstart, b"EBDT"))
+            .and_then(|(offset, size)| EBDT::from_data(&data, offset, size).ok());
+
+        let sbix = try!(find_table_range(data, fontstart, b"sbix"))
+            .and_then(|(offset, size)| SBIX::from_data(&data, offset, size, maxp.num_glyphs() as usize).ok());
+
+        let svg = try!(find_table_range(data, fontstart, b"SVG "))
+            .and_then(|(offset, size)| SVG::from_data(&data, offset, size).ok());
+
+        let fvar = try!(find_table_offset(data, fontstart, b"fvar"))
+            .and_then(|offset| FVAR::from_data(&data, offset).ok());
+
+        let avar = try!(find_table_offset(data, fontstart, b"avar"))
+            .and_then(|offset| AVAR::from_data(&data, offset).ok());
+
+        let gvar = try!(find_table_range(data, fontstart, b"gvar"))
+            .and_then(|(offset, size)| GVAR::from_data(&data, offset, size).ok());
+        let coordinates = vec![0.0; fvar.as_ref().map_or(0, |fvar| fvar.axes().len())];
 
         let info = FontInfo {
             data: data,
             fontstart: fontstart,
             hhea: hhea,
             head: head,
+            maxp: maxp,
             hmtx: hmtx,
             loca: loca,
             cmap: cmap,
             glyf: glyf,
-            _glyf: _glyf,
+            name: name,
+            os2: os2,
+            post: post,
             kern: kern,
+            gpos: gpos,
+            gsub: gsub,
+            vhea: vhea,
+            vmtx: vmtx,
+            cff: cff,
+            cvt: cvt,
+            fpgm: fpgm,
+            prep: prep,
+            colr: colr,
+            cpal: cpal,
+            cblc: cblc,
+            cbdt: cbdt,
+            eblc: eblc,
+            ebdt: ebdt,
+            sbix: sbix,
+            svg: svg,
+            fvar: fvar,
+            avar: avar,
+            gvar: gvar,
+            coordinates: coordinates,
+            _glyf: _glyf,
         };
 
         Ok(info)
@@ -514,6 +762,788 @@ impl<'a> FontInfo<'a> {
         let offset = self.loca.offset_for_glyph_at_index(i).unwrap_or(0);
         self.glyf.glyph_data(offset)
     }
+
+    /// Returns the naming table of the font, if present.
+    pub fn name(&self) -> Option<&NAME> {
+        self.name.as_ref()
+    }
+
+    /// Returns the OS/2 and Windows specific metrics table of the font,
+    /// if present.
+    pub fn os2(&self) -> Option<&OS2> {
+        self.os2.as_ref()
+    }
+
+    /// Returns the PostScript table of the font, if present.
+    pub fn post(&self) -> Option<&POST> {
+        self.post.as_ref()
+    }
+
+    /// Returns the PostScript name of the glyph at index `i`.
+    ///
+    /// Returns `None` if the font doesn't contain glyph names.
+    pub fn glyph_name(&self, i: usize) -> Option<&str> {
+        self.post.as_ref().and_then(|post| post.glyph_name(i))
+    }
+
+    /// Returns the index of the glyph with the PostScript `name`.
+    pub fn glyph_index_for_name(&self, name: &str) -> Option<usize> {
+        self.post.as_ref().and_then(|post| post.glyph_index_for_name(name))
+    }
+
+    /// Returns the kerning table of the font, if present.
+    pub fn kern(&self) -> Option<&KERN> {
+        self.kern.as_ref()
+    }
+
+    /// Returns the glyph positioning table of the font, if present.
+    pub fn gpos(&self) -> Option<&GPOS> {
+        self.gpos.as_ref()
+    }
+
+    /// Returns the glyph substitution table of the font, if present.
+    pub fn gsub(&self) -> Option<&GSUB> {
+        self.gsub.as_ref()
+    }
+
+    /// Returns an additional amount to add to the advance of the glyph
+    /// at index `left` when it is followed by the glyph at index `right`.
+    ///
+    /// Pair adjustments of the `kern` feature of the `GPOS` table are used
+    /// if the font has them, otherwise the `kern` table is used.
+    /// The value is expressed in unscaled coordinates.
+    pub fn kerning(&self, left: usize, right: usize) -> i32 {
+        match self.gpos {
+            Some(ref gpos) if gpos.has_kerning() => gpos.kerning(left, right),
+            _ => self.kern.as_ref().map_or(0, |kern| kern.kerning(left, right)),
+        }
+    }
+
+    /// Returns metrics for laying out lines of text.
+    ///
+    /// The typographic metrics of the `OS/2` table are used if the font sets
+    /// the `USE_TYPO_METRICS` flag, otherwise metrics of the `hhea` table.
+    pub fn line_metrics(&self) -> LineMetrics {
+        match self.os2 {
+            Some(ref os2) if os2.use_typo_metrics() => LineMetrics {
+                ascent: os2.typo_ascender(),
+                descent: os2.typo_descender(),
+                line_gap: os2.typo_line_gap(),
+            },
+            _ => LineMetrics {
+                ascent: self.hhea.ascent(),
+                descent: self.hhea.descent(),
+                line_gap: self.hhea.line_gap(),
+            },
+        }
+    }
+
+    /// Returns the vertical header table of the font, if present.
+    pub fn vhea(&self) -> Option<&VHEA> {
+        self.vhea.as_ref()
+    }
+
+    /// Returns the vertical metrics table of the font, if present.
+    pub fn vmtx(&self) -> Option<&VMTX> {
+        self.vmtx.as_ref()
+    }
+
+    /// Returns the compact font format table of the font, if the font has
+    /// PostScript outlines.
+    pub fn cff(&self) -> Option<&CFF> {
+        self.cff.as_ref()
+    }
+
+    /// Returns the control value table of the font, if present.
+    pub fn cvt(&self) -> Option<&CVT> {
+        self.cvt.as_ref()
+    }
+
+    /// Returns the font program of the font, if present.
+    pub fn fpgm(&self) -> Option<&FPGM> {
+        self.fpgm.as_ref()
+    }
+
+    /// Returns the control value program of the font, if present.
+    pub fn prep(&self) -> Option<&PREP> {
+        self.prep.as_ref()
+    }
+
+    /// Returns the color table of the font, if present.
+    pub fn colr(&self) -> Option<&COLR> {
+        self.colr.as_ref()
+    }
+
+    /// Returns the color palette table of the font, if present.
+    pub fn cpal(&self) -> Option<&CPAL> {
+        self.cpal.as_ref()
+    }
+
+    /// Returns the color bitmap location table of the font, if present.
+    pub fn cblc(&self) -> Option<&EBLC> {
+        self.cblc.as_ref()
+    }
+
+    /// Returns the color bitmap data table of the font, if present.
+    pub fn cbdt(&self) -> Option<&EBDT> {
+        self.cbdt.as_ref()
+    }
+
+    /// Returns the embedded bitmap location table of the font, if present.
+    pub fn eblc(&self) -> Option<&EBLC> {
+        self.eblc.as_ref()
+    }
+
+    /// Returns the embedded bitmap data table of the font, if present.
+    pub fn ebdt(&self) -> Option<&EBDT> {
+        self.ebdt.as_ref()
+    }
+
+    /// Returns the standard bitmap graphics table of the font, if present.
+    pub fn sbix(&self) -> Option<&SBIX> {
+        self.sbix.as_ref()
+    }
+
+    /// Returns the SVG table of the font, if present.
+    pub fn svg(&self) -> Option<&SVG> {
+        self.svg.as_ref()
+    }
+
+    /// Returns the font variations table of the font, if present.
+    pub fn fvar(&self) -> Option<&FVAR> {
+        self.fvar.as_ref()
+    }
+
+    /// Returns the axis variations table of the font, if present.
+    pub fn avar(&self) -> Option<&AVAR> {
+        self.avar.as_ref()
+    }
+
+    /// Returns the glyph variations table of the font, if present.
+    pub fn gvar(&self) -> Option<&GVAR> {
+        self.gvar.as_ref()
+    }
+
+    /// Sets the position in the design space of a variable font, which
+    /// outlines of glyphs are varied to, from `coordinates` in user space,
+    /// e.g. `(*b"wght", 700.0)`.
+    ///
+    /// Axes not listed are set to their default values, unknown axes are
+    /// ignored. Values are clamped to the range of their axis.
+    pub fn set_variation_coordinates(&mut self, coordinates: &[([u8; 4], f32)]) {
+        let axes = match self.fvar {
+            Some(ref fvar) => fvar.axes(),
+            None => return,
+        };
+        let mut normalized = vec![0.0; axes.len()];
+        for &(tag, value) in coordinates {
+            if let Some(axis) = axes.iter().position(|axis| axis.tag == tag) {
+                let mut value = axes[axis].normalize(value);
+                if let Some(ref avar) = self.avar {
+                    value = avar.map(axis, value);
+                }
+                // Fonts store coordinates with 14 fractional bits.
+                normalized[axis] = (value.clamp(-1.0, 1.0) * 16384.0).round() / 16384.0;
+            }
+        }
+        self.coordinates = normalized;
+    }
+
+    /// Returns normalized variation coordinates, one for each axis of
+    /// the `fvar` table.
+    pub fn variation_coordinates(&self) -> &[f32] {
+        &self.coordinates
+    }
+
+    /// Returns metrics of the glyph at index `i` for vertical layout.
+    ///
+    /// Metrics of the `vmtx` table are used if the font has it, otherwise
+    /// glyphs are advanced by the distance between the typographic ascender
+    /// and descender of the `OS/2` table, or between the top and bottom of
+    /// the font bounding box, and placed below the ascender or the top.
+    pub fn vertical_metrics(&self, i: usize) -> VerticalMetrics {
+        if let Some(ref vmtx) = self.vmtx {
+            let metric = vmtx.vmetric_for_glyph_at_index(i);
+            return VerticalMetrics {
+                advance_height: metric.advance_height as i32,
+                top_side_bearing: metric.top_side_bearing as i32,
+            };
+        }
+
+        let (ascent, descent) = match self.os2 {
+            Some(ref os2) => (os2.typo_ascender(), os2.typo_descender()),
+            None => {
+                let bbox = self.head.bounding_box();
+                (bbox.y1, bbox.y0)
+            },
+        };
+        let top = self.glyph_bounding_box(i).map_or(0, |bbox| bbox.y1);
+        VerticalMetrics {
+            advance_height: ascent - descent,
+            top_side_bearing: ascent - top,
+        }
+    }
+
+    /// Returns the outline of the glyph at index `i` expressed in unscaled
+    /// coordinates.
+    ///
+    /// Composite glyphs are resolved into the outlines of their components.
+    /// The outline is empty if the font does not contain an outline for
+    /// the glyph (e.g. for the space character).
+    ///
+    /// # Errors
+    /// Returns error if the glyph data is malformed.
+    pub fn glyph_outline(&self, i: usize) -> Result<Outline> {
+        let mut outline = Outline::new();
+        try!(self.build_glyph_outline(i, &mut outline));
+        Ok(outline)
+    }
+
+    /// Decodes the outline of the glyph at index `i` directly into `builder`
+    /// without allocating intermediate data.
+    ///
+    /// Contours of composite glyphs are emitted with the transformations
+    /// of their components applied.
+    ///
+    /// # Errors
+    /// Returns error if the glyph data is malformed. Contours decoded before
+    /// the error was found are already passed to `builder`.
+    pub fn build_glyph_outline<B: OutlineBuilder>(&self, i: usize, builder: &mut B) -> Result<()> {
+        match self.cff {
+            Some(ref cff) => cff.build_outline(i, builder),
+            None => self.build_glyph(i, &Transform::identity(), builder, 0),
+        }
+    }
+
+    /// Returns the bounding box of the glyph at index `i` expressed in
+    /// unscaled coordinates, or `None` if the glyph has no outline.
+    ///
+    /// The box is taken from the `glyf` table for TrueType outlines and
+    /// computed from the outline for PostScript outlines and outlines varied
+    /// by variation coordinates.
+    pub fn glyph_bounding_box(&self, i: usize) -> Option<BBox> {
+        if self.cff.is_some() || self.varies_glyphs() {
+            self.glyph_outline(i).ok().and_then(|outline| outline.bounding_box())
+        } else {
+            self.loca.offset_for_glyph_at_index(i)
+                .and_then(|offset| self.glyf.glyph_data(offset).bounding_box())
+        }
+    }
+
+    fn build_glyph<B: OutlineBuilder>(&self, i: usize, transform: &Transform,
+        builder: &mut B, depth: usize) -> Result<()>
+    {
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(Error::Malformed);
+        }
+
+        let glyph_data = match self.loca.offset_for_glyph_at_index(i) {
+            Some(offset) => self.glyf.glyph_data(offset),
+            None => return Ok(()),
+        };
+
+        let deltas = try!(self.glyph_deltas(i, &glyph_data));
+        let delta = |n: usize| deltas.as_ref().map_or((0.0, 0.0), |deltas| deltas[n]);
+        if !glyph_data.is_composite() {
+            let mut contour = ContourBuilder::new();
+            for (n, (point, end)) in try!(glyph_data.point_iter()).enumerate() {
+                let (dx, dy) = delta(n);
+                let (x, y) = transform.apply(point.x + dx, point.y + dy);
+                contour.push(builder, x, y, point.on_curve);
+                if end {
+                    contour.close(builder);
+                }
+            }
+            return Ok(());
+        }
+
+        let mut placed = 0;
+        for (n, component) in glyph_data.components().enumerate() {
+            let component = try!(component);
+            let component_transform = try!(self.component_transform(i, &component, delta(n), placed, depth));
+            try!(self.build_glyph(component.glyph_index, &transform.combine(&component_transform),
+                                  builder, depth + 1));
+            placed += try!(self.glyph_point_count(component.glyph_index, depth + 1));
+        }
+        Ok(())
+    }
+
+    /// Renders the glyph at index `i` into an owned bitmap with antialiasing.
+    ///
+    /// If one of the scales is zero, the other one is used for both axes.
+    /// The bitmap is empty if the glyph has no outline or both scales
+    /// are zero.
+    ///
+    /// # Errors
+    /// Returns error if the glyph data is malformed.
+    pub fn render_glyph(&self, i: usize, scale_x: f32, scale_y: f32,
+        shift_x: f32, shift_y: f32) -> Result<GlyphBitmap>
+    {
+        let (scale_x, scale_y) = match effective_scale(scale_x, scale_y) {
+            Some(scale) => scale,
+            None => return Ok(GlyphBitmap::default()),
+        };
+        let bbox = match self.glyph_bitmap_box(i, scale_x, scale_y, shift_x, shift_y) {
+            Some(bbox) => bbox,
+            None => return Ok(GlyphBitmap::default()),
+        };
+
+        let width = (bbox.x1 - bbox.x0) as usize;
+        let height = (bbox.y1 - bbox.y0) as usize;
+        let mut bitmap = GlyphBitmap::new(width, height, bbox.x0, bbox.y0);
+        try!(self.render_glyph_into(i, scale_x, scale_y, shift_x, shift_y,
+                                    &mut bitmap.pixels, width, height, width, -bbox.x0, -bbox.y0));
+        Ok(bitmap)
+    }
+
+    /// Renders the glyph at index `i` into an owned RGB bitmap with subpixel
+    /// antialiasing for LCDs.
+    ///
+    /// The glyph is rendered at three times the resolution along the
+    /// subpixel axis, filtered with the FIR filter of `options` and each
+    /// subpixel goes to its channel. The bitmap is wider or taller than
+    /// the grayscale one by the radius of the filter. Scales and shifts are
+    /// the same as for `render_glyph`.
+    ///
+    /// # Errors
+    /// Returns error if the glyph data is malformed.
+    pub fn render_glyph_lcd(&self, i: usize, scale_x: f32, scale_y: f32,
+        shift_x: f32, shift_y: f32, options: &LcdOptions) -> Result<RgbBitmap>
+    {
+        let (scale_x, scale_y) = match effective_scale(scale_x, scale_y) {
+            Some(scale) => scale,
+            None => return Ok(RgbBitmap::default()),
+        };
+        let (scale_x, scale_y, shift_x, shift_y) = if options.is_horizontal() {
+            (scale_x * 3.0, scale_y, shift_x * 3.0, shift_y)
+        } else {
+            (scale_x, scale_y * 3.0, shift_x, shift_y * 3.0)
+        };
+        let bbox = match self.glyph_bitmap_box(i, scale_x, scale_y, shift_x, shift_y) {
+            Some(bbox) => bbox,
+            None => return Ok(RgbBitmap::default()),
+        };
+        lcd::render(bbox, options, |buffer, width, height, x, y| {
+            self.render_glyph_into(i, scale_x, scale_y, shift_x, shift_y, buffer, width, height, width, x, y)
+        })
+    }
+
+    /// Renders the glyph at index `i` into `buffer` with antialiasing.
+    ///
+    /// `buffer` holds `height` rows of `width` pixels, the rows start
+    /// `stride` bytes apart. The glyph origin is placed at `x`, `y` and
+    /// everything outside of the buffer is clipped. Only pixels inside
+    /// the bitmap box of the glyph are overwritten.
+    ///
+    /// # Errors
+    /// Returns error if the glyph data is malformed.
+    ///
+    /// # Panics
+    /// Panics if `stride` is less than `width` or `buffer` is too small.
+    pub fn render_glyph_into(&self, i: usize, scale_x: f32, scale_y: f32,
+        shift_x: f32, shift_y: f32, buffer: &mut [u8], width: usize, height: usize,
+        stride: usize, x: i32, y: i32) -> Result<()>
+    {
+        assert!(stride >= width, "stride is less than width");
+        assert!(height == 0 || buffer.len() >= (height - 1) * stride + width,
+                "buffer is too small");
+
+        let (scale_x, scale_y) = match effective_scale(scale_x, scale_y) {
+            Some(scale) => scale,
+            None => return Ok(()),
+        };
+        let bbox = match self.glyph_bitmap_box(i, scale_x, scale_y, shift_x, shift_y) {
+            Some(bbox) => bbox,
+            None => return Ok(()),
+        };
+        let outline = try!(self.glyph_outline(i));
+
+        // The bitmap box of the glyph in the buffer, clipped to its bounds.
+        let x0 = cmp::max(bbox.x0 + x, 0);
+        let y0 = cmp::max(bbox.y0 + y, 0);
+        let x1 = cmp::min(bbox.x1 + x, width as i32);
+        let y1 = cmp::min(bbox.y1 + y, height as i32);
+        if x0 >= x1 || y0 >= y1 || outline.is_empty() {
+            return Ok(());
+        }
+
+        let start = y0 as usize * stride + x0 as usize;
+        let mut gbm = Bitmap {
+            w: (x1 - x0) as isize,
+            h: (y1 - y0) as isize,
+            stride: stride as isize,
+            pixels: buffer[start..].as_mut_ptr(),
+        };
+        unsafe {
+            rasterize_outline(&mut gbm, &outline, scale_x, scale_y, shift_x, shift_y,
+                              (x0 - x) as isize, (y0 - y) as isize);
+        }
+        Ok(())
+    }
+
+    /// Renders a signed distance field of the glyph at index `i`.
+    ///
+    /// Distances are computed from the exact outline scaled by `scale`.
+    /// The bitmap box of the glyph is extended by `padding` pixels on each
+    /// side, a pixel on the edge gets `onedge_value` and the value changes
+    /// by `pixel_dist_scale` per pixel of distance, increasing inside.
+    /// Values are clamped to 0-255. The bitmap is empty if the glyph has
+    /// no outline or the scale is zero.
+    ///
+    /// # Errors
+    /// Returns error if the glyph data is malformed.
+    pub fn render_glyph_sdf(&self, i: usize, scale: f32, padding: i32, onedge_value: u8,
+        pixel_dist_scale: f32) -> Result<GlyphBitmap>
+    {
+        if scale == 0.0 {
+            return Ok(GlyphBitmap::default());
+        }
+        let bbox = match self.glyph_bitmap_box(i, scale, scale, 0.0, 0.0) {
+            Some(bbox) => bbox,
+            None => return Ok(GlyphBitmap::default()),
+        };
+        let outline = try!(self.glyph_outline(i));
+        if outline.is_empty() {
+            return Ok(GlyphBitmap::default());
+        }
+
+        let bbox = BBox {
+            x0: bbox.x0 - padding,
+            y0: bbox.y0 - padding,
+            x1: bbox.x1 + padding,
+            y1: bbox.y1 + padding,
+        };
+        Ok(sdf::distance_field(&outline, scale, bbox, onedge_value, pixel_dist_scale))
+    }
+
+    /// Renders a multi-channel signed distance field of the glyph at `i` into
+    /// a new RGB bitmap.
+    ///
+    /// The median of the red, green and blue values is above 127 inside of
+    /// the glyph and below it outside, sharp corners are kept when the field
+    /// is scaled up. `range` is the distance in pixels between the values 0
+    /// and 255, `padding` extends the bitmap on each side. Glyphs without
+    /// outlines produce an empty bitmap.
+    ///
+    /// # Errors
+    /// Returns error if the glyph data is malformed.
+    pub fn render_glyph_msdf(&self, i: usize, scale: f32, padding: i32, range: f32) -> Result<RgbBitmap> {
+        if scale == 0.0 {
+            return Ok(RgbBitmap::default());
+        }
+        let bbox = match self.glyph_bitmap_box(i, scale, scale, 0.0, 0.0) {
+            Some(bbox) => bbox,
+            None => return Ok(RgbBitmap::default()),
+        };
+        let outline = try!(self.glyph_outline(i));
+        if outline.is_empty() {
+            return Ok(RgbBitmap::default());
+        }
+
+        let bbox = BBox {
+            x0: bbox.x0 - padding,
+            y0: bbox.y0 - padding,
+            x1: bbox.x1 + padding,
+            y1: bbox.y1 + padding,
+        };
+        Ok(msdf::multi_channel_distance_field(&outline, scale, bbox, range))
+    }
+
+    /// Renders the glyph at index `i` grid-fitted by `hinter` into an owned
+    /// bitmap with antialiasing.
+    ///
+    /// The glyph is scaled to the size of `hinter`, which must be created
+    /// for this font. The bitmap is empty if the glyph has no outline.
+    ///
+    /// # Errors
+    /// Returns error if the glyph data is malformed or its instructions fail.
+    pub fn render_hinted_glyph(&self, hinter: &Hinter, i: usize,
+        shift_x: f32, shift_y: f32) -> Result<GlyphBitmap>
+    {
+        let glyph = try!(hinter.hint_glyph(self, i));
+        Ok(render_pixel_outline(&glyph.outline, shift_x, shift_y))
+    }
+
+    /// Renders the glyph at index `i` scaled by `scale` and grid-fitted by
+    /// `hinter` into an owned bitmap with antialiasing.
+    ///
+    /// Unlike `render_hinted_glyph`, instructions of the font are not used,
+    /// so this also works for fonts without them. The bitmap is empty if
+    /// the glyph has no outline or the scale is zero.
+    ///
+    /// # Errors
+    /// Returns error if the glyph data is malformed.
+    pub fn render_autohinted_glyph(&self, hinter: &AutoHinter, i: usize, scale: f32,
+        shift_x: f32, shift_y: f32) -> Result<GlyphBitmap>
+    {
+        if scale == 0.0 {
+            return Ok(GlyphBitmap::default());
+        }
+        let outline = try!(self.glyph_outline(i));
+        Ok(render_pixel_outline(&hinter.hint_outline(&outline, scale), shift_x, shift_y))
+    }
+
+    /// Renders the glyph at index `i` transformed by `transform` into an owned
+    /// bitmap with antialiasing.
+    ///
+    /// The transformation maps font units to pixels with y increasing up,
+    /// so `Transform([scale, 0.0, 0.0, scale, shift_x, -shift_y])` renders
+    /// like `render_glyph`. It can rotate, shear or mirror the glyph.
+    /// The bitmap is empty if the glyph has no outline.
+    ///
+    /// # Errors
+    /// Returns error if the glyph data is malformed.
+    pub fn render_glyph_transformed(&self, i: usize, transform: &Transform) -> Result<GlyphBitmap> {
+        let outline = try!(self.glyph_outline(i));
+        Ok(render_pixel_outline(&transform.apply_to_outline(&outline), 0.0, 0.0))
+    }
+
+    /// Returns the box of pixels touched by the glyph at index `i` transformed
+    /// by `transform` like in `render_glyph_transformed`, or `None` if
+    /// the glyph has no outline or its data is malformed.
+    ///
+    /// The box is computed from the transformed outline, so it is tight for
+    /// any transformation. Y increases down like in bitmaps.
+    pub fn glyph_bitmap_box_transformed(&self, i: usize, transform: &Transform) -> Option<BBox> {
+        let outline = match self.glyph_outline(i) {
+            Ok(outline) => transform.apply_to_outline(&outline),
+            Err(_) => return None,
+        };
+        outline.bounding_box().map(|bbox| BBox { x0: bbox.x0, y0: -bbox.y1, x1: bbox.x1, y1: -bbox.y0 })
+    }
+
+    /// Renders the color glyph at index `i` into an owned RGBA bitmap with
+    /// antialiasing, or returns `None` if the glyph has no color layers.
+    ///
+    /// Layer glyphs of the `COLR` table are filled with colors and gradients
+    /// of the `CPAL` palette at index `palette` (the first one if there is no
+    /// such palette) and composed. `foreground` is the color of the text, it
+    /// is used where the font refers to it. Scales and shifts are the same as
+    /// for `render_glyph`. The bitmap is empty if the glyph paints nothing.
+    ///
+    /// # Errors
+    /// Returns error if the glyph or color data is malformed.
+    pub fn render_color_glyph(&self, i: usize, scale_x: f32, scale_y: f32,
+        shift_x: f32, shift_y: f32, palette: usize, foreground: Color) -> Result<Option<RgbaBitmap>>
+    {
+        let colr = match self.colr {
+            Some(ref colr) if i <= u16::MAX as usize && colr.has_glyph(i as u16) => colr,
+            _ => return Ok(None),
+        };
+        let (scale_x, scale_y) = match effective_scale(scale_x, scale_y) {
+            Some(scale) => scale,
+            None => return Ok(Some(RgbaBitmap::default())),
+        };
+        let palette = match self.cpal {
+            Some(ref cpal) => cpal.palette(palette).or_else(|| cpal.palette(0)).unwrap_or(&[]),
+            None => &[],
+        };
+        let transform = Transform([scale_x, 0.0, 0.0, scale_y, shift_x, -shift_y]);
+        let bitmap = try!(color::render(colr, i as u16, palette, foreground, &transform,
+                                        &|glyph| self.glyph_outline(glyph as usize)));
+        Ok(Some(bitmap))
+    }
+
+    /// Returns the SVG document of the glyph at index `i`, or `None` if
+    /// the font has no document of the glyph.
+    ///
+    /// Documents compressed with gzip are decompressed. A document may
+    /// contain several glyphs, the glyph is the element with the `glyph{i}`
+    /// id.
+    ///
+    /// # Errors
+    /// Returns error if the document is malformed.
+    pub fn svg_document(&self, i: usize) -> Result<Option<Cow<'_, [u8]>>> {
+        match self.svg {
+            Some(ref svg) if i <= u16::MAX as usize => svg.document(i as u16),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the image embedded into the font for the glyph at index `i`
+    /// from the strike with the size nearest to `ppem` pixels per em, or
+    /// `None` if the font has no image of the glyph.
+    ///
+    /// Strikes of the `CBDT`, `sbix` and `EBDT` tables are searched. Of two
+    /// strikes as near as each other the larger one is taken, since images
+    /// look better scaled down than up. Strikes without an image of
+    /// the glyph are skipped.
+    ///
+    /// # Errors
+    /// Returns error if the image data is malformed.
+    pub fn embedded_image(&self, i: usize, ppem: u16) -> Result<Option<EmbeddedImage<'_>>> {
+        #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+        enum Source { Cbdt, Sbix, Ebdt }
+
+        if i > u16::MAX as usize {
+            return Ok(None);
+        }
+        let mut strikes = vec![];
+        if let (Some(cblc), Some(_)) = (self.cblc.as_ref(), self.cbdt.as_ref()) {
+            strikes.extend(cblc.ppems().into_iter().enumerate().map(|(i, size)| (size, Source::Cbdt, i)));
+        }
+        if let Some(sbix) = self.sbix.as_ref() {
+            strikes.extend(sbix.ppems().into_iter().enumerate().map(|(i, size)| (size, Source::Sbix, i)));
+        }
+        if let (Some(eblc), Some(_)) = (self.eblc.as_ref(), self.ebdt.as_ref()) {
+            strikes.extend(eblc.ppems().into_iter().enumerate().map(|(i, size)| (size, Source::Ebdt, i)));
+        }
+        strikes.sort_by_key(|&(size, source, _)| ((size as i32 - ppem as i32).abs(), size < ppem, source));
+
+        for (_, source, strike) in strikes {
+            let image = match source {
+                Source::Cbdt => match (self.cblc.as_ref(), self.cbdt.as_ref()) {
+                    (Some(cblc), Some(cbdt)) => try!(cbdt.image(cblc, strike, i as u16)),
+                    _ => None,
+                },
+                Source::Ebdt => match (self.eblc.as_ref(), self.ebdt.as_ref()) {
+                    (Some(eblc), Some(ebdt)) => try!(ebdt.image(eblc, strike, i as u16)),
+                    _ => None,
+                },
+                Source::Sbix => match self.sbix.as_ref() {
+                    Some(sbix) => try!(sbix.image(strike, i as u16)),
+                    None => None,
+                },
+            };
+            if image.is_some() {
+                return Ok(image);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the box of pixels touched by the glyph at index `i`, or `None`
+    /// if the glyph has no outline.
+    fn glyph_bitmap_box(&self, i: usize, scale_x: f32, scale_y: f32,
+        shift_x: f32, shift_y: f32) -> Option<BBox>
+    {
+        self.glyph_bounding_box(i).map(|bbox| {
+            BBox {
+                x0: (bbox.x0 as f32 * scale_x + shift_x).floor() as i32,
+                y0: (-bbox.y1 as f32 * scale_y + shift_y).floor() as i32,
+                x1: (bbox.x1 as f32 * scale_x + shift_x).ceil() as i32,
+                y1: (-bbox.y0 as f32 * scale_y + shift_y).ceil() as i32,
+            }
+        })
+    }
+
+    /// Returns the transformation of a `component` of the composite glyph
+    /// at index `i`, `delta` is the variation of its offset and `placed` is
+    /// the number of points of preceding components.
+    fn component_transform(&self, i: usize, component: &Component, delta: (f32, f32),
+        placed: usize, depth: usize) -> Result<Transform>
+    {
+        let m = component.matrix;
+        let (dx, dy) = match component.offset {
+            ComponentOffset::Offset { x, y, scaled: true, .. } => {
+                let (x, y) = (x + delta.0, y + delta.1);
+                (m[0] * x + m[2] * y, m[1] * x + m[3] * y)
+            },
+            ComponentOffset::Offset { x, y, scaled: false, .. } => (x + delta.0, y + delta.1),
+            ComponentOffset::MatchingPoints { parent, child } => {
+                // Only points of preceding components could be matched.
+                if parent >= placed {
+                    return Err(Error::Malformed);
+                }
+                let (px, py) = try!(self.glyph_point(i, parent, depth));
+                let (cx, cy) = try!(self.glyph_point(component.glyph_index, child, depth + 1));
+                (px - (m[0] * cx + m[2] * cy), py - (m[1] * cx + m[3] * cy))
+            },
+        };
+        Ok(Transform([m[0], m[1], m[2], m[3], dx, dy]))
+    }
+
+    /// Returns the point `n` of the glyph at index `i`.
+    fn glyph_point(&self, i: usize, n: usize, depth: usize) -> Result<(f32, f32)> {
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(Error::Malformed);
+        }
+
+        let glyph_data = match self.loca.offset_for_glyph_at_index(i) {
+            Some(offset) => self.glyf.glyph_data(offset),
+            None => return Err(Error::Malformed),
+        };
+
+        let deltas = try!(self.glyph_deltas(i, &glyph_data));
+        let delta = |n: usize| deltas.as_ref().map_or((0.0, 0.0), |deltas| deltas[n]);
+        if !glyph_data.is_composite() {
+            return match try!(glyph_data.point_iter()).nth(n) {
+                Some((point, _)) => {
+                    let (dx, dy) = delta(n);
+                    Ok((point.x + dx, point.y + dy))
+                },
+                None => Err(Error::Malformed),
+            };
+        }
+
+        let mut placed = 0;
+        for (index, component) in glyph_data.components().enumerate() {
+            let component = try!(component);
+            let count = try!(self.glyph_point_count(component.glyph_index, depth + 1));
+            if n < placed + count {
+                let transform = try!(self.component_transform(i, &component, delta(index), placed, depth));
+                let (x, y) = try!(self.glyph_point(component.glyph_index, n - placed, depth + 1));
+                return Ok(transform.apply(x, y));
+            }
+            placed += count;
+        }
+        Err(Error::Malformed)
+    }
+
+    /// Returns `true` if outlines of glyphs are varied by variation
+    /// coordinates other than the default ones.
+    fn varies_glyphs(&self) -> bool {
+        self.gvar.is_some() && self.coordinates.iter().any(|&coordinate| coordinate != 0.0)
+    }
+
+    /// Returns deltas of points of the glyph at index `i` at the current
+    /// variation coordinates, or `None` if the glyph doesn't vary. Composite
+    /// glyphs have a delta for the offset of each component.
+    fn glyph_deltas(&self, i: usize, glyph_data: &GlyphData) -> Result<Option<Vec<(f32, f32)>>> {
+        let gvar = match self.gvar {
+            Some(ref gvar) if self.varies_glyphs() => gvar,
+            _ => return Ok(None),
+        };
+
+        // Deltas are inferred from original points of contours only, so
+        // offsets of components are left zero, as are phantom points, which
+        // only vary metrics.
+        let mut points = vec![];
+        let mut ends = vec![];
+        if glyph_data.is_composite() {
+            for component in glyph_data.components() {
+                try!(component);
+                points.push((0.0, 0.0));
+            }
+        } else {
+            for (point, end) in try!(glyph_data.point_iter()) {
+                if end {
+                    ends.push(points.len());
+                }
+                points.push((point.x, point.y));
+            }
+        }
+        points.extend_from_slice(&[(0.0, 0.0); 4]);
+        gvar.deltas(i, &self.coordinates, &points, &ends)
+    }
+
+    /// Returns the number of points of the glyph at index `i`.
+    fn glyph_point_count(&self, i: usize, depth: usize) -> Result<usize> {
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(Error::Malformed);
+        }
+
+        let glyph_data = match self.loca.offset_for_glyph_at_index(i) {
+            Some(offset) => self.glyf.glyph_data(offset),
+            None => return Ok(0),
+        };
+
+        let mut count = glyph_data.number_of_points();
+        for component in glyph_data.components() {
+            count += try!(self.glyph_point_count(try!(component).glyph_index, depth + 1));
+        }
+        Ok(count)
+    }
 }
 
 //////////////////////////////////////////////////////////////////////////////
@@ -535,18 +1565,42 @@ impl<'a> FontInfo<'a> {
 pub enum Cmd {
   Move=1,
   Line=2,
-  Curve=3
+  Curve=3,
+  Cubic=4
 }
 
-type VertexType = i16;
 #[derive(Copy, Clone)]
 pub struct Vertex {
    x: i16,
    y: i16,
    cx: i16,
    cy: i16,
+   // the second control point of a cubic
+   cx1: i16,
+   cy1: i16,
    type_: Cmd,
-   flags: u8,
+}
+
+impl From<Segment> for Vertex {
+    fn from(segment: Segment) -> Vertex {
+        let (type_, x, y, cx, cy, cx1, cy1) = match segment {
+            Segment::MoveTo { x, y } => (Cmd::Move, x, y, 0.0, 0.0, 0.0, 0.0),
+            Segment::LineTo { x, y } => (Cmd::Line, x, y, 0.0, 0.0, 0.0, 0.0),
+            Segment::QuadTo { cx, cy, x, y } => (Cmd::Curve, x, y, cx, cy, 0.0, 0.0),
+            Segment::CurveTo { cx1, cy1, cx2, cy2, x, y } => (Cmd::Cubic, x, y, cx1, cy1, cx2, cy2),
+        };
+        // Flooring matches the `>> 1` used for implied on-curve points
+        // by the original shape decoder.
+        Vertex {
+            x: x.floor() as i16,
+            y: y.floor() as i16,
+            cx: cx.floor() as i16,
+            cy: cy.floor() as i16,
+            cx1: cx1.floor() as i16,
+            cy1: cy1.floor() as i16,
+            type_: type_,
+        }
+    }
 }
 
 // @TODO: don't expose this structure
@@ -629,12 +1683,6 @@ const STBTT_MAX_OVERSAMPLE: usize = 8;
 // on platforms that don't allow misaligned reads, if we want to allow
 // truetype fonts that aren't padded to alignment, define ALLOW_UNALIGNED_TRUETYPE
 
-macro_rules! ttCHAR {
-    ($p:expr) => {
-        *($p as *const i8)
-    }
-}
-
 // #define ttCHAR(p)     (* (stbtt_int8 *) (p))
 // TODO: Macro.
 // #define ttFixed(p)    ttLONG(p)
@@ -643,25 +1691,19 @@ macro_rules! ttCHAR {
 
 macro_rules! ttUSHORT {
     ($p:expr) => {
-        BigEndian::read_u16(slice::from_raw_parts($p, 2))
-    }
-}
-
-macro_rules! ttSHORT {
-    ($p:expr) => {
-        BigEndian::read_i16(slice::from_raw_parts($p, 2))
+        read_u16(slice::from_raw_parts($p, 2))
     }
 }
 
 macro_rules! ttULONG {
     ($p:expr) => {
-        BigEndian::read_u32(slice::from_raw_parts($p, 4))
+        read_u32(slice::from_raw_parts($p, 4))
     }
 }
 
 macro_rules! ttLONG {
     ($p:expr) => {
-        BigEndian::read_i32(slice::from_raw_parts($p, 4))
+        read_i32(slice::from_raw_parts($p, 4))
     }
 }
 
@@ -742,38 +1784,8 @@ pub unsafe fn stbtt_setvertex(
    (*v).y = y as i16;
    (*v).cx = cx as i16;
    (*v).cy = cy as i16;
-}
-
-pub unsafe fn close_shape(
-    vertices: *mut Vertex,
-    mut num_vertices: isize,
-    was_off: isize,
-    start_off: isize,
-    sx: i32,
-    sy: i32,
-    scx: i32,
-    scy: i32,
-    cx: i32,
-    cy: i32
-) -> isize {
-   if start_off != 0 {
-      if was_off != 0 {
-         stbtt_setvertex(vertices.offset(num_vertices),
-             Cmd::Curve, (cx+scx)>>1, (cy+scy)>>1, cx,cy);
-         num_vertices += 1;
-      }
-      stbtt_setvertex(vertices.offset(num_vertices), Cmd::Curve, sx,sy,scx,scy);
-      num_vertices += 1;
-   } else {
-      if was_off != 0 {
-         stbtt_setvertex(vertices.offset(num_vertices), Cmd::Curve,sx,sy,cx,cy);
-         num_vertices += 1;
-      } else {
-         stbtt_setvertex(vertices.offset(num_vertices), Cmd::Line,sx,sy,0,0);
-         num_vertices += 1;
-      }
-   }
-   return num_vertices;
+   (*v).cx1 = 0;
+   (*v).cy1 = 0;
 }
 
 // returns # of vertices and fills *vertices with the pointer to them
@@ -784,322 +1796,48 @@ pub unsafe fn close_shape(
 // STBTT_lineto and STBTT_curveto segments. A lineto
 // draws a line from previous endpoint to its x,y; a curveto
 // draws a quadratic bezier from previous endpoint to
-// its x,y, using cx,cy as the bezier control point.
+// its x,y, using cx,cy as the bezier control point. A cubic
+// draws a cubic bezier to its x,y using cx,cy and cx1,cy1 as
+// the control points, only glyphs of CFF fonts have cubics.
+//
+// Prefer `FontInfo::glyph_outline`, which doesn't need to be freed.
 pub unsafe fn get_glyph_shape(
     info: *const FontInfo,
     glyph_index: isize,
     pvertices: *mut *mut Vertex
 ) -> isize {
-   let number_of_contours: i16;
-   let end_pts_of_contours: *const u8;
-   let data: *const u8 = (*info).data.as_ptr();
-   let mut vertices: *mut Vertex=null_mut();
-   let mut num_vertices: isize =0;
-   let g = (*info).offset_for_glyph_at_index(glyph_index as usize).map(|c| c as isize).unwrap_or(-1);
-
    *pvertices = null_mut();
 
-   if g < 0 { return 0; }
-
-   number_of_contours = ttSHORT!(data.offset(g));
-
-   if number_of_contours > 0 {
-      let mut flags: u8 =0;
-      let mut flagcount: u8;
-      let ins: i32;
-      let mut j: i32 =0;
-      let m: i32;
-      let n: i32;
-      let mut next_move: i32;
-      let mut was_off: i32 =0;
-      let off: i32;
-      let mut start_off: i32 =0;
-      let mut x: i32;
-      let mut y: i32;
-      let mut cx: i32;
-      let mut cy: i32;
-      let mut sx: i32;
-      let mut sy: i32;
-      let mut scx: i32;
-      let mut scy: i32;
-      let mut points: *const u8;
-      end_pts_of_contours = data.offset(g + 10);
-      ins = ttUSHORT!(data.offset(g + 10 + number_of_contours as isize * 2)) as i32;
-      points = data.offset(g + 10 + number_of_contours as isize * 2 + 2 + ins as isize);
-
-      n = 1+ttUSHORT!(end_pts_of_contours.offset(number_of_contours as isize *2-2)) as i32;
-
-      m = n + 2*number_of_contours as i32;  // a loose bound on how many vertices we might need
-      vertices = STBTT_malloc!(m as usize * size_of::<Vertex>()) as *mut Vertex;
-      if vertices == null_mut() {
-         return 0;
-      }
-
-      next_move = 0;
-      flagcount=0;
-
-      // in first pass, we load uninterpreted data into the allocated array
-      // above, shifted to the end of the array so we won't overwrite it when
-      // we create our final data starting from the front
-
-      off = m - n; // starting offset for uninterpreted data, regardless of how m ends up being calculated
-
-      // first load flags
-
-      for i in 0..n {
-         if flagcount == 0 {
-            flags = *points;
-            points = points.offset(1);
-            if (flags & 8) != 0 {
-               flagcount = *points;
-               points = points.offset(1);
-            }
-         } else {
-            flagcount -= 1;
-         }
-         (*vertices.offset(off as isize +i as isize)).flags = flags;
-      }
-      // now load x coordinates
-      x=0;
-      for i in 0..n {
-         flags = (*vertices.offset(off as isize + i as isize)).flags;
-         if (flags & 2) != 0 {
-            let dx: i16 = *points as i16;
-            points = points.offset(1);
-            x += if (flags & 16) != 0 { dx as i32 } else { -dx as i32 }; // ???
-         } else {
-            if (flags & 16) == 0 {
-               x = x + BigEndian::read_i16(slice::from_raw_parts(points, 2)) as i32;
-               points = points.offset(2);
-            }
-         }
-         (*vertices.offset(off as isize +i as isize)).x = x as i16;
-      }
-
-      // now load y coordinates
-      y=0;
-      for i in 0..n {
-         flags = (*vertices.offset(off as isize + i as isize)).flags;
-         if (flags & 4) != 0 {
-            let dy: i16 = *points as i16;
-            points = points.offset(1);
-            y += if (flags & 32) != 0 { dy as i32 } else { -dy as i32 }; // ???
-         } else {
-            if (flags & 32) == 0 {
-               y = y + BigEndian::read_i16(slice::from_raw_parts(points, 2)) as i32;
-               points = points.offset(2);
-            }
-         }
-         (*vertices.offset(off as isize +i as isize)).y = y as i16;
-      }
-
-      // now convert them to our format
-      num_vertices=0;
-      sx = 0; sy = 0;
-      cx = 0; cy = 0;
-      scx = 0; scy = 0;
-      let mut i_iter = (0..n).into_iter();
-      let mut i = 0;
-      while { if let Some(v) = i_iter.next() { i = v; true } else { false } } {
-         flags = (*vertices.offset(off as isize +i as isize)).flags;
-         x     = (*vertices.offset(off as isize +i as isize)).x as i32;
-         y     = (*vertices.offset(off as isize +i as isize)).y as i32;
-         if next_move == i {
-            if i != 0 {
-               num_vertices = close_shape(vertices,
-                   num_vertices, was_off as isize, start_off as isize, sx,sy,scx,scy,cx,cy);
-            }
-
-            // now start the new one
-            start_off = (1 - (flags & 1)) as i32;
-            if start_off != 0 {
-               // if we start off with an off-curve point, then when we need to find a point on the curve
-               // where we can start, and we need to save some state for when we wraparound.
-               scx = x;
-               scy = y;
-               if (*vertices.offset(off as isize +i as isize +1)).type_ == Cmd::Line {
-                  // next point is also a curve point, so interpolate an on-point curve
-                  sx = (x + (*vertices.offset(off as isize +i as isize +1)).x as i32) >> 1;
-                  sy = (y + (*vertices.offset(off as isize +i as isize +1)).y as i32) >> 1;
-               } else {
-                  // otherwise just use the next point as our start point
-                  sx = (*vertices.offset(off as isize +i as isize +1)).x as i32;
-                  sy = (*vertices.offset(off as isize +i as isize +1)).y as i32;
-                  i_iter.next(); // we're using point i+1 as the starting point, so skip it
-               }
-            } else {
-               sx = x;
-               sy = y;
-            }
-            stbtt_setvertex(vertices.offset(num_vertices), Cmd::Move,sx,sy,0,0);
-            num_vertices += 1;
-            was_off = 0;
-            next_move = 1 + ttUSHORT!(end_pts_of_contours.offset(j as isize *2)) as i32;
-            j += 1;
-         } else {
-            if (flags & 1) == 0 { // if it's a curve
-               if was_off != 0 { // two off-curve control points in a row means interpolate an on-curve midpoint
-                  stbtt_setvertex(vertices.offset(num_vertices),
-                      Cmd::Curve, (cx+x)>>1, (cy+y)>>1, cx, cy);
-                  num_vertices += 1;
-               }
-               cx = x;
-               cy = y;
-               was_off = 1;
-            } else {
-               if was_off != 0 {
-                  stbtt_setvertex(vertices.offset(num_vertices), Cmd::Curve, x,y, cx, cy);
-                  num_vertices += 1;
-               } else {
-                  stbtt_setvertex(vertices.offset(num_vertices), Cmd::Line, x,y,0,0);
-                  num_vertices += 1;
-               }
-               was_off = 0;
-            }
-         }
-      }
-      num_vertices = close_shape(vertices, num_vertices, was_off as isize, start_off as isize, sx,sy,scx,scy,cx,cy);
-   } else if number_of_contours == -1 {
-      // Compound shapes.
-      let mut more: isize = 1;
-      let mut comp: *const u8 = data.offset(g + 10);
-      num_vertices = 0;
-      vertices = null_mut();
-      while more != 0 {
-         let flags: u16;
-         let gidx: u16;
-         let comp_num_verts: isize;
-         let mut comp_verts: *mut Vertex = null_mut();
-         let tmp: *mut Vertex;
-         let mut mtx: [f32; 6] = [1.0,0.0,0.0,1.0,0.0,0.0];
-         let m: f32;
-         let n: f32;
-
-         flags = ttSHORT!(comp) as u16; comp=comp.offset(2);
-         gidx = ttSHORT!(comp) as u16; comp=comp.offset(2);
-
-         if (flags & 2) != 0 { // XY values
-            if (flags & 1) != 0 { // shorts
-               mtx[4] = ttSHORT!(comp) as f32; comp=comp.offset(2);
-               mtx[5] = ttSHORT!(comp) as f32; comp=comp.offset(2);
-            } else {
-               mtx[4] = ttCHAR!(comp) as f32; comp=comp.offset(1);
-               mtx[5] = ttCHAR!(comp) as f32; comp=comp.offset(1);
-            }
-         }
-         else {
-            // @TODO handle matching point
-            unimplemented!();
-         }
-         if (flags & (1<<3)) != 0 { // WE_HAVE_A_SCALE
-             let v = ttSHORT!(comp) as f32 /16384.0; comp=comp.offset(2);
-            mtx[0] = v;
-            mtx[3] = v;
-            mtx[1] = 0.0;
-            mtx[2] = 0.0;
-         } else if (flags & (1<<6)) != 0 { // WE_HAVE_AN_X_AND_YSCALE
-            mtx[0] = ttSHORT!(comp) as f32 /16384.0; comp=comp.offset(2);
-            mtx[1] = 0.0;
-            mtx[2] = 0.0;
-            mtx[3] = ttSHORT!(comp) as f32 /16384.0; comp=comp.offset(2);
-         } else if (flags & (1<<7)) != 0 { // WE_HAVE_A_TWO_BY_TWO
-            mtx[0] = ttSHORT!(comp) as f32 /16384.0; comp=comp.offset(2);
-            mtx[1] = ttSHORT!(comp) as f32 /16384.0; comp=comp.offset(2);
-            mtx[2] = ttSHORT!(comp) as f32 /16384.0; comp=comp.offset(2);
-            mtx[3] = ttSHORT!(comp) as f32 /16384.0; comp=comp.offset(2);
-         }
+   let outline = match (*info).glyph_outline(glyph_index as usize) {
+      Ok(outline) => outline,
+      Err(_) => return 0,
+   };
+   if outline.is_empty() {
+      return 0;
+   }
 
-         // Find transformation scales.
-         m = (mtx[0]*mtx[0] + mtx[1]*mtx[1]).sqrt();
-         n = (mtx[2]*mtx[2] + mtx[3]*mtx[3]).sqrt();
-
-         // Get indexed glyph.
-         comp_num_verts = get_glyph_shape(info, gidx as isize, &mut comp_verts);
-         if comp_num_verts > 0 {
-            // Transform vertices.
-            for i in 0..comp_num_verts {
-               let v: *mut Vertex = comp_verts.offset(i);
-               let mut x: VertexType;
-               let mut y: VertexType;
-               x=(*v).x; y=(*v).y;
-               (*v).x = (m as f32 * (mtx[0]*x as f32 + mtx[2]*y as f32 + mtx[4])) as VertexType;
-               (*v).y = (n as f32 * (mtx[1]*x as f32 + mtx[3]*y as f32 + mtx[5])) as VertexType;
-               x=(*v).cx; y=(*v).cy;
-               (*v).cx = (m as f32 * (mtx[0]*x as f32 + mtx[2]*y as f32 + mtx[4])) as VertexType;
-               (*v).cy = (n as f32 * (mtx[1]*x as f32 + mtx[3]*y as f32 + mtx[5])) as VertexType;
-            }
-            // Append vertices.
-            tmp = STBTT_malloc!((num_vertices+comp_num_verts) as usize *size_of::<Vertex>())
-                as *mut Vertex;
-            if tmp == null_mut() {
-               if vertices != null_mut() { STBTT_free!(vertices as *mut c_void); }
-               if comp_verts != null_mut() { STBTT_free!(comp_verts as *mut c_void); }
-               return 0;
-            }
-            if num_vertices > 0 {
-                STBTT_memcpy(tmp, vertices,
-                    num_vertices as usize *size_of::<Vertex>());
-            }
-            STBTT_memcpy(tmp.offset(num_vertices), comp_verts,
-                comp_num_verts as usize *size_of::<Vertex>());
-            if vertices != null_mut() { STBTT_free!(vertices as *mut c_void); }
-            vertices = tmp;
-            STBTT_free!(comp_verts as *mut c_void);
-            num_vertices += comp_num_verts;
-         }
-         // More components ?
-         more = (flags & (1<<5)) as isize;
-      }
-   } else if number_of_contours < 0 {
-        // @TODO other compound variations?
-        unimplemented!();
-   } else {
-      // numberOfCounters == 0, do nothing
+   let vertices = STBTT_malloc!(outline.len() * size_of::<Vertex>()) as *mut Vertex;
+   if vertices.is_null() {
+      return 0;
+   }
+   for (i, segment) in outline.iter().enumerate() {
+      *vertices.offset(i as isize) = Vertex::from(*segment);
    }
 
    *pvertices = vertices;
-   return num_vertices;
+   outline.len() as isize
 }
 
+// Prefer `FontInfo::kerning`.
 pub unsafe fn get_glyph_kern_advance(
     info: *mut FontInfo,
     glyph1: isize,
     glyph2: isize
 ) -> isize {
-   let data: *const u8 = (*info).data.as_ptr().offset((*info).kern as isize);
-   let needle: u32;
-   let mut straw: u32;
-   let mut l: isize;
-   let mut r: isize;
-   let mut m: isize;
-
-   // we only look at the first table. it must be 'horizontal' and format 0.
-   if (*info).kern == 0 {
-      return 0;
-   }
-   if ttUSHORT!(data.offset(2)) < 1 { // number of tables, need at least 1
+   if glyph1 < 0 || glyph2 < 0 {
       return 0;
    }
-   if ttUSHORT!(data.offset(8)) != 1 { // horizontal flag must be set in format
-      return 0;
-   }
-
-   l = 0;
-   r = ttUSHORT!(data.offset(10)) as isize - 1;
-   needle = (glyph1 << 16 | glyph2) as u32;
-   while l <= r {
-      m = (l + r) >> 1;
-      straw = ttULONG!(data.offset(18+(m*6))); // note: unaligned read
-      if needle < straw {
-         r = m - 1;
-      }
-      else if needle > straw {
-         l = m + 1;
-      } else {
-         return ttSHORT!(data.offset(22+(m*6))) as isize;
-      }
-   }
-   return 0;
+   (*info).kerning(glyph1 as usize, glyph2 as usize) as isize
 }
 
 // an additional amount to add to the 'advance' value between ch1 and ch2
@@ -1108,10 +1846,12 @@ pub unsafe fn get_codepoint_kern_advance(
     ch1: isize,
     ch2: isize
 ) -> isize {
-    if (*info).kern == 0 { // if no kerning table, don't waste time looking up both codepoint->glyphs
+    if (*info).kern.is_none() && (*info).gpos.is_none() { // if no kerning table, don't waste time looking up both codepoint->glyphs
+      return 0;
+    }
+    if ch1 < 0 || ch2 < 0 {
       return 0;
     }
-    assert!(ch1 >= 0 && ch2 >= 0);
     let i1 = (*info).glyph_index_for_code(ch1 as usize) as isize;
     let i2 = (*info).glyph_index_for_code(ch2 as usize) as isize;
     get_glyph_kern_advance(info, i1, i2)
@@ -1713,7 +2453,8 @@ pub unsafe fn rasterize_sorted_edges(
 
       // insert all edges that start before the bottom of this scanline
       while (*e).y0 <= scan_y_bottom {
-         if (*e).y0 != (*e).y1 {
+         // edges ending above the first scanline are clipped away
+         if (*e).y0 != (*e).y1 && (*e).y1 > scan_y_top {
             let z: *mut ActiveEdge = new_active(
                 &mut hh, e, off_x, scan_y_top);
             STBTT_assert!((*z).ey >= scan_y_top);
@@ -1996,6 +2737,53 @@ pub unsafe fn tesselate_curve(
    return 1;
 }
 
+// tesselate until the distance to the curve is within the flatness, which is
+// bounded by the largest second difference of the control points
+pub unsafe fn tesselate_cubic(
+    points: *mut Point,
+    num_points: *mut isize,
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    x2: f32,
+    y2: f32,
+    x3: f32,
+    y3: f32,
+    objspace_flatness_squared: f32,
+    n: isize
+) -> isize {
+   let ax: f32 = x0 - 2.0*x1 + x2;
+   let ay: f32 = y0 - 2.0*y1 + y2;
+   let bx: f32 = x1 - 2.0*x2 + x3;
+   let by: f32 = y1 - 2.0*y2 + y3;
+   let dd: f32 = (ax*ax + ay*ay).max(bx*bx + by*by);
+   if n > 16 {
+      return 1;
+   }
+   if dd * (9.0/16.0) > objspace_flatness_squared {
+      // split in the middle
+      let x01: f32 = (x0+x1)/2.0;
+      let y01: f32 = (y0+y1)/2.0;
+      let x12: f32 = (x1+x2)/2.0;
+      let y12: f32 = (y1+y2)/2.0;
+      let x23: f32 = (x2+x3)/2.0;
+      let y23: f32 = (y2+y3)/2.0;
+      let xa: f32 = (x01+x12)/2.0;
+      let ya: f32 = (y01+y12)/2.0;
+      let xb: f32 = (x12+x23)/2.0;
+      let yb: f32 = (y12+y23)/2.0;
+      let mx: f32 = (xa+xb)/2.0;
+      let my: f32 = (ya+yb)/2.0;
+      tesselate_cubic(points, num_points, x0,y0, x01,y01, xa,ya, mx,my, objspace_flatness_squared,n+1);
+      tesselate_cubic(points, num_points, mx,my, xb,yb, x23,y23, x3,y3, objspace_flatness_squared,n+1);
+   } else {
+      add_point(points, *num_points,x3,y3);
+      *num_points = *num_points+1;
+   }
+   return 1;
+}
+
 // returns number of contours
 pub unsafe fn flatten_curves(
     vertices: *mut Vertex,
@@ -2071,6 +2859,15 @@ pub unsafe fn flatten_curves(
                x = (*vertices.offset(i)).x as f32;
                y = (*vertices.offset(i)).y as f32;
            }
+            Cmd::Cubic => {
+               tesselate_cubic(points, &mut num_points, x,y,
+                                        (*vertices.offset(i)).cx as f32, (*vertices.offset(i)).cy as f32,
+                                        (*vertices.offset(i)).cx1 as f32, (*vertices.offset(i)).cy1 as f32,
+                                        (*vertices.offset(i)).x as f32,  (*vertices.offset(i)).y as f32,
+                                        objspace_flatness_squared, 0);
+               x = (*vertices.offset(i)).x as f32;
+               y = (*vertices.offset(i)).y as f32;
+           }
          }
       }
       *(*contour_lengths).offset(n) = num_points - start;
@@ -2085,7 +2882,7 @@ pub unsafe fn flatten_curves(
    return null_mut();
 }
 
-// rasterize a shape with quadratic beziers into a bitmap
+// rasterize a shape with quadratic and cubic beziers into a bitmap
 pub unsafe fn rasterize(
     // 1-channel bitmap to draw into
     result: *mut Bitmap,
@@ -2120,12 +2917,30 @@ pub unsafe fn rasterize(
    }
 }
 
+// rasterizes an outline with the flatness used by the glyph rendering
+// functions, 'result' must point to pixels valid for its size and stride.
+unsafe fn rasterize_outline(
+    result: *mut Bitmap,
+    outline: &Outline,
+    scale_x: f32,
+    scale_y: f32,
+    shift_x: f32,
+    shift_y: f32,
+    x_off: isize,
+    y_off: isize
+) {
+   let mut vertices: Vec<Vertex> = outline.iter().map(|s| Vertex::from(*s)).collect();
+   rasterize(result, 0.35, vertices.as_mut_ptr(), vertices.len() as isize,
+       scale_x, scale_y, shift_x, shift_y, x_off, y_off, 1);
+}
+
 // frees the bitmap allocated below
 pub unsafe fn free_bitmap(bitmap: *mut u8)
 {
    STBTT_free!(bitmap as *mut c_void);
 }
 
+// Prefer `FontInfo::render_glyph`, which doesn't need to be freed.
 pub unsafe fn get_glyph_bitmap_subpixel(
     info: *const FontInfo,
     mut scale_x: f32,
@@ -2147,8 +2962,7 @@ pub unsafe fn get_glyph_bitmap_subpixel(
       scale_y = scale_x;
    }
 
-   let glyph_data = (*info).glyph_data_for_glyph_at_index(glyph as usize);
-   let bbox = glyph_data.bitmap_box_subpixel(scale_x, scale_y, shift_x, shift_y).unwrap_or_default();
+   let bbox = (*info).glyph_bitmap_box(glyph as usize, scale_x, scale_y, shift_x, shift_y).unwrap_or_default();
 
    // now we get the size
    let mut gbm = Bitmap
@@ -2195,6 +3009,7 @@ pub unsafe fn get_glyph_bitmap(
        0.0, 0.0, glyph, width, height, xoff, yoff);
 }
 
+// Prefer `FontInfo::render_glyph_into`, which checks the bounds of the output.
 pub unsafe fn make_glyph_bitmap_subpixel(
     info: *const FontInfo,
     output: *mut u8,
@@ -2210,8 +3025,7 @@ pub unsafe fn make_glyph_bitmap_subpixel(
    let mut vertices: *mut Vertex = null_mut();
    let num_verts: isize = get_glyph_shape(info, glyph, &mut vertices);
 
-   let glyph_data = (*info).glyph_data_for_glyph_at_index(glyph as usize);
-   let bbox = glyph_data.bitmap_box_subpixel(scale_x, scale_y, shift_x, shift_y).unwrap_or_default();
+   let bbox = (*info).glyph_bitmap_box(glyph as usize, scale_x, scale_y, shift_x, shift_y).unwrap_or_default();
 
    let mut gbm: Bitmap = Bitmap
    {
@@ -2351,8 +3165,7 @@ pub unsafe fn bake_font_bitmap(
 
    for i in 0..num_chars {
       let g = f.glyph_index_for_code((first_char + i) as usize) as isize;
-      let glyph_data = f.glyph_data_for_glyph_at_index(g as usize);
-      let bbox = glyph_data.bitmap_box(scale, scale).unwrap_or_default();
+      let bbox = f.glyph_bitmap_box(g as usize, scale, scale, 0.0, 0.0).unwrap_or_default();
       let metric = f.hmtx.hmetric_for_glyph_at_index(g as usize);
 
       let gw = (bbox.x1 - bbox.x0) as isize;
@@ -2571,6 +3384,7 @@ pub unsafe fn pack_begin(
    (*spc).stride_in_bytes = if stride_in_bytes != 0 { stride_in_bytes } else { pw };
    (*spc).h_oversample = 1;
    (*spc).v_oversample = 1;
+   (*spc).msdf_range = 0.0;
 
    stbrp_init_target(context, pw-padding, ph-padding, nodes, num_nodes);
 
@@ -2581,6 +3395,44 @@ pub unsafe fn pack_begin(
    return 1;
 }
 
+// Initializes a packing context like pack_begin, but the characters are
+// packed as multi-channel signed distance fields into a 3-channel RGB bitmap
+// that is width x height. stride_in_bytes is the distance from one row to the
+// next (or 0 to mean width*3). "range" is the distance in pixels between the
+// values 0 and 255 of the field, each character gets a border of half of it.
+// Oversampling is not used for distance fields.
+//
+// Returns 0 on failure, 1 on success.
+pub unsafe fn pack_begin_msdf(
+    spc: *mut PackContext,
+    pixels: *mut u8,
+    pw: isize,
+    ph: isize,
+    stride_in_bytes: isize,
+    padding: isize,
+    range: f32,
+    alloc_context: *const ()
+) -> isize
+{
+   let stride_in_bytes = if stride_in_bytes != 0 { stride_in_bytes } else { pw*3 };
+   if pack_begin(spc, null_mut(), pw, ph, stride_in_bytes, padding, alloc_context) == 0 {
+      return 0;
+   }
+   (*spc).pixels = pixels;
+   (*spc).msdf_range = range;
+
+   if pixels != null_mut() {
+      memset(pixels as *mut c_void, 0, (stride_in_bytes*ph) as usize);
+   }
+
+   return 1;
+}
+
+// The border around a distance field fitting distances up to range/2.
+fn msdf_border(range: f32) -> i32 {
+   (range * 0.5).ceil() as i32
+}
+
 // Cleans up the packing context and frees all memory.
 pub unsafe fn pack_end(spc: *mut PackContext)
 {
@@ -2792,8 +3644,9 @@ pub unsafe fn pack_font_ranges_gather_rects(
         } else {
             (*info).scale_for_mapping_em_to_pixels(-fh)
         };
-      (*ranges.offset(i)).h_oversample = (*spc).h_oversample as u8;
-      (*ranges.offset(i)).v_oversample = (*spc).v_oversample as u8;
+      let msdf = (*spc).msdf_range > 0.0;
+      (*ranges.offset(i)).h_oversample = if msdf { 1 } else { (*spc).h_oversample as u8 };
+      (*ranges.offset(i)).v_oversample = if msdf { 1 } else { (*spc).v_oversample as u8 };
       for j in 0..(*ranges.offset(i)).num_chars {
          let codepoint: isize = if (*ranges.offset(i)).array_of_unicode_codepoints == null() {
                 (*ranges.offset(i)).first_unicode_codepoint_in_range + j
@@ -2802,10 +3655,17 @@ pub unsafe fn pack_font_ranges_gather_rects(
              };
           assert!(codepoint >= 0);
          let glyph = (*info).glyph_index_for_code(codepoint as usize) as isize;
-         let glyph_data = (*info).glyph_data_for_glyph_at_index(glyph as usize);
-         let bbox = glyph_data.bitmap_box(
+         if msdf {
+            let bbox = (*info).glyph_bitmap_box(glyph as usize, scale, scale, 0.0, 0.0).unwrap_or_default();
+            let border = msdf_border((*spc).msdf_range);
+            (*rects.offset(k)).w = ((bbox.x1-bbox.x0+2*border) as isize + (*spc).padding) as Coord;
+            (*rects.offset(k)).h = ((bbox.y1-bbox.y0+2*border) as isize + (*spc).padding) as Coord;
+            k += 1;
+            continue;
+         }
+         let bbox = (*info).glyph_bitmap_box(glyph as usize,
             scale * (*spc).h_oversample as f32,
-            scale * (*spc).v_oversample as f32).unwrap_or_default();
+            scale * (*spc).v_oversample as f32, 0.0, 0.0).unwrap_or_default();
 
          (*rects.offset(k)).w = ((bbox.x1-bbox.x0) as isize + (*spc).padding as isize + (*spc).h_oversample as isize -1) as Coord;
          (*rects.offset(k)).h = ((bbox.y1-bbox.y0) as isize + (*spc).padding as isize + (*spc).v_oversample as isize -1) as Coord;
@@ -2870,31 +3730,43 @@ pub unsafe fn pack_font_ranges_render_into_rects(
             (*r).w -= pad;
             (*r).h -= pad;
 
-            let glyph_data = (*info).glyph_data_for_glyph_at_index(glyph as usize);
-            let bbox = glyph_data.bitmap_box(
+            let mut bbox = (*info).glyph_bitmap_box(glyph as usize,
                 scale * (*spc).h_oversample as f32,
-                scale * (*spc).v_oversample as f32).unwrap_or_default();
-
-            make_glyph_bitmap_subpixel(info,
-                                          (*spc).pixels.offset((*r).x + (*r).y*(*spc).stride_in_bytes),
-                                          (*r).w - (*spc).h_oversample as isize +1,
-                                          (*r).h - (*spc).v_oversample as isize +1,
-                                          (*spc).stride_in_bytes,
-                                          scale * (*spc).h_oversample as f32,
-                                          scale * (*spc).v_oversample as f32,
-                                          0.0,0.0,
-                                          glyph);
-
-            if (*spc).h_oversample > 1 {
-               h_prefilter((*spc).pixels.offset((*r).x + (*r).y*(*spc).stride_in_bytes),
-                                  (*r).w, (*r).h, (*spc).stride_in_bytes,
-                                  (*spc).h_oversample);
-            }
+                scale * (*spc).v_oversample as f32, 0.0, 0.0).unwrap_or_default();
+
+            if (*spc).msdf_range > 0.0 {
+               let border = msdf_border((*spc).msdf_range);
+               if let Ok(msdf) = (*info).render_glyph_msdf(glyph as usize, scale, border, (*spc).msdf_range) {
+                  let w = ::std::cmp::min(msdf.width, (*r).w as usize);
+                  for y in 0..::std::cmp::min(msdf.height, (*r).h as usize) {
+                     let dst = (*spc).pixels.offset((*r).x*3 + ((*r).y + y as isize)*(*spc).stride_in_bytes);
+                     ::std::ptr::copy_nonoverlapping(msdf.row(y).as_ptr(), dst, w*3);
+                  }
+               }
+               bbox.x0 -= border;
+               bbox.y0 -= border;
+            } else {
+               make_glyph_bitmap_subpixel(info,
+                                             (*spc).pixels.offset((*r).x + (*r).y*(*spc).stride_in_bytes),
+                                             (*r).w - (*spc).h_oversample as isize +1,
+                                             (*r).h - (*spc).v_oversample as isize +1,
+                                             (*spc).stride_in_bytes,
+                                             scale * (*spc).h_oversample as f32,
+                                             scale * (*spc).v_oversample as f32,
+                                             0.0,0.0,
+                                             glyph);
+
+               if (*spc).h_oversample > 1 {
+                  h_prefilter((*spc).pixels.offset((*r).x + (*r).y*(*spc).stride_in_bytes),
+                                     (*r).w, (*r).h, (*spc).stride_in_bytes,
+                                     (*spc).h_oversample);
+               }
 
-            if (*spc).v_oversample > 1 {
-               v_prefilter((*spc).pixels.offset((*r).x + (*r).y*(*spc).stride_in_bytes),
-                                  (*r).w, (*r).h, (*spc).stride_in_bytes,
-                                  (*spc).v_oversample);
+               if (*spc).v_oversample > 1 {
+                  v_prefilter((*spc).pixels.offset((*r).x + (*r).y*(*spc).stride_in_bytes),
+                                     (*r).w, (*r).h, (*spc).stride_in_bytes,
+                                     (*spc).v_oversample);
+               }
             }
 
             assert!(glyph >= 0);
@@ -3134,6 +4006,8 @@ pub unsafe fn compare_utf8_to_utf16_bigendian(
 //
 // returns results in whatever encoding you request... but note that 2-byte encodings
 // will be BIG-ENDIAN... use stbtt_CompareUTF8toUTF16_bigendian() to compare
+//
+// Prefer `FontInfo::name`, which decodes the strings.
 pub unsafe fn get_font_name_string(
     font: *const FontInfo,
     length: *mut isize,
@@ -0,0 +1,319 @@
+use bitmap::RgbBitmap;
+use outline::Outline;
+use sdf::{outline_contours, is_inside, cross, distance, dot, Edge, Point};
+use types::BBox;
+
+const RED: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 4;
+const YELLOW: u8 = RED | GREEN;
+const MAGENTA: u8 = RED | BLUE;
+const CYAN: u8 = GREEN | BLUE;
+const WHITE: u8 = RED | GREEN | BLUE;
+
+/// Sine of the angle between directions of two edges above which they meet
+/// at a corner, the angle is 3 radians.
+const CORNER_THRESHOLD: f32 = 0.141_12;
+
+/// An edge with a set of channels it contributes to.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct ColoredEdge {
+    edge: Edge,
+    color: u8,
+}
+
+/// Distance from a point to an edge.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct EdgeDistance {
+    /// Distance to the nearest point, positive inside.
+    distance: f32,
+    /// Cosine of the angle between the edge and the direction to the point,
+    /// used to choose between edges meeting at a corner.
+    dot: f32,
+    /// Parameter of the nearest point on the edge.
+    t: f32,
+}
+
+impl EdgeDistance {
+    fn is_closer_than(&self, other: &EdgeDistance) -> bool {
+        let (a, b) = (self.distance.abs(), other.distance.abs());
+        a < b || (a == b && self.dot < other.dot)
+    }
+}
+
+/// Computes a multi-channel signed distance field of the outline.
+///
+/// Edges of the outline are colored so that edges meeting at a corner never
+/// share two channels, then each channel stores the distance to the nearest
+/// edge of its color. The median of the channels reconstructs sharp corners
+/// when the field is scaled up. Distances are measured in pixels from the
+/// centers of pixels within `bbox`, `range` is the distance between values
+/// 0 and 255 with the edge at 127.5.
+pub fn multi_channel_distance_field(outline: &Outline, scale: f32, bbox: BBox, range: f32) -> RgbBitmap {
+    let width = (bbox.x1 - bbox.x0) as usize;
+    let height = (bbox.y1 - bbox.y0) as usize;
+    let mut bitmap = RgbBitmap::new(width, height, bbox.x0, bbox.y0);
+    let mut contours = outline_contours(outline, scale);
+    orient_contours(&mut contours);
+    let edges = contours.concat();
+    let colored = color_edges(contours);
+
+    let value = |distance: f32| ((distance / range + 0.5) * 255.0).clamp(0.0, 255.0) as u8;
+    for y in 0..height {
+        for x in 0..width {
+            let p = (bbox.x0 as f32 + x as f32 + 0.5, bbox.y0 as f32 + y as f32 + 0.5);
+            let mut nearest: [Option<(EdgeDistance, &Edge)>; 3] = [None; 3];
+            for colored in &colored {
+                let distance = edge_distance(&colored.edge, p);
+                for (channel, nearest) in nearest.iter_mut().enumerate() {
+                    if colored.color & (1 << channel) == 0 {
+                        continue;
+                    }
+                    let is_closer = match *nearest {
+                        Some((other, _)) => distance.is_closer_than(&other),
+                        None => true,
+                    };
+                    if is_closer {
+                        *nearest = Some((distance, &colored.edge));
+                    }
+                }
+            }
+
+            let mut channels = [-f32::MAX; 3];
+            for (channel, nearest) in channels.iter_mut().zip(&nearest) {
+                if let Some((distance, edge)) = *nearest {
+                    *channel = pseudo_distance(edge, p, distance);
+                }
+            }
+
+            // Corners of nearby contours can make the median lie about
+            // the inside, such pixels get the true distance instead.
+            let inside = is_inside(&edges, p);
+            if (median(channels) > 0.0) != inside {
+                let nearest = edges.iter().map(|edge| edge.distance(p)).fold(f32::MAX, f32::min);
+                channels = [if inside { nearest } else { -nearest }; 3];
+            }
+
+            let i = y * bitmap.stride + x * 3;
+            for (pixel, &channel) in bitmap.pixels[i..i + 3].iter_mut().zip(&channels) {
+                *pixel = value(channel);
+            }
+        }
+    }
+    bitmap
+}
+
+/// Returns the median of three values.
+pub fn median(values: [f32; 3]) -> f32 {
+    let [a, b, c] = values;
+    a.min(b).max(a.max(b).min(c))
+}
+
+/// Reverses contours so that the filled area is on the left of each edge,
+/// with y increasing down.
+fn orient_contours(contours: &mut [Vec<Edge>]) {
+    let edges = contours.concat();
+    for contour in contours.iter_mut() {
+        let edge = contour[0];
+        let (q, d) = (edge.point(0.5), normalize(edge.direction(0.5)));
+        let probe = (q.0 - d.1 * 1e-3, q.1 + d.0 * 1e-3);
+        if !is_inside(&edges, probe) {
+            contour.reverse();
+            for edge in contour.iter_mut() {
+                *edge = edge.reversed();
+            }
+        }
+    }
+}
+
+/// Colors edges of contours, switching the color at corners.
+fn color_edges(contours: Vec<Vec<Edge>>) -> Vec<ColoredEdge> {
+    let mut colored = vec![];
+    for mut edges in contours {
+        let mut corners = vec![];
+        let mut previous = edges[edges.len() - 1].direction(1.0);
+        for (i, edge) in edges.iter().enumerate() {
+            if is_corner(previous, edge.direction(0.0)) {
+                corners.push(i);
+            }
+            previous = edge.direction(1.0);
+        }
+
+        match corners.len() {
+            0 => {
+                colored.extend(edges.iter().map(|&edge| ColoredEdge { edge: edge, color: WHITE }));
+            },
+            1 => {
+                // A teardrop is split into three parts around the corner.
+                let colors = [CYAN, WHITE, MAGENTA];
+                edges.rotate_left(corners[0]);
+                if edges.len() < 3 {
+                    edges = edges.iter().flat_map(|edge| edge.split_in_thirds().to_vec()).collect();
+                }
+                let n = edges.len();
+                colored.extend(edges.iter().enumerate().map(|(i, &edge)| {
+                    ColoredEdge { edge: edge, color: colors[trichotomy(i, n)] }
+                }));
+            },
+            count => {
+                let mut color = switch_color(WHITE, 0);
+                let initial = color;
+                let mut spline = 0;
+                let n = edges.len();
+                for i in 0..n {
+                    let index = (corners[0] + i) % n;
+                    if spline + 1 < count && corners[spline + 1] == index {
+                        spline += 1;
+                        let banned = if spline == count - 1 { initial } else { 0 };
+                        color = switch_color(color, banned);
+                    }
+                    colored.push(ColoredEdge { edge: edges[index], color: color });
+                }
+            },
+        }
+    }
+    colored
+}
+
+/// Returns the next color of the cycle cyan, magenta, yellow that doesn't
+/// share two channels with `banned`.
+fn switch_color(color: u8, banned: u8) -> u8 {
+    let combined = color & banned;
+    if combined == RED || combined == GREEN || combined == BLUE {
+        return combined ^ WHITE;
+    }
+    match color {
+        CYAN => MAGENTA,
+        MAGENTA => YELLOW,
+        _ => CYAN,
+    }
+}
+
+/// Splits `n` positions into three nearly equal parts.
+fn trichotomy(position: usize, n: usize) -> usize {
+    (2.875 * position as f32 / (n - 1) as f32 - 1.4375 + 3.5) as usize - 2
+}
+
+fn is_corner(a: Point, b: Point) -> bool {
+    let (a, b) = (normalize(a), normalize(b));
+    dot(a, b) <= 0.0 || cross(a, b).abs() > CORNER_THRESHOLD
+}
+
+fn edge_distance(edge: &Edge, p: Point) -> EdgeDistance {
+    let t = edge.nearest(p);
+    let q = edge.point(t);
+    let d = normalize(edge.direction(t));
+    let v = (p.0 - q.0, p.1 - q.1);
+    let distance = distance(p, q);
+    let sign = if cross(d, v) >= 0.0 { 1.0 } else { -1.0 };
+    EdgeDistance {
+        distance: sign * distance,
+        dot: dot(d, normalize(v)).abs(),
+        t: t,
+    }
+}
+
+/// Extends the ends of the edge along their tangents, so that the distance
+/// field of a corner stays sharp.
+fn pseudo_distance(edge: &Edge, p: Point, nearest: EdgeDistance) -> f32 {
+    let (t, end) = match nearest.t {
+        t if t <= 0.0 => (0.0, -1.0),
+        t if t >= 1.0 => (1.0, 1.0),
+        _ => return nearest.distance,
+    };
+    let q = edge.point(t);
+    let d = normalize(edge.direction(t));
+    let v = (p.0 - q.0, p.1 - q.1);
+    if dot(v, d) * end > 0.0 {
+        let pseudo = cross(d, v);
+        if pseudo.abs() <= nearest.distance.abs() {
+            return pseudo;
+        }
+    }
+    nearest.distance
+}
+
+fn normalize(v: Point) -> Point {
+    let length = (v.0 * v.0 + v.1 * v.1).sqrt();
+    if length > 0.0 { (v.0 / length, v.1 / length) } else { (0.0, 0.0) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use outline::OutlineBuilder;
+    use expectest::prelude::*;
+
+    #[test]
+    fn edge_coloring() {
+        let mut outline = Outline::new();
+        outline.move_to(0.0, 0.0);
+        outline.line_to(10.0, 0.0);
+        outline.line_to(10.0, 10.0);
+        outline.line_to(0.0, 10.0);
+        outline.line_to(0.0, 0.0);
+        outline.close();
+        let colors: Vec<u8> = color_edges(outline_contours(&outline, 1.0)).iter().map(|e| e.color).collect();
+        expect!(colors).to(be_equal_to(vec![CYAN, MAGENTA, YELLOW, MAGENTA]));
+
+        // A circle has no corners.
+        let contours = vec![vec![Edge::Quad((10.0, 0.0), (10.0, 10.0), (0.0, 10.0)),
+                                 Edge::Quad((0.0, 10.0), (-10.0, 10.0), (-10.0, 0.0)),
+                                 Edge::Quad((-10.0, 0.0), (-10.0, -10.0), (0.0, -10.0)),
+                                 Edge::Quad((0.0, -10.0), (10.0, -10.0), (10.0, 0.0))]];
+        expect!(color_edges(contours).iter().all(|e| e.color == WHITE)).to(be_true());
+
+        // A single corner is split into thirds.
+        let contours = vec![vec![Edge::Quad((0.0, 0.0), (10.0, 0.0), (10.0, 5.0)),
+                                 Edge::Quad((10.0, 5.0), (10.0, 10.0), (0.0, 0.0))]];
+        let colors: Vec<u8> = color_edges(contours).iter().map(|e| e.color).collect();
+        expect!(colors).to(be_equal_to(vec![CYAN, CYAN, WHITE, WHITE, MAGENTA, MAGENTA]));
+        expect!((0..3).map(|i| trichotomy(i, 3)).collect::<Vec<_>>()).to(be_equal_to(vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn orientation() {
+        // The filled area is on the left of each edge with y increasing down.
+        let filled_left = vec![Edge::Line((0.0, 0.0), (10.0, 0.0)), Edge::Line((10.0, 0.0), (10.0, 10.0)),
+                               Edge::Line((10.0, 10.0), (0.0, 0.0))];
+        let mut contours = vec![filled_left.clone()];
+        orient_contours(&mut contours);
+        expect!(&contours[0]).to(be_equal_to(&filled_left));
+
+        let mut contours = vec![filled_left.iter().rev().map(|edge| edge.reversed()).collect()];
+        orient_contours(&mut contours);
+        expect!(&contours[0]).to(be_equal_to(&filled_left));
+    }
+
+    #[test]
+    fn square_field() {
+        let mut outline = Outline::new();
+        outline.move_to(0.0, 0.0);
+        outline.line_to(10.0, 0.0);
+        outline.line_to(10.0, 10.0);
+        outline.line_to(0.0, 10.0);
+        outline.line_to(0.0, 0.0);
+        outline.close();
+        let bbox = BBox { x0: -2, y0: -12, x1: 12, y1: 2 };
+        let bitmap = multi_channel_distance_field(&outline, 1.0, bbox, 4.0);
+        expect!((bitmap.width, bitmap.height)).to(be_equal_to((14, 14)));
+        let median = |x, y| {
+            let p = bitmap.pixel(x, y);
+            let mut p = [p[0], p[1], p[2]];
+            p.sort();
+            p[1]
+        };
+        for y in 0..14 {
+            for x in 0..14 {
+                let inside = (2..12).contains(&x) && (2..12).contains(&y);
+                expect!(median(x, y) > 127).to(be_equal_to(inside));
+            }
+        }
+        // Half a pixel inside of the edge.
+        expect!(median(6, 2)).to(be_equal_to(159));
+        // The center is saturated.
+        expect!(bitmap.pixel(6, 6)).to(be_equal_to([255, 255, 255]));
+        // Outside of the corner the pseudo-distance keeps the corner sharp.
+        expect!(median(1, 1)).to(be_equal_to(95));
+    }
+}
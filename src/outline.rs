@@ -0,0 +1,492 @@
+use std::slice;
+use std::vec;
+use types::BBox;
+
+/// A segment of a glyph outline.
+///
+/// Coordinates are expressed in unscaled font units with y increasing up.
+/// Each contour starts with `MoveTo` and is closed: its last segment ends
+/// at the point of the `MoveTo`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Segment {
+    /// Starts a new contour at `x`, `y`.
+    MoveTo { x: f32, y: f32 },
+    /// A straight line from the current point to `x`, `y`.
+    LineTo { x: f32, y: f32 },
+    /// A quadratic bezier from the current point to `x`, `y` using
+    /// `cx`, `cy` as the control point.
+    QuadTo { cx: f32, cy: f32, x: f32, y: f32 },
+    /// A cubic bezier from the current point to `x`, `y` using `cx1`, `cy1`
+    /// and `cx2`, `cy2` as control points.
+    CurveTo { cx1: f32, cy1: f32, cx2: f32, cy2: f32, x: f32, y: f32 },
+}
+
+impl Segment {
+    /// Returns the point where the segment ends.
+    pub fn end(&self) -> (f32, f32) {
+        match *self {
+            Segment::MoveTo { x, y } | Segment::LineTo { x, y } |
+            Segment::QuadTo { x, y, .. } | Segment::CurveTo { x, y, .. } => (x, y),
+        }
+    }
+}
+
+/// A receiver of glyph contours.
+///
+/// `FontInfo::build_glyph_outline` calls these methods while decoding a glyph,
+/// so the outline can be emitted straight into any path representation.
+/// Coordinates are expressed in unscaled font units with y increasing up.
+pub trait OutlineBuilder {
+    /// Starts a new contour at `x`, `y`.
+    fn move_to(&mut self, x: f32, y: f32);
+
+    /// Adds a straight line from the current point to `x`, `y`.
+    fn line_to(&mut self, x: f32, y: f32);
+
+    /// Adds a quadratic bezier from the current point to `x`, `y` using
+    /// `cx`, `cy` as the control point.
+    fn quad_to(&mut self, cx: f32, cy: f32, x: f32, y: f32);
+
+    /// Adds a cubic bezier from the current point to `x`, `y` using
+    /// `cx1`, `cy1` and `cx2`, `cy2` as control points.
+    ///
+    /// Only glyphs with PostScript outlines have cubic beziers.
+    fn curve_to(&mut self, cx1: f32, cy1: f32, cx2: f32, cy2: f32, x: f32, y: f32);
+
+    /// Closes the current contour.
+    ///
+    /// The current point is already equal to the start of the contour
+    /// when this method is called.
+    fn close(&mut self);
+}
+
+/// An owned outline of a glyph.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Outline {
+    segments: Vec<Segment>,
+}
+
+impl Outline {
+    /// Creates an empty outline.
+    pub fn new() -> Outline {
+        Outline::default()
+    }
+
+    /// Returns `true` if the outline has no segments.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the number of segments.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns all segments of the outline.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns an iterator over segments of the outline.
+    pub fn iter(&self) -> slice::Iter<'_, Segment> {
+        self.segments.iter()
+    }
+
+    /// Returns the exact bounding box of the outline rounded outwards to
+    /// whole units, or `None` if the outline is empty.
+    ///
+    /// Control points outside of the curves don't extend the box.
+    pub fn bounding_box(&self) -> Option<BBox> {
+        if self.segments.is_empty() {
+            return None;
+        }
+
+        let mut bounds = Bounds::default();
+        let mut current = (0.0, 0.0);
+        for segment in &self.segments {
+            match *segment {
+                Segment::MoveTo { x, y } | Segment::LineTo { x, y } => bounds.add(x, y),
+                Segment::QuadTo { cx, cy, x, y } => {
+                    let (x0, y0) = current;
+                    bounds.add(x, y);
+                    for t in quad_extrema(x0, cx, x).iter().chain(&quad_extrema(y0, cy, y)) {
+                        if let Some(t) = *t {
+                            let mt = 1.0 - t;
+                            bounds.add(mt * mt * x0 + 2.0 * mt * t * cx + t * t * x,
+                                       mt * mt * y0 + 2.0 * mt * t * cy + t * t * y);
+                        }
+                    }
+                },
+                Segment::CurveTo { cx1, cy1, cx2, cy2, x, y } => {
+                    let (x0, y0) = current;
+                    bounds.add(x, y);
+                    for t in cubic_extrema(x0, cx1, cx2, x).iter().chain(&cubic_extrema(y0, cy1, cy2, y)) {
+                        if let Some(t) = *t {
+                            let mt = 1.0 - t;
+                            let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+                            bounds.add(a * x0 + b * cx1 + c * cx2 + d * x,
+                                       a * y0 + b * cy1 + c * cy2 + d * y);
+                        }
+                    }
+                },
+            }
+            current = segment.end();
+        }
+
+        Some(BBox {
+            x0: bounds.x0.floor() as i32,
+            y0: bounds.y0.floor() as i32,
+            x1: bounds.x1.ceil() as i32,
+            y1: bounds.y1.ceil() as i32,
+        })
+    }
+}
+
+/// Bounds of points being accumulated.
+struct Bounds {
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl Default for Bounds {
+    fn default() -> Bounds {
+        Bounds { x0: f32::MAX, y0: f32::MAX, x1: f32::MIN, y1: f32::MIN }
+    }
+}
+
+impl Bounds {
+    fn add(&mut self, x: f32, y: f32) {
+        self.x0 = self.x0.min(x);
+        self.y0 = self.y0.min(y);
+        self.x1 = self.x1.max(x);
+        self.y1 = self.y1.max(y);
+    }
+}
+
+/// Returns the parameter inside of the quadratic bezier where the coordinate
+/// has its extremum.
+fn quad_extrema(p0: f32, p1: f32, p2: f32) -> [Option<f32>; 1] {
+    let d = p0 - 2.0 * p1 + p2;
+    [if d != 0.0 { inside((p0 - p1) / d) } else { None }]
+}
+
+/// Returns parameters inside of the cubic bezier where the coordinate
+/// has its extrema.
+fn cubic_extrema(p0: f32, p1: f32, p2: f32, p3: f32) -> [Option<f32>; 2] {
+    // Roots of the derivative divided by 3.
+    let a = -p0 + 3.0 * (p1 - p2) + p3;
+    let b = 2.0 * (p0 - 2.0 * p1 + p2);
+    let c = p1 - p0;
+    if a.abs() < 1e-6 {
+        return [if b != 0.0 { inside(-c / b) } else { None }, None];
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return [None, None];
+    }
+    let root = discriminant.sqrt();
+    [inside((-b + root) / (2.0 * a)), inside((-b - root) / (2.0 * a))]
+}
+
+fn inside(t: f32) -> Option<f32> {
+    if t > 0.0 && t < 1.0 { Some(t) } else { None }
+}
+
+impl OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.segments.push(Segment::MoveTo { x: x, y: y });
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.segments.push(Segment::LineTo { x: x, y: y });
+    }
+
+    fn quad_to(&mut self, cx: f32, cy: f32, x: f32, y: f32) {
+        self.segments.push(Segment::QuadTo { cx: cx, cy: cy, x: x, y: y });
+    }
+
+    fn curve_to(&mut self, cx1: f32, cy1: f32, cx2: f32, cy2: f32, x: f32, y: f32) {
+        self.segments.push(Segment::CurveTo { cx1: cx1, cy1: cy1, cx2: cx2, cy2: cy2, x: x, y: y });
+    }
+
+    fn close(&mut self) {}
+}
+
+impl IntoIterator for Outline {
+    type Item = Segment;
+    type IntoIter = vec::IntoIter<Segment>;
+
+    fn into_iter(self) -> vec::IntoIter<Segment> {
+        self.segments.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Outline {
+    type Item = &'a Segment;
+    type IntoIter = slice::Iter<'a, Segment>;
+
+    fn into_iter(self) -> slice::Iter<'a, Segment> {
+        self.segments.iter()
+    }
+}
+
+/// An affine transformation `[a, b, c, d, e, f]` which maps a point to
+/// `(a * x + c * y + e, b * x + d * y + f)`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Transform(pub [f32; 6]);
+
+impl Transform {
+    /// Returns the transformation which doesn't change anything.
+    pub fn identity() -> Transform {
+        Transform([1.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+    }
+
+    /// Returns a scaling by `sx` horizontally and `sy` vertically.
+    pub fn scale(sx: f32, sy: f32) -> Transform {
+        Transform([sx, 0.0, 0.0, sy, 0.0, 0.0])
+    }
+
+    /// Returns a translation by `tx`, `ty`.
+    pub fn translate(tx: f32, ty: f32) -> Transform {
+        Transform([1.0, 0.0, 0.0, 1.0, tx, ty])
+    }
+
+    /// Returns a counterclockwise rotation by `angle` radians around
+    /// the origin.
+    pub fn rotate(angle: f32) -> Transform {
+        let (sin, cos) = angle.sin_cos();
+        Transform([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    /// Returns a shear which moves points horizontally by `sx` times their
+    /// y and vertically by `sy` times their x. A horizontal shear of about
+    /// 0.2 makes a synthetic oblique.
+    pub fn shear(sx: f32, sy: f32) -> Transform {
+        Transform([1.0, sy, sx, 1.0, 0.0, 0.0])
+    }
+
+    /// Applies the transformation to a point.
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let m = &self.0;
+        (m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5])
+    }
+
+    /// Returns `outline` with the transformation applied to all points.
+    pub fn apply_to_outline(&self, outline: &Outline) -> Outline {
+        let mut transformed = Outline::new();
+        for segment in outline {
+            match *segment {
+                Segment::MoveTo { x, y } => {
+                    let (x, y) = self.apply(x, y);
+                    transformed.move_to(x, y);
+                },
+                Segment::LineTo { x, y } => {
+                    let (x, y) = self.apply(x, y);
+                    transformed.line_to(x, y);
+                },
+                Segment::QuadTo { cx, cy, x, y } => {
+                    let (cx, cy) = self.apply(cx, cy);
+                    let (x, y) = self.apply(x, y);
+                    transformed.quad_to(cx, cy, x, y);
+                },
+                Segment::CurveTo { cx1, cy1, cx2, cy2, x, y } => {
+                    let (cx1, cy1) = self.apply(cx1, cy1);
+                    let (cx2, cy2) = self.apply(cx2, cy2);
+                    let (x, y) = self.apply(x, y);
+                    transformed.curve_to(cx1, cy1, cx2, cy2, x, y);
+                },
+            }
+        }
+        transformed
+    }
+
+    /// Returns the transformation which applies `other` first and then `self`.
+    pub fn combine(&self, other: &Transform) -> Transform {
+        let (a, b) = (&self.0, &other.0);
+        Transform([
+            a[0] * b[0] + a[2] * b[1],
+            a[1] * b[0] + a[3] * b[1],
+            a[0] * b[2] + a[2] * b[3],
+            a[1] * b[2] + a[3] * b[3],
+            a[0] * b[4] + a[2] * b[5] + a[4],
+            a[1] * b[4] + a[3] * b[5] + a[5],
+        ])
+    }
+
+    /// Returns the transformation which undoes this one, or `None` if this
+    /// one collapses the plane into a line or a point.
+    pub fn invert(&self) -> Option<Transform> {
+        let m = &self.0;
+        let det = m[0] * m[3] - m[1] * m[2];
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let (a, b, c, d) = (m[3] / det, -m[1] / det, -m[2] / det, m[0] / det);
+        Some(Transform([a, b, c, d, -(a * m[4] + c * m[5]), -(b * m[4] + d * m[5])]))
+    }
+}
+
+/// Turns a sequence of on-curve and off-curve points of a contour into
+/// calls of an `OutlineBuilder`.
+///
+/// Consecutive off-curve points imply an on-curve point at their midpoint,
+/// and a contour may start with an off-curve point.
+#[derive(Debug, Default)]
+pub struct ContourBuilder {
+    // The point where the contour starts (and ends).
+    start: Option<(f32, f32)>,
+    // The first point, if the contour starts with an off-curve point.
+    first_off: Option<(f32, f32)>,
+    // A pending control point.
+    control: Option<(f32, f32)>,
+    current: (f32, f32),
+}
+
+impl ContourBuilder {
+    /// Creates a builder for a new contour.
+    pub fn new() -> ContourBuilder {
+        ContourBuilder::default()
+    }
+
+    /// Adds the next point of the contour.
+    pub fn push<B: OutlineBuilder>(&mut self, builder: &mut B, x: f32, y: f32, on_curve: bool) {
+        if self.start.is_none() {
+            if on_curve {
+                self.move_to(builder, x, y);
+            } else if let Some((fx, fy)) = self.first_off {
+                // Two off-curve points in a row, start in the middle of them.
+                self.move_to(builder, (fx + x) / 2.0, (fy + y) / 2.0);
+                self.control = Some((x, y));
+            } else {
+                self.first_off = Some((x, y));
+            }
+            return;
+        }
+
+        match (self.control, on_curve) {
+            (Some((cx, cy)), true) => {
+                self.quad_to(builder, cx, cy, x, y);
+                self.control = None;
+            },
+            (Some((cx, cy)), false) => {
+                // Two off-curve points in a row imply an on-curve midpoint.
+                self.quad_to(builder, cx, cy, (cx + x) / 2.0, (cy + y) / 2.0);
+                self.control = Some((x, y));
+            },
+            (None, true) => self.line_to(builder, x, y),
+            (None, false) => self.control = Some((x, y)),
+        }
+    }
+
+    /// Finishes the contour, the builder is ready for the next contour.
+    pub fn close<B: OutlineBuilder>(&mut self, builder: &mut B) {
+        let (sx, sy) = match self.start {
+            Some(start) => start,
+            None => {
+                *self = ContourBuilder::new();
+                return;
+            },
+        };
+        if let Some((fx, fy)) = self.first_off.take() {
+            self.push(builder, fx, fy, false);
+        }
+        if let Some((cx, cy)) = self.control.take() {
+            self.quad_to(builder, cx, cy, sx, sy);
+        } else if self.current != (sx, sy) {
+            self.line_to(builder, sx, sy);
+        }
+        builder.close();
+        *self = ContourBuilder::new();
+    }
+
+    fn move_to<B: OutlineBuilder>(&mut self, builder: &mut B, x: f32, y: f32) {
+        builder.move_to(x, y);
+        self.start = Some((x, y));
+        self.current = (x, y);
+    }
+
+    fn line_to<B: OutlineBuilder>(&mut self, builder: &mut B, x: f32, y: f32) {
+        builder.line_to(x, y);
+        self.current = (x, y);
+    }
+
+    fn quad_to<B: OutlineBuilder>(&mut self, builder: &mut B, cx: f32, cy: f32, x: f32, y: f32) {
+        builder.quad_to(cx, cy, x, y);
+        self.current = (x, y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Segment::*;
+
+    fn contour(points: &[(f32, f32, bool)]) -> Outline {
+        let mut outline = Outline::new();
+        let mut contour = ContourBuilder::new();
+        for &(x, y, on_curve) in points {
+            contour.push(&mut outline, x, y, on_curve);
+        }
+        contour.close(&mut outline);
+        outline
+    }
+
+    #[test]
+    fn contour_of_on_curve_points() {
+        let outline = contour(&[(0.0, 0.0, true), (10.0, 0.0, true), (10.0, 10.0, true)]);
+        assert_eq!(outline.segments(), &[
+            MoveTo { x: 0.0, y: 0.0 },
+            LineTo { x: 10.0, y: 0.0 },
+            LineTo { x: 10.0, y: 10.0 },
+            LineTo { x: 0.0, y: 0.0 },
+        ]);
+    }
+
+    #[test]
+    fn contour_starting_off_curve() {
+        let outline = contour(&[(0.0, 10.0, false), (10.0, 10.0, false),
+                                (10.0, 0.0, true), (0.0, 0.0, true)]);
+        assert_eq!(outline.segments(), &[
+            MoveTo { x: 5.0, y: 10.0 },
+            QuadTo { cx: 10.0, cy: 10.0, x: 10.0, y: 0.0 },
+            LineTo { x: 0.0, y: 0.0 },
+            QuadTo { cx: 0.0, cy: 10.0, x: 5.0, y: 10.0 },
+        ]);
+    }
+
+    #[test]
+    fn bounding_box_of_curves() {
+        let mut outline = Outline::new();
+        outline.move_to(0.0, 0.0);
+        outline.quad_to(5.0, 10.0, 10.0, 0.0);
+        outline.curve_to(10.0, -8.0, 0.0, -8.0, 0.0, 0.0);
+        outline.close();
+        // Extrema of the curves are at the middle: y = 5 and y = -6.
+        assert_eq!(outline.bounding_box(), Some(BBox { x0: 0, y0: -6, x1: 10, y1: 5 }));
+        assert_eq!(Outline::new().bounding_box(), None);
+    }
+
+    #[test]
+    fn combine_transforms() {
+        let scale = Transform([2.0, 0.0, 0.0, 2.0, 0.0, 0.0]);
+        let translate = Transform([1.0, 0.0, 0.0, 1.0, 3.0, 4.0]);
+        assert_eq!(scale.combine(&translate).apply(1.0, 1.0), (8.0, 10.0));
+        assert_eq!(translate.combine(&scale).apply(1.0, 1.0), (5.0, 6.0));
+        assert_eq!(Transform::identity().combine(&scale), scale);
+    }
+
+    #[test]
+    fn transform_constructors() {
+        assert_eq!(Transform::scale(2.0, 3.0).apply(1.0, 1.0), (2.0, 3.0));
+        assert_eq!(Transform::translate(2.0, 3.0).apply(1.0, 1.0), (3.0, 4.0));
+        assert_eq!(Transform::shear(0.5, 0.0).apply(1.0, 2.0), (2.0, 2.0));
+        let (x, y) = Transform::rotate(::std::f32::consts::PI / 2.0).apply(1.0, 0.0);
+        assert!(x.abs() < 1e-6 && (y - 1.0).abs() < 1e-6);
+
+        let transform = Transform([2.0, 1.0, 1.0, 1.0, 3.0, -4.0]);
+        assert_eq!(transform.invert().unwrap().apply(7.0, -1.0), (1.0, 2.0));
+        assert_eq!(Transform::scale(0.0, 1.0).invert(), None);
+    }
+}
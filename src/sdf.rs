@@ -0,0 +1,349 @@
+use bitmap::GlyphBitmap;
+use outline::{Outline, Segment};
+use types::BBox;
+
+/// A point in pixel space.
+pub type Point = (f32, f32);
+
+/// An edge of a contour in pixel space with y increasing down.
+///
+/// Quadratic edges are monotonic in y, so a horizontal ray crosses
+/// each edge at most once.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Edge {
+    Line(Point, Point),
+    Quad(Point, Point, Point),
+}
+
+impl Edge {
+    /// Returns the point of the edge at `t`.
+    pub fn point(&self, t: f32) -> Point {
+        match *self {
+            Edge::Line(a, b) => lerp(a, b, t),
+            Edge::Quad(p0, p1, p2) => quad_point(p0, p1, p2, t),
+        }
+    }
+
+    /// Returns the direction of the edge at `t`, which is not normalized.
+    pub fn direction(&self, t: f32) -> Point {
+        match *self {
+            Edge::Line(a, b) => (b.0 - a.0, b.1 - a.1),
+            Edge::Quad(p0, p1, p2) => {
+                let d = lerp((p1.0 - p0.0, p1.1 - p0.1), (p2.0 - p1.0, p2.1 - p1.1), t);
+                // The control point could be at an end.
+                if d == (0.0, 0.0) { (p2.0 - p0.0, p2.1 - p0.1) } else { d }
+            },
+        }
+    }
+
+    /// Returns the same edge going in the opposite direction.
+    pub fn reversed(&self) -> Edge {
+        match *self {
+            Edge::Line(a, b) => Edge::Line(b, a),
+            Edge::Quad(p0, p1, p2) => Edge::Quad(p2, p1, p0),
+        }
+    }
+
+    /// Splits the edge into three edges of equal parameter ranges.
+    pub fn split_in_thirds(&self) -> [Edge; 3] {
+        match *self {
+            Edge::Line(a, b) => {
+                let (p, q) = (lerp(a, b, 1.0 / 3.0), lerp(a, b, 2.0 / 3.0));
+                [Edge::Line(a, p), Edge::Line(p, q), Edge::Line(q, b)]
+            },
+            Edge::Quad(p0, p1, p2) => {
+                let (p, q) = (self.point(1.0 / 3.0), self.point(2.0 / 3.0));
+                [Edge::Quad(p0, lerp(p0, p1, 1.0 / 3.0), p),
+                 Edge::Quad(p, lerp(lerp(p0, p1, 5.0 / 9.0), lerp(p1, p2, 4.0 / 9.0), 0.5), q),
+                 Edge::Quad(q, lerp(p1, p2, 2.0 / 3.0), p2)]
+            },
+        }
+    }
+
+    /// Returns the parameter of the nearest point of the edge to `p`.
+    pub fn nearest(&self, p: Point) -> f32 {
+        match *self {
+            Edge::Line(a, b) => {
+                let d = (b.0 - a.0, b.1 - a.1);
+                let length = dot(d, d);
+                if length > 0.0 {
+                    (dot((p.0 - a.0, p.1 - a.1), d) / length).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                }
+            },
+            Edge::Quad(p0, p1, p2) => {
+                // The nearest point is at an end or where the derivative of
+                // the squared distance is zero, which is a cubic in t.
+                let a = (p1.0 - p0.0, p1.1 - p0.1);
+                let b = (p0.0 - 2.0 * p1.0 + p2.0, p0.1 - 2.0 * p1.1 + p2.1);
+                let d = (p0.0 - p.0, p0.1 - p.1);
+                let roots = solve_cubic(dot(b, b), 3.0 * dot(a, b), 2.0 * dot(a, a) + dot(d, b), dot(d, a));
+                let mut nearest = if distance(p, p0) <= distance(p, p2) { 0.0 } else { 1.0 };
+                let mut nearest_distance = distance(p, self.point(nearest));
+                for &t in roots.iter().filter(|&&t| t > 0.0 && t < 1.0) {
+                    let distance = distance(p, self.point(t));
+                    if distance < nearest_distance {
+                        nearest = t;
+                        nearest_distance = distance;
+                    }
+                }
+                nearest
+            },
+        }
+    }
+
+    /// Returns the distance from `p` to the nearest point of the edge.
+    pub fn distance(&self, p: Point) -> f32 {
+        distance(p, self.point(self.nearest(p)))
+    }
+
+    /// Returns the winding of the edge around `p`, i.e. `1` or `-1` if
+    /// the edge crosses the ray from `p` to the right going down or up.
+    pub fn winding(&self, p: Point) -> i32 {
+        let (y0, y1) = match *self {
+            Edge::Line(a, b) => (a.1, b.1),
+            Edge::Quad(a, _, b) => (a.1, b.1),
+        };
+        // Ends are treated as half-open, so a ray through a vertex crosses
+        // only one of its edges.
+        if (y0 <= p.1) == (y1 <= p.1) {
+            return 0;
+        }
+
+        let x = match *self {
+            Edge::Line(a, b) => a.0 + (p.1 - a.1) / (b.1 - a.1) * (b.0 - a.0),
+            Edge::Quad(p0, p1, p2) => {
+                let roots = solve_quadratic(p0.1 - 2.0 * p1.1 + p2.1, 2.0 * (p1.1 - p0.1), p0.1 - p.1);
+                let t = roots.iter().cloned().find(|t| (0.0..=1.0).contains(t))
+                    .unwrap_or(if roots.is_empty() { 0.0 } else { roots[0].clamp(0.0, 1.0) });
+                quad_point(p0, p1, p2, t).0
+            },
+        };
+        if x <= p.0 {
+            0
+        } else if y1 > y0 {
+            1
+        } else {
+            -1
+        }
+    }
+}
+
+/// Converts contours of the outline to edges in pixel space.
+///
+/// Quadratic beziers are split where they turn vertically and cubic beziers
+/// are approximated by two quadratic beziers. Empty lines are skipped.
+pub fn outline_contours(outline: &Outline, scale: f32) -> Vec<Vec<Edge>> {
+    let mut contours = vec![];
+    let mut edges = vec![];
+    let mut current = (0.0, 0.0);
+    let point = |x: f32, y: f32| (x * scale, -y * scale);
+    for segment in outline {
+        match *segment {
+            Segment::MoveTo { .. } => {
+                if !edges.is_empty() {
+                    contours.push(edges);
+                    edges = vec![];
+                }
+            },
+            Segment::LineTo { x, y } => if current != point(x, y) {
+                edges.push(Edge::Line(current, point(x, y)));
+            },
+            Segment::QuadTo { cx, cy, x, y } => push_quad(&mut edges, current, point(cx, cy), point(x, y)),
+            Segment::CurveTo { cx1, cy1, cx2, cy2, x, y } => {
+                let (p0, p1, p2, p3) = (current, point(cx1, cy1), point(cx2, cy2), point(x, y));
+                let a = lerp(p0, p1, 0.5);
+                let b = lerp(p1, p2, 0.5);
+                let c = lerp(p2, p3, 0.5);
+                let (ab, bc) = (lerp(a, b, 0.5), lerp(b, c, 0.5));
+                let m = lerp(ab, bc, 0.5);
+                // The control point of a quadratic close to a cubic is
+                // (3 * (c1 + c2) - (p0 + p3)) / 4.
+                let control = |p0: Point, c1: Point, c2: Point, p3: Point| {
+                    ((3.0 * (c1.0 + c2.0) - p0.0 - p3.0) / 4.0, (3.0 * (c1.1 + c2.1) - p0.1 - p3.1) / 4.0)
+                };
+                push_quad(&mut edges, p0, control(p0, a, ab, m), m);
+                push_quad(&mut edges, m, control(m, bc, c, p3), p3);
+            },
+        }
+        current = point(segment.end().0, segment.end().1);
+    }
+    if !edges.is_empty() {
+        contours.push(edges);
+    }
+    contours
+}
+
+/// Returns `true` if `p` is inside of the edges by the nonzero winding rule.
+pub fn is_inside(edges: &[Edge], p: Point) -> bool {
+    edges.iter().map(|edge| edge.winding(p)).sum::<i32>() != 0
+}
+
+/// Renders a signed distance field of the outline scaled by `scale` into
+/// the box of pixels `bbox`.
+///
+/// Pixels are sampled at their centers. The distance is positive inside
+/// of the outline by the nonzero winding rule, so holes of nested contours
+/// are outside.
+pub fn distance_field(outline: &Outline, scale: f32, bbox: BBox, onedge_value: u8,
+    pixel_dist_scale: f32) -> GlyphBitmap
+{
+    let width = (bbox.x1 - bbox.x0) as usize;
+    let height = (bbox.y1 - bbox.y0) as usize;
+    let mut bitmap = GlyphBitmap::new(width, height, bbox.x0, bbox.y0);
+    let edges = outline_contours(outline, scale).concat();
+
+    for y in 0..height {
+        for x in 0..width {
+            let p = (bbox.x0 as f32 + x as f32 + 0.5, bbox.y0 as f32 + y as f32 + 0.5);
+            let nearest = edges.iter().map(|edge| edge.distance(p)).fold(f32::MAX, f32::min);
+            let signed = if is_inside(&edges, p) { nearest } else { -nearest };
+            let value = onedge_value as f32 + pixel_dist_scale * signed;
+            bitmap.pixels[y * width + x] = value.clamp(0.0, 255.0) as u8;
+        }
+    }
+    bitmap
+}
+
+/// Adds edges of a quadratic bezier split where it turns vertically.
+fn push_quad(edges: &mut Vec<Edge>, p0: Point, p1: Point, p2: Point) {
+    let d = p0.1 - 2.0 * p1.1 + p2.1;
+    let t = if d != 0.0 { (p0.1 - p1.1) / d } else { 0.0 };
+    if t > 0.0 && t < 1.0 {
+        let (a, b) = (lerp(p0, p1, t), lerp(p1, p2, t));
+        let m = lerp(a, b, t);
+        edges.push(Edge::Quad(p0, a, m));
+        edges.push(Edge::Quad(m, b, p2));
+    } else {
+        edges.push(Edge::Quad(p0, p1, p2));
+    }
+}
+
+fn quad_point(p0: Point, p1: Point, p2: Point, t: f32) -> Point {
+    lerp(lerp(p0, p1, t), lerp(p1, p2, t), t)
+}
+
+fn lerp(a: Point, b: Point, t: f32) -> Point {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+pub fn dot(a: Point, b: Point) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+pub fn cross(a: Point, b: Point) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+pub fn distance(a: Point, b: Point) -> f32 {
+    ((a.0 - b.0) * (a.0 - b.0) + (a.1 - b.1) * (a.1 - b.1)).sqrt()
+}
+
+/// Returns real roots of `a * t^2 + b * t + c`.
+fn solve_quadratic(a: f32, b: f32, c: f32) -> Vec<f32> {
+    let (a, b, c) = (a as f64, b as f64, c as f64);
+    if a.abs() < 1e-9 {
+        return if b != 0.0 { vec![(-c / b) as f32] } else { vec![] };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return vec![];
+    }
+    let root = discriminant.sqrt();
+    vec![((-b + root) / (2.0 * a)) as f32, ((-b - root) / (2.0 * a)) as f32]
+}
+
+/// Returns real roots of `a * t^3 + b * t^2 + c * t + d`.
+fn solve_cubic(a: f32, b: f32, c: f32, d: f32) -> Vec<f32> {
+    if (a as f64).abs() < 1e-9 {
+        return solve_quadratic(b, c, d);
+    }
+    let (b, c, d) = (b as f64 / a as f64, c as f64 / a as f64, d as f64 / a as f64);
+    // Roots of the depressed cubic t^3 + p * t + q shifted back.
+    let p = c - b * b / 3.0;
+    let q = 2.0 * b * b * b / 27.0 - b * c / 3.0 + d;
+    let shift = -b / 3.0;
+    let discriminant = q * q / 4.0 + p * p * p / 27.0;
+    if discriminant > 0.0 {
+        let root = discriminant.sqrt();
+        vec![((-q / 2.0 + root).cbrt() + (-q / 2.0 - root).cbrt() + shift) as f32]
+    } else if p == 0.0 {
+        vec![shift as f32]
+    } else {
+        let r = (-p / 3.0).sqrt();
+        let phi = (-q / (2.0 * r * r * r)).clamp(-1.0, 1.0).acos() / 3.0;
+        let third = 2.0 * ::std::f64::consts::PI / 3.0;
+        (0..3).map(|k| (2.0 * r * (phi - third * k as f64).cos() + shift) as f32).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use outline::{Outline, OutlineBuilder};
+    use types::BBox;
+    use expectest::prelude::*;
+
+    fn square(outline: &mut Outline, x0: f32, y0: f32, x1: f32, y1: f32) {
+        outline.move_to(x0, y0);
+        outline.line_to(x1, y0);
+        outline.line_to(x1, y1);
+        outline.line_to(x0, y1);
+        outline.line_to(x0, y0);
+        outline.close();
+    }
+
+    #[test]
+    fn cubic_roots() {
+        let mut roots = solve_cubic(1.0, -6.0, 11.0, -6.0);
+        roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        expect!(roots.len()).to(be_equal_to(3));
+        for (root, expected) in roots.iter().zip(&[1.0, 2.0, 3.0]) {
+            expect!(*root).to(be_close_to(*expected));
+        }
+        expect!(solve_cubic(0.0, 1.0, 0.0, -4.0).iter().any(|&t| (t - 2.0).abs() < 1e-6)).to(be_true());
+    }
+
+    #[test]
+    fn quad_distance_and_winding() {
+        let mut edges = vec![];
+        push_quad(&mut edges, (0.0, 0.0), (5.0, 10.0), (10.0, 0.0));
+        // Split at the bottom of the curve at y = 5.
+        expect!(edges.len()).to(be_equal_to(2));
+        let distance = edges.iter().map(|e| e.distance((5.0, 8.0))).fold(f32::MAX, f32::min);
+        expect!(distance).to(be_close_to(3.0));
+        // The ray from the point crosses the right half going up.
+        let winding: i32 = edges.iter().map(|e| e.winding((5.0, 3.0))).sum();
+        expect!(winding).to(be_equal_to(-1));
+        let winding: i32 = edges.iter().map(|e| e.winding((-1.0, 3.0))).sum();
+        expect!(winding).to(be_equal_to(0));
+    }
+
+    #[test]
+    fn square_distance_field() {
+        let mut outline = Outline::new();
+        square(&mut outline, 0.0, 0.0, 10.0, 10.0);
+        let bbox = BBox { x0: -2, y0: -12, x1: 12, y1: 2 };
+        let bitmap = distance_field(&outline, 1.0, bbox, 128, 10.0);
+        expect!((bitmap.width, bitmap.height)).to(be_equal_to((14, 14)));
+        // Centers of pixels are half a pixel away from the edge.
+        expect!(bitmap.pixel(2, 2)).to(be_equal_to(133));
+        expect!(bitmap.pixel(1, 7)).to(be_equal_to(123));
+        expect!(bitmap.pixel(6, 6)).to(be_equal_to(173));
+        // The corner is 1.5 * sqrt(2) pixels away.
+        expect!(bitmap.pixel(0, 0)).to(be_equal_to(106));
+    }
+
+    #[test]
+    fn nested_contours() {
+        let mut outline = Outline::new();
+        square(&mut outline, 0.0, 0.0, 10.0, 10.0);
+        // The hole goes in the opposite direction.
+        square(&mut outline, 3.0, 7.0, 7.0, 3.0);
+        let bbox = BBox { x0: 0, y0: -10, x1: 10, y1: 0 };
+        let bitmap = distance_field(&outline, 1.0, bbox, 128, 10.0);
+        expect!(bitmap.pixel(5, 5)).to(be_less_than(128));
+        expect!(bitmap.pixel(1, 5)).to(be_greater_than(128));
+    }
+}
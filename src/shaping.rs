@@ -0,0 +1,52 @@
+use FontInfo;
+
+/// A glyph placed on a line of text.
+///
+/// Positions and advances are expressed in pixels, relative to the origin of
+/// the line on the baseline.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct PositionedGlyph {
+    /// Index of the glyph in the font.
+    pub glyph: usize,
+    /// Byte index in the text of the character the glyph was made from.
+    pub cluster: usize,
+    /// Horizontal position of the glyph origin.
+    pub x: f32,
+    /// Vertical position of the glyph origin.
+    pub y: f32,
+    /// The offset from the glyph origin to the origin of the next glyph,
+    /// kerning with the next glyph included.
+    pub advance: f32,
+}
+
+/// Lays out `text` on a single line with the font scaled to the pixel
+/// height `size`.
+///
+/// Each character is mapped to a glyph with the `cmap` table, glyphs are
+/// advanced by their horizontal metrics and kerning. No substitutions are
+/// made, so a glyph is returned for each character of the text, including
+/// control characters.
+pub fn shape(font: &FontInfo, text: &str, size: f32) -> Vec<PositionedGlyph> {
+    let scale = font.scale_for_pixel_height(size);
+    let mut glyphs: Vec<PositionedGlyph> = text.char_indices().map(|(cluster, c)| {
+        let glyph = font.glyph_index_for_code(c as usize);
+        PositionedGlyph {
+            glyph: glyph,
+            cluster: cluster,
+            x: 0.0,
+            y: 0.0,
+            advance: font.hmtx.hmetric_for_glyph_at_index(glyph).advance_width as f32,
+        }
+    }).collect();
+
+    for i in 1..glyphs.len() {
+        glyphs[i - 1].advance += font.kerning(glyphs[i - 1].glyph, glyphs[i].glyph) as f32;
+    }
+    let mut pen = 0.0;
+    for glyph in &mut glyphs {
+        glyph.advance *= scale;
+        glyph.x = pen;
+        pen += glyph.advance;
+    }
+    glyphs
+}
@@ -0,0 +1,546 @@
+use std::f32::consts::PI;
+use outline::{Outline, OutlineBuilder, Segment};
+
+type Point = (f32, f32);
+
+// Maximum distance in font units between a curve and its flattened lines.
+const TOLERANCE: f32 = 0.5;
+
+/// Shape of the corners where segments of a stroke meet.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LineJoin {
+    /// Outer edges are extended until they meet, up to the miter limit.
+    Miter,
+    /// Corners are rounded with the radius of half the width.
+    Round,
+    /// Corners are cut off.
+    Bevel,
+}
+
+/// Shape of the ends of open paths.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LineCap {
+    /// The stroke ends at the end point.
+    Butt,
+    /// The stroke ends with a half circle around the end point.
+    Round,
+    /// The stroke extends by half the width past the end point.
+    Square,
+}
+
+/// Settings of outline stroking.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct StrokeStyle {
+    /// Width of the stroke in font units.
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// Ratio of the miter length to the width, beyond which miter joins
+    /// are beveled.
+    pub miter_limit: f32,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> StrokeStyle {
+        StrokeStyle {
+            width: 1.0,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.0,
+        }
+    }
+}
+
+/// Builds the outline of a stroke along paths passed to it as an
+/// `OutlineBuilder`.
+///
+/// Paths closed with `close` get joins all around, other paths get caps
+/// at both ends. Curves are flattened into lines. The stroke is filled by
+/// the nonzero winding rule, which the rasterizer uses.
+#[derive(Debug, Clone)]
+pub struct Stroker {
+    style: StrokeStyle,
+    outline: Outline,
+    path: Vec<Point>,
+}
+
+impl Stroker {
+    /// Creates a stroker with the given style.
+    pub fn new(style: &StrokeStyle) -> Stroker {
+        Stroker {
+            style: *style,
+            outline: Outline::new(),
+            path: vec![],
+        }
+    }
+
+    /// Returns the outline of the stroke.
+    pub fn finish(mut self) -> Outline {
+        self.stroke_path(false);
+        self.outline
+    }
+
+    fn current(&self) -> Point {
+        self.path.last().cloned().unwrap_or((0.0, 0.0))
+    }
+
+    fn stroke_path(&mut self, closed: bool) {
+        let mut points: Vec<Point> = vec![];
+        for &point in &self.path {
+            if points.last() != Some(&point) {
+                points.push(point);
+            }
+        }
+        if closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        self.path.clear();
+
+        let style = self.style;
+        let h = style.width / 2.0;
+        if points.is_empty() || h <= 0.0 {
+            return;
+        }
+        if points.len() == 1 {
+            // A dot is drawn by the caps of a zero length line.
+            if !closed && style.cap != LineCap::Butt {
+                let (e, n, d) = (points[0], (0.0, 1.0), (1.0, 0.0));
+                let mut contour = vec![add(e, n, h)];
+                cap(&mut contour, &style, e, n, d);
+                cap(&mut contour, &style, e, neg(n), neg(d));
+                emit(&mut self.outline, &contour);
+            }
+            return;
+        }
+
+        let mut reversed = points.clone();
+        reversed.reverse();
+        if closed {
+            let mut contour = vec![];
+            offset_side(&mut contour, &style, &points, true);
+            emit(&mut self.outline, &contour);
+            contour.clear();
+            offset_side(&mut contour, &style, &reversed, true);
+            emit(&mut self.outline, &contour);
+        } else {
+            let mut contour = vec![];
+            offset_side(&mut contour, &style, &points, false);
+            let (e, p) = (points[points.len() - 1], points[points.len() - 2]);
+            let d = direction(p, e);
+            cap(&mut contour, &style, e, left_normal(d), d);
+            offset_side(&mut contour, &style, &reversed, false);
+            let (e, p) = (points[0], points[1]);
+            let d = direction(p, e);
+            cap(&mut contour, &style, e, left_normal(d), d);
+            emit(&mut self.outline, &contour);
+        }
+    }
+
+    fn flatten<F: Fn(f32) -> Point>(&mut self, pieces: usize, curve: F) {
+        for k in 1..pieces + 1 {
+            self.path.push(curve(k as f32 / pieces as f32));
+        }
+    }
+}
+
+impl OutlineBuilder for Stroker {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.stroke_path(false);
+        self.path.push((x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.path.push((x, y));
+    }
+
+    fn quad_to(&mut self, cx: f32, cy: f32, x: f32, y: f32) {
+        let p0 = self.current();
+        let pieces = pieces(length(add(sub(p0, (cx, cy)), sub((x, y), (cx, cy)), 1.0)) / 4.0);
+        self.flatten(pieces, |t| {
+            let mt = 1.0 - t;
+            (mt * mt * p0.0 + 2.0 * mt * t * cx + t * t * x, mt * mt * p0.1 + 2.0 * mt * t * cy + t * t * y)
+        });
+    }
+
+    fn curve_to(&mut self, cx1: f32, cy1: f32, cx2: f32, cy2: f32, x: f32, y: f32) {
+        let p0 = self.current();
+        let dd1 = length(add(sub(p0, (cx1, cy1)), sub((cx2, cy2), (cx1, cy1)), 1.0));
+        let dd2 = length(add(sub((cx1, cy1), (cx2, cy2)), sub((x, y), (cx2, cy2)), 1.0));
+        self.flatten(pieces(dd1.max(dd2) * 3.0 / 4.0), |t| {
+            let mt = 1.0 - t;
+            let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+            (a * p0.0 + b * cx1 + c * cx2 + d * x, a * p0.1 + b * cy1 + c * cy2 + d * y)
+        });
+    }
+
+    fn close(&mut self) {
+        self.stroke_path(true);
+    }
+}
+
+/// Returns the outline of a stroke along the contours of `outline`.
+pub fn stroke_outline(outline: &Outline, style: &StrokeStyle) -> Outline {
+    let mut stroker = Stroker::new(style);
+    let mut started = false;
+    for segment in outline {
+        match *segment {
+            Segment::MoveTo { x, y } => {
+                if started {
+                    stroker.close();
+                }
+                started = true;
+                stroker.move_to(x, y);
+            },
+            Segment::LineTo { x, y } => stroker.line_to(x, y),
+            Segment::QuadTo { cx, cy, x, y } => stroker.quad_to(cx, cy, x, y),
+            Segment::CurveTo { cx1, cy1, cx2, cy2, x, y } => stroker.curve_to(cx1, cy1, cx2, cy2, x, y),
+        }
+    }
+    if started {
+        stroker.close();
+    }
+    stroker.finish()
+}
+
+/// Returns `outline` with contours moved outwards by `amount` font units,
+/// so stems get thicker by twice the amount. Negative amounts make
+/// the outline thinner.
+///
+/// All points including control points are moved, so curves are kept.
+/// Points at very sharp corners are not moved.
+pub fn embolden_outline(outline: &Outline, amount: f32) -> Outline {
+    let contours = split_contours(outline);
+
+    // Outer contours of TrueType outlines are clockwise, while PostScript
+    // ones are counterclockwise.
+    let mut area = 0.0;
+    for contour in &contours {
+        let points = contour_points(contour);
+        for (i, &p) in points.iter().enumerate() {
+            let q = points[(i + 1) % points.len()];
+            area += p.0 * q.1 - q.0 * p.1;
+        }
+    }
+    let outward = if area <= 0.0 { left_normal } else { right_normal };
+
+    let mut emboldened = Outline::new();
+    for contour in &contours {
+        let points = contour_points(contour);
+        let count = points.len();
+        let moved: Vec<Point> = (0..count).map(|k| {
+            let p = points[k];
+            let prev = (1..count).map(|j| points[(k + count - j) % count]).find(|&q| q != p);
+            let next = (1..count).map(|j| points[(k + j) % count]).find(|&q| q != p);
+            let (prev, next) = match (prev, next) {
+                (Some(prev), Some(next)) => (prev, next),
+                _ => return p,
+            };
+            let (n0, n1) = (outward(direction(prev, p)), outward(direction(p, next)));
+            let q = 1.0 + dot(n0, n1);
+            if q < 1.0 / 16.0 {
+                p
+            } else {
+                add(p, add(n0, n1, 1.0), amount / q)
+            }
+        }).collect();
+
+        // The contour ends where it starts.
+        let mut iter = moved.iter().cloned();
+        let start = moved[count - 1];
+        for segment in contour {
+            let mut next = || iter.next().unwrap_or(start);
+            match *segment {
+                Segment::MoveTo { .. } => emboldened.move_to(start.0, start.1),
+                Segment::LineTo { .. } => {
+                    let p = next();
+                    emboldened.line_to(p.0, p.1);
+                },
+                Segment::QuadTo { .. } => {
+                    let (c, p) = (next(), next());
+                    emboldened.quad_to(c.0, c.1, p.0, p.1);
+                },
+                Segment::CurveTo { .. } => {
+                    let (c1, c2, p) = (next(), next(), next());
+                    emboldened.curve_to(c1.0, c1.1, c2.0, c2.1, p.0, p.1);
+                },
+            }
+        }
+    }
+    emboldened
+}
+
+fn split_contours(outline: &Outline) -> Vec<Vec<Segment>> {
+    let mut contours: Vec<Vec<Segment>> = vec![];
+    for segment in outline {
+        match (*segment, contours.last_mut()) {
+            (Segment::MoveTo { .. }, _) | (_, None) => contours.push(vec![*segment]),
+            (_, Some(contour)) => contour.push(*segment),
+        }
+    }
+    contours
+}
+
+// Returns on-curve and control points of a contour after its start point,
+// the last one is the start point.
+fn contour_points(contour: &[Segment]) -> Vec<Point> {
+    let mut points = vec![];
+    for segment in &contour[1..] {
+        match *segment {
+            Segment::MoveTo { x, y } | Segment::LineTo { x, y } => points.push((x, y)),
+            Segment::QuadTo { cx, cy, x, y } => points.extend_from_slice(&[(cx, cy), (x, y)]),
+            Segment::CurveTo { cx1, cy1, cx2, cy2, x, y } => {
+                points.extend_from_slice(&[(cx1, cy1), (cx2, cy2), (x, y)]);
+            },
+        }
+    }
+    if points.is_empty() {
+        points.push(contour[0].end());
+    }
+    points
+}
+
+// Appends the left side of the stroke along `points` to `contour`. Closed
+// paths get a join at every point, open ones have no joins at the ends.
+fn offset_side(contour: &mut Vec<Point>, style: &StrokeStyle, points: &[Point], closed: bool) {
+    let h = style.width / 2.0;
+    let n = points.len();
+    let segments = if closed { n } else { n - 1 };
+    for i in 0..segments {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        let normal = left_normal(direction(a, b));
+        if i == 0 {
+            push(contour, add(a, normal, h));
+        }
+        push(contour, add(b, normal, h));
+        if closed || i + 1 < segments {
+            let next = left_normal(direction(b, points[(i + 2) % n]));
+            join(contour, style, b, normal, next);
+        }
+    }
+}
+
+fn join(contour: &mut Vec<Point>, style: &StrokeStyle, v: Point, n0: Point, n1: Point) {
+    let h = style.width / 2.0;
+    let (turn, cos) = (cross(n0, n1), dot(n0, n1));
+    let end = add(v, n1, h);
+    if turn > 0.0 || (turn == 0.0 && cos > 0.0) {
+        // The inner side goes through the point, overlaps are filled by
+        // the nonzero winding rule.
+        if cos < 1.0 - 1e-6 {
+            push(contour, v);
+        }
+    } else {
+        match style.join {
+            LineJoin::Miter => {
+                let q = 1.0 + cos;
+                if q > 1e-6 && (2.0 / q).sqrt() <= style.miter_limit {
+                    push(contour, add(v, add(n0, n1, 1.0), h / q));
+                }
+            },
+            LineJoin::Round => {
+                let sweep = turn.atan2(cos);
+                let sweep = if sweep > 0.0 { sweep - 2.0 * PI } else { sweep };
+                arc(contour, v, h, n0, sweep);
+            },
+            LineJoin::Bevel => {},
+        }
+    }
+    push(contour, end);
+}
+
+// Appends the cap at the end point `e` of a path going in direction `d`,
+// from the left side at normal `n` to the right side.
+fn cap(contour: &mut Vec<Point>, style: &StrokeStyle, e: Point, n: Point, d: Point) {
+    let h = style.width / 2.0;
+    match style.cap {
+        LineCap::Butt => {},
+        LineCap::Round => arc(contour, e, h, n, -PI),
+        LineCap::Square => {
+            push(contour, add(add(e, n, h), d, h));
+            push(contour, add(add(e, n, -h), d, h));
+        },
+    }
+    push(contour, add(e, n, -h));
+}
+
+// Appends an arc around `center` starting at the direction `from`.
+fn arc(contour: &mut Vec<Point>, center: Point, radius: f32, from: Point, sweep: f32) {
+    let step = if radius > TOLERANCE { 2.0 * (1.0 - TOLERANCE / radius).acos() } else { PI / 2.0 };
+    let steps = (sweep.abs() / step).ceil().max(1.0) as usize;
+    let start = from.1.atan2(from.0);
+    for k in 1..steps + 1 {
+        let angle = start + sweep * k as f32 / steps as f32;
+        push(contour, (center.0 + radius * angle.cos(), center.1 + radius * angle.sin()));
+    }
+}
+
+fn emit(outline: &mut Outline, contour: &[Point]) {
+    if contour.len() < 3 {
+        return;
+    }
+    let start = contour[0];
+    outline.move_to(start.0, start.1);
+    for &(x, y) in &contour[1..] {
+        outline.line_to(x, y);
+    }
+    if contour[contour.len() - 1] != start {
+        outline.line_to(start.0, start.1);
+    }
+}
+
+fn push(contour: &mut Vec<Point>, point: Point) {
+    if contour.last() != Some(&point) {
+        contour.push(point);
+    }
+}
+
+// Returns the number of lines for a curve with the given deviation.
+fn pieces(deviation: f32) -> usize {
+    ((deviation / TOLERANCE).sqrt().ceil() as usize).clamp(1, 100)
+}
+
+fn add(a: Point, b: Point, scale: f32) -> Point {
+    (a.0 + b.0 * scale, a.1 + b.1 * scale)
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn neg(a: Point) -> Point {
+    (-a.0, -a.1)
+}
+
+fn dot(a: Point, b: Point) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn cross(a: Point, b: Point) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn length(a: Point) -> f32 {
+    dot(a, a).sqrt()
+}
+
+// Returns the unit vector from `a` to `b`, or the x axis if they are equal.
+fn direction(a: Point, b: Point) -> Point {
+    let d = sub(b, a);
+    let l = length(d);
+    if l > 0.0 { (d.0 / l, d.1 / l) } else { (1.0, 0.0) }
+}
+
+fn left_normal(d: Point) -> Point {
+    (-d.1, d.0)
+}
+
+fn right_normal(d: Point) -> Point {
+    (d.1, -d.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+    use outline::{Outline, OutlineBuilder};
+    use types::BBox;
+
+    fn square(reversed: bool) -> Outline {
+        let mut points = vec![(0.0, 0.0), (0.0, 100.0), (100.0, 100.0), (100.0, 0.0), (0.0, 0.0)];
+        if reversed {
+            points.reverse();
+        }
+        let mut outline = Outline::new();
+        outline.move_to(points[0].0, points[0].1);
+        for p in &points[1..] {
+            outline.line_to(p.0, p.1);
+        }
+        outline
+    }
+
+    fn has_point(outline: &Outline, x: f32, y: f32) -> bool {
+        outline.iter().any(|s| {
+            let (px, py) = s.end();
+            (px - x).abs() < 1e-3 && (py - y).abs() < 1e-3
+        })
+    }
+
+    fn bbox(x0: i32, y0: i32, x1: i32, y1: i32) -> Option<BBox> {
+        Some(BBox { x0: x0, y0: y0, x1: x1, y1: y1 })
+    }
+
+    #[test]
+    fn joins() {
+        let style = StrokeStyle { width: 10.0, ..StrokeStyle::default() };
+        let miter = stroke_outline(&square(false), &style);
+        expect!(miter.bounding_box()).to(be_equal_to(bbox(-5, -5, 105, 105)));
+        expect!(has_point(&miter, -5.0, -5.0)).to(be_true());
+        // The inner side turns around the corner.
+        expect!(has_point(&miter, 0.0, 0.0)).to(be_true());
+
+        let style = StrokeStyle { width: 10.0, join: LineJoin::Bevel, ..StrokeStyle::default() };
+        let bevel = stroke_outline(&square(true), &style);
+        expect!(bevel.bounding_box()).to(be_equal_to(bbox(-5, -5, 105, 105)));
+        expect!(has_point(&bevel, -5.0, -5.0)).to(be_false());
+        expect!(has_point(&bevel, -5.0, 0.0)).to(be_true());
+
+        let style = StrokeStyle { width: 10.0, join: LineJoin::Round, ..StrokeStyle::default() };
+        let round = stroke_outline(&square(false), &style);
+        let corner = round.iter().map(|s| s.end()).filter(|p| p.0 < 0.0 && p.1 < 0.0).collect::<Vec<_>>();
+        expect!(corner.is_empty()).to(be_false());
+        for p in corner {
+            expect!(((p.0 * p.0 + p.1 * p.1).sqrt() - 5.0).abs() < 1e-3).to(be_true());
+        }
+
+        // Sharp corners beyond the miter limit are beveled.
+        let style = StrokeStyle { width: 10.0, miter_limit: 1.2, ..StrokeStyle::default() };
+        expect!(has_point(&stroke_outline(&square(false), &style), -5.0, -5.0)).to(be_false());
+    }
+
+    #[test]
+    fn caps() {
+        let line = |cap| {
+            let mut stroker = Stroker::new(&StrokeStyle { width: 10.0, cap: cap, ..StrokeStyle::default() });
+            stroker.move_to(0.0, 0.0);
+            stroker.line_to(100.0, 0.0);
+            stroker.finish()
+        };
+        expect!(line(LineCap::Butt).bounding_box()).to(be_equal_to(bbox(0, -5, 100, 5)));
+        expect!(line(LineCap::Square).bounding_box()).to(be_equal_to(bbox(-5, -5, 105, 5)));
+        let round = line(LineCap::Round);
+        expect!(round.bounding_box()).to(be_equal_to(bbox(-5, -5, 105, 5)));
+        expect!(has_point(&round, 105.0, 0.0)).to(be_true());
+
+        // A single point is a dot.
+        let mut stroker = Stroker::new(&StrokeStyle { width: 10.0, cap: LineCap::Square, ..StrokeStyle::default() });
+        stroker.move_to(50.0, 50.0);
+        expect!(stroker.finish().bounding_box()).to(be_equal_to(bbox(45, 45, 55, 55)));
+    }
+
+    #[test]
+    fn curves() {
+        let mut stroker = Stroker::new(&StrokeStyle { width: 2.0, join: LineJoin::Bevel, ..StrokeStyle::default() });
+        stroker.move_to(0.0, 0.0);
+        stroker.quad_to(50.0, 100.0, 100.0, 0.0);
+        let stroke = stroker.finish();
+        expect!(stroke.len() > 20).to(be_true());
+        expect!(stroke.bounding_box()).to(be_equal_to(bbox(-1, -1, 101, 51)));
+    }
+
+    #[test]
+    fn embolden() {
+        expect!(embolden_outline(&square(false), 10.0).bounding_box()).to(be_equal_to(bbox(-10, -10, 110, 110)));
+        expect!(embolden_outline(&square(true), 10.0).bounding_box()).to(be_equal_to(bbox(-10, -10, 110, 110)));
+        expect!(embolden_outline(&square(false), -10.0).bounding_box()).to(be_equal_to(bbox(10, 10, 90, 90)));
+
+        // Holes get smaller and curves stay curves.
+        let mut outline = square(false);
+        outline.move_to(20.0, 20.0);
+        outline.quad_to(80.0, 20.0, 80.0, 80.0);
+        outline.line_to(20.0, 80.0);
+        outline.line_to(20.0, 20.0);
+        let emboldened = embolden_outline(&outline, 5.0);
+        expect!(emboldened.len()).to(be_equal_to(outline.len()));
+        expect!(has_point(&emboldened, 25.0, 75.0)).to(be_true());
+    }
+}
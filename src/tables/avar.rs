@@ -0,0 +1,112 @@
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+
+/// An axis variations table.
+///
+/// The `avar` table modifies normalized coordinates of axes of variation
+/// with piecewise linear maps, one map for each axis of the `fvar` table.
+#[derive(Debug, Default)]
+pub struct AVAR {
+    /// Pairs of normalized coordinates and their modified values.
+    segment_maps: Vec<Vec<(f32, f32)>>,
+}
+
+impl AVAR {
+    /// Returns `avar` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `avar` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<AVAR> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        // Version 2 adds data after the segment maps, which is ignored.
+        let major_version = try!(cursor.read_u16::<BigEndian>());
+        let _minor_version = try!(cursor.read_u16::<BigEndian>());
+        if major_version != 1 && major_version != 2 {
+            return Err(Error::AVARVersionIsNotSupported);
+        }
+        let _reserved = try!(cursor.read_u16::<BigEndian>());
+        let axis_count = try!(cursor.read_u16::<BigEndian>());
+
+        let mut segment_maps = Vec::with_capacity(axis_count as usize);
+        for _ in 0..axis_count {
+            let mut segment_map = vec![];
+            for _ in 0..try!(cursor.read_u16::<BigEndian>()) {
+                let from = try!(read_f2dot14(&mut cursor));
+                let to = try!(read_f2dot14(&mut cursor));
+                segment_map.push((from, to));
+            }
+            segment_maps.push(segment_map);
+        }
+
+        Ok(AVAR { segment_maps: segment_maps })
+    }
+
+    /// Returns the modified normalized `value` of the axis at index `axis`.
+    ///
+    /// Values of axes without a map are not modified.
+    pub fn map(&self, axis: usize, value: f32) -> f32 {
+        let segment_map = match self.segment_maps.get(axis) {
+            Some(segment_map) => segment_map,
+            None => return value,
+        };
+        // Maps must contain -1, 0 and 1, shorter maps are ignored.
+        if segment_map.len() < 3 {
+            return value;
+        }
+
+        let mut previous = segment_map[0];
+        if value <= previous.0 {
+            return previous.1 + (value - previous.0);
+        }
+        for &(from, to) in &segment_map[1..] {
+            if value <= from {
+                if from == previous.0 {
+                    return to;
+                }
+                return previous.1 + (value - previous.0) * (to - previous.1) / (from - previous.0);
+            }
+            previous = (from, to);
+        }
+        previous.1 + (value - previous.0)
+    }
+}
+
+fn read_f2dot14(cursor: &mut Cursor<&[u8]>) -> Result<f32> {
+    Ok(try!(cursor.read_i16::<BigEndian>()) as f32 / 16384.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        // The first axis maps 0.5 to 0.25, the second axis has no map.
+        let data = [0, 1, 0, 0, 0, 0, 0, 2, 0, 4, 0xc0, 0, 0xc0, 0, 0, 0, 0, 0,
+                    0x20, 0, 0x10, 0, 0x40, 0, 0x40, 0, 0, 0];
+        let avar = AVAR::from_data(&data, 0).unwrap();
+        expect!(avar.map(0, -1.0)).to(be_equal_to(-1.0));
+        expect!(avar.map(0, -0.5)).to(be_equal_to(-0.5));
+        expect!(avar.map(0, 0.0)).to(be_equal_to(0.0));
+        expect!(avar.map(0, 0.25)).to(be_equal_to(0.125));
+        expect!(avar.map(0, 0.5)).to(be_equal_to(0.25));
+        expect!(avar.map(0, 0.75)).to(be_equal_to(0.625));
+        expect!(avar.map(0, 1.0)).to(be_equal_to(1.0));
+        expect!(avar.map(1, 0.5)).to(be_equal_to(0.5));
+        expect!(avar.map(2, 0.5)).to(be_equal_to(0.5));
+
+        expect!(AVAR::from_data(&data[..20], 0)).to(be_err().value(Malformed));
+        expect!(AVAR::from_data(&[0, 3, 0, 0], 0)).to(be_err().value(AVARVersionIsNotSupported));
+    }
+}
@@ -0,0 +1,1197 @@
+use Error;
+use Result;
+use OutlineBuilder;
+use std::cmp;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+
+/// A compact font format table.
+///
+/// The `CFF ` and `CFF2` tables contain PostScript outlines of glyphs
+/// encoded as Type 2 charstrings. Both name-keyed and CID-keyed fonts are
+/// supported, hints are skipped and variations are not applied, so `CFF2`
+/// outlines are those of the default instance.
+#[derive(Debug)]
+pub struct CFF {
+    data: Vec<u8>,
+    cff2: bool,
+    global_subrs: Index,
+    char_strings: Index,
+    /// Local subroutines of each font dict.
+    local_subrs: Vec<Index>,
+    fd_select: FdSelect,
+    charset: Charset,
+    /// The number of variation regions of each item variation data.
+    region_counts: Vec<usize>,
+}
+
+/// Operators of DICT data.
+const CHARSET: u16 = 15;
+const CHAR_STRINGS: u16 = 17;
+const PRIVATE: u16 = 18;
+const SUBRS: u16 = 19;
+const VSTORE: u16 = 24;
+const CHARSTRING_TYPE: u16 = 1206;
+const FD_ARRAY: u16 = 1236;
+const FD_SELECT: u16 = 1237;
+const ROS: u16 = 1230;
+
+/// The maximum depth of nested subroutine calls.
+const MAX_SUBR_DEPTH: usize = 10;
+/// The maximum number of arguments on the stack of `CFF2` charstrings,
+/// which is larger than the limit of `CFF ` charstrings.
+const MAX_STACK: usize = 513;
+
+impl CFF {
+    /// Returns `CFF ` font table.
+    ///
+    /// Attempts to read `size` bytes of `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `CFF ` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize, size: usize) -> Result<CFF> {
+        if offset >= data.len() || offset + size > data.len() {
+            return Err(Error::Malformed);
+        }
+        // Offsets in the table are relative to its start, so it is read
+        // from an owned copy.
+        let table = &data[offset..offset + size];
+
+        if table.len() < 4 || table[0] != 1 {
+            return Err(Error::CFFVersionIsNotSupported);
+        }
+        let header_size = table[2] as usize;
+        let (_names, end) = try!(Index::from_data(table, header_size, false));
+        let (top_dicts, end) = try!(Index::from_data(table, end, false));
+        let (_strings, end) = try!(Index::from_data(table, end, false));
+        let (global_subrs, _) = try!(Index::from_data(table, end, false));
+        let top_dict = match top_dicts.get(table, 0) {
+            Some(top_dict) => try!(read_dict(top_dict)),
+            None => return Err(Error::Malformed),
+        };
+        if let Some(kind) = operand(&top_dict, CHARSTRING_TYPE) {
+            if kind != 2.0 {
+                return Err(Error::CFFVersionIsNotSupported);
+            }
+        }
+
+        CFF::from_top_dict(table, false, global_subrs, &top_dict)
+    }
+
+    /// Returns `CFF2` font table.
+    ///
+    /// Attempts to read `size` bytes of `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `CFF2` font table is not supported.
+    pub fn from_data_cff2(data: &[u8], offset: usize, size: usize) -> Result<CFF> {
+        if offset >= data.len() || offset + size > data.len() {
+            return Err(Error::Malformed);
+        }
+        let table = &data[offset..offset + size];
+
+        if table.len() < 5 || table[0] != 2 {
+            return Err(Error::CFFVersionIsNotSupported);
+        }
+        let header_size = table[2] as usize;
+        let top_dict_length = try!(Cursor::new(&table[3..]).read_u16::<BigEndian>()) as usize;
+        let end = header_size + top_dict_length;
+        if end > table.len() {
+            return Err(Error::Malformed);
+        }
+        let top_dict = try!(read_dict(&table[header_size..end]));
+        let (global_subrs, _) = try!(Index::from_data(table, end, true));
+
+        CFF::from_top_dict(table, true, global_subrs, &top_dict)
+    }
+
+    fn from_top_dict(table: &[u8], cff2: bool, global_subrs: Index, top_dict: &[(u16, Vec<f64>)]) -> Result<CFF> {
+        let char_strings = match operand(top_dict, CHAR_STRINGS) {
+            Some(offset) => try!(Index::from_data(table, offset as usize, cff2)).0,
+            None => return Err(Error::Malformed),
+        };
+
+        // CID-keyed and `CFF2` fonts have a private dict for each font dict.
+        let mut local_subrs = vec![];
+        match operand(top_dict, FD_ARRAY) {
+            Some(offset) => {
+                let (font_dicts, _) = try!(Index::from_data(table, offset as usize, cff2));
+                for i in 0..font_dicts.len() {
+                    let font_dict = try!(read_dict(font_dicts.get(table, i).unwrap_or(&[])));
+                    local_subrs.push(try!(read_local_subrs(table, &font_dict, cff2)));
+                }
+            },
+            None => local_subrs.push(try!(read_local_subrs(table, top_dict, cff2))),
+        }
+
+        let fd_select = match operand(top_dict, FD_SELECT) {
+            Some(offset) => try!(FdSelect::from_data(table, offset as usize, char_strings.len())),
+            None => FdSelect::Single,
+        };
+
+        // Charsets of CID-keyed fonts map glyphs to CIDs instead of names.
+        let charset = if cff2 || operand(top_dict, ROS).is_some() {
+            Charset::Unavailable
+        } else {
+            let offset = operand(top_dict, CHARSET).unwrap_or(0.0) as usize;
+            try!(Charset::from_data(table, offset, char_strings.len()))
+        };
+
+        let region_counts = match operand(top_dict, VSTORE) {
+            Some(offset) if cff2 => try!(read_region_counts(table, offset as usize)),
+            _ => vec![],
+        };
+
+        Ok(CFF {
+            data: table.to_owned(),
+            cff2: cff2,
+            global_subrs: global_subrs,
+            char_strings: char_strings,
+            local_subrs: local_subrs,
+            fd_select: fd_select,
+            charset: charset,
+            region_counts: region_counts,
+        })
+    }
+
+    /// Returns `true` if the table is a `CFF2` table.
+    pub fn is_cff2(&self) -> bool {
+        self.cff2
+    }
+
+    /// Returns the number of glyphs in the table.
+    pub fn glyph_count(&self) -> usize {
+        self.char_strings.len()
+    }
+
+    /// Decodes the charstring of the glyph at index `i` into calls of
+    /// the `builder`.
+    ///
+    /// Nothing is built if the table has no such glyph.
+    ///
+    /// # Errors
+    /// Returns error if the charstring is malformed, uses deprecated
+    /// arithmetic operators or composes an accented glyph of glyphs
+    /// missing in the font.
+    pub fn build_outline<B: OutlineBuilder>(&self, i: usize, builder: &mut B) -> Result<()> {
+        self.run_glyph(i, builder, 0.0, 0.0, 0)
+    }
+
+    /// Runs the charstring of the glyph at index `i` with the origin moved
+    /// to `x`, `y`.
+    fn run_glyph<B: OutlineBuilder>(&self, i: usize, builder: &mut B, x: f32, y: f32, depth: usize) -> Result<()> {
+        let char_string = match self.char_strings.get(&self.data, i) {
+            Some(char_string) => char_string,
+            None => return Ok(()),
+        };
+        let empty = Index::default();
+        let local_subrs = self.fd_select.font_dict(i)
+            .and_then(|fd| self.local_subrs.get(fd))
+            .unwrap_or(&empty);
+
+        let mut interpreter = Interpreter {
+            cff: self,
+            local_subrs: local_subrs,
+            builder: builder,
+            stack: Vec::with_capacity(MAX_STACK),
+            x: x,
+            y: y,
+            start: None,
+            stems: 0,
+            width_parsed: self.cff2,
+            vsindex: 0,
+        };
+        try!(interpreter.run(char_string, depth));
+        interpreter.close();
+        Ok(())
+    }
+
+    /// Returns the glyph of the character `code` of the standard encoding,
+    /// which components of accented glyphs of `seac` are given by.
+    fn standard_glyph(&self, code: f32) -> Result<usize> {
+        if !(0.0..=255.0).contains(&code) || code.fract() != 0.0 {
+            return Err(Error::Malformed);
+        }
+        match standard_encoding(code as u8).and_then(|sid| self.charset.glyph(sid)) {
+            Some(glyph) if glyph < self.glyph_count() => Ok(glyph),
+            _ => Err(Error::Malformed),
+        }
+    }
+}
+
+/// An INDEX, i.e. an array of variable-sized objects.
+#[derive(Debug, Default)]
+struct Index {
+    /// Offsets of objects from the start of the table, with the end of
+    /// the last object.
+    offsets: Vec<usize>,
+}
+
+impl Index {
+    /// Reads an INDEX at `offset` of the table and returns it with the offset
+    /// of the end of the INDEX. `CFF2` INDEX has a 32-bit count.
+    fn from_data(table: &[u8], offset: usize, cff2: bool) -> Result<(Index, usize)> {
+        if offset >= table.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&table[offset..]);
+        let count = if cff2 {
+            try!(cursor.read_u32::<BigEndian>()) as usize
+        } else {
+            try!(cursor.read_u16::<BigEndian>()) as usize
+        };
+        if count == 0 {
+            return Ok((Index::default(), offset + cursor.position() as usize));
+        }
+
+        let offset_size = try!(cursor.read_u8());
+        if offset_size == 0 || offset_size > 4 {
+            return Err(Error::Malformed);
+        }
+        // All offsets have to fit in the table before they are allocated.
+        if count >= (table.len() - offset) / offset_size as usize {
+            return Err(Error::Malformed);
+        }
+        // Offsets are relative to the byte preceding the object data.
+        let base = offset + cursor.position() as usize + (count + 1) * offset_size as usize - 1;
+        let mut offsets = Vec::with_capacity(count + 1);
+        for _ in 0..count + 1 {
+            let mut value = 0;
+            for _ in 0..offset_size {
+                value = value << 8 | try!(cursor.read_u8()) as usize;
+            }
+            let value = base + value;
+            if value < base + 1 || value > table.len() || offsets.last().cloned().unwrap_or(0) > value {
+                return Err(Error::Malformed);
+            }
+            offsets.push(value);
+        }
+
+        let end = offsets[count];
+        Ok((Index { offsets: offsets }, end))
+    }
+
+    fn len(&self) -> usize {
+        if self.offsets.is_empty() { 0 } else { self.offsets.len() - 1 }
+    }
+
+    fn get<'a>(&self, table: &'a [u8], i: usize) -> Option<&'a [u8]> {
+        if i < self.len() {
+            Some(&table[self.offsets[i]..self.offsets[i + 1]])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the bias added to subroutine numbers.
+    fn bias(&self) -> i32 {
+        match self.len() {
+            0..=1239 => 107,
+            1240..=33899 => 1131,
+            _ => 32768,
+        }
+    }
+}
+
+/// A mapping of glyphs to font dicts.
+#[derive(Debug)]
+enum FdSelect {
+    /// All glyphs use the first font dict.
+    Single,
+    /// Font dicts of each glyph.
+    Glyphs(Vec<u16>),
+    /// Ranges of glyphs with their font dict, followed by the sentinel.
+    Ranges(Vec<(u32, u16)>, u32),
+}
+
+impl FdSelect {
+    fn from_data(table: &[u8], offset: usize, glyphs: usize) -> Result<FdSelect> {
+        if offset >= table.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&table[offset..]);
+        match try!(cursor.read_u8()) {
+            0 => {
+                let mut fds = Vec::with_capacity(glyphs);
+                for _ in 0..glyphs {
+                    fds.push(try!(cursor.read_u8()) as u16);
+                }
+                Ok(FdSelect::Glyphs(fds))
+            },
+            3 => {
+                let count = try!(cursor.read_u16::<BigEndian>());
+                let mut ranges = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let first = try!(cursor.read_u16::<BigEndian>()) as u32;
+                    ranges.push((first, try!(cursor.read_u8()) as u16));
+                }
+                Ok(FdSelect::Ranges(ranges, try!(cursor.read_u16::<BigEndian>()) as u32))
+            },
+            4 => {
+                let count = try!(cursor.read_u32::<BigEndian>());
+                // Each range takes 6 bytes.
+                if count as usize > (table.len() - offset) / 6 {
+                    return Err(Error::Malformed);
+                }
+                let mut ranges = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let first = try!(cursor.read_u32::<BigEndian>());
+                    ranges.push((first, try!(cursor.read_u16::<BigEndian>())));
+                }
+                Ok(FdSelect::Ranges(ranges, try!(cursor.read_u32::<BigEndian>())))
+            },
+            _ => Err(Error::Malformed),
+        }
+    }
+
+    /// Returns the font dict of the glyph at index `i`.
+    fn font_dict(&self, i: usize) -> Option<usize> {
+        match *self {
+            FdSelect::Single => Some(0),
+            FdSelect::Glyphs(ref fds) => fds.get(i).map(|&fd| fd as usize),
+            FdSelect::Ranges(ref ranges, sentinel) => {
+                if i >= sentinel as usize {
+                    return None;
+                }
+                ranges.iter().rev().find(|&&(first, _)| first as usize <= i).map(|&(_, fd)| fd as usize)
+            },
+        }
+    }
+}
+
+/// A mapping of glyphs to string identifiers of their names.
+#[derive(Debug)]
+enum Charset {
+    /// The predefined ISOAdobe charset, glyphs are their identifiers.
+    IsoAdobe,
+    /// Identifiers of glyphs from the glyph 1, `.notdef` is omitted.
+    Sids(Vec<u16>),
+    /// Predefined expert charsets and charsets of CID-keyed fonts, which
+    /// don't name glyphs of the standard encoding.
+    Unavailable,
+}
+
+impl Charset {
+    fn from_data(table: &[u8], offset: usize, glyphs: usize) -> Result<Charset> {
+        match offset {
+            0 => return Ok(Charset::IsoAdobe),
+            1 | 2 => return Ok(Charset::Unavailable),
+            _ if offset >= table.len() => return Err(Error::Malformed),
+            _ => {},
+        }
+
+        let count = glyphs.saturating_sub(1);
+        let mut sids = Vec::with_capacity(count);
+        let mut cursor = Cursor::new(&table[offset..]);
+        let format = try!(cursor.read_u8());
+        while sids.len() < count {
+            let first = try!(cursor.read_u16::<BigEndian>());
+            let left = match format {
+                0 => 0,
+                1 => try!(cursor.read_u8()) as u16,
+                2 => try!(cursor.read_u16::<BigEndian>()),
+                _ => return Err(Error::Malformed),
+            };
+            for sid in first as u32..first as u32 + left as u32 + 1 {
+                sids.push(sid as u16);
+            }
+        }
+        sids.truncate(count);
+        Ok(Charset::Sids(sids))
+    }
+
+    /// Returns the glyph named by the string identifier `sid`.
+    fn glyph(&self, sid: u16) -> Option<usize> {
+        match *self {
+            Charset::IsoAdobe if sid <= 228 => Some(sid as usize),
+            Charset::Sids(ref sids) => sids.iter().position(|&s| s == sid).map(|i| i + 1),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the string identifier of the name of the character `code` of
+/// the standard encoding.
+fn standard_encoding(code: u8) -> Option<u16> {
+    let code = code as u16;
+    let sid = match code {
+        32..=126 => code - 31,
+        161..=175 => code - 65,
+        177..=180 => code - 66,
+        182..=189 => code - 67,
+        191 => 123,
+        193..=200 => code - 69,
+        202 | 203 => code - 70,
+        205..=208 => code - 71,
+        225 => 138,
+        227 => 139,
+        232..=235 => code - 92,
+        241 => 144,
+        245 => 145,
+        248..=251 => code - 102,
+        _ => return None,
+    };
+    Some(sid)
+}
+
+/// Reads DICT data into pairs of operators and their operands.
+///
+/// Escaped operators are returned as `1200 + the second byte`.
+fn read_dict(data: &[u8]) -> Result<Vec<(u16, Vec<f64>)>> {
+    let mut entries = vec![];
+    let mut operands = vec![];
+    let mut cursor = Cursor::new(data);
+    while (cursor.position() as usize) < data.len() {
+        let b0 = try!(cursor.read_u8());
+        match b0 {
+            0..=22 | 24..=27 => {
+                let operator = if b0 == 12 { 1200 + try!(cursor.read_u8()) as u16 } else { b0 as u16 };
+                entries.push((operator, operands));
+                operands = vec![];
+            },
+            // `blend` of `CFF2` private dicts, blended values are not used.
+            23 => operands.clear(),
+            28 => operands.push(try!(cursor.read_i16::<BigEndian>()) as f64),
+            29 => operands.push(try!(cursor.read_i32::<BigEndian>()) as f64),
+            30 => operands.push(try!(read_real(&mut cursor))),
+            32..=246 => operands.push(b0 as f64 - 139.0),
+            247..=250 => {
+                let b1 = try!(cursor.read_u8()) as f64;
+                operands.push((b0 as f64 - 247.0) * 256.0 + b1 + 108.0);
+            },
+            251..=254 => {
+                let b1 = try!(cursor.read_u8()) as f64;
+                operands.push(-(b0 as f64 - 251.0) * 256.0 - b1 - 108.0);
+            },
+            _ => return Err(Error::Malformed),
+        }
+    }
+    Ok(entries)
+}
+
+/// Reads a real number of DICT data encoded with nibbles.
+fn read_real(cursor: &mut Cursor<&[u8]>) -> Result<f64> {
+    let mut text = String::new();
+    loop {
+        let byte = try!(cursor.read_u8());
+        for &nibble in &[byte >> 4, byte & 0xf] {
+            match nibble {
+                0..=9 => text.push((b'0' + nibble) as char),
+                0xa => text.push('.'),
+                0xb => text.push('E'),
+                0xc => text.push_str("E-"),
+                0xe => text.push('-'),
+                0xf => return text.parse().map_err(|_| Error::Malformed),
+                _ => return Err(Error::Malformed),
+            }
+        }
+    }
+}
+
+/// Returns the first operand of the operator in DICT entries.
+fn operand(dict: &[(u16, Vec<f64>)], operator: u16) -> Option<f64> {
+    dict.iter().find(|entry| entry.0 == operator).and_then(|entry| entry.1.first().cloned())
+}
+
+/// Reads local subroutines of the private dict referenced by the dict.
+fn read_local_subrs(table: &[u8], dict: &[(u16, Vec<f64>)], cff2: bool) -> Result<Index> {
+    let (size, offset) = match dict.iter().find(|entry| entry.0 == PRIVATE) {
+        Some(entry) if entry.1.len() == 2 => (entry.1[0] as usize, entry.1[1] as usize),
+        _ => return Ok(Index::default()),
+    };
+    if offset + size > table.len() {
+        return Err(Error::Malformed);
+    }
+    let private_dict = try!(read_dict(&table[offset..offset + size]));
+    match operand(&private_dict, SUBRS) {
+        // The offset of subroutines is relative to the private dict.
+        Some(subrs) => Ok(try!(Index::from_data(table, offset + subrs as usize, cff2)).0),
+        None => Ok(Index::default()),
+    }
+}
+
+/// Reads the number of regions of each item variation data of the variation
+/// store at `offset`.
+fn read_region_counts(table: &[u8], offset: usize) -> Result<Vec<usize>> {
+    if offset + 2 >= table.len() {
+        return Err(Error::Malformed);
+    }
+
+    // The store is preceded by its length.
+    let store = offset + 2;
+    let mut cursor = Cursor::new(&table[store..]);
+    let _format = try!(cursor.read_u16::<BigEndian>());
+    let _region_list = try!(cursor.read_u32::<BigEndian>());
+    let count = try!(cursor.read_u16::<BigEndian>());
+    let mut region_counts = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let data = store + try!(cursor.read_u32::<BigEndian>()) as usize;
+        if data + 6 > table.len() {
+            return Err(Error::Malformed);
+        }
+        let mut data_cursor = Cursor::new(&table[data + 4..]);
+        region_counts.push(try!(data_cursor.read_u16::<BigEndian>()) as usize);
+    }
+    Ok(region_counts)
+}
+
+/// An interpreter of Type 2 charstrings.
+struct Interpreter<'a, B: 'a + OutlineBuilder> {
+    cff: &'a CFF,
+    local_subrs: &'a Index,
+    builder: &'a mut B,
+    stack: Vec<f32>,
+    x: f32,
+    y: f32,
+    /// The start of the open contour.
+    start: Option<(f32, f32)>,
+    /// The number of stem hints, which is needed to skip hint masks.
+    stems: usize,
+    /// `false` until an operator which could be preceded by the advance
+    /// width is met.
+    width_parsed: bool,
+    vsindex: usize,
+}
+
+impl<'a, B: OutlineBuilder> Interpreter<'a, B> {
+    /// Runs the charstring, returns `true` if `endchar` was met.
+    fn run(&mut self, char_string: &[u8], depth: usize) -> Result<bool> {
+        if depth > MAX_SUBR_DEPTH {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(char_string);
+        while (cursor.position() as usize) < char_string.len() {
+            let b0 = try!(cursor.read_u8());
+            match b0 {
+                // hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => {
+                    self.parse_width(0);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                },
+                // hintmask, cntrmask
+                19 | 20 => {
+                    // Arguments are stems of an implied vstemhm.
+                    self.parse_width(0);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                    let position = cursor.position();
+                    cursor.set_position(position + (self.stems as u64).div_ceil(8));
+                },
+                // rmoveto
+                21 => {
+                    self.parse_width(0);
+                    let (dx, dy) = (try!(self.arg(0)), try!(self.arg(1)));
+                    self.move_to(dx, dy);
+                },
+                // hmoveto
+                22 => {
+                    self.parse_width(1);
+                    let dx = try!(self.arg(0));
+                    self.move_to(dx, 0.0);
+                },
+                // vmoveto
+                4 => {
+                    self.parse_width(1);
+                    let dy = try!(self.arg(0));
+                    self.move_to(0.0, dy);
+                },
+                // rlineto
+                5 => {
+                    let mut i = 0;
+                    while i + 1 < self.stack.len() {
+                        let (dx, dy) = (self.stack[i], self.stack[i + 1]);
+                        self.line_to(dx, dy);
+                        i += 2;
+                    }
+                    self.stack.clear();
+                },
+                // hlineto, vlineto
+                6 | 7 => {
+                    let mut horizontal = b0 == 6;
+                    for i in 0..self.stack.len() {
+                        let d = self.stack[i];
+                        if horizontal { self.line_to(d, 0.0) } else { self.line_to(0.0, d) }
+                        horizontal = !horizontal;
+                    }
+                    self.stack.clear();
+                },
+                // rrcurveto
+                8 => {
+                    let mut i = 0;
+                    while i + 5 < self.stack.len() {
+                        self.curve_at(i);
+                        i += 6;
+                    }
+                    self.stack.clear();
+                },
+                // rcurveline
+                24 => {
+                    if self.stack.len() < 8 {
+                        return Err(Error::Malformed);
+                    }
+                    let mut i = 0;
+                    while i + 7 < self.stack.len() {
+                        self.curve_at(i);
+                        i += 6;
+                    }
+                    let (dx, dy) = (self.stack[i], self.stack[i + 1]);
+                    self.line_to(dx, dy);
+                    self.stack.clear();
+                },
+                // rlinecurve
+                25 => {
+                    if self.stack.len() < 8 {
+                        return Err(Error::Malformed);
+                    }
+                    let mut i = 0;
+                    while i + 7 < self.stack.len() {
+                        let (dx, dy) = (self.stack[i], self.stack[i + 1]);
+                        self.line_to(dx, dy);
+                        i += 2;
+                    }
+                    self.curve_at(i);
+                    self.stack.clear();
+                },
+                // vvcurveto, hhcurveto
+                26 | 27 => {
+                    let mut i = self.stack.len() % 4;
+                    let mut d1 = if i == 1 { self.stack[0] } else { 0.0 };
+                    while i + 3 < self.stack.len() {
+                        let s = &self.stack[i..i + 4];
+                        let c = if b0 == 26 {
+                            [d1, s[0], s[1], s[2], 0.0, s[3]]
+                        } else {
+                            [s[0], d1, s[1], s[2], s[3], 0.0]
+                        };
+                        self.curve(c);
+                        d1 = 0.0;
+                        i += 4;
+                    }
+                    self.stack.clear();
+                },
+                // vhcurveto, hvcurveto
+                30 | 31 => {
+                    let mut horizontal = b0 == 31;
+                    let mut i = 0;
+                    while i + 3 < self.stack.len() {
+                        let s = &self.stack[i..cmp::min(i + 5, self.stack.len())];
+                        // The last curve may have the final coordinate.
+                        let last = if s.len() == 5 && i + 5 == self.stack.len() { s[4] } else { 0.0 };
+                        let c = if horizontal {
+                            [s[0], 0.0, s[1], s[2], last, s[3]]
+                        } else {
+                            [0.0, s[0], s[1], s[2], s[3], last]
+                        };
+                        self.curve(c);
+                        horizontal = !horizontal;
+                        i += 4;
+                    }
+                    self.stack.clear();
+                },
+                // callsubr, callgsubr
+                10 | 29 => {
+                    let subrs = if b0 == 10 { self.local_subrs } else { &self.cff.global_subrs };
+                    let n = match self.stack.pop() {
+                        Some(n) => n as i32 + subrs.bias(),
+                        None => return Err(Error::Malformed),
+                    };
+                    let subr = if n < 0 { None } else { subrs.get(&self.cff.data, n as usize) };
+                    match subr {
+                        Some(subr) => if try!(self.run(subr, depth + 1)) {
+                            return Ok(true);
+                        },
+                        None => return Err(Error::Malformed),
+                    }
+                },
+                // return
+                11 => return Ok(false),
+                // endchar
+                14 => {
+                    if !self.cff.cff2 {
+                        self.parse_width(0);
+                        // With four arguments it's the deprecated `seac`, an accented
+                        // glyph composed of a base and an accent moved by `adx`, `ady`.
+                        if self.stack.len() == 4 {
+                            let (adx, ady) = (self.stack[0], self.stack[1]);
+                            let base = try!(self.cff.standard_glyph(self.stack[2]));
+                            let accent = try!(self.cff.standard_glyph(self.stack[3]));
+                            self.close();
+                            try!(self.cff.run_glyph(base, self.builder, 0.0, 0.0, depth + 1));
+                            try!(self.cff.run_glyph(accent, self.builder, adx, ady, depth + 1));
+                        }
+                        self.stack.clear();
+                        return Ok(true);
+                    }
+                },
+                // vsindex
+                15 => {
+                    match self.stack.pop() {
+                        Some(vsindex) => self.vsindex = vsindex as usize,
+                        None => return Err(Error::Malformed),
+                    }
+                    self.stack.clear();
+                },
+                // blend
+                16 => {
+                    // Only default values are kept, deltas of regions are dropped.
+                    let n = match self.stack.pop() {
+                        Some(n) => n as usize,
+                        None => return Err(Error::Malformed),
+                    };
+                    let regions = self.cff.region_counts.get(self.vsindex).cloned().unwrap_or(0);
+                    if n * (regions + 1) > self.stack.len() {
+                        return Err(Error::Malformed);
+                    }
+                    let len = self.stack.len() - n * regions;
+                    self.stack.truncate(len);
+                },
+                12 => {
+                    let b1 = try!(cursor.read_u8());
+                    try!(self.flex(b1));
+                },
+                28 => {
+                    let value = try!(cursor.read_i16::<BigEndian>()) as f32;
+                    try!(self.push(value));
+                },
+                32..=246 => try!(self.push(b0 as f32 - 139.0)),
+                247..=250 => {
+                    let b1 = try!(cursor.read_u8()) as f32;
+                    try!(self.push((b0 as f32 - 247.0) * 256.0 + b1 + 108.0));
+                },
+                251..=254 => {
+                    let b1 = try!(cursor.read_u8()) as f32;
+                    try!(self.push(-(b0 as f32 - 251.0) * 256.0 - b1 - 108.0));
+                },
+                255 => {
+                    let value = try!(cursor.read_i32::<BigEndian>()) as f32 / 65536.0;
+                    try!(self.push(value));
+                },
+                _ => return Err(Error::Malformed),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Runs the escaped operator, only flex operators are supported.
+    fn flex(&mut self, operator: u8) -> Result<()> {
+        let s = self.stack.clone();
+        match operator {
+            // flex
+            35 if s.len() >= 13 => {
+                self.curve([s[0], s[1], s[2], s[3], s[4], s[5]]);
+                self.curve([s[6], s[7], s[8], s[9], s[10], s[11]]);
+            },
+            // hflex
+            34 if s.len() >= 7 => {
+                self.curve([s[0], 0.0, s[1], s[2], s[3], 0.0]);
+                self.curve([s[4], 0.0, s[5], -s[2], s[6], 0.0]);
+            },
+            // hflex1
+            36 if s.len() >= 9 => {
+                self.curve([s[0], s[1], s[2], s[3], s[4], 0.0]);
+                self.curve([s[5], 0.0, s[6], s[7], s[8], -(s[1] + s[3] + s[7])]);
+            },
+            // flex1
+            37 if s.len() >= 11 => {
+                let dx = s[0] + s[2] + s[4] + s[6] + s[8];
+                let dy = s[1] + s[3] + s[5] + s[7] + s[9];
+                let (x, y) = if dx.abs() > dy.abs() { (s[10], -dy) } else { (-dx, s[10]) };
+                self.curve([s[0], s[1], s[2], s[3], s[4], s[5]]);
+                self.curve([s[6], s[7], s[8], s[9], x, y]);
+            },
+            _ => return Err(Error::Malformed),
+        }
+        self.stack.clear();
+        Ok(())
+    }
+
+    fn push(&mut self, value: f32) -> Result<()> {
+        if self.stack.len() >= MAX_STACK {
+            return Err(Error::Malformed);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn arg(&self, i: usize) -> Result<f32> {
+        self.stack.get(i).cloned().ok_or(Error::Malformed)
+    }
+
+    /// Drops the advance width preceding arguments of the first stack
+    /// clearing operator, `parity` is the number of its arguments modulo 2
+    /// without the width.
+    fn parse_width(&mut self, parity: usize) {
+        if !self.width_parsed {
+            self.width_parsed = true;
+            if self.stack.len() % 2 != parity {
+                self.stack.remove(0);
+            }
+        }
+    }
+
+    fn move_to(&mut self, dx: f32, dy: f32) {
+        self.close();
+        self.x += dx;
+        self.y += dy;
+        self.builder.move_to(self.x, self.y);
+        self.start = Some((self.x, self.y));
+        self.stack.clear();
+    }
+
+    fn line_to(&mut self, dx: f32, dy: f32) {
+        self.ensure_contour();
+        self.x += dx;
+        self.y += dy;
+        self.builder.line_to(self.x, self.y);
+    }
+
+    /// Adds a curve of the arguments at `i`.
+    fn curve_at(&mut self, i: usize) {
+        let s = &self.stack[i..i + 6];
+        let c = [s[0], s[1], s[2], s[3], s[4], s[5]];
+        self.curve(c);
+    }
+
+    /// Adds a curve of relative coordinates of the control points and
+    /// the end point.
+    fn curve(&mut self, d: [f32; 6]) {
+        self.ensure_contour();
+        let (x1, y1) = (self.x + d[0], self.y + d[1]);
+        let (x2, y2) = (x1 + d[2], y1 + d[3]);
+        self.x = x2 + d[4];
+        self.y = y2 + d[5];
+        self.builder.curve_to(x1, y1, x2, y2, self.x, self.y);
+    }
+
+    /// Starts a contour at the current point if drawing starts without
+    /// a move.
+    fn ensure_contour(&mut self) {
+        if self.start.is_none() {
+            self.builder.move_to(self.x, self.y);
+            self.start = Some((self.x, self.y));
+        }
+    }
+
+    /// Closes the open contour, if any.
+    fn close(&mut self) {
+        if let Some((x, y)) = self.start.take() {
+            if (self.x, self.y) != (x, y) {
+                self.builder.line_to(x, y);
+            }
+            self.builder.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error;
+    use outline::{Outline, Segment};
+    use outline::Segment::*;
+    use byteorder::{BigEndian, WriteBytesExt};
+    use expectest::prelude::*;
+
+    /// Assembles a charstring of numbers, operators and raw bytes of hint
+    /// masks written as `0x..`.
+    fn charstring(source: &str) -> Vec<u8> {
+        let mut data = vec![];
+        for token in source.split_whitespace() {
+            let operator: &[u8] = match token {
+                "hstem" => &[1], "vstem" => &[3], "vmoveto" => &[4], "rlineto" => &[5],
+                "hlineto" => &[6], "vlineto" => &[7], "rrcurveto" => &[8], "callsubr" => &[10],
+                "return" => &[11], "endchar" => &[14], "vsindex" => &[15], "blend" => &[16],
+                "hstemhm" => &[18], "hintmask" => &[19], "rmoveto" => &[21], "hmoveto" => &[22],
+                "vstemhm" => &[23], "rcurveline" => &[24], "rlinecurve" => &[25],
+                "vvcurveto" => &[26], "hhcurveto" => &[27], "callgsubr" => &[29],
+                "vhcurveto" => &[30], "hvcurveto" => &[31], "hflex" => &[12, 34],
+                "flex" => &[12, 35], "hflex1" => &[12, 36], "flex1" => &[12, 37],
+                _ => &[],
+            };
+            if !operator.is_empty() {
+                data.extend_from_slice(operator);
+            } else if token.starts_with("0x") {
+                data.push(u8::from_str_radix(&token[2..], 16).unwrap());
+            } else if token.contains('.') {
+                data.push(255);
+                data.write_i32::<BigEndian>((token.parse::<f32>().unwrap() * 65536.0) as i32).unwrap();
+            } else {
+                let value: i32 = token.parse().unwrap();
+                match value {
+                    -107..=107 => data.push((value + 139) as u8),
+                    108..=1131 => {
+                        data.push(((value - 108) / 256 + 247) as u8);
+                        data.push(((value - 108) % 256) as u8);
+                    },
+                    -1131..=-108 => {
+                        data.push(((-value - 108) / 256 + 251) as u8);
+                        data.push(((-value - 108) % 256) as u8);
+                    },
+                    _ => {
+                        data.push(28);
+                        data.write_i16::<BigEndian>(value as i16).unwrap();
+                    },
+                }
+            }
+        }
+        data
+    }
+
+    fn index(items: &[Vec<u8>], cff2: bool) -> Vec<u8> {
+        let mut data = vec![];
+        if cff2 {
+            data.write_u32::<BigEndian>(items.len() as u32).unwrap();
+        } else {
+            data.write_u16::<BigEndian>(items.len() as u16).unwrap();
+        }
+        if items.is_empty() {
+            return data;
+        }
+        data.push(2);
+        let mut offset = 1;
+        data.write_u16::<BigEndian>(offset).unwrap();
+        for item in items {
+            offset += item.len() as u16;
+            data.write_u16::<BigEndian>(offset).unwrap();
+        }
+        for item in items {
+            data.extend_from_slice(item);
+        }
+        data
+    }
+
+    /// Encodes DICT data, all operands take 5 bytes.
+    fn dict(entries: &[(u16, &[usize])]) -> Vec<u8> {
+        let mut data = vec![];
+        for &(operator, operands) in entries {
+            for &operand in operands {
+                data.push(29);
+                data.write_i32::<BigEndian>(operand as i32).unwrap();
+            }
+            if operator >= 1200 {
+                data.extend_from_slice(&[12, (operator - 1200) as u8]);
+            } else {
+                data.push(operator as u8);
+            }
+        }
+        data
+    }
+
+    fn assemble(sources: &[&str]) -> Vec<Vec<u8>> {
+        sources.iter().map(|source| charstring(source)).collect()
+    }
+
+    fn cff(char_strings: &[&str], local_subrs: &[&str], global_subrs: &[&str]) -> Vec<u8> {
+        let names = index(&[b"Test".to_vec()], false);
+        let global_subrs = index(&assemble(global_subrs), false);
+        // The top dict INDEX takes 24 bytes and the string INDEX is empty.
+        let char_strings_offset = 4 + names.len() + 24 + 2 + global_subrs.len();
+        let char_strings = index(&assemble(char_strings), false);
+        let private_offset = char_strings_offset + char_strings.len();
+        let private = dict(&[(SUBRS, &[6])]);
+        let top_dict = dict(&[(CHAR_STRINGS, &[char_strings_offset]),
+                              (PRIVATE, &[private.len(), private_offset])]);
+
+        let mut data = vec![1, 0, 4, 2];
+        data.extend(names);
+        data.extend(index(&[top_dict], false));
+        data.extend(index(&[], false));
+        data.extend(global_subrs);
+        data.extend(char_strings);
+        data.extend(private);
+        data.extend(index(&assemble(local_subrs), false));
+        data
+    }
+
+    fn outlines(data: &[u8]) -> Vec<Vec<Segment>> {
+        let cff = CFF::from_data(data, 0, data.len()).unwrap();
+        (0..cff.glyph_count()).map(|i| {
+            let mut outline = Outline::new();
+            cff.build_outline(i, &mut outline).unwrap();
+            outline.segments().to_vec()
+        }).collect()
+    }
+
+    #[test]
+    fn smoke() {
+        let data = cff(&[
+            "endchar",
+            "50 10 20 rmoveto 100 hlineto 100 vlineto -100 hlineto endchar",
+            "10 20 30 40 hstemhm 5 10 hintmask 0xe0 0 0 rmoveto -107 callsubr -107 callgsubr",
+        ], &["100 0 100 100 0 100 rrcurveto return"], &["-200 -200 rlineto endchar"]);
+        let cff = CFF::from_data(&data, 0, data.len()).unwrap();
+        expect!(cff.is_cff2()).to(be_false());
+        expect!(cff.glyph_count()).to(be_equal_to(3));
+
+        // Only the table is copied, without the data of following tables.
+        let mut font = vec![0; 4];
+        font.extend_from_slice(&data);
+        font.extend_from_slice(&[0; 100]);
+        expect!(CFF::from_data(&font, 4, data.len()).unwrap().data).to(be_equal_to(data.clone()));
+        expect!(CFF::from_data(&font, 4, font.len())).to(be_err().value(Error::Malformed));
+
+        let outlines = outlines(&data);
+        expect!(outlines[0].is_empty()).to(be_true());
+        expect!(&outlines[1][..]).to(be_equal_to(&[
+            MoveTo { x: 10.0, y: 20.0 },
+            LineTo { x: 110.0, y: 20.0 },
+            LineTo { x: 110.0, y: 120.0 },
+            LineTo { x: 10.0, y: 120.0 },
+            LineTo { x: 10.0, y: 20.0 },
+        ][..]));
+        expect!(&outlines[2][..]).to(be_equal_to(&[
+            MoveTo { x: 0.0, y: 0.0 },
+            CurveTo { cx1: 100.0, cy1: 0.0, cx2: 200.0, cy2: 100.0, x: 200.0, y: 200.0 },
+            LineTo { x: 0.0, y: 0.0 },
+        ][..]));
+    }
+
+    #[test]
+    fn curve_operators() {
+        let data = cff(&[
+            "0 0 rmoveto 10 20 30 40 hvcurveto 10 20 30 40 50 vhcurveto \
+             10 0 10 0 10 0 10 0 10 0 10 0 50 flex 10 20 5 30 40 50 60 hflex \
+             10 10 10 10 10 10 10 -10 10 -10 5 flex1 endchar",
+            "0 0 rmoveto 5 10 20 30 40 vvcurveto 5 10 20 30 40 hhcurveto \
+             10 0 20 20 0 10 5 5 rcurveline 0.5 hmoveto 10 10 1 2 3 4 5 6 rlinecurve endchar",
+        ], &[], &[]);
+        let outlines = outlines(&data);
+        expect!(&outlines[0][..]).to(be_equal_to(&[
+            MoveTo { x: 0.0, y: 0.0 },
+            CurveTo { cx1: 10.0, cy1: 0.0, cx2: 30.0, cy2: 30.0, x: 30.0, y: 70.0 },
+            CurveTo { cx1: 30.0, cy1: 80.0, cx2: 50.0, cy2: 110.0, x: 90.0, y: 160.0 },
+            CurveTo { cx1: 100.0, cy1: 160.0, cx2: 110.0, cy2: 160.0, x: 120.0, y: 160.0 },
+            CurveTo { cx1: 130.0, cy1: 160.0, cx2: 140.0, cy2: 160.0, x: 150.0, y: 160.0 },
+            CurveTo { cx1: 160.0, cy1: 160.0, cx2: 180.0, cy2: 165.0, x: 210.0, y: 165.0 },
+            CurveTo { cx1: 250.0, cy1: 165.0, cx2: 300.0, cy2: 160.0, x: 360.0, y: 160.0 },
+            CurveTo { cx1: 370.0, cy1: 170.0, cx2: 380.0, cy2: 180.0, x: 390.0, y: 190.0 },
+            CurveTo { cx1: 400.0, cy1: 180.0, cx2: 410.0, cy2: 170.0, x: 415.0, y: 160.0 },
+            LineTo { x: 0.0, y: 0.0 },
+        ][..]));
+        expect!(&outlines[1][..]).to(be_equal_to(&[
+            MoveTo { x: 0.0, y: 0.0 },
+            CurveTo { cx1: 5.0, cy1: 10.0, cx2: 25.0, cy2: 40.0, x: 25.0, y: 80.0 },
+            CurveTo { cx1: 35.0, cy1: 85.0, cx2: 55.0, cy2: 115.0, x: 95.0, y: 115.0 },
+            CurveTo { cx1: 105.0, cy1: 115.0, cx2: 125.0, cy2: 135.0, x: 125.0, y: 145.0 },
+            LineTo { x: 130.0, y: 150.0 },
+            LineTo { x: 0.0, y: 0.0 },
+            MoveTo { x: 130.5, y: 150.0 },
+            LineTo { x: 140.5, y: 160.0 },
+            CurveTo { cx1: 141.5, cy1: 162.0, cx2: 144.5, cy2: 166.0, x: 149.5, y: 172.0 },
+            LineTo { x: 130.5, y: 150.0 },
+        ][..]));
+    }
+
+    #[test]
+    fn cff2_blend() {
+        let char_strings = index(&assemble(&["100 200 1 2 3 4 2 blend rmoveto -107 callsubr"]), true);
+        let private = dict(&[(SUBRS, &[6])]);
+        let local_subrs = index(&assemble(&["50 hlineto"]), true);
+        // A variation store with one item variation data of two regions.
+        let vstore = vec![0, 22, 0, 1, 0, 0, 0, 0, 0, 1, 0, 0, 0, 12,
+                          0, 0, 0, 0, 0, 2, 0, 0, 0, 1];
+
+        // The top dict takes 19 bytes and global subroutines are empty.
+        let vstore_offset = 5 + 19 + 4;
+        let char_strings_offset = vstore_offset + vstore.len();
+        let private_offset = char_strings_offset + char_strings.len();
+        let font_dicts_offset = private_offset + private.len() + local_subrs.len();
+        let font_dict = dict(&[(PRIVATE, &[private.len(), private_offset])]);
+        let top_dict = dict(&[(CHAR_STRINGS, &[char_strings_offset]),
+                              (FD_ARRAY, &[font_dicts_offset]),
+                              (VSTORE, &[vstore_offset])]);
+
+        let mut data = vec![2, 0, 5];
+        data.write_u16::<BigEndian>(top_dict.len() as u16).unwrap();
+        data.extend(top_dict);
+        data.extend(index(&[], true));
+        data.extend(vstore);
+        data.extend(char_strings);
+        data.extend(private);
+        data.extend(local_subrs);
+        data.extend(index(&[font_dict], true));
+
+        let cff = CFF::from_data_cff2(&data, 0, data.len()).unwrap();
+        expect!(cff.is_cff2()).to(be_true());
+        let mut outline = Outline::new();
+        cff.build_outline(0, &mut outline).unwrap();
+        expect!(outline.segments()).to(be_equal_to(&[
+            MoveTo { x: 100.0, y: 200.0 },
+            LineTo { x: 150.0, y: 200.0 },
+            LineTo { x: 100.0, y: 200.0 },
+        ][..]));
+    }
+
+    #[test]
+    fn seac() {
+        // Glyphs of the ISOAdobe charset are their string identifiers, 'A'
+        // is 34 and 'acute' is 125.
+        let mut char_strings = vec!["endchar"; 126];
+        char_strings[34] = "0 0 rmoveto 100 hlineto 100 vlineto endchar";
+        char_strings[125] = "10 20 rmoveto 30 40 rlineto endchar";
+        char_strings.push("500 200 300 65 194 endchar");
+        char_strings.push("200 300 65 0 endchar");
+        let data = cff(&char_strings, &[], &[]);
+        let cff = CFF::from_data(&data, 0, data.len()).unwrap();
+
+        let mut outline = Outline::new();
+        cff.build_outline(126, &mut outline).unwrap();
+        expect!(outline.segments()).to(be_equal_to(&[
+            MoveTo { x: 0.0, y: 0.0 },
+            LineTo { x: 100.0, y: 0.0 },
+            LineTo { x: 100.0, y: 100.0 },
+            LineTo { x: 0.0, y: 0.0 },
+            MoveTo { x: 210.0, y: 320.0 },
+            LineTo { x: 240.0, y: 360.0 },
+            LineTo { x: 210.0, y: 320.0 },
+        ][..]));
+        // The code 0 is not in the standard encoding.
+        expect!(cff.build_outline(127, &mut Outline::new())).to(be_err().value(Error::Malformed));
+    }
+
+    #[test]
+    fn charsets() {
+        let charset = Charset::from_data(&[0, 0, 0, 0, 0, 34, 0, 125], 3, 3).unwrap();
+        expect!(charset.glyph(125)).to(be_some().value(2));
+        expect!(charset.glyph(35)).to(be_none());
+        let charset = Charset::from_data(&[0, 0, 0, 1, 0, 10, 2], 3, 4).unwrap();
+        expect!(charset.glyph(12)).to(be_some().value(3));
+        let charset = Charset::from_data(&[0, 0, 0, 2, 0, 10, 0, 1, 0, 50, 0, 0], 3, 4).unwrap();
+        expect!(charset.glyph(50)).to(be_some().value(3));
+        expect!(Charset::from_data(&[0, 0, 0, 0, 0, 34], 3, 3)).to(be_err().value(Error::Malformed));
+
+        expect!(Charset::IsoAdobe.glyph(228)).to(be_some().value(228));
+        expect!(Charset::IsoAdobe.glyph(229)).to(be_none());
+        expect!(Charset::Unavailable.glyph(1)).to(be_none());
+        expect!(standard_encoding(b'A')).to(be_some().value(34));
+        expect!(standard_encoding(251)).to(be_some().value(149));
+    }
+
+    #[test]
+    fn malformed_counts() {
+        let (index, end) = Index::from_data(&[0, 0, 0, 1, 1, 1, 2, 0], 0, true).unwrap();
+        expect!(index.len()).to(be_equal_to(1));
+        expect!(end).to(be_equal_to(8));
+        expect!(Index::from_data(&[0, 0, 0, 2, 1, 1, 2, 0], 0, true)).to(be_err().value(Error::Malformed));
+        expect!(Index::from_data(&[0xff, 0xff, 0xff, 0xff, 4, 0, 0, 0, 1], 0, true))
+            .to(be_err().value(Error::Malformed));
+
+        let fd_select = FdSelect::from_data(&[4, 0, 0, 0, 1, 0, 0, 0, 0, 0, 2, 0, 0, 0, 3], 0, 3).unwrap();
+        expect!(fd_select.font_dict(2)).to(be_some().value(2));
+        expect!(FdSelect::from_data(&[4, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0, 0, 0], 0, 3))
+            .to(be_err().value(Error::Malformed));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut data = cff(&["endchar"], &[], &[]);
+        data[0] = 2;
+        expect!(CFF::from_data(&data, 0, data.len())).to(be_err().value(Error::CFFVersionIsNotSupported));
+    }
+}
@@ -1,8 +1,7 @@
 
 use Error;
 use Result;
-use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
-use utils::{read_u16_from_raw_data, read_i16_from_raw_data};
+use utils::{read_u16, read_u32, read_u16_from_raw_data, read_i16_from_raw_data};
 
 #[derive(Debug)]
 pub struct CMAP {
@@ -30,7 +29,7 @@ impl CMAP {
         }
 
         // +2 skip version field.
-        let number_subtables = BigEndian::read_u16(&data[offset + 2..]) as usize;
+        let number_subtables = read_u16(&data[offset + 2..]) as usize;
         let subtables_data = &data[offset + 4..];
         if number_subtables * (2 + 2 + 4) > data.len() {
             return Err(Error::Malformed);
@@ -38,9 +37,9 @@ impl CMAP {
 
         let mut encoding_subtables: Vec<_> = (0..number_subtables).filter_map(|n| {
             let z = n as usize * 8;
-            let platform_id = BigEndian::read_u16(&subtables_data[z + 0..]);
-            let platform_specific_id = BigEndian::read_u16(&subtables_data[z + 2..]);
-            let offset = BigEndian::read_u32(&subtables_data[z + 4..]);
+            let platform_id = read_u16(&subtables_data[z + 0..]);
+            let platform_specific_id = read_u16(&subtables_data[z + 2..]);
+            let offset = read_u32(&subtables_data[z + 4..]);
             Platform::new(platform_id, platform_specific_id).map(|platform| {
                 EncodingSubtable { platform: platform, offset: offset}
             })
@@ -168,7 +167,7 @@ impl Format {
             return Err(Error::Malformed);
         }
 
-        let format = BigEndian::read_u16(&data[offset..]);
+        let format = read_u16(&data[offset..]);
         match format {
             0 => Ok(F0(try!(Format0::from_data(data, offset)))),
             4 => Ok(F4(try!(Format4::from_data(data, offset)))),
@@ -204,13 +203,13 @@ impl Format0 {
             return Err(Error::Malformed);
         }
 
-        let format = BigEndian::read_u16(&data[offset..]);
-        let length = BigEndian::read_u16(&data[offset + 2..]);
+        let format = read_u16(&data[offset..]);
+        let length = read_u16(&data[offset + 2..]);
 
         if length as usize != SIZE {
             return Err(Error::Malformed);
         }
-        let language = BigEndian::read_u16(&data[offset + 4..]);
+        let language = read_u16(&data[offset + 4..]);
 
         Ok(Format0 {
             format: format,
@@ -250,19 +249,19 @@ impl Format4 {
 
         let mut z = offset;
         let mut f = Format4::default();
-        f.format = BigEndian::read_u16(&data[z..]);
+        f.format = read_u16(&data[z..]);
         z += 2;
-        f.length = BigEndian::read_u16(&data[z..]);
+        f.length = read_u16(&data[z..]);
         z += 2;
-        f.language = BigEndian::read_u16(&data[z..]);
+        f.language = read_u16(&data[z..]);
         z += 2;
-        f.seg_count_x2 = BigEndian::read_u16(&data[z..]);
+        f.seg_count_x2 = read_u16(&data[z..]);
         z += 2;
-        f.search_range = BigEndian::read_u16(&data[z..]);
+        f.search_range = read_u16(&data[z..]);
         z += 2;
-        f.entry_selector = BigEndian::read_u16(&data[z..]);
+        f.entry_selector = read_u16(&data[z..]);
         z += 2;
-        f.range_shift = BigEndian::read_u16(&data[z..]);
+        f.range_shift = read_u16(&data[z..]);
         z += 2;
 
 
@@ -273,7 +272,7 @@ impl Format4 {
 
         f.end_code = data[z..z + f.seg_count_x2 as usize].to_owned();
         z += f.seg_count_x2 as usize;
-        f.reserved_pad = BigEndian::read_u16(&data[z..]);
+        f.reserved_pad = read_u16(&data[z..]);
         z += 2;
         f.start_code = data[z..z + f.seg_count_x2 as usize].to_owned();
         z += f.seg_count_x2 as usize;
@@ -293,7 +292,7 @@ impl Format4 {
 
         let mut r = (None, None); // Just to reduce indentation.
         for i in 0..self.end_code.len() / 2 {
-            if BigEndian::read_u16(&self.end_code[i * 2..]) as usize >= code {
+            if read_u16(&self.end_code[i * 2..]) as usize >= code {
                 r = (self.segment_at_index(i), Some(i));
                 break;
             }
@@ -363,11 +362,11 @@ impl Format6 {
             return Err(Error::Malformed);
         }
 
-        let format = BigEndian::read_u16(&data[offset..]);
-        let length = BigEndian::read_u16(&data[offset + 2..]);
-        let language = BigEndian::read_u16(&data[offset + 4..]);
-        let first_code = BigEndian::read_u16(&data[offset + 6..]);
-        let entry_count = BigEndian::read_u16(&data[offset + 8..]);
+        let format = read_u16(&data[offset..]);
+        let length = read_u16(&data[offset + 2..]);
+        let language = read_u16(&data[offset + 4..]);
+        let first_code = read_u16(&data[offset + 6..]);
+        let entry_count = read_u16(&data[offset + 8..]);
 
         let size = entry_count as usize * 2;
         if offset + 2 * 5 + size > data.len() {
@@ -394,7 +393,7 @@ impl Format6 {
             if offset >= self.raw_glyph_index_array.len() {
                 None
             } else {
-                Some(BigEndian::read_u16(&self.raw_glyph_index_array[offset..]) as usize)
+                Some(read_u16(&self.raw_glyph_index_array[offset..]) as usize)
             }
         }
     }
@@ -423,10 +422,10 @@ impl Format1213 {
         }
 
         let mut f = Format1213::default();
-        f.format = BigEndian::read_u32(&data[offset..]);
-        f.length = BigEndian::read_u32(&data[offset + 4..]);
-        f.language = BigEndian::read_u32(&data[offset + 8..]);
-        f.n_groups = BigEndian::read_u32(&data[offset + 12..]);
+        f.format = read_u32(&data[offset..]);
+        f.length = read_u32(&data[offset + 4..]);
+        f.language = read_u32(&data[offset + 8..]);
+        f.n_groups = read_u32(&data[offset + 12..]);
 
         if offset + f.n_groups as usize * 12 > data.len() {
             return Err(Error::Malformed);
@@ -435,9 +434,9 @@ impl Format1213 {
         let data = &data[offset + 4 * 4..];
         for n in 0..f.n_groups {
             let z = n as usize * 3 * 4;
-            let sc = BigEndian::read_u32(&data[z..]);
-            let ec = BigEndian::read_u32(&data[z + 4..]);
-            let sg = BigEndian::read_u32(&data[z + 8..]);
+            let sc = read_u32(&data[z..]);
+            let ec = read_u32(&data[z + 4..]);
+            let sg = read_u32(&data[z + 8..]);
             f.groups.push(GroupFormat1213 {
                 start_char_code: sc,
                 end_char_code: ec,
@@ -0,0 +1,592 @@
+use Error;
+use Result;
+use std::collections::HashMap;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+use outline::Transform;
+use types::BBox;
+
+/// A color table.
+///
+/// The `COLR` table describes color glyphs. Version 0 glyphs are stacks of
+/// layer glyphs filled with solid palette colors, version 1 glyphs are
+/// graphs of paints with gradients, transformations and compositing.
+/// Variations of version 1 paints are ignored, default values are used.
+#[derive(Debug)]
+pub struct COLR {
+    version: u16,
+    /// Glyph, first layer and number of layers of version 0 glyphs.
+    base_glyphs: Vec<(u16, usize, usize)>,
+    layers: Vec<LayerRecord>,
+    /// Glyph and root paint of version 1 glyphs.
+    base_glyph_paints: Vec<(u16, usize)>,
+    layer_paints: Vec<usize>,
+    /// First glyph, last glyph and clip box of ranges of glyphs.
+    clip_boxes: Vec<(u16, u16, BBox)>,
+    paints: Vec<Paint>,
+}
+
+/// A layer of a version 0 color glyph.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LayerRecord {
+    /// The glyph whose outline is filled.
+    pub glyph: u16,
+    /// Index of the color in a palette, `0xffff` is the text color.
+    pub palette_index: u16,
+}
+
+/// A paint of a version 1 color glyph.
+///
+/// Paints are referenced by their index in the table. Coordinates are
+/// in font units, angles are in degrees counterclockwise.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Paint {
+    /// Paints of the layer list from `first_layer` composed on top of each
+    /// other.
+    ColrLayers { first_layer: usize, num_layers: usize },
+    /// A solid palette color.
+    Solid { palette_index: u16, alpha: f32 },
+    /// A gradient along the line from `p0` to `p1`, rotated to be
+    /// perpendicular to the line from `p0` to `p2`.
+    LinearGradient { color_line: ColorLine, p0: (f32, f32), p1: (f32, f32), p2: (f32, f32) },
+    /// A gradient between two circles.
+    RadialGradient { color_line: ColorLine, c0: (f32, f32), r0: f32, c1: (f32, f32), r1: f32 },
+    /// A gradient around `center` between two angles.
+    SweepGradient { color_line: ColorLine, center: (f32, f32), start_angle: f32, end_angle: f32 },
+    /// A paint clipped by the outline of a glyph.
+    Glyph { glyph: u16, paint: usize },
+    /// The paint of another color glyph.
+    ColrGlyph { glyph: u16 },
+    /// A transformed paint.
+    Transform { transform: Transform, paint: usize },
+    /// Two paints composed with each other.
+    Composite { source: usize, mode: CompositeMode, backdrop: usize },
+}
+
+/// Colors of a gradient.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ColorLine {
+    pub extend: Extend,
+    /// Stops sorted by offset.
+    pub stops: Vec<ColorStop>,
+}
+
+/// A color at a position of a gradient.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ColorStop {
+    /// Position on the gradient, 0 is the start and 1 is the end.
+    pub offset: f32,
+    /// Index of the color in a palette, `0xffff` is the text color.
+    pub palette_index: u16,
+    /// Opacity multiplied with the alpha of the color.
+    pub alpha: f32,
+}
+
+/// How a gradient continues outside of its stops.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Extend {
+    /// Colors of the first and the last stop are used.
+    Pad,
+    /// Stops are repeated.
+    Repeat,
+    /// Stops are repeated and every other repetition is mirrored.
+    Reflect,
+}
+
+/// Compositing operators of Porter and Duff and blend modes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CompositeMode {
+    Clear,
+    Source,
+    Destination,
+    SourceOver,
+    DestinationOver,
+    SourceIn,
+    DestinationIn,
+    SourceOut,
+    DestinationOut,
+    SourceAtop,
+    DestinationAtop,
+    Xor,
+    Plus,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Multiply,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+/// The maximum nesting of paints.
+const MAX_PAINT_DEPTH: usize = 64;
+
+impl COLR {
+    /// Returns `COLR` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `COLR` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<COLR> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let version = try!(cursor.read_u16::<BigEndian>());
+        if version > 1 {
+            return Err(Error::COLRVersionIsNotSupported);
+        }
+        let num_base_glyphs = try!(cursor.read_u16::<BigEndian>());
+        let base_glyphs_offset = try!(cursor.read_u32::<BigEndian>()) as usize;
+        let layers_offset = try!(cursor.read_u32::<BigEndian>()) as usize;
+        let num_layers = try!(cursor.read_u16::<BigEndian>());
+
+        let mut colr = COLR {
+            version: version,
+            base_glyphs: vec![],
+            layers: vec![],
+            base_glyph_paints: vec![],
+            layer_paints: vec![],
+            clip_boxes: vec![],
+            paints: vec![],
+        };
+
+        let mut records = try!(cursor_at(data, offset + base_glyphs_offset));
+        for _ in 0..num_base_glyphs {
+            let glyph = try!(records.read_u16::<BigEndian>());
+            let first_layer = try!(records.read_u16::<BigEndian>()) as usize;
+            let count = try!(records.read_u16::<BigEndian>()) as usize;
+            if first_layer + count > num_layers as usize {
+                return Err(Error::Malformed);
+            }
+            colr.base_glyphs.push((glyph, first_layer, count));
+        }
+        colr.base_glyphs.sort_by_key(|record| record.0);
+
+        let mut records = try!(cursor_at(data, offset + layers_offset));
+        for _ in 0..num_layers {
+            colr.layers.push(LayerRecord {
+                glyph: try!(records.read_u16::<BigEndian>()),
+                palette_index: try!(records.read_u16::<BigEndian>()),
+            });
+        }
+
+        if version == 0 {
+            return Ok(colr);
+        }
+
+        let base_glyph_list = try!(cursor.read_u32::<BigEndian>()) as usize;
+        let layer_list = try!(cursor.read_u32::<BigEndian>()) as usize;
+        let clip_list = try!(cursor.read_u32::<BigEndian>()) as usize;
+
+        let mut parser = PaintParser { data: data, offsets: HashMap::new(), paints: vec![] };
+        if base_glyph_list != 0 {
+            let start = offset + base_glyph_list;
+            let mut records = try!(cursor_at(data, start));
+            for _ in 0..try!(records.read_u32::<BigEndian>()) {
+                let glyph = try!(records.read_u16::<BigEndian>());
+                let paint = try!(records.read_u32::<BigEndian>()) as usize;
+                colr.base_glyph_paints.push((glyph, try!(parser.parse(start + paint, 0))));
+            }
+            colr.base_glyph_paints.sort_by_key(|record| record.0);
+        }
+
+        if layer_list != 0 {
+            let start = offset + layer_list;
+            let mut records = try!(cursor_at(data, start));
+            for _ in 0..try!(records.read_u32::<BigEndian>()) {
+                let paint = try!(records.read_u32::<BigEndian>()) as usize;
+                colr.layer_paints.push(try!(parser.parse(start + paint, 0)));
+            }
+        }
+
+        if clip_list != 0 {
+            let start = offset + clip_list;
+            let mut records = try!(cursor_at(data, start));
+            let _format = try!(records.read_u8());
+            for _ in 0..try!(records.read_u32::<BigEndian>()) {
+                let first = try!(records.read_u16::<BigEndian>());
+                let last = try!(records.read_u16::<BigEndian>());
+                let clip_box = try!(records.read_uint::<BigEndian>(3)) as usize;
+                // Both formats start with the box, the second one adds
+                // a variation index.
+                let mut clip_box = try!(cursor_at(data, start + clip_box + 1));
+                let x0 = try!(clip_box.read_i16::<BigEndian>()) as i32;
+                let y0 = try!(clip_box.read_i16::<BigEndian>()) as i32;
+                let x1 = try!(clip_box.read_i16::<BigEndian>()) as i32;
+                let y1 = try!(clip_box.read_i16::<BigEndian>()) as i32;
+                colr.clip_boxes.push((first, last, BBox { x0: x0, y0: y0, x1: x1, y1: y1 }));
+            }
+        }
+
+        colr.paints = parser.paints;
+        Ok(colr)
+    }
+
+    /// Returns the version of the table.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Returns `true` if the glyph is a color glyph of either version.
+    pub fn has_glyph(&self, glyph: u16) -> bool {
+        self.glyph_paint(glyph).is_some() || self.layers(glyph).is_some()
+    }
+
+    /// Returns layers of the version 0 color glyph from bottom to top, or
+    /// `None` if there is no such glyph.
+    pub fn layers(&self, glyph: u16) -> Option<&[LayerRecord]> {
+        self.base_glyphs.binary_search_by_key(&glyph, |record| record.0).ok().map(|i| {
+            let (_, first, count) = self.base_glyphs[i];
+            &self.layers[first..first + count]
+        })
+    }
+
+    /// Returns the index of the root paint of the version 1 color glyph, or
+    /// `None` if there is no such glyph.
+    pub fn glyph_paint(&self, glyph: u16) -> Option<usize> {
+        self.base_glyph_paints.binary_search_by_key(&glyph, |record| record.0).ok()
+            .map(|i| self.base_glyph_paints[i].1)
+    }
+
+    /// Returns the index of the paint at `index` in the layer list.
+    pub fn layer_paint(&self, index: usize) -> Option<usize> {
+        self.layer_paints.get(index).cloned()
+    }
+
+    /// Returns the paint at `index`.
+    pub fn paint(&self, index: usize) -> Option<&Paint> {
+        self.paints.get(index)
+    }
+
+    /// Returns the box in font units outside of which the version 1 color
+    /// glyph is not painted, if the font defines it.
+    pub fn clip_box(&self, glyph: u16) -> Option<BBox> {
+        self.clip_boxes.iter().find(|clip| clip.0 <= glyph && glyph <= clip.1).map(|clip| clip.2)
+    }
+}
+
+fn cursor_at(data: &[u8], offset: usize) -> Result<Cursor<&[u8]>> {
+    if offset > data.len() {
+        return Err(Error::Malformed);
+    }
+    Ok(Cursor::new(&data[offset..]))
+}
+
+fn read_f2dot14(cursor: &mut Cursor<&[u8]>) -> Result<f32> {
+    Ok(try!(cursor.read_i16::<BigEndian>()) as f32 / 16384.0)
+}
+
+fn read_fword(cursor: &mut Cursor<&[u8]>) -> Result<f32> {
+    Ok(try!(cursor.read_i16::<BigEndian>()) as f32)
+}
+
+fn read_point(cursor: &mut Cursor<&[u8]>) -> Result<(f32, f32)> {
+    Ok((try!(read_fword(cursor)), try!(read_fword(cursor))))
+}
+
+/// Reads an angle in degrees stored as a fraction of a half turn.
+fn read_angle(cursor: &mut Cursor<&[u8]>) -> Result<f32> {
+    Ok(try!(read_f2dot14(cursor)) * 180.0)
+}
+
+/// Reads an offset relative to `start`.
+fn read_offset24(cursor: &mut Cursor<&[u8]>, start: usize) -> Result<usize> {
+    Ok(start + try!(cursor.read_uint::<BigEndian>(3)) as usize)
+}
+
+fn read_color_line(data: &[u8], offset: usize, variable: bool) -> Result<ColorLine> {
+    let mut cursor = try!(cursor_at(data, offset));
+    let extend = match try!(cursor.read_u8()) {
+        1 => Extend::Repeat,
+        2 => Extend::Reflect,
+        // Unknown values are treated as padding.
+        _ => Extend::Pad,
+    };
+    let mut stops = vec![];
+    for _ in 0..try!(cursor.read_u16::<BigEndian>()) {
+        stops.push(ColorStop {
+            offset: try!(read_f2dot14(&mut cursor)),
+            palette_index: try!(cursor.read_u16::<BigEndian>()),
+            alpha: try!(read_f2dot14(&mut cursor)),
+        });
+        if variable {
+            try!(cursor.read_u32::<BigEndian>());
+        }
+    }
+    // The sort is stable, so stops at the same offset keep their order.
+    stops.sort_by(|a, b| a.offset.partial_cmp(&b.offset).unwrap());
+    Ok(ColorLine { extend: extend, stops: stops })
+}
+
+fn composite_mode(value: u8) -> Result<CompositeMode> {
+    use self::CompositeMode::*;
+    const MODES: [CompositeMode; 28] = [
+        Clear, Source, Destination, SourceOver, DestinationOver, SourceIn, DestinationIn,
+        SourceOut, DestinationOut, SourceAtop, DestinationAtop, Xor, Plus, Screen, Overlay,
+        Darken, Lighten, ColorDodge, ColorBurn, HardLight, SoftLight, Difference, Exclusion,
+        Multiply, Hue, Saturation, Color, Luminosity,
+    ];
+    MODES.get(value as usize).cloned().ok_or(Error::Malformed)
+}
+
+/// Reads paints into a list, a paint referenced several times is read once.
+struct PaintParser<'a> {
+    data: &'a [u8],
+    /// Indices of read paints by their offsets.
+    offsets: HashMap<usize, usize>,
+    paints: Vec<Paint>,
+}
+
+impl<'a> PaintParser<'a> {
+    /// Reads the paint at `offset` and paints it references, returns its index.
+    fn parse(&mut self, offset: usize, depth: usize) -> Result<usize> {
+        if let Some(&index) = self.offsets.get(&offset) {
+            return Ok(index);
+        }
+        if depth > MAX_PAINT_DEPTH {
+            return Err(Error::Malformed);
+        }
+
+        let data = self.data;
+        let mut cursor = try!(cursor_at(data, offset));
+        let format = try!(cursor.read_u8());
+        // Formats from 3 to 31 with odd numbers, except 11, are variable
+        // versions of the preceding formats.
+        let variable = format % 2 == 1 && format > 1 && format < 32 && format != 11;
+        let paint = match format {
+            1 => {
+                let num_layers = try!(cursor.read_u8()) as usize;
+                let first_layer = try!(cursor.read_u32::<BigEndian>()) as usize;
+                Paint::ColrLayers { first_layer: first_layer, num_layers: num_layers }
+            },
+            2 | 3 => Paint::Solid {
+                palette_index: try!(cursor.read_u16::<BigEndian>()),
+                alpha: try!(read_f2dot14(&mut cursor)),
+            },
+            4 | 5 => {
+                let color_line = try!(read_offset24(&mut cursor, offset));
+                Paint::LinearGradient {
+                    color_line: try!(read_color_line(data, color_line, variable)),
+                    p0: try!(read_point(&mut cursor)),
+                    p1: try!(read_point(&mut cursor)),
+                    p2: try!(read_point(&mut cursor)),
+                }
+            },
+            6 | 7 => {
+                let color_line = try!(read_offset24(&mut cursor, offset));
+                Paint::RadialGradient {
+                    color_line: try!(read_color_line(data, color_line, variable)),
+                    c0: try!(read_point(&mut cursor)),
+                    r0: try!(cursor.read_u16::<BigEndian>()) as f32,
+                    c1: try!(read_point(&mut cursor)),
+                    r1: try!(cursor.read_u16::<BigEndian>()) as f32,
+                }
+            },
+            8 | 9 => {
+                let color_line = try!(read_offset24(&mut cursor, offset));
+                // Sweep angles are biased by a half turn, so that a full turn
+                // fits into the range.
+                Paint::SweepGradient {
+                    color_line: try!(read_color_line(data, color_line, variable)),
+                    center: try!(read_point(&mut cursor)),
+                    start_angle: try!(read_angle(&mut cursor)) + 180.0,
+                    end_angle: try!(read_angle(&mut cursor)) + 180.0,
+                }
+            },
+            10 => {
+                let paint = try!(read_offset24(&mut cursor, offset));
+                let glyph = try!(cursor.read_u16::<BigEndian>());
+                Paint::Glyph { glyph: glyph, paint: try!(self.parse(paint, depth + 1)) }
+            },
+            11 => Paint::ColrGlyph { glyph: try!(cursor.read_u16::<BigEndian>()) },
+            12..=31 => {
+                let paint = try!(read_offset24(&mut cursor, offset));
+                let transform = try!(read_transform(data, format, offset, &mut cursor));
+                Paint::Transform { transform: transform, paint: try!(self.parse(paint, depth + 1)) }
+            },
+            32 => {
+                let source = try!(read_offset24(&mut cursor, offset));
+                let mode = try!(composite_mode(try!(cursor.read_u8())));
+                let backdrop = try!(read_offset24(&mut cursor, offset));
+                Paint::Composite {
+                    source: try!(self.parse(source, depth + 1)),
+                    mode: mode,
+                    backdrop: try!(self.parse(backdrop, depth + 1)),
+                }
+            },
+            _ => return Err(Error::Malformed),
+        };
+
+        self.paints.push(paint);
+        self.offsets.insert(offset, self.paints.len() - 1);
+        Ok(self.paints.len() - 1)
+    }
+}
+
+/// Reads the transformation of paints of formats from 12 to 31, `cursor`
+/// is after the offset of the transformed paint.
+fn read_transform(data: &[u8], format: u8, offset: usize, cursor: &mut Cursor<&[u8]>) -> Result<Transform> {
+    // Formats come in pairs with the same fields.
+    let transform = match format & !1 {
+        12 => {
+            let mut affine = try!(cursor_at(data, try!(read_offset24(cursor, offset))));
+            let mut m = [0.0; 6];
+            for value in &mut m {
+                *value = try!(affine.read_i32::<BigEndian>()) as f32 / 65536.0;
+            }
+            Transform(m)
+        },
+        14 => Transform::translate(try!(read_fword(cursor)), try!(read_fword(cursor))),
+        16 | 18 => Transform::scale(try!(read_f2dot14(cursor)), try!(read_f2dot14(cursor))),
+        20 | 22 => {
+            let scale = try!(read_f2dot14(cursor));
+            Transform::scale(scale, scale)
+        },
+        24 | 26 => Transform::rotate(try!(read_angle(cursor)).to_radians()),
+        _ => {
+            let x_angle = try!(read_angle(cursor)).to_radians();
+            let y_angle = try!(read_angle(cursor)).to_radians();
+            // A positive horizontal angle leans the paint to the left.
+            Transform::shear(-x_angle.tan(), y_angle.tan())
+        },
+    };
+
+    match format & !1 {
+        18 | 22 | 26 | 30 => {
+            let (x, y) = try!(read_point(cursor));
+            Ok(Transform::translate(x, y).combine(&transform).combine(&Transform::translate(-x, -y)))
+        },
+        _ => Ok(transform),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+    use outline::Transform;
+    use types::BBox;
+
+    fn push_u16(data: &mut Vec<u8>, value: u16) {
+        data.extend_from_slice(&[(value >> 8) as u8, value as u8]);
+    }
+
+    fn push_u32(data: &mut Vec<u8>, value: u32) {
+        push_u16(data, (value >> 16) as u16);
+        push_u16(data, value as u16);
+    }
+
+    #[test]
+    fn version_0() {
+        // Header, base glyphs at 14 and layers at 26.
+        let mut data = vec![0, 0, 0, 2, 0, 0, 0, 14, 0, 0, 0, 26, 0, 4];
+        for &value in &[7, 0, 1, 3, 1, 3, 8, 2, 4, 0xffff, 5, 0, 6, 1] {
+            push_u16(&mut data, value);
+        }
+        let colr = COLR::from_data(&data, 0).unwrap();
+        expect!(colr.version()).to(be_equal_to(0));
+        expect!(colr.layers(3)).to(be_some().value(&[
+            LayerRecord { glyph: 4, palette_index: 0xffff },
+            LayerRecord { glyph: 5, palette_index: 0 },
+            LayerRecord { glyph: 6, palette_index: 1 },
+        ][..]));
+        expect!(colr.layers(7)).to(be_some().value(&[LayerRecord { glyph: 8, palette_index: 2 }][..]));
+        expect!(colr.layers(5)).to(be_none());
+        expect!(colr.glyph_paint(3)).to(be_none());
+        expect!(colr.has_glyph(7)).to(be_true());
+        expect!(colr.has_glyph(8)).to(be_false());
+
+        expect!(COLR::from_data(&data[..30], 0)).to(be_err().value(Malformed));
+        expect!(COLR::from_data(&[0, 2], 0)).to(be_err().value(COLRVersionIsNotSupported));
+    }
+
+    #[test]
+    fn version_1() {
+        // Header with base glyph list at 34, layer list at 50 and clip
+        // list at 67.
+        let mut data = vec![0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        for &offset in &[34, 50, 67, 0, 0] {
+            push_u32(&mut data, offset);
+        }
+        // Glyph 2 is a composite at 137, glyph 1 is a scaling at 88.
+        push_u32(&mut data, 2);
+        push_u16(&mut data, 2);
+        push_u32(&mut data, 103);
+        push_u16(&mut data, 1);
+        push_u32(&mut data, 54);
+        // Two layers with the same solid paint at 62.
+        push_u32(&mut data, 2);
+        push_u32(&mut data, 12);
+        push_u32(&mut data, 12);
+        data.extend_from_slice(&[2, 0, 3, 0x20, 0]);
+        // A clip box of glyphs from 1 to 2 at 79.
+        data.extend_from_slice(&[1, 0, 0, 0, 1, 0, 1, 0, 2, 0, 0, 12]);
+        data.extend_from_slice(&[1, 0, 0, 0xff, 0xf6, 0, 100, 0, 200]);
+        // Scaling by 0.5, 1 around 10, 20 of the glyph at 100.
+        data.extend_from_slice(&[18, 0, 0, 12, 0x20, 0, 0x40, 0, 0, 10, 0, 20]);
+        // Glyph 5 filled with the gradient at 106.
+        data.extend_from_slice(&[10, 0, 0, 6, 0, 5]);
+        // Linear gradient with the color line at 122, stops are unsorted.
+        data.extend_from_slice(&[4, 0, 0, 16, 0, 0, 0, 0, 0, 100, 0, 0, 0, 0, 0, 100]);
+        data.extend_from_slice(&[1, 0, 2, 0x40, 0, 0, 1, 0x40, 0, 0, 0, 0, 0, 0x40, 0]);
+        // Source in of the same solid paint at 145.
+        data.extend_from_slice(&[32, 0, 0, 8, 5, 0, 0, 8, 2, 0, 3, 0x20, 0]);
+
+        let colr = COLR::from_data(&data, 0).unwrap();
+        expect!(colr.version()).to(be_equal_to(1));
+        expect!(colr.layers(1)).to(be_none());
+        expect!(colr.has_glyph(1)).to(be_true());
+        expect!(colr.clip_box(2)).to(be_some().value(BBox { x0: 0, y0: -10, x1: 100, y1: 200 }));
+        expect!(colr.clip_box(3)).to(be_none());
+
+        let root = colr.paint(colr.glyph_paint(1).unwrap()).unwrap();
+        let (transform, glyph) = match *root {
+            Paint::Transform { transform, paint } => (transform, colr.paint(paint).unwrap()),
+            _ => panic!("unexpected paint {:?}", root),
+        };
+        expect!(transform).to(be_equal_to(Transform([0.5, 0.0, 0.0, 1.0, 5.0, 0.0])));
+        let gradient = match *glyph {
+            Paint::Glyph { glyph: 5, paint } => colr.paint(paint).unwrap(),
+            _ => panic!("unexpected paint {:?}", glyph),
+        };
+        expect!(gradient).to(be_equal_to(&Paint::LinearGradient {
+            color_line: ColorLine {
+                extend: Extend::Repeat,
+                stops: vec![
+                    ColorStop { offset: 0.0, palette_index: 0, alpha: 1.0 },
+                    ColorStop { offset: 1.0, palette_index: 1, alpha: 1.0 },
+                ],
+            },
+            p0: (0.0, 0.0),
+            p1: (100.0, 0.0),
+            p2: (0.0, 100.0),
+        }));
+
+        let solid = colr.layer_paint(0).unwrap();
+        expect!(colr.layer_paint(1)).to(be_some().value(solid));
+        expect!(colr.paint(solid)).to(be_some().value(&Paint::Solid { palette_index: 3, alpha: 0.5 }));
+        let composite = colr.paint(colr.glyph_paint(2).unwrap()).unwrap();
+        let source = match *composite {
+            Paint::Composite { source, mode: CompositeMode::SourceIn, backdrop } if source == backdrop => source,
+            _ => panic!("unexpected paint {:?}", composite),
+        };
+        expect!(source).not_to(be_equal_to(solid));
+        expect!(colr.paint(source)).to(be_some().value(&Paint::Solid { palette_index: 3, alpha: 0.5 }));
+
+        data[137] = 40;
+        expect!(COLR::from_data(&data, 0)).to(be_err().value(Malformed));
+    }
+}
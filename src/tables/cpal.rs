@@ -0,0 +1,94 @@
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+use types::Color;
+
+/// A color palette table.
+///
+/// The `CPAL` table contains palettes of colors referenced by index from
+/// the `COLR` table. All palettes have the same number of entries.
+#[derive(Debug)]
+pub struct CPAL {
+    palettes: Vec<Vec<Color>>,
+}
+
+impl CPAL {
+    /// Returns `CPAL` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `CPAL` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<CPAL> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let version = try!(cursor.read_u16::<BigEndian>());
+        if version > 1 {
+            return Err(Error::CPALVersionIsNotSupported);
+        }
+        let num_palette_entries = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let num_palettes = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let num_color_records = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let color_records = offset + try!(cursor.read_u32::<BigEndian>()) as usize;
+
+        let mut palettes = Vec::with_capacity(num_palettes);
+        for _ in 0..num_palettes {
+            let first = try!(cursor.read_u16::<BigEndian>()) as usize;
+            if first + num_palette_entries > num_color_records {
+                return Err(Error::Malformed);
+            }
+            let start = color_records + first * 4;
+            let end = start + num_palette_entries * 4;
+            if end > data.len() {
+                return Err(Error::Malformed);
+            }
+            // Colors are stored as blue, green, red and alpha.
+            palettes.push(data[start..end].chunks(4).map(|c| {
+                Color { red: c[2], green: c[1], blue: c[0], alpha: c[3] }
+            }).collect());
+        }
+
+        Ok(CPAL { palettes: palettes })
+    }
+
+    /// Returns the number of palettes.
+    pub fn palette_count(&self) -> usize {
+        self.palettes.len()
+    }
+
+    /// Returns colors of the palette at `index`, or `None` if there is no
+    /// such palette.
+    pub fn palette(&self, index: usize) -> Option<&[Color]> {
+        self.palettes.get(index).map(|palette| &palette[..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+    use types::Color;
+
+    #[test]
+    fn smoke() {
+        let data = [0, 0, 0, 2, 0, 2, 0, 3, 0, 0, 0, 16, 0, 0, 0, 1,
+                    0, 0, 255, 255, 0, 255, 0, 128, 255, 0, 0, 255];
+        let cpal = CPAL::from_data(&data, 0).unwrap();
+        expect!(cpal.palette_count()).to(be_equal_to(2));
+        let red = Color { red: 255, green: 0, blue: 0, alpha: 255 };
+        let green = Color { red: 0, green: 255, blue: 0, alpha: 128 };
+        let blue = Color { red: 0, green: 0, blue: 255, alpha: 255 };
+        expect!(cpal.palette(0)).to(be_some().value(&[red, green][..]));
+        expect!(cpal.palette(1)).to(be_some().value(&[green, blue][..]));
+        expect!(cpal.palette(2)).to(be_none());
+
+        expect!(CPAL::from_data(&data[..24], 0)).to(be_err().value(Malformed));
+        expect!(CPAL::from_data(&[0, 2], 0)).to(be_err().value(CPALVersionIsNotSupported));
+    }
+}
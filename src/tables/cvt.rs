@@ -0,0 +1,60 @@
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+
+/// A control value table.
+///
+/// The 'cvt ' table holds distances in font units that are referenced by
+/// TrueType instructions, e.g. stem widths and heights of glyph features.
+#[derive(Debug, Default)]
+pub struct CVT {
+    values: Vec<i16>,
+}
+
+impl CVT {
+    /// Returns `cvt ` font table.
+    ///
+    /// Attempts to read `size` bytes of `data` starting from `offset`
+    /// position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read.
+    pub fn from_data(data: &[u8], offset: usize, size: usize) -> Result<CVT> {
+        if offset >= data.len() || offset + size > data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..offset + size]);
+        let mut values = Vec::with_capacity(size / 2);
+        for _ in 0..size / 2 {
+            values.push(try!(cursor.read_i16::<BigEndian>()));
+        }
+        Ok(CVT { values: values })
+    }
+
+    /// Returns control values in font units.
+    pub fn values(&self) -> &[i16] {
+        &self.values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        let data = ::utils::read_file("tests/Tuffy_Bold.ttf");
+        let (offset, size) = ::utils::find_table_range(&data, 0, b"cvt ").unwrap().unwrap();
+        let cvt = CVT::from_data(&data, offset, size).unwrap();
+        expect!(cvt.values().len()).to(be_equal_to(2));
+
+        let cvt = CVT::from_data(&[0xff, 0xfe, 0x01, 0x00, 0x07], 0, 5).unwrap();
+        expect!(cvt.values()).to(be_equal_to(&[-2, 256][..]));
+
+        expect!(CVT::from_data(&data, offset, data.len())).to(be_err().value(Malformed));
+    }
+}
@@ -0,0 +1,236 @@
+use Error;
+use Result;
+use std::borrow::Cow;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+use bitmap::{EmbeddedImage, ImageFormat};
+use tables::eblc::{EBLC, BitmapMetrics};
+use MAX_COMPONENT_DEPTH;
+
+/// An embedded bitmap data table.
+///
+/// The `EBDT` table contains monochrome or grayscale bitmaps of glyphs,
+/// the `CBDT` table of the same format also contains PNG images. Bitmaps
+/// are located by the `EBLC` or `CBLC` table.
+#[derive(Debug, Default)]
+pub struct EBDT {
+    bytes: Vec<u8>,
+}
+
+impl EBDT {
+    /// Returns `EBDT` or `CBDT` font table.
+    ///
+    /// Attempts to read `size` bytes of `data` starting from `offset`
+    /// position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize, size: usize) -> Result<EBDT> {
+        if offset >= data.len() || offset + size > data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let bytes = &data[offset..offset + size];
+        // Version 2 is of `EBDT`, version 3 is of `CBDT`.
+        let major_version = try!(Cursor::new(bytes).read_u16::<BigEndian>());
+        if major_version != 2 && major_version != 3 {
+            return Err(Error::EBDTVersionIsNotSupported);
+        }
+        Ok(EBDT { bytes: bytes.to_owned() })
+    }
+
+    /// Returns the image of `glyph` in the strike at index `strike` of
+    /// the location table `eblc`, or `None` if there is no image or its
+    /// format is not supported.
+    ///
+    /// Monochrome, grayscale and composite bitmaps are decoded into coverage,
+    /// PNG images are returned as they are.
+    ///
+    /// # Errors
+    /// Returns error if the bitmap data is malformed.
+    pub fn image(&self, eblc: &EBLC, strike: usize, glyph: u16) -> Result<Option<EmbeddedImage<'_>>> {
+        self.image_at_depth(eblc, strike, glyph, 0)
+    }
+
+    fn image_at_depth(&self, eblc: &EBLC, strike: usize, glyph: u16, depth: usize) -> Result<Option<EmbeddedImage<'_>>> {
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(Error::Malformed);
+        }
+        let location = match eblc.locate(strike, glyph) {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+        if location.offset + location.length > self.bytes.len() {
+            return Err(Error::Malformed);
+        }
+
+        let data = &self.bytes[location.offset..location.offset + location.length];
+        let mut cursor = Cursor::new(data);
+        let metrics = match location.image_format {
+            1 | 2 | 8 | 17 => try!(BitmapMetrics::read_small(&mut cursor)),
+            6 | 7 | 9 | 18 => try!(BitmapMetrics::read_big(&mut cursor)),
+            5 | 19 => try!(location.metrics.ok_or(Error::Malformed)),
+            _ => return Ok(None),
+        };
+        let (width, height) = (metrics.width as usize, metrics.height as usize);
+        let position = cursor.position() as usize;
+
+        let (format, pixels) = match location.image_format {
+            17..=19 => {
+                let length = try!(cursor.read_u32::<BigEndian>()) as usize;
+                let start = position + 4;
+                if start + length > data.len() {
+                    return Err(Error::Malformed);
+                }
+                (ImageFormat::Png, Cow::Borrowed(&data[start..start + length]))
+            },
+            1 | 6 => {
+                let pixels = try!(decode_bitmap(&data[position..], width, height, location.bit_depth, true));
+                (ImageFormat::Coverage, Cow::Owned(pixels))
+            },
+            2 | 5 | 7 => {
+                let pixels = try!(decode_bitmap(&data[position..], width, height, location.bit_depth, false));
+                (ImageFormat::Coverage, Cow::Owned(pixels))
+            },
+            _ => {
+                if location.image_format == 8 {
+                    let _pad = try!(cursor.read_u8());
+                }
+                // Components are placed by their top left corner.
+                let mut pixels = vec![0; width * height];
+                for _ in 0..try!(cursor.read_u16::<BigEndian>()) {
+                    let component = try!(cursor.read_u16::<BigEndian>());
+                    let x_offset = try!(cursor.read_i8()) as isize;
+                    let y_offset = try!(cursor.read_i8()) as isize;
+                    let image = match try!(self.image_at_depth(eblc, strike, component, depth + 1)) {
+                        Some(image) => image,
+                        None => continue,
+                    };
+                    if image.format != ImageFormat::Coverage {
+                        continue;
+                    }
+                    for y in 0..image.height as isize {
+                        for x in 0..image.width as isize {
+                            let (px, py) = (x + x_offset, y + y_offset);
+                            if px >= 0 && py >= 0 && (px as usize) < width && (py as usize) < height {
+                                let pixel = &mut pixels[py as usize * width + px as usize];
+                                *pixel = (*pixel).max(image.data[y as usize * image.width as usize + x as usize]);
+                            }
+                        }
+                    }
+                }
+                (ImageFormat::Coverage, Cow::Owned(pixels))
+            },
+        };
+
+        Ok(Some(EmbeddedImage {
+            format: format,
+            data: pixels,
+            ppem: location.ppem,
+            width: width as u32,
+            height: height as u32,
+            bearing_x: metrics.bearing_x as i32,
+            bearing_y: metrics.bearing_y as i32,
+            advance: Some(metrics.advance as u32),
+        }))
+    }
+}
+
+/// Decodes bitmap data with the most significant bit first into coverage.
+/// Rows of byte aligned data start at whole bytes.
+fn decode_bitmap(data: &[u8], width: usize, height: usize, bit_depth: u8, byte_aligned: bool) -> Result<Vec<u8>> {
+    let depth = bit_depth as usize;
+    if depth == 0 || depth > 8 || 8 % depth != 0 {
+        return Err(Error::Malformed);
+    }
+    let row_bits = if byte_aligned { (width * depth).div_ceil(8) * 8 } else { width * depth };
+    let max = (1 << depth) - 1;
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let bit = y * row_bits + x * depth;
+            let byte = try!(data.get(bit / 8).ok_or(Error::Malformed));
+            let value = (*byte as usize >> (8 - depth - bit % 8)) & max;
+            pixels.push((value * 255 / max) as u8);
+        }
+    }
+    Ok(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    // Returns a location table of one strike with bitmaps of glyphs from 1
+    // on, given by their image formats and lengths at the start of data.
+    fn eblc(bit_depth: u8, images: &[(u16, u32)]) -> EBLC {
+        let mut data = vec![0, 2, 0, 0, 0, 0, 0, 1, 0, 0, 0, 56, 0, 0, 0, 0];
+        data.extend_from_slice(&[0, 0, 0, images.len() as u8]);
+        data.extend_from_slice(&[0; 28]);
+        data.extend_from_slice(&[0, 0, 0, 0, 12, 12, bit_depth, 1]);
+        for (i, _) in images.iter().enumerate() {
+            let glyph = i as u8 + 1;
+            data.extend_from_slice(&[0, glyph, 0, glyph, 0, 0, 0, (images.len() * 8 + i * 16) as u8]);
+        }
+        let mut offset = 4;
+        for &(format, length) in images {
+            data.extend_from_slice(&[0, 1, 0, format as u8, 0, 0, 0, offset as u8, 0, 0, 0, 0]);
+            data.extend_from_slice(&[0, 0, 0, length as u8]);
+            offset += length;
+        }
+        EBLC::from_data(&data, 0).unwrap()
+    }
+
+    #[test]
+    fn bitmaps() {
+        // A 3x2 byte aligned bitmap, a 3x2 bit aligned one and a composite
+        // of them.
+        let mut data = vec![0, 2, 0, 0];
+        data.extend_from_slice(&[2, 3, 1, 2, 4, 0b1010_0000, 0b0100_0000]);
+        data.extend_from_slice(&[2, 3, 0, 2, 4, 0b1010_1000]);
+        data.extend_from_slice(&[3, 4, 0, 3, 5, 0, 0, 2, 0, 1, 1, 1, 0, 2, 0, 2]);
+        let eblc = eblc(1, &[(1, 7), (2, 6), (8, 16)]);
+        let ebdt = EBDT::from_data(&data, 0, data.len()).unwrap();
+
+        let image = ebdt.image(&eblc, 0, 1).unwrap().unwrap();
+        expect!(image.format).to(be_equal_to(ImageFormat::Coverage));
+        expect!((image.width, image.height, image.bearing_x, image.bearing_y)).to(be_equal_to((3, 2, 1, 2)));
+        expect!((image.ppem, image.advance)).to(be_equal_to((12, Some(4))));
+        expect!(&image.data[..]).to(be_equal_to(&[255, 0, 255, 0, 255, 0][..]));
+
+        let image = ebdt.image(&eblc, 0, 2).unwrap().unwrap();
+        expect!(&image.data[..]).to(be_equal_to(&[255, 0, 255, 0, 255, 0][..]));
+
+        let image = ebdt.image(&eblc, 0, 3).unwrap().unwrap();
+        expect!((image.width, image.height)).to(be_equal_to((4, 3)));
+        expect!(&image.data[..]).to(be_equal_to(&[
+            0, 0, 0, 0,
+            0, 255, 0, 255,
+            255, 0, 255, 0,
+        ][..]));
+        expect!(ebdt.image(&eblc, 0, 4)).to(be_ok().value(None));
+
+        expect!(EBDT::from_data(&data, 0, data.len() + 1)).to(be_err().value(Malformed));
+        expect!(EBDT::from_data(&[0, 1, 0, 0], 0, 4)).to(be_err().value(EBDTVersionIsNotSupported));
+    }
+
+    #[test]
+    fn grayscale_and_png() {
+        let mut data = vec![0, 3, 0, 0];
+        data.extend_from_slice(&[1, 2, 0, 1, 2, 0b0001_1011]);
+        data.extend_from_slice(&[1, 2, 0, 1, 2, 0, 0, 0, 3, 1, 2, 3]);
+        let eblc = eblc(2, &[(2, 6), (17, 12)]);
+        let ebdt = EBDT::from_data(&data, 0, data.len()).unwrap();
+
+        let image = ebdt.image(&eblc, 0, 1).unwrap().unwrap();
+        expect!(&image.data[..]).to(be_equal_to(&[0, 85][..]));
+
+        let image = ebdt.image(&eblc, 0, 2).unwrap().unwrap();
+        expect!(image.format).to(be_equal_to(ImageFormat::Png));
+        expect!((image.width, image.height)).to(be_equal_to((2, 1)));
+        expect!(&image.data[..]).to(be_equal_to(&[1, 2, 3][..]));
+    }
+}
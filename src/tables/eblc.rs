@@ -0,0 +1,357 @@
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+
+/// An embedded bitmap location table.
+///
+/// The `EBLC` table and the `CBLC` table, which has the same format, locate
+/// bitmaps of glyphs in the `EBDT` and `CBDT` tables respectively. Bitmaps
+/// are grouped into strikes, each of them is for one size in pixels per em.
+#[derive(Debug)]
+pub struct EBLC {
+    strikes: Vec<Strike>,
+}
+
+#[derive(Debug)]
+struct Strike {
+    ppem_x: u8,
+    ppem_y: u8,
+    bit_depth: u8,
+    subtables: Vec<IndexSubtable>,
+}
+
+#[derive(Debug)]
+struct IndexSubtable {
+    first_glyph: u16,
+    last_glyph: u16,
+    image_format: u16,
+    image_data_offset: usize,
+    glyphs: GlyphOffsets,
+}
+
+#[derive(Debug)]
+enum GlyphOffsets {
+    /// Offsets of all glyphs of the range and the end of the last one,
+    /// index formats 1 and 3.
+    Consecutive(Vec<u32>),
+    /// Images of the same size and metrics of all glyphs of the range or of
+    /// the listed glyphs, index formats 2 and 5.
+    Constant { image_size: usize, metrics: BitmapMetrics, glyphs: Option<Vec<u16>> },
+    /// Glyphs with their offsets and the end of the last one, index format 4.
+    Sparse(Vec<(u16, u32)>),
+}
+
+/// Horizontal metrics of an embedded bitmap in pixels.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct BitmapMetrics {
+    pub height: u8,
+    pub width: u8,
+    /// Offset from the glyph origin to the left of the bitmap.
+    pub bearing_x: i8,
+    /// Offset from the baseline up to the top of the bitmap.
+    pub bearing_y: i8,
+    pub advance: u8,
+}
+
+impl BitmapMetrics {
+    /// Reads small glyph metrics.
+    pub fn read_small(cursor: &mut Cursor<&[u8]>) -> Result<BitmapMetrics> {
+        Ok(BitmapMetrics {
+            height: try!(cursor.read_u8()),
+            width: try!(cursor.read_u8()),
+            bearing_x: try!(cursor.read_i8()),
+            bearing_y: try!(cursor.read_i8()),
+            advance: try!(cursor.read_u8()),
+        })
+    }
+
+    /// Reads big glyph metrics, vertical metrics are skipped.
+    pub fn read_big(cursor: &mut Cursor<&[u8]>) -> Result<BitmapMetrics> {
+        let metrics = try!(BitmapMetrics::read_small(cursor));
+        for _ in 0..3 {
+            try!(cursor.read_u8());
+        }
+        Ok(metrics)
+    }
+}
+
+/// The location of the bitmap of a glyph in the `EBDT` or `CBDT` table.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BitmapLocation {
+    /// Format of the bitmap data.
+    pub image_format: u16,
+    pub offset: usize,
+    pub length: usize,
+    /// Metrics of bitmaps which don't include them.
+    pub metrics: Option<BitmapMetrics>,
+    /// Bits per pixel of the strike.
+    pub bit_depth: u8,
+    /// Pixels per em of the strike.
+    pub ppem: u16,
+}
+
+impl EBLC {
+    /// Returns `EBLC` or `CBLC` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<EBLC> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        // Version 2 is of `EBLC`, version 3 is of `CBLC`.
+        let major_version = try!(cursor.read_u16::<BigEndian>());
+        let _minor_version = try!(cursor.read_u16::<BigEndian>());
+        if major_version != 2 && major_version != 3 {
+            return Err(Error::EBLCVersionIsNotSupported);
+        }
+
+        let mut strikes = vec![];
+        for _ in 0..try!(cursor.read_u32::<BigEndian>()) {
+            let array = offset + try!(cursor.read_u32::<BigEndian>()) as usize;
+            let _index_tables_size = try!(cursor.read_u32::<BigEndian>());
+            let num_subtables = try!(cursor.read_u32::<BigEndian>());
+            // Skips the color reference and metrics of lines.
+            let position = cursor.position();
+            cursor.set_position(position + 4 + 24);
+            let _start_glyph = try!(cursor.read_u16::<BigEndian>());
+            let _end_glyph = try!(cursor.read_u16::<BigEndian>());
+            let ppem_x = try!(cursor.read_u8());
+            let ppem_y = try!(cursor.read_u8());
+            let bit_depth = try!(cursor.read_u8());
+            let _flags = try!(cursor.read_u8());
+
+            if array > data.len() {
+                return Err(Error::Malformed);
+            }
+            let mut records = Cursor::new(&data[array..]);
+            let mut subtables = vec![];
+            for _ in 0..num_subtables {
+                let first_glyph = try!(records.read_u16::<BigEndian>());
+                let last_glyph = try!(records.read_u16::<BigEndian>());
+                let subtable = array + try!(records.read_u32::<BigEndian>()) as usize;
+                if last_glyph < first_glyph {
+                    return Err(Error::Malformed);
+                }
+                subtables.push(try!(IndexSubtable::from_data(data, subtable, first_glyph, last_glyph)));
+            }
+
+            strikes.push(Strike {
+                ppem_x: ppem_x,
+                ppem_y: ppem_y,
+                bit_depth: bit_depth,
+                subtables: subtables,
+            });
+        }
+
+        Ok(EBLC { strikes: strikes })
+    }
+
+    /// Returns sizes in pixels per em of strikes.
+    pub fn ppems(&self) -> Vec<u16> {
+        self.strikes.iter().map(|strike| strike.ppem_y as u16).collect()
+    }
+
+    /// Returns horizontal and vertical sizes in pixels per em of the strike
+    /// at index `strike`.
+    pub fn strike_ppem(&self, strike: usize) -> Option<(u8, u8)> {
+        self.strikes.get(strike).map(|strike| (strike.ppem_x, strike.ppem_y))
+    }
+
+    /// Returns the location of the bitmap of `glyph` in the strike at index
+    /// `strike`, or `None` if the strike has no bitmap for the glyph.
+    pub fn locate(&self, strike: usize, glyph: u16) -> Option<BitmapLocation> {
+        self.strikes.get(strike).and_then(|strike| {
+            strike.subtables.iter()
+                .find(|subtable| subtable.first_glyph <= glyph && glyph <= subtable.last_glyph)
+                .and_then(|subtable| subtable.glyph_range(glyph).map(|range| (subtable, range)))
+                // Glyphs without bitmaps have no data.
+                .filter(|&(_, (start, end, _))| start < end)
+                .map(|(subtable, (start, end, metrics))| BitmapLocation {
+                    image_format: subtable.image_format,
+                    offset: subtable.image_data_offset + start,
+                    length: end - start,
+                    metrics: metrics,
+                    bit_depth: strike.bit_depth,
+                    ppem: strike.ppem_y as u16,
+                })
+        })
+    }
+}
+
+impl IndexSubtable {
+    fn from_data(data: &[u8], offset: usize, first_glyph: u16, last_glyph: u16) -> Result<IndexSubtable> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let index_format = try!(cursor.read_u16::<BigEndian>());
+        let image_format = try!(cursor.read_u16::<BigEndian>());
+        let image_data_offset = try!(cursor.read_u32::<BigEndian>()) as usize;
+        let count = (last_glyph - first_glyph) as usize + 1;
+        let glyphs = match index_format {
+            1 | 3 => {
+                let mut offsets = Vec::with_capacity(count + 1);
+                for _ in 0..count + 1 {
+                    offsets.push(if index_format == 1 {
+                        try!(cursor.read_u32::<BigEndian>())
+                    } else {
+                        try!(cursor.read_u16::<BigEndian>()) as u32
+                    });
+                }
+                GlyphOffsets::Consecutive(offsets)
+            },
+            2 | 5 => {
+                let image_size = try!(cursor.read_u32::<BigEndian>()) as usize;
+                let metrics = try!(BitmapMetrics::read_big(&mut cursor));
+                let glyphs = if index_format == 5 {
+                    let mut glyphs = vec![];
+                    for _ in 0..try!(cursor.read_u32::<BigEndian>()) {
+                        glyphs.push(try!(cursor.read_u16::<BigEndian>()));
+                    }
+                    Some(glyphs)
+                } else {
+                    None
+                };
+                GlyphOffsets::Constant { image_size: image_size, metrics: metrics, glyphs: glyphs }
+            },
+            4 => {
+                // The last pair only gives the end of the last image.
+                let num_glyphs = try!(cursor.read_u32::<BigEndian>());
+                let count = try!(num_glyphs.checked_add(1).ok_or(Error::Malformed));
+                let mut glyphs = vec![];
+                for _ in 0..count {
+                    let glyph = try!(cursor.read_u16::<BigEndian>());
+                    glyphs.push((glyph, try!(cursor.read_u16::<BigEndian>()) as u32));
+                }
+                GlyphOffsets::Sparse(glyphs)
+            },
+            _ => return Err(Error::Malformed),
+        };
+
+        Ok(IndexSubtable {
+            first_glyph: first_glyph,
+            last_glyph: last_glyph,
+            image_format: image_format,
+            image_data_offset: image_data_offset,
+            glyphs: glyphs,
+        })
+    }
+
+    /// Returns the start and the end of the bitmap data of `glyph` relative
+    /// to the image data offset, and metrics shared by glyphs of
+    /// the subtable, if any.
+    fn glyph_range(&self, glyph: u16) -> Option<(usize, usize, Option<BitmapMetrics>)> {
+        let index = (glyph - self.first_glyph) as usize;
+        match self.glyphs {
+            GlyphOffsets::Consecutive(ref offsets) => {
+                Some((offsets[index] as usize, offsets[index + 1] as usize, None))
+            },
+            GlyphOffsets::Constant { image_size, metrics, ref glyphs } => {
+                let index = match *glyphs {
+                    Some(ref glyphs) => glyphs.iter().position(|&g| g == glyph),
+                    None => Some(index),
+                };
+                index.map(|index| (index * image_size, (index + 1) * image_size, Some(metrics)))
+            },
+            GlyphOffsets::Sparse(ref glyphs) => {
+                glyphs[..glyphs.len() - 1].iter().position(|&(g, _)| g == glyph)
+                    .map(|i| (glyphs[i].1 as usize, glyphs[i + 1].1 as usize, None))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    fn push_u16(data: &mut Vec<u8>, value: u16) {
+        data.extend_from_slice(&[(value >> 8) as u8, value as u8]);
+    }
+
+    fn push_u32(data: &mut Vec<u8>, value: u32) {
+        push_u16(data, (value >> 16) as u16);
+        push_u16(data, value as u16);
+    }
+
+    // Returns a table with one strike of the given size and index subtables
+    // given as glyph ranges and data.
+    fn table(ppem: u8, subtables: &[(u16, u16, Vec<u8>)]) -> Vec<u8> {
+        let mut data = vec![0, 3, 0, 0, 0, 0, 0, 1];
+        push_u32(&mut data, 56);
+        push_u32(&mut data, 0);
+        push_u32(&mut data, subtables.len() as u32);
+        data.extend_from_slice(&[0; 28]);
+        data.extend_from_slice(&[0, 0, 0, 0, ppem, ppem, 32, 1]);
+        let mut offset = subtables.len() * 8;
+        for &(first, last, ref subtable) in subtables {
+            push_u16(&mut data, first);
+            push_u16(&mut data, last);
+            push_u32(&mut data, offset as u32);
+            offset += subtable.len();
+        }
+        for &(_, _, ref subtable) in subtables {
+            data.extend_from_slice(subtable);
+        }
+        data
+    }
+
+    #[test]
+    fn index_formats() {
+        let metrics = [3, 2, 1, 3, 4, 0, 0, 0];
+        let mut format1 = vec![0, 1, 0, 17, 0, 0, 1, 0];
+        for &offset in &[0, 10, 10, 25] {
+            push_u32(&mut format1, offset);
+        }
+        let mut format2 = vec![0, 2, 0, 5, 0, 0, 2, 0, 0, 0, 0, 6];
+        format2.extend_from_slice(&metrics);
+        let format3 = vec![0, 3, 0, 17, 0, 0, 3, 0, 0, 0, 0, 7];
+        let format4 = vec![0, 4, 0, 18, 0, 0, 4, 0, 0, 0, 0, 2, 0, 30, 0, 0, 0, 32, 0, 5, 0, 0, 0, 9];
+        let mut format5 = vec![0, 5, 0, 19, 0, 0, 5, 0, 0, 0, 0, 4];
+        format5.extend_from_slice(&metrics);
+        format5.extend_from_slice(&[0, 0, 0, 2, 0, 41, 0, 40]);
+
+        let data = table(16, &[(1, 3, format1), (10, 11, format2), (20, 20, format3),
+                               (30, 33, format4), (40, 45, format5)]);
+        let eblc = EBLC::from_data(&data, 0).unwrap();
+        expect!(eblc.ppems()).to(be_equal_to(vec![16]));
+        expect!(eblc.strike_ppem(0)).to(be_some().value((16, 16)));
+        expect!(eblc.strike_ppem(1)).to(be_none());
+
+        let location = |image_format, offset, length, metrics| BitmapLocation {
+            image_format: image_format,
+            offset: offset,
+            length: length,
+            metrics: metrics,
+            bit_depth: 32,
+            ppem: 16,
+        };
+        let metrics = Some(BitmapMetrics { height: 3, width: 2, bearing_x: 1, bearing_y: 3, advance: 4 });
+        expect!(eblc.locate(0, 1)).to(be_some().value(location(17, 0x100, 10, None)));
+        expect!(eblc.locate(0, 2)).to(be_none());
+        expect!(eblc.locate(0, 3)).to(be_some().value(location(17, 0x10a, 15, None)));
+        expect!(eblc.locate(0, 11)).to(be_some().value(location(5, 0x206, 6, metrics)));
+        expect!(eblc.locate(0, 20)).to(be_some().value(location(17, 0x300, 7, None)));
+        expect!(eblc.locate(0, 32)).to(be_some().value(location(18, 0x405, 4, None)));
+        expect!(eblc.locate(0, 31)).to(be_none());
+        expect!(eblc.locate(0, 40)).to(be_some().value(location(19, 0x504, 4, metrics)));
+        expect!(eblc.locate(0, 42)).to(be_none());
+        expect!(eblc.locate(0, 50)).to(be_none());
+        expect!(eblc.locate(1, 1)).to(be_none());
+
+        expect!(EBLC::from_data(&data[..data.len() - 1], 0)).to(be_err().value(Malformed));
+        let overflow = vec![0, 4, 0, 18, 0, 0, 4, 0, 0xff, 0xff, 0xff, 0xff];
+        expect!(EBLC::from_data(&table(16, &[(30, 33, overflow)]), 0)).to(be_err().value(Malformed));
+        expect!(EBLC::from_data(&[0, 1, 0, 0], 0)).to(be_err().value(EBLCVersionIsNotSupported));
+    }
+}
@@ -0,0 +1,51 @@
+use Error;
+use Result;
+
+/// A font program.
+///
+/// The 'fpgm' table holds TrueType instructions that are executed once,
+/// when the font is first used. It usually defines functions for other
+/// programs.
+#[derive(Debug, Default)]
+pub struct FPGM {
+    instructions: Vec<u8>,
+}
+
+impl FPGM {
+    /// Returns `fpgm` font table.
+    ///
+    /// Attempts to read `size` bytes of `data` starting from `offset`
+    /// position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read.
+    pub fn from_data(data: &[u8], offset: usize, size: usize) -> Result<FPGM> {
+        if offset >= data.len() || offset + size > data.len() {
+            return Err(Error::Malformed);
+        }
+
+        Ok(FPGM { instructions: data[offset..offset + size].to_owned() })
+    }
+
+    /// Returns the instructions of the program.
+    pub fn instructions(&self) -> &[u8] {
+        &self.instructions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        let data = [0, 0, 0xb0, 0x01, 0x21];
+        let fpgm = FPGM::from_data(&data, 2, 3).unwrap();
+        expect!(fpgm.instructions()).to(be_equal_to(&[0xb0, 0x01, 0x21][..]));
+
+        expect!(FPGM::from_data(&data, 2, 4)).to(be_err().value(Malformed));
+        expect!(FPGM::from_data(&data, data.len(), 0)).to(be_err().value(Malformed));
+    }
+}
@@ -0,0 +1,191 @@
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+
+/// A font variations table.
+///
+/// The `fvar` table describes axes of variation of a variable font, e.g.
+/// weight or width, and named instances, i.e. predefined positions on
+/// the axes.
+#[derive(Debug, Default)]
+pub struct FVAR {
+    axes: Vec<VariationAxis>,
+    instances: Vec<NamedInstance>,
+}
+
+/// An axis of variation.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct VariationAxis {
+    /// The tag of the axis, e.g. `wght` for weight.
+    pub tag: [u8; 4],
+    pub min_value: f32,
+    pub default_value: f32,
+    pub max_value: f32,
+    /// Axis flags, `1` is set for axes not to be exposed to users.
+    pub flags: u16,
+    /// The name identifier of the axis name in the naming table.
+    pub name_id: u16,
+}
+
+/// A named instance of a variable font.
+#[derive(Debug, PartialEq, Clone)]
+pub struct NamedInstance {
+    /// The name identifier of the subfamily name in the naming table.
+    pub subfamily_name_id: u16,
+    /// Coordinates of the instance in user space, one for each axis.
+    pub coordinates: Vec<f32>,
+    /// The name identifier of the PostScript name in the naming table,
+    /// if present.
+    pub postscript_name_id: Option<u16>,
+}
+
+impl VariationAxis {
+    /// Returns `value` in user space normalized to the range from -1 to 1,
+    /// where 0 is the default value. Values out of the range of the axis
+    /// are clamped.
+    pub fn normalize(&self, value: f32) -> f32 {
+        let value = value.max(self.min_value).min(self.max_value);
+        if value < self.default_value {
+            (value - self.default_value) / (self.default_value - self.min_value)
+        } else if value > self.default_value {
+            (value - self.default_value) / (self.max_value - self.default_value)
+        } else {
+            0.0
+        }
+    }
+}
+
+impl FVAR {
+    /// Returns `fvar` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `fvar` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<FVAR> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let major_version = try!(cursor.read_u16::<BigEndian>());
+        let _minor_version = try!(cursor.read_u16::<BigEndian>());
+        if major_version != 1 {
+            return Err(Error::FVARVersionIsNotSupported);
+        }
+        let axes_offset = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let _reserved = try!(cursor.read_u16::<BigEndian>());
+        let axis_count = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let axis_size = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let instance_count = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let instance_size = try!(cursor.read_u16::<BigEndian>()) as usize;
+        if axis_size < 20 || instance_size < axis_count * 4 + 4 {
+            return Err(Error::Malformed);
+        }
+
+        let start = offset + axes_offset;
+        let end = start + axis_count * axis_size + instance_count * instance_size;
+        if end > data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut axes = Vec::with_capacity(axis_count);
+        for record in data[start..].chunks(axis_size).take(axis_count) {
+            let mut cursor = Cursor::new(record);
+            let mut tag = [0; 4];
+            tag.copy_from_slice(&record[..4]);
+            cursor.set_position(4);
+            let axis = VariationAxis {
+                tag: tag,
+                min_value: try!(read_fixed(&mut cursor)),
+                default_value: try!(read_fixed(&mut cursor)),
+                max_value: try!(read_fixed(&mut cursor)),
+                flags: try!(cursor.read_u16::<BigEndian>()),
+                name_id: try!(cursor.read_u16::<BigEndian>()),
+            };
+            if axis.min_value > axis.default_value || axis.default_value > axis.max_value {
+                return Err(Error::Malformed);
+            }
+            axes.push(axis);
+        }
+
+        let mut instances = Vec::with_capacity(instance_count);
+        for record in data[start + axis_count * axis_size..].chunks(instance_size).take(instance_count) {
+            let mut cursor = Cursor::new(record);
+            let subfamily_name_id = try!(cursor.read_u16::<BigEndian>());
+            let _flags = try!(cursor.read_u16::<BigEndian>());
+            let mut coordinates = Vec::with_capacity(axis_count);
+            for _ in 0..axis_count {
+                coordinates.push(try!(read_fixed(&mut cursor)));
+            }
+            // The PostScript name identifier is present only in larger records.
+            let postscript_name_id = if instance_size >= axis_count * 4 + 6 {
+                Some(try!(cursor.read_u16::<BigEndian>()))
+            } else {
+                None
+            };
+            instances.push(NamedInstance {
+                subfamily_name_id: subfamily_name_id,
+                coordinates: coordinates,
+                postscript_name_id: postscript_name_id,
+            });
+        }
+
+        Ok(FVAR { axes: axes, instances: instances })
+    }
+
+    /// Returns axes of variation.
+    pub fn axes(&self) -> &[VariationAxis] {
+        &self.axes
+    }
+
+    /// Returns named instances.
+    pub fn instances(&self) -> &[NamedInstance] {
+        &self.instances
+    }
+}
+
+fn read_fixed(cursor: &mut Cursor<&[u8]>) -> Result<f32> {
+    Ok(try!(cursor.read_i32::<BigEndian>()) as f32 / 65536.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        // The weight axis from 100 to 900 and the width axis from 50 to 100,
+        // with the instance of weight 700.
+        let data = [0, 1, 0, 0, 0, 16, 0, 2, 0, 2, 0, 20, 0, 1, 0, 14,
+                    b'w', b'g', b'h', b't', 0, 100, 0, 0, 1, 144, 0, 0, 3, 132, 0, 0, 0, 0, 1, 0,
+                    b'w', b'd', b't', b'h', 0, 50, 0, 0, 0, 100, 0, 0, 0, 100, 0, 0, 0, 1, 1, 1,
+                    0, 2, 0, 0, 2, 188, 0, 0, 0, 100, 0, 0, 1, 2];
+        let fvar = FVAR::from_data(&data, 0).unwrap();
+        expect!(fvar.axes().len()).to(be_equal_to(2));
+        let weight = fvar.axes()[0];
+        expect!(weight.tag).to(be_equal_to(*b"wght"));
+        expect!((weight.min_value, weight.default_value, weight.max_value)).to(be_equal_to((100.0, 400.0, 900.0)));
+        expect!(weight.name_id).to(be_equal_to(256));
+        expect!(fvar.axes()[1].flags).to(be_equal_to(1));
+        expect!(fvar.instances()).to(be_equal_to(&[NamedInstance {
+            subfamily_name_id: 2,
+            coordinates: vec![700.0, 100.0],
+            postscript_name_id: Some(258),
+        }][..]));
+
+        expect!(weight.normalize(400.0)).to(be_equal_to(0.0));
+        expect!(weight.normalize(250.0)).to(be_equal_to(-0.5));
+        expect!(weight.normalize(650.0)).to(be_equal_to(0.5));
+        expect!(weight.normalize(1000.0)).to(be_equal_to(1.0));
+        expect!(weight.normalize(0.0)).to(be_equal_to(-1.0));
+        expect!(fvar.axes()[1].normalize(75.0)).to(be_equal_to(-0.5));
+
+        expect!(FVAR::from_data(&data[..60], 0)).to(be_err().value(Malformed));
+        expect!(FVAR::from_data(&[0, 2, 0, 0], 0)).to(be_err().value(FVARVersionIsNotSupported));
+    }
+}
@@ -5,7 +5,7 @@ use types::BBox;
 use std::io::Cursor;
 use byteorder::{BigEndian, ReadBytesExt};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct GLYF {
     bytes: Vec<u8>,
 }
@@ -88,11 +88,353 @@ impl<'a> GlyphData<'a> {
     pub fn bitmap_box(&self, scale_x: f32, scale_y: f32) -> Option<BBox> {
         self.bitmap_box_subpixel(scale_x, scale_y, 0.0, 0.0)
     }
+
+    /// Returns `true` if the glyph is composed of other glyphs.
+    pub fn is_composite(&self) -> bool {
+        self.number_of_contours() < 0
+    }
+
+    /// Returns the number of points of a simple glyph description.
+    pub fn number_of_points(&self) -> usize {
+        let contours = self.number_of_contours();
+        if contours <= 0 {
+            return 0;
+        }
+        let mut cursor = Cursor::new(self.bytes);
+        cursor.set_position(10 + (contours as u64 - 1) * 2);
+        cursor.read_u16::<BigEndian>().map(|last| last as usize + 1).unwrap_or(0)
+    }
+
+    /// Returns an iterator over points of a simple glyph description.
+    ///
+    /// The iterator yields each point along with a flag which is `true`
+    /// for the last point of a contour. Points are decoded on the fly,
+    /// nothing is allocated.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or the glyph is
+    /// a composite one.
+    pub fn point_iter(&self) -> Result<GlyphPointIter<'a>> {
+        let number_of_contours = self.number_of_contours();
+        if number_of_contours < 0 {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(self.bytes);
+        cursor.set_position(10);
+
+        let mut count = 0;
+        for _ in 0..number_of_contours {
+            let end = try!(cursor.read_u16::<BigEndian>()) as usize + 1;
+            if end < count {
+                return Err(Error::Malformed);
+            }
+            count = end;
+        }
+
+        // Skip instructions.
+        let instruction_length = try!(cursor.read_u16::<BigEndian>()) as u64;
+        let flags_start = cursor.position() + instruction_length;
+        cursor.set_position(flags_start);
+
+        // Find out where the x and y coordinates start.
+        let mut x_length = 0;
+        let mut y_length = 0;
+        let mut n = 0;
+        while n < count {
+            let flag = try!(cursor.read_u8());
+            let repeat = if flag & REPEAT_FLAG != 0 { try!(cursor.read_u8()) as u64 } else { 0 };
+            x_length += coordinate_size(flag, X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE) * (repeat + 1);
+            y_length += coordinate_size(flag, Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE) * (repeat + 1);
+            n += repeat as usize + 1;
+        }
+        let x_start = cursor.position();
+        let y_start = x_start + x_length;
+        if (y_start + y_length) as usize > self.bytes.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut end_points = Cursor::new(self.bytes);
+        end_points.set_position(10);
+        let mut flags = Cursor::new(self.bytes);
+        flags.set_position(flags_start);
+        let mut xs = Cursor::new(self.bytes);
+        xs.set_position(x_start);
+        let mut ys = Cursor::new(self.bytes);
+        ys.set_position(y_start);
+
+        Ok(GlyphPointIter {
+            end_points: end_points,
+            flags: flags,
+            xs: xs,
+            ys: ys,
+            flag: 0,
+            repeat: 0,
+            index: 0,
+            count: count,
+            next_end: None,
+            x: 0,
+            y: 0,
+        })
+    }
+
+    /// Returns an iterator over components of a composite glyph description.
+    ///
+    /// The iterator is empty for simple glyphs.
+    pub fn components(&self) -> ComponentIter<'a> {
+        let mut cursor = Cursor::new(self.bytes);
+        cursor.set_position(10);
+        ComponentIter { cursor: cursor, done: !self.is_composite(), has_instructions: false }
+    }
+
+    /// Returns TrueType instructions of the glyph description.
+    ///
+    /// Instructions of a composite glyph follow its components. The slice
+    /// is empty if the glyph has no instructions or the data is malformed.
+    pub fn instructions(&self) -> &'a [u8] {
+        let contours = self.number_of_contours();
+        let position = if contours >= 0 {
+            10 + contours as u64 * 2
+        } else {
+            let mut components = self.components();
+            while let Some(Ok(_)) = components.next() {}
+            if components.next().is_some() || !components.has_instructions {
+                return &[];
+            }
+            components.cursor.position()
+        };
+
+        let mut cursor = Cursor::new(self.bytes);
+        cursor.set_position(position);
+        let length = cursor.read_u16::<BigEndian>().unwrap_or(0) as usize;
+        let start = position as usize + 2;
+        if start + length > self.bytes.len() {
+            return &[];
+        }
+        &self.bytes[start..start + length]
+    }
+}
+
+/// A point of a glyph outline expressed in unscaled coordinates.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GlyphPoint {
+    pub x: f32,
+    pub y: f32,
+    /// `false` if the point is a quadratic control point.
+    pub on_curve: bool,
+}
+
+/// An iterator over points of a simple glyph description.
+pub struct GlyphPointIter<'a> {
+    end_points: Cursor<&'a [u8]>,
+    flags: Cursor<&'a [u8]>,
+    xs: Cursor<&'a [u8]>,
+    ys: Cursor<&'a [u8]>,
+    flag: u8,
+    repeat: u8,
+    index: usize,
+    count: usize,
+    next_end: Option<usize>,
+    x: i32,
+    y: i32,
+}
+
+impl<'a> Iterator for GlyphPointIter<'a> {
+    type Item = (GlyphPoint, bool);
+
+    fn next(&mut self) -> Option<(GlyphPoint, bool)> {
+        if self.index >= self.count {
+            return None;
+        }
+
+        // All reads are within bounds, it was checked in `point_iter`.
+        if self.repeat > 0 {
+            self.repeat -= 1;
+        } else {
+            self.flag = self.flags.read_u8().unwrap_or(0);
+            if self.flag & REPEAT_FLAG != 0 {
+                self.repeat = self.flags.read_u8().unwrap_or(0);
+            }
+        }
+
+        self.x += read_coordinate(&mut self.xs, self.flag, X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE).unwrap_or(0);
+        self.y += read_coordinate(&mut self.ys, self.flag, Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE).unwrap_or(0);
+
+        // Find the end of the contour the point belongs to.
+        loop {
+            match self.next_end {
+                Some(end) if self.index <= end => break,
+                _ => match self.end_points.read_u16::<BigEndian>() {
+                    Ok(end) => self.next_end = Some(end as usize),
+                    Err(_) => break,
+                },
+            }
+        }
+        let end = self.next_end == Some(self.index);
+        self.index += 1;
+
+        let point = GlyphPoint {
+            x: self.x as f32,
+            y: self.y as f32,
+            on_curve: self.flag & ON_CURVE_POINT != 0,
+        };
+        Some((point, end))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.count - self.index;
+        (left, Some(left))
+    }
+}
+
+/// A reference to another glyph used by a composite glyph.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Component {
+    pub glyph_index: usize,
+    /// The linear part `[a, b, c, d]` of the transformation, so a point
+    /// is mapped to `(a * x + c * y, b * x + d * y)`.
+    pub matrix: [f32; 4],
+    pub offset: ComponentOffset,
+}
+
+/// Describes how a component is positioned.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ComponentOffset {
+    /// The component is moved by `x`, `y`. If `scaled` is `true`, the offset
+    /// should be transformed by the component's matrix. If `round_to_grid`
+    /// is `true`, the offset is rounded to whole pixels when hinting.
+    Offset { x: f32, y: f32, scaled: bool, round_to_grid: bool },
+    /// The point `child` of the component is aligned with the point
+    /// `parent` of already placed components.
+    MatchingPoints { parent: usize, child: usize },
+}
+
+/// An iterator over components of a composite glyph description.
+pub struct ComponentIter<'a> {
+    cursor: Cursor<&'a [u8]>,
+    done: bool,
+    has_instructions: bool,
+}
+
+impl<'a> ComponentIter<'a> {
+    fn read_component(&mut self) -> Result<Component> {
+        let cursor = &mut self.cursor;
+        let flags = try!(cursor.read_u16::<BigEndian>());
+        let glyph_index = try!(cursor.read_u16::<BigEndian>()) as usize;
+
+        let (arg1, arg2) = match (flags & ARG_1_AND_2_ARE_WORDS != 0, flags & ARGS_ARE_XY_VALUES != 0) {
+            (true, true) => (try!(cursor.read_i16::<BigEndian>()) as i32,
+                             try!(cursor.read_i16::<BigEndian>()) as i32),
+            (true, false) => (try!(cursor.read_u16::<BigEndian>()) as i32,
+                              try!(cursor.read_u16::<BigEndian>()) as i32),
+            (false, true) => (try!(cursor.read_i8()) as i32, try!(cursor.read_i8()) as i32),
+            (false, false) => (try!(cursor.read_u8()) as i32, try!(cursor.read_u8()) as i32),
+        };
+
+        let mut matrix = [1.0, 0.0, 0.0, 1.0];
+        if flags & WE_HAVE_A_SCALE != 0 {
+            let scale = try!(read_f2dot14(cursor));
+            matrix[0] = scale;
+            matrix[3] = scale;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            matrix[0] = try!(read_f2dot14(cursor));
+            matrix[3] = try!(read_f2dot14(cursor));
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            matrix[0] = try!(read_f2dot14(cursor));
+            matrix[1] = try!(read_f2dot14(cursor));
+            matrix[2] = try!(read_f2dot14(cursor));
+            matrix[3] = try!(read_f2dot14(cursor));
+        }
+
+        let offset = if flags & ARGS_ARE_XY_VALUES != 0 {
+            ComponentOffset::Offset {
+                x: arg1 as f32,
+                y: arg2 as f32,
+                scaled: flags & SCALED_COMPONENT_OFFSET != 0 &&
+                        flags & UNSCALED_COMPONENT_OFFSET == 0,
+                round_to_grid: flags & ROUND_XY_TO_GRID != 0,
+            }
+        } else {
+            ComponentOffset::MatchingPoints { parent: arg1 as usize, child: arg2 as usize }
+        };
+
+        self.done = flags & MORE_COMPONENTS == 0;
+        self.has_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+
+        Ok(Component {
+            glyph_index: glyph_index,
+            matrix: matrix,
+            offset: offset,
+        })
+    }
+}
+
+impl<'a> Iterator for ComponentIter<'a> {
+    type Item = Result<Component>;
+
+    fn next(&mut self) -> Option<Result<Component>> {
+        if self.done {
+            return None;
+        }
+        let component = self.read_component();
+        if component.is_err() {
+            self.done = true;
+        }
+        Some(component)
+    }
+}
+
+// Flags of a simple glyph description.
+const ON_CURVE_POINT: u8 = 0x01;
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const REPEAT_FLAG: u8 = 0x08;
+const X_IS_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE: u8 = 0x20;
+
+// Flags of a composite glyph description.
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+const ROUND_XY_TO_GRID: u16 = 0x0004;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+const SCALED_COMPONENT_OFFSET: u16 = 0x0800;
+const UNSCALED_COMPONENT_OFFSET: u16 = 0x1000;
+
+/// Returns the number of bytes used by a coordinate encoded according to `flag`.
+fn coordinate_size(flag: u8, short: u8, same_or_positive: u8) -> u64 {
+    if flag & short != 0 {
+        1
+    } else if flag & same_or_positive != 0 {
+        0
+    } else {
+        2
+    }
+}
+
+/// Reads a delta of a coordinate encoded according to `flag`.
+fn read_coordinate(cursor: &mut Cursor<&[u8]>, flag: u8, short: u8, same_or_positive: u8) -> Result<i32> {
+    if flag & short != 0 {
+        let delta = try!(cursor.read_u8()) as i32;
+        Ok(if flag & same_or_positive != 0 { delta } else { -delta })
+    } else if flag & same_or_positive != 0 {
+        Ok(0)
+    } else {
+        Ok(try!(cursor.read_i16::<BigEndian>()) as i32)
+    }
+}
+
+fn read_f2dot14(cursor: &mut Cursor<&[u8]>) -> Result<f32> {
+    Ok(try!(cursor.read_i16::<BigEndian>()) as f32 / 16384.0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use Result;
     use tables::{MAXP, HEAD, LOCA};
 
     #[test]
@@ -108,4 +450,64 @@ mod tests {
         let glyf_offset = ::utils::find_table_offset(&data, 0, b"glyf").unwrap().unwrap();
         let _ = GLYF::from_data(&data, glyf_offset, loca.size_of_glyf_table()).unwrap();
     }
+
+    #[test]
+    fn simple_glyph_points() {
+        // Two contours: an on-curve triangle and a single off-curve point.
+        let data = &[0, 2, 0, 0, 0, 0, 0, 10, 0, 10,
+                     0, 2, 0, 3, // end points of contours
+                     0, 0, // no instructions
+                     0x33, 0x37, 0x15, 0x26, // flags
+                     10, 10, 20, // x
+                     10, 5, 3]; // y
+        let glyf = GLYF { bytes: data.to_vec() };
+        let glyph_data = glyf.glyph_data(0);
+        assert_eq!(glyph_data.number_of_points(), 4);
+        assert!(glyph_data.instructions().is_empty());
+        let points: Vec<_> = glyph_data.point_iter().unwrap().map(|(p, end)| (p.x, p.y, p.on_curve, end)).collect();
+        assert_eq!(points, [(10.0, 0.0, true, false), (20.0, 10.0, true, false),
+                            (20.0, 5.0, true, true), (0.0, 8.0, false, true)]);
+    }
+
+    #[test]
+    fn composite_glyph_components() {
+        let data = &[0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0,
+                     0x00, 0x23, 0, 5, 0, 10, 0xff, 0xf6, // words, xy values, more
+                     0x00, 0x08, 0, 7, 1, 2, 0x20, 0x00]; // bytes, points, scale
+        let glyf = GLYF { bytes: data.to_vec() };
+        let components: Result<Vec<_>> = glyf.glyph_data(0).components().collect();
+        let components = components.unwrap();
+        assert_eq!(components, [
+            Component {
+                glyph_index: 5,
+                matrix: [1.0, 0.0, 0.0, 1.0],
+                offset: ComponentOffset::Offset { x: 10.0, y: -10.0, scaled: false, round_to_grid: false },
+            },
+            Component {
+                glyph_index: 7,
+                matrix: [0.5, 0.0, 0.0, 0.5],
+                offset: ComponentOffset::MatchingPoints { parent: 1, child: 2 },
+            },
+        ]);
+        assert!(glyf.glyph_data(0).instructions().is_empty());
+    }
+
+    #[test]
+    fn glyph_instructions() {
+        let data = &[0, 1, 0, 0, 0, 0, 0, 0, 0, 0,
+                     0, 0, // end point of the contour
+                     0, 2, 0x2e, 0x30, // MDAP, IUP
+                     0x37, 0]; // flags, x
+        let glyf = GLYF { bytes: data.to_vec() };
+        assert_eq!(glyf.glyph_data(0).instructions(), &[0x2e, 0x30]);
+
+        // Rounded offsets and instructions after the last component.
+        let data = &[0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0,
+                     0x01, 0x06, 0, 5, 1, 2,
+                     0, 1, 0x31];
+        let glyf = GLYF { bytes: data.to_vec() };
+        let component = glyf.glyph_data(0).components().next().unwrap().unwrap();
+        assert_eq!(component.offset, ComponentOffset::Offset { x: 1.0, y: 2.0, scaled: false, round_to_grid: true });
+        assert_eq!(glyf.glyph_data(0).instructions(), &[0x31]);
+    }
 }
@@ -0,0 +1,440 @@
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+use tables::layout::{self, LayoutHeader, Coverage, ClassDef, Script, Feature};
+
+/// A glyph positioning table.
+///
+/// The `GPOS` table contains lookups that adjust positions of glyphs.
+/// Only pair adjustment lookups (type 2) of both formats are used, directly
+/// or through extension lookups (type 9), subtables of other types are
+/// ignored.
+#[derive(Debug)]
+pub struct GPOS {
+    scripts: Vec<Script>,
+    features: Vec<Feature>,
+    lookups: Vec<Vec<PairAdjustment>>,
+    kern_lookups: Vec<usize>,
+}
+
+/// The type of pair adjustment lookups.
+const PAIR_ADJUSTMENT: u16 = 2;
+/// The type of extension lookups.
+const EXTENSION: u16 = 9;
+
+impl GPOS {
+    /// Returns `GPOS` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `GPOS` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<GPOS> {
+        let header = match try!(LayoutHeader::from_data(data, offset)) {
+            Some(header) => header,
+            None => return Err(Error::GPOSVersionIsNotSupported),
+        };
+
+        let scripts = try!(layout::read_script_list(data, header.script_list));
+        let features = try!(layout::read_feature_list(data, header.feature_list));
+        let mut lookups = vec![];
+        for lookup in try!(layout::read_lookup_list(data, header.lookup_list, EXTENSION)) {
+            let mut subtables = vec![];
+            if lookup.lookup_type == PAIR_ADJUSTMENT {
+                for &subtable in &lookup.subtables {
+                    subtables.push(try!(PairAdjustment::from_data(data, subtable)));
+                }
+            }
+            lookups.push(subtables);
+        }
+
+        // Kerning does not depend on a script, so lookups of all language
+        // systems are used. Required features are not kerning, unless they
+        // are `kern` features.
+        let lang_systems: Vec<_> = scripts.iter().flat_map(|script| script.all_lang_systems()).collect();
+        let kern_lookups = layout::feature_lookups(&features, &lang_systems, &[*b"kern"], false)
+            .into_iter()
+            .filter(|&lookup| lookup < lookups.len())
+            .collect();
+
+        Ok(GPOS {
+            scripts: scripts,
+            features: features,
+            lookups: lookups,
+            kern_lookups: kern_lookups,
+        })
+    }
+
+    /// Returns tags of scripts of the table.
+    pub fn script_tags(&self) -> Vec<[u8; 4]> {
+        self.scripts.iter().map(|script| script.tag).collect()
+    }
+
+    /// Returns tags of features of the table.
+    pub fn feature_tags(&self) -> Vec<[u8; 4]> {
+        self.features.iter().map(|feature| feature.tag).collect()
+    }
+
+    /// Returns `true` if the table has pair adjustment lookups of
+    /// the `kern` feature.
+    pub fn has_kerning(&self) -> bool {
+        self.kern_lookups.iter().any(|&lookup| !self.lookups[lookup].is_empty())
+    }
+
+    /// Returns adjustments of the glyphs at indices `left` and `right`
+    /// made by lookups of the `kern` feature, or `None` if no lookup
+    /// applies to the pair.
+    pub fn pair_adjustment(&self, left: usize, right: usize) -> Option<(ValueRecord, ValueRecord)> {
+        let mut result = None;
+        for &lookup in &self.kern_lookups {
+            // The first subtable which applies to the pair ends the lookup.
+            let adjustment = self.lookups[lookup].iter()
+                .filter_map(|subtable| subtable.adjustment(left, right))
+                .next();
+            if let Some((first, second)) = adjustment {
+                let (total_first, total_second) = result.unwrap_or_default();
+                result = Some((first.add(total_first), second.add(total_second)));
+            }
+        }
+        result
+    }
+
+    /// Returns the kerning between glyphs at indices `left` and `right`
+    /// for horizontal text.
+    ///
+    /// The value is an adjustment of the horizontal advance of the `left`
+    /// glyph expressed in unscaled coordinates. It includes the horizontal
+    /// placement of the `right` glyph, which moves it by the same distance.
+    pub fn kerning(&self, left: usize, right: usize) -> i32 {
+        self.pair_adjustment(left, right).map_or(0, |(first, second)| first.x_advance + second.x_placement)
+    }
+}
+
+/// Adjustments of a glyph position and advance in unscaled coordinates.
+///
+/// Device and variation adjustments are not supported.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ValueRecord {
+    pub x_placement: i32,
+    pub y_placement: i32,
+    pub x_advance: i32,
+    pub y_advance: i32,
+}
+
+impl ValueRecord {
+    fn read(cursor: &mut Cursor<&[u8]>, format: u16) -> Result<ValueRecord> {
+        let mut values = [0; 4];
+        for (i, value) in values.iter_mut().enumerate() {
+            if format & (1 << i) != 0 {
+                *value = try!(cursor.read_i16::<BigEndian>()) as i32;
+            }
+        }
+        // Offsets of device tables.
+        for i in 4..8 {
+            if format & (1 << i) != 0 {
+                try!(cursor.read_u16::<BigEndian>());
+            }
+        }
+
+        Ok(ValueRecord {
+            x_placement: values[0],
+            y_placement: values[1],
+            x_advance: values[2],
+            y_advance: values[3],
+        })
+    }
+
+    fn add(self, other: ValueRecord) -> ValueRecord {
+        ValueRecord {
+            x_placement: self.x_placement + other.x_placement,
+            y_placement: self.y_placement + other.y_placement,
+            x_advance: self.x_advance + other.x_advance,
+            y_advance: self.y_advance + other.y_advance,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum PairAdjustment {
+    /// Adjustments of individual pairs, indexed by coverage indices of
+    /// the first glyph and sorted by the second glyph.
+    Format1 {
+        coverage: Coverage,
+        pair_sets: Vec<Vec<(u16, ValueRecord, ValueRecord)>>,
+    },
+    /// Adjustments of pairs of glyph classes.
+    Format2 {
+        coverage: Coverage,
+        first_classes: ClassDef,
+        second_classes: ClassDef,
+        first_class_count: usize,
+        second_class_count: usize,
+        records: Vec<(ValueRecord, ValueRecord)>,
+    },
+}
+
+impl PairAdjustment {
+    fn from_data(data: &[u8], offset: usize) -> Result<PairAdjustment> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let format = try!(cursor.read_u16::<BigEndian>());
+        let coverage = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+        let coverage = try!(Coverage::from_data(data, coverage));
+        let first_format = try!(cursor.read_u16::<BigEndian>());
+        let second_format = try!(cursor.read_u16::<BigEndian>());
+        match format {
+            1 => {
+                let count = try!(cursor.read_u16::<BigEndian>());
+                let mut pair_sets = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let pair_set = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                    if pair_set >= data.len() {
+                        return Err(Error::Malformed);
+                    }
+                    let mut pair_cursor = Cursor::new(&data[pair_set..]);
+                    let pair_count = try!(pair_cursor.read_u16::<BigEndian>());
+                    let mut pairs = Vec::with_capacity(pair_count as usize);
+                    for _ in 0..pair_count {
+                        let second = try!(pair_cursor.read_u16::<BigEndian>());
+                        let first_value = try!(ValueRecord::read(&mut pair_cursor, first_format));
+                        let second_value = try!(ValueRecord::read(&mut pair_cursor, second_format));
+                        pairs.push((second, first_value, second_value));
+                    }
+                    pair_sets.push(pairs);
+                }
+                Ok(PairAdjustment::Format1 {
+                    coverage: coverage,
+                    pair_sets: pair_sets,
+                })
+            },
+            2 => {
+                let first_classes = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                let second_classes = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                let first_class_count = try!(cursor.read_u16::<BigEndian>()) as usize;
+                let second_class_count = try!(cursor.read_u16::<BigEndian>()) as usize;
+                let mut records = Vec::with_capacity(first_class_count * second_class_count);
+                for _ in 0..first_class_count * second_class_count {
+                    let first_value = try!(ValueRecord::read(&mut cursor, first_format));
+                    let second_value = try!(ValueRecord::read(&mut cursor, second_format));
+                    records.push((first_value, second_value));
+                }
+                Ok(PairAdjustment::Format2 {
+                    coverage: coverage,
+                    first_classes: try!(ClassDef::from_data(data, first_classes)),
+                    second_classes: try!(ClassDef::from_data(data, second_classes)),
+                    first_class_count: first_class_count,
+                    second_class_count: second_class_count,
+                    records: records,
+                })
+            },
+            _ => Err(Error::Malformed),
+        }
+    }
+
+    fn adjustment(&self, left: usize, right: usize) -> Option<(ValueRecord, ValueRecord)> {
+        match *self {
+            PairAdjustment::Format1 { ref coverage, ref pair_sets } => {
+                if right > 0xffff {
+                    return None;
+                }
+                coverage.index(left).and_then(|i| pair_sets.get(i)).and_then(|pairs| {
+                    pairs.binary_search_by_key(&(right as u16), |&(second, _, _)| second).ok()
+                        .map(|i| (pairs[i].1, pairs[i].2))
+                })
+            },
+            PairAdjustment::Format2 {
+                ref coverage, ref first_classes, ref second_classes,
+                first_class_count, second_class_count, ref records
+            } => {
+                let first = first_classes.class(left) as usize;
+                let second = second_classes.class(right) as usize;
+                if !coverage.contains(left) || first >= first_class_count || second >= second_class_count {
+                    return None;
+                }
+                records.get(first * second_class_count + second).cloned()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tables::layout::tests::{header, lookup_list, coverage, class_def};
+    use byteorder::{BigEndian, WriteBytesExt};
+    use expectest::prelude::*;
+
+    /// Returns a pair adjustment subtable of the format 1 with x advances
+    /// of the first glyphs.
+    fn format1(pairs: &[(u16, u16, i16)]) -> Vec<u8> {
+        let mut firsts: Vec<u16> = pairs.iter().map(|&(first, _, _)| first).collect();
+        firsts.dedup();
+        let mut data = vec![];
+        data.write_u16::<BigEndian>(1).unwrap();
+        let mut offset = 10 + firsts.len() * 2;
+        data.write_u16::<BigEndian>(offset as u16).unwrap();
+        data.write_u16::<BigEndian>(0x0004).unwrap();
+        data.write_u16::<BigEndian>(0).unwrap();
+        data.write_u16::<BigEndian>(firsts.len() as u16).unwrap();
+        offset += 4 + firsts.len() * 2;
+        let mut pair_sets = vec![];
+        for &first in &firsts {
+            data.write_u16::<BigEndian>(offset as u16).unwrap();
+            let seconds: Vec<_> = pairs.iter().filter(|&&(f, _, _)| f == first).collect();
+            pair_sets.write_u16::<BigEndian>(seconds.len() as u16).unwrap();
+            for &&(_, second, value) in &seconds {
+                pair_sets.write_u16::<BigEndian>(second).unwrap();
+                pair_sets.write_i16::<BigEndian>(value).unwrap();
+            }
+            offset += 2 + seconds.len() * 4;
+        }
+        data.extend_from_slice(&coverage(&firsts));
+        data.extend_from_slice(&pair_sets);
+        data
+    }
+
+    /// Returns a pair adjustment subtable of the format 2 with x advances
+    /// of the first glyphs and x placements of the second glyphs.
+    fn format2(covered: &[u16], first_classes: &[(u16, u16, u16)],
+               second_classes: &[(u16, u16, u16)], values: &[&[(i16, i16)]]) -> Vec<u8> {
+        let mut data = vec![];
+        data.write_u16::<BigEndian>(2).unwrap();
+        let mut offset = 16 + values.iter().map(|row| row.len() * 4).sum::<usize>();
+        data.write_u16::<BigEndian>(offset as u16).unwrap();
+        data.write_u16::<BigEndian>(0x0004).unwrap();
+        data.write_u16::<BigEndian>(0x0001).unwrap();
+        offset += 4 + covered.len() * 2;
+        data.write_u16::<BigEndian>(offset as u16).unwrap();
+        offset += 4 + first_classes.len() * 6;
+        data.write_u16::<BigEndian>(offset as u16).unwrap();
+        data.write_u16::<BigEndian>(values.len() as u16).unwrap();
+        data.write_u16::<BigEndian>(values[0].len() as u16).unwrap();
+        for row in values {
+            for &(advance, placement) in row.iter() {
+                data.write_i16::<BigEndian>(advance).unwrap();
+                data.write_i16::<BigEndian>(placement).unwrap();
+            }
+        }
+        data.extend_from_slice(&coverage(covered));
+        data.extend_from_slice(&class_def(first_classes));
+        data.extend_from_slice(&class_def(second_classes));
+        data
+    }
+
+    /// Returns an extension subtable followed by `subtable`.
+    fn extension(lookup_type: u16, subtable: &[u8]) -> Vec<u8> {
+        let mut data = vec![];
+        data.write_u16::<BigEndian>(1).unwrap();
+        data.write_u16::<BigEndian>(lookup_type).unwrap();
+        data.write_u32::<BigEndian>(8).unwrap();
+        data.extend_from_slice(subtable);
+        data
+    }
+
+    #[test]
+    fn pair_adjustment_format1() {
+        let mut data = header(&[(b"kern", &[0])]);
+        let subtable = format1(&[(36, 57, -80), (36, 58, -60), (40, 53, 20)]);
+        lookup_list(&mut data, &[(2, &[&subtable])]);
+        let gpos = GPOS::from_data(&data, 0).unwrap();
+        expect!(gpos.script_tags()).to(be_equal_to(vec![*b"DFLT"]));
+        expect!(gpos.feature_tags()).to(be_equal_to(vec![*b"kern"]));
+        expect!(gpos.has_kerning()).to(be_true());
+        expect!(gpos.kerning(36, 57)).to(be_equal_to(-80));
+        expect!(gpos.kerning(36, 58)).to(be_equal_to(-60));
+        expect!(gpos.kerning(40, 53)).to(be_equal_to(20));
+        expect!(gpos.kerning(57, 36)).to(be_equal_to(0));
+        expect!(gpos.pair_adjustment(36, 59)).to(be_none());
+    }
+
+    #[test]
+    fn pair_adjustment_format2() {
+        let mut data = header(&[(b"kern", &[0])]);
+        let subtable = format2(&[10, 11, 12], &[(11, 12, 1)], &[(20, 21, 1), (22, 22, 2)],
+                               &[&[(0, 0), (-10, 0), (-20, 5)], &[(0, 0), (-30, 0), (-40, 7)]]);
+        lookup_list(&mut data, &[(2, &[&subtable])]);
+        let gpos = GPOS::from_data(&data, 0).unwrap();
+        expect!(gpos.kerning(10, 20)).to(be_equal_to(-10));
+        expect!(gpos.kerning(10, 22)).to(be_equal_to(-15));
+        expect!(gpos.kerning(12, 21)).to(be_equal_to(-30));
+        expect!(gpos.kerning(11, 22)).to(be_equal_to(-33));
+        // Uncovered glyphs and glyphs of the class 0.
+        expect!(gpos.pair_adjustment(13, 20)).to(be_none());
+        expect!(gpos.kerning(12, 30)).to(be_equal_to(0));
+
+        let (first, second) = gpos.pair_adjustment(11, 22).unwrap();
+        expect!(first.x_advance).to(be_equal_to(-40));
+        expect!(second.x_placement).to(be_equal_to(7));
+    }
+
+    #[test]
+    fn second_value_record() {
+        // Kerning in placements of the second glyphs only.
+        let mut data = header(&[(b"kern", &[0])]);
+        let subtable = format2(&[10], &[(10, 10, 1)], &[(20, 20, 1)], &[&[(0, 0), (0, 0)], &[(0, 0), (0, -25)]]);
+        lookup_list(&mut data, &[(2, &[&subtable])]);
+        let gpos = GPOS::from_data(&data, 0).unwrap();
+        expect!(gpos.kerning(10, 20)).to(be_equal_to(-25));
+        expect!(gpos.kerning(10, 21)).to(be_equal_to(0));
+    }
+
+    #[test]
+    fn extension_lookups() {
+        // The format 1 subtable overrides the format 2 subtable in the same
+        // lookup, values of distinct lookups are summed, lookups of other
+        // features are ignored.
+        let mut data = header(&[(b"kern", &[0, 2]), (b"dist", &[1])]);
+        let pairs = extension(2, &format1(&[(10, 20, -5)]));
+        let classes = extension(2, &format2(&[10], &[(10, 10, 1)], &[(20, 21, 1)],
+                                            &[&[(0, 0), (0, 0)], &[(0, 0), (-15, 0)]]));
+        let dist = format1(&[(10, 20, 100)]);
+        let other = format1(&[(10, 21, -1)]);
+        lookup_list(&mut data, &[(9, &[&pairs, &classes]), (2, &[&dist]), (2, &[&other])]);
+        let gpos = GPOS::from_data(&data, 0).unwrap();
+        expect!(gpos.kerning(10, 20)).to(be_equal_to(-5));
+        expect!(gpos.kerning(10, 21)).to(be_equal_to(-16));
+    }
+
+    #[test]
+    fn without_kern_feature() {
+        let mut data = header(&[(b"mark", &[0])]);
+        let subtable = format1(&[(36, 57, -80)]);
+        lookup_list(&mut data, &[(2, &[&subtable])]);
+        let gpos = GPOS::from_data(&data, 0).unwrap();
+        expect!(gpos.has_kerning()).to(be_false());
+        expect!(gpos.kerning(36, 57)).to(be_equal_to(0));
+    }
+
+    #[test]
+    fn required_feature() {
+        // The language system requires the 'dist' feature, whose pairs are
+        // not kerning.
+        let mut data = header(&[(b"kern", &[0]), (b"dist", &[1])]);
+        data[24..26].copy_from_slice(&[0, 1]);
+        let kern = format1(&[(36, 57, -80)]);
+        let dist = format1(&[(36, 57, 100), (36, 58, 50)]);
+        lookup_list(&mut data, &[(2, &[&kern]), (2, &[&dist])]);
+        let gpos = GPOS::from_data(&data, 0).unwrap();
+        expect!(gpos.kerning(36, 57)).to(be_equal_to(-80));
+        expect!(gpos.kerning(36, 58)).to(be_equal_to(0));
+
+        let mut data = header(&[(b"dist", &[0])]);
+        data[24..26].copy_from_slice(&[0, 0]);
+        lookup_list(&mut data, &[(2, &[&dist])]);
+        let gpos = GPOS::from_data(&data, 0).unwrap();
+        expect!(gpos.has_kerning()).to(be_false());
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut data = header(&[]);
+        data[1] = 2;
+        lookup_list(&mut data, &[]);
+        expect!(GPOS::from_data(&data, 0).is_err()).to(be_true());
+    }
+}
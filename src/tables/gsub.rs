@@ -0,0 +1,494 @@
+use Error;
+use Result;
+use std::cmp;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+use tables::layout::{self, LayoutHeader, Coverage, Context, Script, Feature};
+
+/// A glyph substitution table.
+///
+/// The `GSUB` table contains lookups that replace glyphs, e.g. with
+/// ligatures or alternate forms. Single (type 1), multiple (type 2),
+/// alternate (type 3), ligature (type 4), context (type 5) and chaining
+/// context (type 6) lookups are used, directly or through extension lookups
+/// (type 7), subtables of other types are ignored. Lookup flags are not
+/// supported, so marks are never skipped.
+#[derive(Debug)]
+pub struct GSUB {
+    scripts: Vec<Script>,
+    features: Vec<Feature>,
+    lookups: Vec<Vec<Substitution>>,
+}
+
+/// The type of extension lookups.
+const EXTENSION: u16 = 7;
+
+/// The maximum depth of lookups applied by context lookups.
+const MAX_NESTING_LEVEL: usize = 64;
+
+/// Limits of the number of applied lookups and of the number of glyphs
+/// for each input glyph, with minimal values for short inputs. Context
+/// lookups may apply lookups recursively and multiple substitutions grow
+/// the input, so fonts may ask for unbounded work.
+const MAX_OPERATIONS_FACTOR: usize = 1024;
+const MIN_MAX_OPERATIONS: usize = 16384;
+const MAX_LENGTH_FACTOR: usize = 32;
+const MIN_MAX_LENGTH: usize = 8192;
+
+impl GSUB {
+    /// Returns `GSUB` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `GSUB` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<GSUB> {
+        let header = match try!(LayoutHeader::from_data(data, offset)) {
+            Some(header) => header,
+            None => return Err(Error::GSUBVersionIsNotSupported),
+        };
+
+        let scripts = try!(layout::read_script_list(data, header.script_list));
+        let features = try!(layout::read_feature_list(data, header.feature_list));
+        let mut lookups = vec![];
+        for lookup in try!(layout::read_lookup_list(data, header.lookup_list, EXTENSION)) {
+            let mut subtables = vec![];
+            for &subtable in &lookup.subtables {
+                if let Some(substitution) = try!(Substitution::from_data(data, subtable, lookup.lookup_type)) {
+                    subtables.push(substitution);
+                }
+            }
+            lookups.push(subtables);
+        }
+
+        Ok(GSUB {
+            scripts: scripts,
+            features: features,
+            lookups: lookups,
+        })
+    }
+
+    /// Returns tags of scripts of the table.
+    pub fn script_tags(&self) -> Vec<[u8; 4]> {
+        self.scripts.iter().map(|script| script.tag).collect()
+    }
+
+    /// Returns tags of features of the table.
+    pub fn feature_tags(&self) -> Vec<[u8; 4]> {
+        self.features.iter().map(|feature| feature.tag).collect()
+    }
+
+    /// Applies `features` of the `script` and `language` to glyph indices.
+    ///
+    /// The default language system is used if `language` is `None` or
+    /// the script has no such language. Lookups of all features are applied
+    /// in the order of the lookup list, alternate substitutions choose
+    /// the first alternate.
+    pub fn apply(&self, glyphs: &[usize], script: &[u8; 4], language: Option<&[u8; 4]>,
+                 features: &[[u8; 4]]) -> Vec<usize> {
+        let mut glyphs = glyphs.to_vec();
+        let mut clusters: Vec<usize> = (0..glyphs.len()).collect();
+        self.apply_with_clusters(&mut glyphs, &mut clusters, script, language, features);
+        glyphs
+    }
+
+    /// Applies `features` like `apply`, but in place and keeping track of
+    /// clusters.
+    ///
+    /// `clusters` must have a value for each glyph, a glyph produced by
+    /// a multiple substitution keeps the cluster of the replaced glyph and
+    /// a ligature gets the smallest cluster of its components.
+    ///
+    /// # Panics
+    /// Panics if `glyphs` and `clusters` have different lengths.
+    pub fn apply_with_clusters(&self, glyphs: &mut Vec<usize>, clusters: &mut Vec<usize>,
+                               script: &[u8; 4], language: Option<&[u8; 4]>, features: &[[u8; 4]]) {
+        assert_eq!(glyphs.len(), clusters.len());
+
+        let lang_sys = match layout::find_lang_sys(&self.scripts, script, language) {
+            Some(lang_sys) => lang_sys,
+            None => return,
+        };
+        let length = glyphs.len();
+        let mut buffer = Buffer {
+            glyphs: glyphs,
+            clusters: clusters,
+            operations: cmp::max(length * MAX_OPERATIONS_FACTOR, MIN_MAX_OPERATIONS),
+            max_length: cmp::max(length * MAX_LENGTH_FACTOR, MIN_MAX_LENGTH),
+        };
+        for lookup in layout::feature_lookups(&self.features, &[lang_sys], features, true) {
+            let mut i = 0;
+            while i < buffer.glyphs.len() && buffer.operations > 0 {
+                i = self.apply_lookup(lookup, &mut buffer, i, 0).unwrap_or(i + 1);
+            }
+        }
+    }
+
+    /// Applies the first subtable of the lookup which matches at `i`.
+    ///
+    /// Returns the position after the substituted glyphs, or `None` if no
+    /// subtable matches or the buffer runs out of operations.
+    fn apply_lookup(&self, lookup: usize, buffer: &mut Buffer, i: usize, level: usize) -> Option<usize> {
+        if level > MAX_NESTING_LEVEL || buffer.operations == 0 {
+            return None;
+        }
+        buffer.operations -= 1;
+        for subtable in self.lookups.get(lookup).map_or(&[][..], |subtables| &subtables[..]) {
+            let next = match *subtable {
+                Substitution::Context(ref context) => self.apply_context(context, buffer, i, level),
+                ref substitution => substitution.apply(buffer, i),
+            };
+            if next.is_some() {
+                return next;
+            }
+        }
+        None
+    }
+
+    fn apply_context(&self, context: &Context, buffer: &mut Buffer, i: usize, level: usize) -> Option<usize> {
+        context.matches(buffer.glyphs, i).map(|(length, lookups)| {
+            let mut end = i + length;
+            for &(sequence_index, lookup) in lookups {
+                let position = i + sequence_index as usize;
+                if position >= end {
+                    continue;
+                }
+                // Substitutions may change the length of the input sequence.
+                let length = buffer.glyphs.len();
+                self.apply_lookup(lookup as usize, buffer, position, level + 1);
+                end = cmp::max(end + buffer.glyphs.len(), length) - length;
+            }
+            cmp::max(end, i + 1)
+        })
+    }
+}
+
+/// Glyphs being substituted with their clusters.
+struct Buffer<'a> {
+    glyphs: &'a mut Vec<usize>,
+    clusters: &'a mut Vec<usize>,
+    /// The number of lookups which may still be applied.
+    operations: usize,
+    /// The number of glyphs the buffer may grow to.
+    max_length: usize,
+}
+
+impl<'a> Buffer<'a> {
+    /// Replaces glyphs in the range with `substitutes` of the smallest
+    /// cluster of the range.
+    fn replace(&mut self, start: usize, end: usize, substitutes: &[u16]) {
+        let cluster = self.clusters[start..end].iter().cloned().min().unwrap_or(0);
+        self.glyphs.splice(start..end, substitutes.iter().map(|&glyph| glyph as usize));
+        self.clusters.splice(start..end, substitutes.iter().map(|_| cluster));
+    }
+}
+
+#[derive(Debug)]
+enum Substitution {
+    /// Substitutes a glyph with the glyph at the delta from it.
+    SingleDelta {
+        coverage: Coverage,
+        delta: u16,
+    },
+    /// Substitutes a glyph with the glyph at its coverage index.
+    Single {
+        coverage: Coverage,
+        substitutes: Vec<u16>,
+    },
+    /// Substitutes a glyph with the sequence at its coverage index.
+    Multiple {
+        coverage: Coverage,
+        sequences: Vec<Vec<u16>>,
+    },
+    /// Substitutes a glyph with the first alternate at its coverage index.
+    Alternate {
+        coverage: Coverage,
+        alternate_sets: Vec<Vec<u16>>,
+    },
+    /// Substitutes sequences starting with a glyph with the ligatures at its
+    /// coverage index, ligatures are pairs of a glyph and components after
+    /// the first one in the order of preference.
+    Ligature {
+        coverage: Coverage,
+        ligature_sets: Vec<Vec<(u16, Vec<u16>)>>,
+    },
+    /// Applies lookups to a matched sequence.
+    Context(Context),
+}
+
+impl Substitution {
+    /// Reads a subtable of the lookup type, returns `None` if the type is
+    /// not supported.
+    fn from_data(data: &[u8], offset: usize, lookup_type: u16) -> Result<Option<Substitution>> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let format = try!(cursor.read_u16::<BigEndian>());
+        let substitution = match lookup_type {
+            1 => {
+                let coverage = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                let coverage = try!(Coverage::from_data(data, coverage));
+                match format {
+                    1 => Substitution::SingleDelta {
+                        coverage: coverage,
+                        delta: try!(cursor.read_u16::<BigEndian>()),
+                    },
+                    2 => Substitution::Single {
+                        coverage: coverage,
+                        substitutes: try!(layout::read_u16_array(&mut cursor)),
+                    },
+                    _ => return Err(Error::Malformed),
+                }
+            },
+            2 | 3 => {
+                let coverage = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                let coverage = try!(Coverage::from_data(data, coverage));
+                let count = try!(cursor.read_u16::<BigEndian>());
+                let mut sequences = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let sequence = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                    if sequence >= data.len() {
+                        return Err(Error::Malformed);
+                    }
+                    sequences.push(try!(layout::read_u16_array(&mut Cursor::new(&data[sequence..]))));
+                }
+                if lookup_type == 2 {
+                    Substitution::Multiple { coverage: coverage, sequences: sequences }
+                } else {
+                    Substitution::Alternate { coverage: coverage, alternate_sets: sequences }
+                }
+            },
+            4 => {
+                let coverage = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                let coverage = try!(Coverage::from_data(data, coverage));
+                let count = try!(cursor.read_u16::<BigEndian>());
+                let mut ligature_sets = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let ligature_set = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                    if ligature_set >= data.len() {
+                        return Err(Error::Malformed);
+                    }
+                    let mut set_cursor = Cursor::new(&data[ligature_set..]);
+                    let ligature_count = try!(set_cursor.read_u16::<BigEndian>());
+                    let mut ligatures = Vec::with_capacity(ligature_count as usize);
+                    for _ in 0..ligature_count {
+                        let ligature = ligature_set + try!(set_cursor.read_u16::<BigEndian>()) as usize;
+                        if ligature >= data.len() {
+                            return Err(Error::Malformed);
+                        }
+                        let mut ligature_cursor = Cursor::new(&data[ligature..]);
+                        let glyph = try!(ligature_cursor.read_u16::<BigEndian>());
+                        let component_count = try!(ligature_cursor.read_u16::<BigEndian>());
+                        let mut components = vec![];
+                        for _ in 1..component_count {
+                            components.push(try!(ligature_cursor.read_u16::<BigEndian>()));
+                        }
+                        ligatures.push((glyph, components));
+                    }
+                    ligature_sets.push(ligatures);
+                }
+                Substitution::Ligature { coverage: coverage, ligature_sets: ligature_sets }
+            },
+            5 | 6 => Substitution::Context(try!(Context::from_data(data, offset, lookup_type == 6))),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(substitution))
+    }
+
+    /// Applies the subtable at `i` unless it is a context subtable.
+    ///
+    /// Returns the position after the substituted glyphs.
+    fn apply(&self, buffer: &mut Buffer, i: usize) -> Option<usize> {
+        let glyph = buffer.glyphs[i];
+        match *self {
+            Substitution::SingleDelta { ref coverage, delta } => {
+                coverage.index(glyph).map(|_| {
+                    buffer.glyphs[i] = (glyph as u16).wrapping_add(delta) as usize;
+                    i + 1
+                })
+            },
+            Substitution::Single { ref coverage, ref substitutes } => {
+                coverage.index(glyph).and_then(|index| substitutes.get(index)).map(|&substitute| {
+                    buffer.glyphs[i] = substitute as usize;
+                    i + 1
+                })
+            },
+            Substitution::Multiple { ref coverage, ref sequences } => {
+                let sequence = coverage.index(glyph).and_then(|index| sequences.get(index));
+                sequence.filter(|sequence| buffer.glyphs.len() + sequence.len() <= buffer.max_length + 1)
+                    .map(|sequence| {
+                        buffer.replace(i, i + 1, sequence);
+                        i + sequence.len()
+                    })
+            },
+            Substitution::Alternate { ref coverage, ref alternate_sets } => {
+                coverage.index(glyph).and_then(|index| alternate_sets.get(index))
+                    .and_then(|alternates| alternates.first()).map(|&alternate| {
+                        buffer.glyphs[i] = alternate as usize;
+                        i + 1
+                    })
+            },
+            Substitution::Ligature { ref coverage, ref ligature_sets } => {
+                let ligature = coverage.index(glyph).and_then(|index| ligature_sets.get(index)).and_then(|ligatures| {
+                    ligatures.iter().find(|ligature| {
+                        let components = &ligature.1;
+                        let end = i + 1 + components.len();
+                        end <= buffer.glyphs.len() &&
+                        components.iter().zip(&buffer.glyphs[i + 1..end]).all(|(&c, &g)| c as usize == g)
+                    })
+                });
+                ligature.map(|&(ligature, ref components)| {
+                    buffer.replace(i, i + 1 + components.len(), &[ligature]);
+                    i + 1
+                })
+            },
+            Substitution::Context(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tables::layout::tests::{header, lookup_list};
+    use byteorder::{BigEndian, WriteBytesExt};
+    use expectest::prelude::*;
+
+    fn words(values: &[u16]) -> Vec<u8> {
+        let mut data = vec![];
+        for &value in values {
+            data.write_u16::<BigEndian>(value).unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn single_and_ligature() {
+        let mut data = header(&[(b"smcp", &[0, 1]), (b"liga", &[2])]);
+        let single = words(&[2, 10, 2, 20, 21, 1, 2, 10, 11]);
+        let delta = words(&[1, 6, 5, 1, 1, 12]);
+        // Ligatures of 'f' (1) with 'f', 'i' (2) and 'l' (3).
+        let ligatures = words(&[1, 8, 1, 14, 1, 1, 1, 3, 8, 16, 22,
+                                100, 3, 1, 2, 101, 2, 2, 102, 2, 3]);
+        lookup_list(&mut data, &[(1, &[&single]), (1, &[&delta]), (4, &[&ligatures])]);
+        let gsub = GSUB::from_data(&data, 0).unwrap();
+        expect!(gsub.feature_tags()).to(be_equal_to(vec![*b"smcp", *b"liga"]));
+
+        expect!(gsub.apply(&[10, 11, 12, 13], b"latn", None, &[*b"smcp"]))
+            .to(be_equal_to(vec![20, 21, 17, 13]));
+        expect!(gsub.apply(&[10, 11, 12, 13], b"latn", None, &[*b"liga"]))
+            .to(be_equal_to(vec![10, 11, 12, 13]));
+
+        let mut glyphs = vec![1, 1, 2, 3, 1, 3, 1, 2, 1];
+        let mut clusters = vec![0, 1, 2, 3, 4, 5, 6, 7, 8];
+        gsub.apply_with_clusters(&mut glyphs, &mut clusters, b"latn", None, &[*b"liga"]);
+        expect!(glyphs).to(be_equal_to(vec![100, 3, 102, 101, 1]));
+        expect!(clusters).to(be_equal_to(vec![0, 3, 4, 6, 8]));
+    }
+
+    #[test]
+    fn multiple_and_alternate() {
+        let mut data = header(&[(b"ccmp", &[0]), (b"salt", &[1])]);
+        let multiple = words(&[1, 8, 1, 14, 1, 1, 5, 2, 6, 7]);
+        let alternate = words(&[1, 8, 1, 14, 1, 1, 9, 2, 30, 31]);
+        lookup_list(&mut data, &[(2, &[&multiple]), (3, &[&alternate])]);
+        let gsub = GSUB::from_data(&data, 0).unwrap();
+
+        let mut glyphs = vec![5, 9, 4];
+        let mut clusters = vec![0, 1, 2];
+        gsub.apply_with_clusters(&mut glyphs, &mut clusters, b"DFLT", None, &[*b"ccmp", *b"salt"]);
+        expect!(glyphs).to(be_equal_to(vec![6, 7, 30, 4]));
+        expect!(clusters).to(be_equal_to(vec![0, 0, 1, 2]));
+    }
+
+    #[test]
+    fn context_substitutions() {
+        let mut data = header(&[(b"calt", &[1, 2, 4])]);
+        let add_ten = words(&[1, 6, 10, 1, 2, 10, 11]);
+        // Glyph 10 after 1 and before 2.
+        let chain_coverages = words(&[3, 1, 20, 1, 26, 1, 32, 1, 0, 0, 1, 1, 1, 1, 1, 10, 1, 1, 2]);
+        // Glyph 3 after 11.
+        let chain_glyphs = words(&[1, 8, 1, 14, 1, 1, 11, 1, 4, 0, 2, 3, 0, 1, 1, 3]);
+        let mut extension = words(&[1, 6]);
+        extension.write_u32::<BigEndian>(8).unwrap();
+        extension.extend_from_slice(&chain_glyphs);
+        let add_one = words(&[1, 6, 1, 1, 1, 3]);
+        // Glyph 40 of the class 1 before 41 of the class 2.
+        let context_classes = words(&[2, 12, 18, 2, 0, 34, 1, 1, 40, 2, 2, 40, 40, 1, 41, 41, 2,
+                                      1, 4, 2, 1, 2, 0, 5]);
+        let add_two = words(&[1, 6, 2, 1, 1, 40]);
+        lookup_list(&mut data, &[(1, &[&add_ten]), (6, &[&chain_coverages]), (7, &[&extension]),
+                                 (1, &[&add_one]), (5, &[&context_classes]), (1, &[&add_two])]);
+        let gsub = GSUB::from_data(&data, 0).unwrap();
+
+        expect!(gsub.apply(&[1, 10, 2, 3, 10, 2, 1, 10, 3], b"latn", None, &[*b"calt"]))
+            .to(be_equal_to(vec![1, 20, 2, 3, 10, 2, 1, 10, 3]));
+        expect!(gsub.apply(&[11, 3, 3, 11, 2], b"latn", None, &[*b"calt"]))
+            .to(be_equal_to(vec![11, 4, 3, 11, 2]));
+        expect!(gsub.apply(&[40, 41, 40, 40, 41], b"latn", None, &[*b"calt"]))
+            .to(be_equal_to(vec![42, 41, 40, 42, 41]));
+    }
+
+    #[test]
+    fn chain_classes_without_backtrack_and_lookahead() {
+        let mut data = header(&[(b"calt", &[1])]);
+        let add_one = words(&[1, 6, 1, 1, 1, 50]);
+        // Glyph 50 of the class 1 before 51 of the class 2, null offsets of
+        // backtrack and lookahead class definitions.
+        let chain_classes = words(&[2, 16, 0, 22, 0, 2, 0, 38, 1, 1, 50, 2, 2, 50, 50, 1, 51, 51, 2,
+                                    1, 4, 0, 2, 2, 0, 1, 0, 0]);
+        lookup_list(&mut data, &[(1, &[&add_one]), (6, &[&chain_classes])]);
+        let gsub = GSUB::from_data(&data, 0).unwrap();
+
+        expect!(gsub.apply(&[50, 51, 50, 50], b"latn", None, &[*b"calt"]))
+            .to(be_equal_to(vec![51, 51, 50, 50]));
+    }
+
+    #[test]
+    fn self_referencing_context() {
+        let mut data = header(&[(b"calt", &[0])]);
+        // Glyph 1 applying its own lookup twice.
+        let context = words(&[3, 1, 2, 16, 0, 0, 0, 0, 1, 1, 1]);
+        lookup_list(&mut data, &[(5, &[&context])]);
+        let gsub = GSUB::from_data(&data, 0).unwrap();
+        expect!(gsub.apply(&[1; 10], b"latn", None, &[*b"calt"])).to(be_equal_to(vec![1; 10]));
+
+        let mut data = header(&[(b"calt", &[0])]);
+        // Glyph 1 multiplied and applying its own lookup again.
+        let context = words(&[3, 1, 2, 16, 0, 1, 0, 0, 1, 1, 1]);
+        let multiple = words(&[1, 8, 1, 14, 1, 1, 1, 2, 1, 1]);
+        lookup_list(&mut data, &[(5, &[&context]), (2, &[&multiple])]);
+        let gsub = GSUB::from_data(&data, 0).unwrap();
+        let glyphs = gsub.apply(&[1; 10], b"latn", None, &[*b"calt"]);
+        expect!(glyphs.len() > 10 && glyphs.len() <= MIN_MAX_LENGTH).to(be_true());
+    }
+
+    #[test]
+    fn scripts_and_languages() {
+        let mut data = header(&[(b"smcp", &[0])]);
+        let delta = words(&[1, 6, 5, 1, 1, 12]);
+        lookup_list(&mut data, &[(1, &[&delta])]);
+        let gsub = GSUB::from_data(&data, 0).unwrap();
+        expect!(gsub.script_tags()).to(be_equal_to(vec![*b"DFLT"]));
+        // Unknown scripts and languages fall back to the default ones.
+        expect!(gsub.apply(&[12], b"cyrl", Some(b"RUS "), &[*b"smcp"])).to(be_equal_to(vec![17]));
+        expect!(gsub.apply(&[12], b"latn", None, &[])).to(be_equal_to(vec![12]));
+
+        // The required feature is applied even if not requested.
+        data[24..26].copy_from_slice(&[0, 0]);
+        let gsub = GSUB::from_data(&data, 0).unwrap();
+        expect!(gsub.apply(&[12], b"latn", None, &[])).to(be_equal_to(vec![17]));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut data = header(&[]);
+        data[1] = 2;
+        lookup_list(&mut data, &[]);
+        expect!(GSUB::from_data(&data, 0).is_err()).to(be_true());
+    }
+}
@@ -0,0 +1,438 @@
+use Error;
+use Result;
+use std::cmp;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+
+/// A glyph variations table.
+///
+/// The `gvar` table contains deltas of points of TrueType glyphs for
+/// regions of the design space of a variable font. Deltas of glyphs are
+/// combined with scalars depending on the position in the design space.
+#[derive(Debug, Default)]
+pub struct GVAR {
+    bytes: Vec<u8>,
+    axis_count: usize,
+    shared_tuples: Vec<Vec<f32>>,
+    /// Offsets of variation data of glyphs with the end of the last one.
+    offsets: Vec<usize>,
+}
+
+/// A header of a set of deltas.
+struct TupleHeader {
+    data_size: usize,
+    peak: Vec<f32>,
+    intermediate: Option<(Vec<f32>, Vec<f32>)>,
+    private_points: bool,
+}
+
+const SHARED_POINT_NUMBERS: u16 = 0x8000;
+const TUPLE_COUNT_MASK: u16 = 0x0fff;
+const EMBEDDED_PEAK_TUPLE: u16 = 0x8000;
+const INTERMEDIATE_REGION: u16 = 0x4000;
+const PRIVATE_POINT_NUMBERS: u16 = 0x2000;
+const TUPLE_INDEX_MASK: u16 = 0x0fff;
+
+impl GVAR {
+    /// Returns `gvar` font table.
+    ///
+    /// Attempts to read `size` bytes of `data` starting from `offset`
+    /// position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `gvar` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize, size: usize) -> Result<GVAR> {
+        if offset >= data.len() || offset + size > data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let bytes = &data[offset..offset + size];
+        let mut cursor = Cursor::new(bytes);
+        let major_version = try!(cursor.read_u16::<BigEndian>());
+        let _minor_version = try!(cursor.read_u16::<BigEndian>());
+        if major_version != 1 {
+            return Err(Error::GVARVersionIsNotSupported);
+        }
+        let axis_count = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let shared_tuple_count = try!(cursor.read_u16::<BigEndian>());
+        let shared_tuples_offset = try!(cursor.read_u32::<BigEndian>()) as usize;
+        let glyph_count = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let flags = try!(cursor.read_u16::<BigEndian>());
+        let data_offset = try!(cursor.read_u32::<BigEndian>()) as usize;
+
+        // Short offsets are divided by 2.
+        let mut offsets = Vec::with_capacity(glyph_count + 1);
+        for _ in 0..glyph_count + 1 {
+            let offset = if flags & 1 == 0 {
+                try!(cursor.read_u16::<BigEndian>()) as usize * 2
+            } else {
+                try!(cursor.read_u32::<BigEndian>()) as usize
+            };
+            offsets.push(data_offset + offset);
+        }
+        if offsets.windows(2).any(|pair| pair[1] < pair[0]) || offsets[glyph_count] > size {
+            return Err(Error::Malformed);
+        }
+
+        if shared_tuples_offset > size {
+            return Err(Error::Malformed);
+        }
+        let mut cursor = Cursor::new(&bytes[shared_tuples_offset..]);
+        let mut shared_tuples = Vec::with_capacity(shared_tuple_count as usize);
+        for _ in 0..shared_tuple_count {
+            shared_tuples.push(try!(read_tuple(&mut cursor, axis_count)));
+        }
+
+        Ok(GVAR {
+            bytes: bytes.to_owned(),
+            axis_count: axis_count,
+            shared_tuples: shared_tuples,
+            offsets: offsets,
+        })
+    }
+
+    /// Returns deltas of points of the glyph at index `glyph` at the position
+    /// in the design space given by normalized `coordinates`, or `None` if
+    /// the glyph doesn't vary there.
+    ///
+    /// `points` are original points of the glyph, followed by the four
+    /// phantom points, and `ends` are indices of the last points of its
+    /// contours. Deltas of points without explicit deltas are inferred from
+    /// neighbouring points of their contour, points of composite glyphs,
+    /// which have no contours, are left unchanged.
+    ///
+    /// # Errors
+    /// Returns error if the variation data is malformed.
+    pub fn deltas(&self, glyph: usize, coordinates: &[f32], points: &[(f32, f32)],
+        ends: &[usize]) -> Result<Option<Vec<(f32, f32)>>>
+    {
+        if glyph + 1 >= self.offsets.len() || self.offsets[glyph] == self.offsets[glyph + 1] {
+            return Ok(None);
+        }
+
+        let start = self.offsets[glyph];
+        let data = &self.bytes[start..self.offsets[glyph + 1]];
+        let mut cursor = Cursor::new(data);
+        let tuple_count = try!(cursor.read_u16::<BigEndian>());
+        let data_offset = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let mut headers = vec![];
+        for _ in 0..tuple_count & TUPLE_COUNT_MASK {
+            headers.push(try!(self.read_tuple_header(&mut cursor)));
+        }
+
+        if data_offset > data.len() {
+            return Err(Error::Malformed);
+        }
+        let mut cursor = Cursor::new(&data[data_offset..]);
+        let shared_points = if tuple_count & SHARED_POINT_NUMBERS != 0 {
+            Some(try!(read_points(&mut cursor)))
+        } else {
+            None
+        };
+
+        let mut deltas = vec![(0.0, 0.0); points.len()];
+        let mut varies = false;
+        let mut position = data_offset + cursor.position() as usize;
+        for header in headers {
+            let tuple_start = position;
+            position += header.data_size;
+            if position > data.len() {
+                return Err(Error::Malformed);
+            }
+            let scalar = tuple_scalar(coordinates, &header.peak, header.intermediate.as_ref());
+            if scalar == 0.0 {
+                continue;
+            }
+
+            let mut cursor = Cursor::new(&data[tuple_start..position]);
+            let private_points = if header.private_points {
+                Some(try!(read_points(&mut cursor)))
+            } else {
+                None
+            };
+            let tuple_points = match (private_points.as_ref(), shared_points.as_ref()) {
+                (Some(points), _) | (None, Some(points)) => points,
+                (None, None) => return Err(Error::Malformed),
+            };
+
+            // No point numbers mean all points.
+            let count = match *tuple_points {
+                Some(ref numbers) => numbers.len(),
+                None => points.len(),
+            };
+            let x_deltas = try!(read_deltas(&mut cursor, count));
+            let y_deltas = try!(read_deltas(&mut cursor, count));
+
+            let numbers = match *tuple_points {
+                Some(ref numbers) => numbers,
+                None => {
+                    for (delta, (x, y)) in deltas.iter_mut().zip(x_deltas.into_iter().zip(y_deltas)) {
+                        delta.0 += x * scalar;
+                        delta.1 += y * scalar;
+                    }
+                    varies = true;
+                    continue;
+                },
+            };
+            let mut tuple_deltas = vec![None; points.len()];
+            for (&number, (x, y)) in numbers.iter().zip(x_deltas.into_iter().zip(y_deltas)) {
+                // Deltas of points out of the glyph are ignored.
+                if let Some(delta) = tuple_deltas.get_mut(number as usize) {
+                    *delta = Some((x, y));
+                }
+            }
+            infer_deltas(&mut tuple_deltas, points, ends);
+            for (delta, tuple_delta) in deltas.iter_mut().zip(tuple_deltas) {
+                if let Some((x, y)) = tuple_delta {
+                    delta.0 += x * scalar;
+                    delta.1 += y * scalar;
+                }
+            }
+            varies = true;
+        }
+
+        Ok(if varies { Some(deltas) } else { None })
+    }
+
+    fn read_tuple_header(&self, cursor: &mut Cursor<&[u8]>) -> Result<TupleHeader> {
+        let data_size = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let index = try!(cursor.read_u16::<BigEndian>());
+        let peak = if index & EMBEDDED_PEAK_TUPLE != 0 {
+            try!(read_tuple(cursor, self.axis_count))
+        } else {
+            match self.shared_tuples.get((index & TUPLE_INDEX_MASK) as usize) {
+                Some(tuple) => tuple.clone(),
+                None => return Err(Error::Malformed),
+            }
+        };
+        let intermediate = if index & INTERMEDIATE_REGION != 0 {
+            let start = try!(read_tuple(cursor, self.axis_count));
+            let end = try!(read_tuple(cursor, self.axis_count));
+            Some((start, end))
+        } else {
+            None
+        };
+        Ok(TupleHeader {
+            data_size: data_size,
+            peak: peak,
+            intermediate: intermediate,
+            private_points: index & PRIVATE_POINT_NUMBERS != 0,
+        })
+    }
+}
+
+/// Returns the scalar of deltas of the region given by `peak` and optional
+/// `intermediate` start and end coordinates at normalized `coordinates`.
+fn tuple_scalar(coordinates: &[f32], peak: &[f32], intermediate: Option<&(Vec<f32>, Vec<f32>)>) -> f32 {
+    let mut scalar = 1.0;
+    for (axis, &peak) in peak.iter().enumerate() {
+        // Axes with zero peak don't affect the region.
+        if peak == 0.0 {
+            continue;
+        }
+        let coordinate = coordinates.get(axis).cloned().unwrap_or(0.0);
+        if coordinate == peak {
+            continue;
+        }
+        if coordinate == 0.0 {
+            return 0.0;
+        }
+        let (start, end) = match intermediate {
+            Some((start, end)) => (start[axis], end[axis]),
+            None => (peak.min(0.0), peak.max(0.0)),
+        };
+        // Axes of invalid regions are ignored.
+        if start > peak || peak > end || (start < 0.0 && end > 0.0) {
+            continue;
+        }
+        if coordinate <= start || coordinate >= end {
+            return 0.0;
+        }
+        scalar *= if coordinate < peak {
+            (coordinate - start) / (peak - start)
+        } else {
+            (end - coordinate) / (end - peak)
+        };
+    }
+    scalar
+}
+
+/// Infers missing deltas of points of each contour from the nearest points
+/// with deltas before and after them, interpolating between the points or
+/// taking the nearest delta if the point is outside of them.
+fn infer_deltas(deltas: &mut [Option<(f32, f32)>], points: &[(f32, f32)], ends: &[usize]) {
+    let mut start = 0;
+    for &end in ends {
+        if end >= points.len() || end < start {
+            return;
+        }
+        let contour = start..end + 1;
+        start = end + 1;
+
+        let touched: Vec<usize> = contour.clone().filter(|&i| deltas[i].is_some()).collect();
+        if touched.is_empty() || touched.len() == contour.len() {
+            continue;
+        }
+        for i in contour.clone() {
+            if deltas[i].is_some() {
+                continue;
+            }
+            // The nearest touched points before and after the point,
+            // wrapping around the contour.
+            let next = touched.iter().cloned().find(|&t| t > i).unwrap_or(touched[0]);
+            let previous = touched.iter().cloned().rev().find(|&t| t < i).unwrap_or(touched[touched.len() - 1]);
+            let (previous_delta, next_delta) = (deltas[previous].unwrap(), deltas[next].unwrap());
+            let x = infer_delta(points[i].0, points[previous].0, points[next].0, previous_delta.0, next_delta.0);
+            let y = infer_delta(points[i].1, points[previous].1, points[next].1, previous_delta.1, next_delta.1);
+            deltas[i] = Some((x, y));
+        }
+    }
+}
+
+/// Infers a delta of the coordinate `value` from coordinates of two
+/// reference points and their deltas.
+fn infer_delta(value: f32, a: f32, b: f32, a_delta: f32, b_delta: f32) -> f32 {
+    let ((min, min_delta), (max, max_delta)) = if a <= b {
+        ((a, a_delta), (b, b_delta))
+    } else {
+        ((b, b_delta), (a, a_delta))
+    };
+    if min == max {
+        if min_delta == max_delta { min_delta } else { 0.0 }
+    } else if value <= min {
+        min_delta
+    } else if value >= max {
+        max_delta
+    } else {
+        min_delta + (value - min) * (max_delta - min_delta) / (max - min)
+    }
+}
+
+/// Reads packed point numbers, `None` means all points.
+fn read_points(cursor: &mut Cursor<&[u8]>) -> Result<Option<Vec<u16>>> {
+    let first = try!(cursor.read_u8()) as usize;
+    let count = if first & 0x80 != 0 {
+        (first & 0x7f) << 8 | try!(cursor.read_u8()) as usize
+    } else {
+        first
+    };
+    if count == 0 {
+        return Ok(None);
+    }
+
+    // Numbers are stored as differences from the previous number.
+    let mut points = Vec::with_capacity(count);
+    let mut number = 0u16;
+    while points.len() < count {
+        let control = try!(cursor.read_u8());
+        let run = (control & 0x7f) as usize + 1;
+        for _ in 0..cmp::min(run, count - points.len()) {
+            let difference = if control & 0x80 != 0 {
+                try!(cursor.read_u16::<BigEndian>())
+            } else {
+                try!(cursor.read_u8()) as u16
+            };
+            number = number.wrapping_add(difference);
+            points.push(number);
+        }
+    }
+    Ok(Some(points))
+}
+
+/// Reads `count` packed deltas.
+fn read_deltas(cursor: &mut Cursor<&[u8]>, count: usize) -> Result<Vec<f32>> {
+    let mut deltas = Vec::with_capacity(count);
+    while deltas.len() < count {
+        let control = try!(cursor.read_u8());
+        let run = (control & 0x3f) as usize + 1;
+        for _ in 0..cmp::min(run, count - deltas.len()) {
+            let delta = if control & 0x80 != 0 {
+                0
+            } else if control & 0x40 != 0 {
+                try!(cursor.read_i16::<BigEndian>())
+            } else {
+                try!(cursor.read_i8()) as i16
+            };
+            deltas.push(delta as f32);
+        }
+    }
+    Ok(deltas)
+}
+
+fn read_tuple(cursor: &mut Cursor<&[u8]>, axis_count: usize) -> Result<Vec<f32>> {
+    let mut tuple = Vec::with_capacity(axis_count);
+    for _ in 0..axis_count {
+        tuple.push(try!(read_f2dot14(cursor)));
+    }
+    Ok(tuple)
+}
+
+fn read_f2dot14(cursor: &mut Cursor<&[u8]>) -> Result<f32> {
+    Ok(try!(cursor.read_i16::<BigEndian>()) as f32 / 16384.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        // One axis, glyph 1 has three sets of deltas: for the peak 1 of
+        // the shared tuple and all points, for the peak -1 and points 0 and
+        // 2, and for the peak 0.5 from 0.25 to 1 and point 1.
+        let mut data = vec![0, 1, 0, 0, 0, 1, 0, 1, 0, 0, 0, 26, 0, 2, 0, 0, 0, 0, 0, 28,
+                            0, 0, 0, 0, 0, 26, 0x40, 0];
+        data.extend_from_slice(&[0x80, 3, 0, 24]);
+        data.extend_from_slice(&[0, 10, 0, 0]);
+        data.extend_from_slice(&[0, 10, 0xa0, 0, 0xc0, 0]);
+        data.extend_from_slice(&[0, 7, 0xe0, 0, 0x20, 0, 0x10, 0, 0x40, 0]);
+        data.push(0);
+        data.extend_from_slice(&[7, 10, 10, 10, 10, 10, 10, 10, 10, 0x87]);
+        data.extend_from_slice(&[2, 1, 0, 2, 1, 0xf6, 10, 1, 0, 20]);
+        data.extend_from_slice(&[1, 0, 1, 0, 4, 0, 4]);
+        let gvar = GVAR::from_data(&data, 0, data.len()).unwrap();
+
+        // A square with four phantom points.
+        let points = [(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0),
+                      (0.0, 0.0), (0.0, 0.0), (0.0, 0.0), (0.0, 0.0)];
+        let deltas = |coordinate| gvar.deltas(1, &[coordinate], &points, &[3]);
+        expect!(deltas(0.0)).to(be_ok().value(None));
+        expect!(gvar.deltas(0, &[1.0], &points, &[3])).to(be_ok().value(None));
+        expect!(gvar.deltas(2, &[1.0], &points, &[3])).to(be_ok().value(None));
+        expect!(deltas(1.0)).to(be_ok().value(Some(vec![(10.0, 0.0); 8])));
+        expect!(deltas(-0.5)).to(be_ok().value(Some(vec![
+            (-5.0, 0.0), (5.0, 0.0), (5.0, 10.0), (-5.0, 10.0),
+            (0.0, 0.0), (0.0, 0.0), (0.0, 0.0), (0.0, 0.0),
+        ])));
+        expect!(deltas(0.75)).to(be_ok().value(Some(vec![
+            (9.5, 2.0), (9.5, 2.0), (9.5, 2.0), (9.5, 2.0),
+            (7.5, 0.0), (7.5, 0.0), (7.5, 0.0), (7.5, 0.0),
+        ])));
+
+        // Points of composite glyphs have no contours.
+        expect!(gvar.deltas(1, &[-1.0], &points, &[])).to(be_ok().value(Some(vec![
+            (-10.0, 0.0), (0.0, 0.0), (10.0, 20.0), (0.0, 0.0),
+            (0.0, 0.0), (0.0, 0.0), (0.0, 0.0), (0.0, 0.0),
+        ])));
+
+        expect!(GVAR::from_data(&data, 0, 60)).to(be_err().value(Malformed));
+        expect!(GVAR::from_data(&[0, 2, 0, 0], 0, 4)).to(be_err().value(GVARVersionIsNotSupported));
+    }
+
+    #[test]
+    fn scalars() {
+        expect!(tuple_scalar(&[0.5, 0.0], &[1.0, 0.0], None)).to(be_equal_to(0.5));
+        expect!(tuple_scalar(&[0.5, 0.5], &[1.0, 1.0], None)).to(be_equal_to(0.25));
+        expect!(tuple_scalar(&[-0.5], &[1.0], None)).to(be_equal_to(0.0));
+        expect!(tuple_scalar(&[-0.25], &[-0.5], None)).to(be_equal_to(0.5));
+        expect!(tuple_scalar(&[], &[1.0], None)).to(be_equal_to(0.0));
+        let region = (vec![0.25], vec![0.75]);
+        expect!(tuple_scalar(&[0.375], &[0.5], Some(&region))).to(be_equal_to(0.5));
+        expect!(tuple_scalar(&[0.625], &[0.5], Some(&region))).to(be_equal_to(0.5));
+        expect!(tuple_scalar(&[0.875], &[0.5], Some(&region))).to(be_equal_to(0.0));
+        expect!(tuple_scalar(&[0.25], &[0.5], Some(&(vec![-0.5], vec![1.0])))).to(be_equal_to(1.0));
+    }
+}
@@ -114,7 +114,6 @@ impl HHEA {
     }
 
     /// The spacing between one row's descent and the next row's ascent.
-    #[allow(dead_code)]
     pub fn line_gap(&self) -> i32 {
         self.line_gap as i32
     }
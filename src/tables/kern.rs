@@ -0,0 +1,399 @@
+
+use Error;
+use Result;
+use std::cmp;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+
+/// A kerning table.
+///
+/// The `kern` table contains values that adjust the intercharacter spacing
+/// for pairs of glyphs. Both the OpenType version 0 and the Apple version 1.0
+/// of the table are supported, subtables of formats 0 (ordered list of pairs)
+/// and 2 (class-based two-dimensional array) are used, subtables of other
+/// formats are ignored.
+#[derive(Debug, Default)]
+pub struct KERN {
+    subtables: Vec<Subtable>,
+}
+
+impl KERN {
+    /// Returns `kern` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `kern` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<KERN> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        // The OpenType version is 16-bit 0, the Apple version is 32-bit 1.0.
+        let version = try!(cursor.read_u16::<BigEndian>());
+        let (apple, count) = if version == 0 {
+            (false, try!(cursor.read_u16::<BigEndian>()) as u32)
+        } else if version == 1 && try!(cursor.read_u16::<BigEndian>()) == 0 {
+            (true, try!(cursor.read_u32::<BigEndian>()))
+        } else {
+            return Err(Error::KERNVersionIsNotSupported);
+        };
+
+        let mut kern = KERN::default();
+        let mut start = offset + cursor.position() as usize;
+        for _ in 0..count {
+            let subtable = try!(Subtable::from_data(data, start, apple));
+            start += subtable.length;
+            kern.subtables.push(subtable);
+        }
+
+        Ok(kern)
+    }
+
+    /// Returns the kerning between glyphs at indices `left` and `right`
+    /// for horizontal text.
+    ///
+    /// The value is an adjustment of the horizontal advance of the `left`
+    /// glyph expressed in unscaled coordinates.
+    pub fn kerning(&self, left: usize, right: usize) -> i32 {
+        self.accumulate(left, right, false)
+    }
+
+    /// Returns the cross-stream kerning between glyphs at indices `left` and
+    /// `right` for horizontal text.
+    ///
+    /// The value is a vertical shift of the `right` glyph expressed in
+    /// unscaled coordinates.
+    pub fn cross_stream_kerning(&self, left: usize, right: usize) -> i32 {
+        self.accumulate(left, right, true)
+    }
+
+    fn accumulate(&self, left: usize, right: usize, cross_stream: bool) -> i32 {
+        let mut total = 0;
+        for subtable in &self.subtables {
+            if !subtable.horizontal || subtable.variation || subtable.cross_stream != cross_stream {
+                continue;
+            }
+            let value = match subtable.value(left, right) {
+                Some(value) => value,
+                None => continue,
+            };
+            if cross_stream && value == -0x8000 {
+                // Resets the cross-stream kerning.
+                total = 0;
+            } else if subtable.override_ {
+                total = value;
+            } else if subtable.minimum {
+                // The value limits how far the glyphs can be moved.
+                total = if value < 0 { cmp::max(total, value) } else { cmp::min(total, value) };
+            } else {
+                total += value;
+            }
+        }
+        total
+    }
+}
+
+/// Bits of the coverage field of the OpenType subtable.
+const OT_HORIZONTAL: u16 = 1 << 0;
+const OT_MINIMUM: u16 = 1 << 1;
+const OT_CROSS_STREAM: u16 = 1 << 2;
+const OT_OVERRIDE: u16 = 1 << 3;
+
+/// Bits of the coverage field of the Apple subtable.
+const APPLE_VERTICAL: u16 = 0x8000;
+const APPLE_CROSS_STREAM: u16 = 0x4000;
+const APPLE_VARIATION: u16 = 0x2000;
+
+#[derive(Debug)]
+struct Subtable {
+    length: usize,
+    horizontal: bool,
+    minimum: bool,
+    cross_stream: bool,
+    override_: bool,
+    variation: bool,
+    format: Format,
+}
+
+impl Subtable {
+    fn from_data(data: &[u8], offset: usize, apple: bool) -> Result<Subtable> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let mut subtable = if apple {
+            let length = try!(cursor.read_u32::<BigEndian>()) as usize;
+            let coverage = try!(cursor.read_u16::<BigEndian>());
+            let _tuple_index = try!(cursor.read_u16::<BigEndian>());
+            Subtable {
+                length: length,
+                horizontal: coverage & APPLE_VERTICAL == 0,
+                minimum: false,
+                cross_stream: coverage & APPLE_CROSS_STREAM != 0,
+                override_: false,
+                variation: coverage & APPLE_VARIATION != 0,
+                format: Format::Unsupported(coverage & 0xff),
+            }
+        } else {
+            let _version = try!(cursor.read_u16::<BigEndian>());
+            let length = try!(cursor.read_u16::<BigEndian>()) as usize;
+            let coverage = try!(cursor.read_u16::<BigEndian>());
+            Subtable {
+                length: length,
+                horizontal: coverage & OT_HORIZONTAL != 0,
+                minimum: coverage & OT_MINIMUM != 0,
+                cross_stream: coverage & OT_CROSS_STREAM != 0,
+                override_: coverage & OT_OVERRIDE != 0,
+                variation: false,
+                format: Format::Unsupported(coverage >> 8),
+            }
+        };
+
+        let header_size = cursor.position() as usize;
+        if let Format::Unsupported(0) = subtable.format {
+            let format0 = try!(Format0::from_data(&data[offset..], header_size));
+            if !apple {
+                // The 16-bit length overflows for large subtables, so it is
+                // calculated from the number of pairs.
+                subtable.length = header_size + 8 + format0.pairs.len() * 6;
+            }
+            subtable.format = Format::F0(format0);
+        }
+
+        if subtable.length < header_size || offset + subtable.length > data.len() {
+            return Err(Error::Malformed);
+        }
+        if let Format::Unsupported(2) = subtable.format {
+            let subtable_data = &data[offset..offset + subtable.length];
+            subtable.format = Format::F2(try!(Format2::from_data(subtable_data, header_size)));
+        }
+
+        Ok(subtable)
+    }
+
+    fn value(&self, left: usize, right: usize) -> Option<i32> {
+        match self.format {
+            Format::F0(ref f) => f.value(left, right),
+            Format::F2(ref f) => f.value(left, right),
+            Format::Unsupported(_) => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Format {
+    F0(Format0),
+    F2(Format2),
+    Unsupported(u16),
+}
+
+#[derive(Debug)]
+struct Format0 {
+    // Pairs of glyph indices combined into one key and their values,
+    // sorted by keys.
+    pairs: Vec<(u32, i16)>,
+}
+
+impl Format0 {
+    fn from_data(data: &[u8], offset: usize) -> Result<Format0> {
+        let mut cursor = Cursor::new(&data[offset..]);
+        let count = try!(cursor.read_u16::<BigEndian>());
+        let _search_range = try!(cursor.read_u16::<BigEndian>());
+        let _entry_selector = try!(cursor.read_u16::<BigEndian>());
+        let _range_shift = try!(cursor.read_u16::<BigEndian>());
+
+        let mut pairs = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let key = try!(cursor.read_u32::<BigEndian>());
+            let value = try!(cursor.read_i16::<BigEndian>());
+            pairs.push((key, value));
+        }
+        pairs.sort_by_key(|&(key, _)| key);
+
+        Ok(Format0 { pairs: pairs })
+    }
+
+    fn value(&self, left: usize, right: usize) -> Option<i32> {
+        if left > 0xffff || right > 0xffff {
+            return None;
+        }
+        let key = (left as u32) << 16 | right as u32;
+        self.pairs.binary_search_by_key(&key, |&(key, _)| key).ok()
+            .map(|i| self.pairs[i].1 as i32)
+    }
+}
+
+#[derive(Debug)]
+struct Format2 {
+    left_classes: ClassTable,
+    right_classes: ClassTable,
+    array_offset: usize,
+    // The whole subtable, class values are offsets from its start.
+    data: Vec<u8>,
+}
+
+impl Format2 {
+    fn from_data(data: &[u8], offset: usize) -> Result<Format2> {
+        let mut cursor = Cursor::new(&data[offset..]);
+        let _row_width = try!(cursor.read_u16::<BigEndian>());
+        let left_offset = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let right_offset = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let array_offset = try!(cursor.read_u16::<BigEndian>()) as usize;
+
+        Ok(Format2 {
+            left_classes: try!(ClassTable::from_data(data, left_offset)),
+            right_classes: try!(ClassTable::from_data(data, right_offset)),
+            array_offset: array_offset,
+            data: data.to_owned(),
+        })
+    }
+
+    fn value(&self, left: usize, right: usize) -> Option<i32> {
+        // Glyphs which are not in a class table belong to the class 0,
+        // which addresses the start of the subtable.
+        let offset = self.left_classes.class(left) as usize + self.right_classes.class(right) as usize;
+        if offset < self.array_offset || offset + 2 > self.data.len() {
+            return None;
+        }
+        Cursor::new(&self.data[offset..]).read_i16::<BigEndian>().ok().map(|v| v as i32)
+    }
+}
+
+#[derive(Debug)]
+struct ClassTable {
+    first_glyph: usize,
+    classes: Vec<u16>,
+}
+
+impl ClassTable {
+    fn from_data(data: &[u8], offset: usize) -> Result<ClassTable> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let first_glyph = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let count = try!(cursor.read_u16::<BigEndian>());
+        let mut classes = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            classes.push(try!(cursor.read_u16::<BigEndian>()));
+        }
+
+        Ok(ClassTable { first_glyph: first_glyph, classes: classes })
+    }
+
+    fn class(&self, glyph: usize) -> u16 {
+        if glyph < self.first_glyph {
+            return 0;
+        }
+        self.classes.get(glyph - self.first_glyph).cloned().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+    use byteorder::{BigEndian, WriteBytesExt};
+
+    #[test]
+    fn smoke() {
+        let data = ::utils::read_file("tests/Tuffy_Bold.ttf");
+        let offset = ::utils::find_table_offset(&data, 0, b"kern").unwrap().unwrap();
+
+        let kern = KERN::from_data(&data, offset).unwrap();
+        expect!(kern.kerning(36, 57)).to(be_equal_to(-213));
+        expect!(kern.kerning(40, 53)).to(be_equal_to(41));
+        expect!(kern.kerning(57, 36)).to(be_equal_to(-244));
+        expect!(kern.kerning(36, 36)).to(be_equal_to(0));
+        expect!(kern.cross_stream_kerning(36, 57)).to(be_equal_to(0));
+
+        expect!(KERN::from_data(&[0, 2, 0, 0], 0)).to(be_err().value(KERNVersionIsNotSupported));
+        expect!(KERN::from_data(&data, data.len())).to(be_err().value(Malformed));
+    }
+
+    fn format0(data: &mut Vec<u8>, pairs: &[(u16, u16, i16)]) {
+        data.write_u16::<BigEndian>(pairs.len() as u16).unwrap();
+        data.extend_from_slice(&[0; 6]);
+        for &(left, right, value) in pairs {
+            data.write_u16::<BigEndian>(left).unwrap();
+            data.write_u16::<BigEndian>(right).unwrap();
+            data.write_i16::<BigEndian>(value).unwrap();
+        }
+    }
+
+    fn ot_subtable(data: &mut Vec<u8>, coverage: u16, pairs: &[(u16, u16, i16)]) {
+        data.write_u16::<BigEndian>(0).unwrap();
+        data.write_u16::<BigEndian>(6 + 8 + pairs.len() as u16 * 6).unwrap();
+        data.write_u16::<BigEndian>(coverage).unwrap();
+        format0(data, pairs);
+    }
+
+    #[test]
+    fn multiple_subtables() {
+        let mut data = vec![];
+        data.write_u16::<BigEndian>(0).unwrap();
+        data.write_u16::<BigEndian>(5).unwrap();
+        ot_subtable(&mut data, OT_HORIZONTAL, &[(1, 2, -10), (3, 4, -50)]);
+        ot_subtable(&mut data, OT_HORIZONTAL, &[(1, 2, -5), (5, 6, 20)]);
+        ot_subtable(&mut data, OT_HORIZONTAL | OT_MINIMUM, &[(3, 4, -30)]);
+        ot_subtable(&mut data, OT_HORIZONTAL | OT_CROSS_STREAM, &[(1, 2, 7)]);
+        ot_subtable(&mut data, 0, &[(5, 6, 100)]);
+
+        let kern = KERN::from_data(&data, 0).unwrap();
+        expect!(kern.kerning(1, 2)).to(be_equal_to(-15));
+        expect!(kern.kerning(3, 4)).to(be_equal_to(-30));
+        expect!(kern.kerning(5, 6)).to(be_equal_to(20));
+        expect!(kern.cross_stream_kerning(1, 2)).to(be_equal_to(7));
+
+        let mut data = vec![];
+        data.write_u16::<BigEndian>(0).unwrap();
+        data.write_u16::<BigEndian>(2).unwrap();
+        ot_subtable(&mut data, OT_HORIZONTAL, &[(1, 2, -10)]);
+        ot_subtable(&mut data, OT_HORIZONTAL | OT_OVERRIDE, &[(1, 2, -3)]);
+        expect!(KERN::from_data(&data, 0).unwrap().kerning(1, 2)).to(be_equal_to(-3));
+    }
+
+    #[test]
+    fn apple_format2() {
+        let mut subtable = vec![];
+        // Header.
+        subtable.write_u32::<BigEndian>(0).unwrap();
+        subtable.write_u16::<BigEndian>(2).unwrap();
+        subtable.write_u16::<BigEndian>(0).unwrap();
+        // Row width and offsets of the left and right class tables and the array.
+        subtable.write_u16::<BigEndian>(4).unwrap();
+        subtable.write_u16::<BigEndian>(16).unwrap();
+        subtable.write_u16::<BigEndian>(24).unwrap();
+        subtable.write_u16::<BigEndian>(32).unwrap();
+        // Left classes for glyphs 10 and 11, values include the array offset.
+        for &v in &[10, 2, 32, 36] {
+            subtable.write_u16::<BigEndian>(v).unwrap();
+        }
+        // Right classes for glyphs 20 and 21.
+        for &v in &[20, 2, 0, 2] {
+            subtable.write_u16::<BigEndian>(v).unwrap();
+        }
+        for &v in &[-10, -20, -30, -40] {
+            subtable.write_i16::<BigEndian>(v).unwrap();
+        }
+        let length = subtable.len() as u32;
+        (&mut subtable[..4]).write_u32::<BigEndian>(length).unwrap();
+
+        let mut data = vec![];
+        data.write_u32::<BigEndian>(0x00010000).unwrap();
+        data.write_u32::<BigEndian>(1).unwrap();
+        data.extend_from_slice(&subtable);
+
+        let kern = KERN::from_data(&data, 0).unwrap();
+        expect!(kern.kerning(10, 20)).to(be_equal_to(-10));
+        expect!(kern.kerning(10, 21)).to(be_equal_to(-20));
+        expect!(kern.kerning(11, 21)).to(be_equal_to(-40));
+        expect!(kern.kerning(12, 21)).to(be_equal_to(0));
+    }
+}
@@ -0,0 +1,793 @@
+//! Common structures of the OpenType layout tables `GPOS` and `GSUB`.
+
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+use utils::read_bytes;
+
+/// A four byte tag of a script, language system or feature.
+pub type Tag = [u8; 4];
+
+/// A header of a layout table.
+#[derive(Debug)]
+pub struct LayoutHeader {
+    pub script_list: usize,
+    pub feature_list: usize,
+    pub lookup_list: usize,
+}
+
+impl LayoutHeader {
+    /// Reads the header of a table at `offset`, offsets of lists are
+    /// converted to offsets from the start of `data`.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<Option<LayoutHeader>> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let major_version = try!(cursor.read_u16::<BigEndian>());
+        let minor_version = try!(cursor.read_u16::<BigEndian>());
+        if major_version != 1 || minor_version > 1 {
+            return Ok(None);
+        }
+
+        Ok(Some(LayoutHeader {
+            script_list: offset + try!(cursor.read_u16::<BigEndian>()) as usize,
+            feature_list: offset + try!(cursor.read_u16::<BigEndian>()) as usize,
+            lookup_list: offset + try!(cursor.read_u16::<BigEndian>()) as usize,
+        }))
+    }
+}
+
+/// A language system, i.e. features used for a language of a script.
+#[derive(Debug, Default, Clone)]
+pub struct LangSys {
+    pub required_feature: Option<u16>,
+    pub features: Vec<u16>,
+}
+
+impl LangSys {
+    fn from_data(data: &[u8], offset: usize) -> Result<LangSys> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let _lookup_order = try!(cursor.read_u16::<BigEndian>());
+        let required_feature = try!(cursor.read_u16::<BigEndian>());
+        let count = try!(cursor.read_u16::<BigEndian>());
+        let mut features = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            features.push(try!(cursor.read_u16::<BigEndian>()));
+        }
+
+        Ok(LangSys {
+            required_feature: if required_feature == 0xffff { None } else { Some(required_feature) },
+            features: features,
+        })
+    }
+}
+
+/// A script and its language systems.
+#[derive(Debug)]
+pub struct Script {
+    pub tag: Tag,
+    pub default_lang_sys: Option<LangSys>,
+    pub lang_systems: Vec<(Tag, LangSys)>,
+}
+
+impl Script {
+    /// Returns all language systems of the script, the default one first.
+    pub fn all_lang_systems(&self) -> Vec<&LangSys> {
+        self.default_lang_sys.iter().chain(self.lang_systems.iter().map(|lang_sys| &lang_sys.1)).collect()
+    }
+}
+
+/// Returns the language system for the `language` of the `script`.
+///
+/// The default language system is used if the script has no such language,
+/// the `DFLT`, `dflt` and `latn` scripts are tried if the font has no such
+/// script.
+pub fn find_lang_sys<'a>(scripts: &'a [Script], script: &Tag, language: Option<&Tag>) -> Option<&'a LangSys> {
+    let found = scripts.iter().find(|s| &s.tag == script)
+        .or_else(|| scripts.iter().find(|s| &s.tag == b"DFLT"))
+        .or_else(|| scripts.iter().find(|s| &s.tag == b"dflt"))
+        .or_else(|| scripts.iter().find(|s| &s.tag == b"latn"));
+    found.and_then(|script| {
+        language.and_then(|language| {
+            script.lang_systems.iter().find(|lang_sys| &lang_sys.0 == language).map(|lang_sys| &lang_sys.1)
+        }).or(script.default_lang_sys.as_ref())
+    })
+}
+
+/// Returns sorted indices of lookups of features enabled by `lang_systems`
+/// whose tags are in `tags`, required features are enabled regardless of
+/// their tags if `include_required` is `true`.
+pub fn feature_lookups(features: &[Feature], lang_systems: &[&LangSys], tags: &[Tag],
+                       include_required: bool) -> Vec<usize> {
+    let mut lookups = vec![];
+    for lang_sys in lang_systems {
+        let required = lang_sys.required_feature.iter().map(|&index| (index, include_required));
+        for (index, required) in required.chain(lang_sys.features.iter().map(|&index| (index, false))) {
+            match features.get(index as usize) {
+                Some(feature) if required || tags.contains(&feature.tag) => {
+                    lookups.extend(feature.lookups.iter().map(|&lookup| lookup as usize));
+                },
+                _ => {},
+            }
+        }
+    }
+    // Lookups are applied in the order of the lookup list.
+    lookups.sort();
+    lookups.dedup();
+    lookups
+}
+
+/// Reads a script list at `offset`.
+pub fn read_script_list(data: &[u8], offset: usize) -> Result<Vec<Script>> {
+    if offset >= data.len() {
+        return Err(Error::Malformed);
+    }
+
+    let mut cursor = Cursor::new(&data[offset..]);
+    let count = try!(cursor.read_u16::<BigEndian>());
+    let mut scripts = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let tag = try!(read_tag(&mut cursor));
+        let script = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+        if script >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut script_cursor = Cursor::new(&data[script..]);
+        let default_lang_sys = try!(script_cursor.read_u16::<BigEndian>()) as usize;
+        let lang_sys_count = try!(script_cursor.read_u16::<BigEndian>());
+        let mut lang_systems = Vec::with_capacity(lang_sys_count as usize);
+        for _ in 0..lang_sys_count {
+            let lang_tag = try!(read_tag(&mut script_cursor));
+            let lang_sys = script + try!(script_cursor.read_u16::<BigEndian>()) as usize;
+            lang_systems.push((lang_tag, try!(LangSys::from_data(data, lang_sys))));
+        }
+
+        scripts.push(Script {
+            tag: tag,
+            default_lang_sys: if default_lang_sys == 0 {
+                None
+            } else {
+                Some(try!(LangSys::from_data(data, script + default_lang_sys)))
+            },
+            lang_systems: lang_systems,
+        });
+    }
+
+    Ok(scripts)
+}
+
+/// A feature and indices of its lookups.
+#[derive(Debug)]
+pub struct Feature {
+    pub tag: Tag,
+    pub lookups: Vec<u16>,
+}
+
+/// Reads a feature list at `offset`.
+pub fn read_feature_list(data: &[u8], offset: usize) -> Result<Vec<Feature>> {
+    if offset >= data.len() {
+        return Err(Error::Malformed);
+    }
+
+    let mut cursor = Cursor::new(&data[offset..]);
+    let count = try!(cursor.read_u16::<BigEndian>());
+    let mut features = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let tag = try!(read_tag(&mut cursor));
+        let feature = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+        if feature >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut feature_cursor = Cursor::new(&data[feature..]);
+        let _feature_params = try!(feature_cursor.read_u16::<BigEndian>());
+        let lookup_count = try!(feature_cursor.read_u16::<BigEndian>());
+        let mut lookups = Vec::with_capacity(lookup_count as usize);
+        for _ in 0..lookup_count {
+            lookups.push(try!(feature_cursor.read_u16::<BigEndian>()));
+        }
+        features.push(Feature { tag: tag, lookups: lookups });
+    }
+
+    Ok(features)
+}
+
+/// A header of a lookup.
+#[derive(Debug)]
+pub struct LookupHeader {
+    pub lookup_type: u16,
+    /// Offsets of subtables from the start of the data.
+    pub subtables: Vec<usize>,
+}
+
+/// Reads a lookup list at `offset`.
+///
+/// Extension subtables of the `extension_type` are resolved, so the lookups
+/// contain their actual types and subtables.
+pub fn read_lookup_list(data: &[u8], offset: usize, extension_type: u16) -> Result<Vec<LookupHeader>> {
+    if offset >= data.len() {
+        return Err(Error::Malformed);
+    }
+
+    let mut cursor = Cursor::new(&data[offset..]);
+    let count = try!(cursor.read_u16::<BigEndian>());
+    let mut lookups = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let lookup = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+        if lookup >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut lookup_cursor = Cursor::new(&data[lookup..]);
+        let mut lookup_type = try!(lookup_cursor.read_u16::<BigEndian>());
+        let _flag = try!(lookup_cursor.read_u16::<BigEndian>());
+        let subtable_count = try!(lookup_cursor.read_u16::<BigEndian>());
+        let mut subtables = Vec::with_capacity(subtable_count as usize);
+        for _ in 0..subtable_count {
+            subtables.push(lookup + try!(lookup_cursor.read_u16::<BigEndian>()) as usize);
+        }
+
+        if lookup_type == extension_type {
+            let mut actual_type = None;
+            for subtable in &mut subtables {
+                if *subtable >= data.len() {
+                    return Err(Error::Malformed);
+                }
+                let mut extension_cursor = Cursor::new(&data[*subtable..]);
+                let _format = try!(extension_cursor.read_u16::<BigEndian>());
+                let extension_lookup_type = try!(extension_cursor.read_u16::<BigEndian>());
+                // All extension subtables of a lookup must have the same type.
+                if actual_type.is_some() && actual_type != Some(extension_lookup_type) {
+                    return Err(Error::Malformed);
+                }
+                actual_type = Some(extension_lookup_type);
+                *subtable += try!(extension_cursor.read_u32::<BigEndian>()) as usize;
+            }
+            lookup_type = actual_type.unwrap_or(extension_type);
+        }
+
+        lookups.push(LookupHeader {
+            lookup_type: lookup_type,
+            subtables: subtables,
+        });
+    }
+
+    Ok(lookups)
+}
+
+/// A coverage table, i.e. a set of glyphs with their coverage indices.
+#[derive(Debug)]
+pub enum Coverage {
+    /// Sorted glyph indices, coverage indices are positions in the list.
+    Glyphs(Vec<u16>),
+    /// Sorted ranges of glyph indices with the coverage index of the first glyph.
+    Ranges(Vec<(u16, u16, u16)>),
+}
+
+impl Coverage {
+    /// Reads a coverage table at `offset`.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<Coverage> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let format = try!(cursor.read_u16::<BigEndian>());
+        let count = try!(cursor.read_u16::<BigEndian>());
+        match format {
+            1 => {
+                let mut glyphs = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    glyphs.push(try!(cursor.read_u16::<BigEndian>()));
+                }
+                Ok(Coverage::Glyphs(glyphs))
+            },
+            2 => {
+                let mut ranges = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let start = try!(cursor.read_u16::<BigEndian>());
+                    let end = try!(cursor.read_u16::<BigEndian>());
+                    let index = try!(cursor.read_u16::<BigEndian>());
+                    ranges.push((start, end, index));
+                }
+                Ok(Coverage::Ranges(ranges))
+            },
+            _ => Err(Error::Malformed),
+        }
+    }
+
+    /// Returns `true` if the glyph is covered.
+    pub fn contains(&self, glyph: usize) -> bool {
+        self.index(glyph).is_some()
+    }
+
+    /// Returns the coverage index of the glyph, or `None` if the glyph
+    /// is not covered.
+    pub fn index(&self, glyph: usize) -> Option<usize> {
+        use std::cmp::Ordering::*;
+
+        if glyph > 0xffff {
+            return None;
+        }
+        let glyph = glyph as u16;
+        match *self {
+            Coverage::Glyphs(ref glyphs) => glyphs.binary_search(&glyph).ok(),
+            Coverage::Ranges(ref ranges) => ranges.binary_search_by(|&(start, end, _)| {
+                if glyph < start {
+                    Greater
+                } else if glyph > end {
+                    Less
+                } else {
+                    Equal
+                }
+            }).ok().map(|i| {
+                let (start, _, index) = ranges[i];
+                index as usize + (glyph - start) as usize
+            }),
+        }
+    }
+}
+
+/// A class definition table, i.e. a mapping of glyphs to classes.
+///
+/// Glyphs which are not mentioned in the table belong to the class 0.
+#[derive(Debug)]
+pub enum ClassDef {
+    /// Classes of consecutive glyphs starting from the first glyph.
+    Array(u16, Vec<u16>),
+    /// Sorted ranges of glyph indices with their classes.
+    Ranges(Vec<(u16, u16, u16)>),
+}
+
+impl ClassDef {
+    /// Reads a class definition table at `offset`.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<ClassDef> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        match try!(cursor.read_u16::<BigEndian>()) {
+            1 => {
+                let start = try!(cursor.read_u16::<BigEndian>());
+                let count = try!(cursor.read_u16::<BigEndian>());
+                let mut classes = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    classes.push(try!(cursor.read_u16::<BigEndian>()));
+                }
+                Ok(ClassDef::Array(start, classes))
+            },
+            2 => {
+                let count = try!(cursor.read_u16::<BigEndian>());
+                let mut ranges = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let start = try!(cursor.read_u16::<BigEndian>());
+                    let end = try!(cursor.read_u16::<BigEndian>());
+                    let class = try!(cursor.read_u16::<BigEndian>());
+                    ranges.push((start, end, class));
+                }
+                Ok(ClassDef::Ranges(ranges))
+            },
+            _ => Err(Error::Malformed),
+        }
+    }
+
+    /// Returns the class of the glyph.
+    pub fn class(&self, glyph: usize) -> u16 {
+        use std::cmp::Ordering::*;
+
+        if glyph > 0xffff {
+            return 0;
+        }
+        let glyph = glyph as u16;
+        match *self {
+            ClassDef::Array(start, ref classes) => {
+                if glyph < start {
+                    0
+                } else {
+                    classes.get((glyph - start) as usize).cloned().unwrap_or(0)
+                }
+            },
+            ClassDef::Ranges(ref ranges) => ranges.binary_search_by(|&(start, end, _)| {
+                if glyph < start {
+                    Greater
+                } else if glyph > end {
+                    Less
+                } else {
+                    Equal
+                }
+            }).ok().map_or(0, |i| ranges[i].2),
+        }
+    }
+}
+
+/// A rule of a context subtable.
+///
+/// Values are glyphs or classes depending on the format of the subtable.
+#[derive(Debug)]
+pub struct ContextRule {
+    /// Values before the input sequence, the closest first.
+    pub backtrack: Vec<u16>,
+    /// Values of the input sequence after the first glyph.
+    pub input: Vec<u16>,
+    /// Values after the input sequence.
+    pub lookahead: Vec<u16>,
+    /// Sequence indices and lookup indices applied to the input sequence.
+    pub lookups: Vec<(u16, u16)>,
+}
+
+impl ContextRule {
+    fn from_data(data: &[u8], offset: usize, chaining: bool) -> Result<ContextRule> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let mut rule = ContextRule {
+            backtrack: vec![],
+            input: vec![],
+            lookahead: vec![],
+            lookups: vec![],
+        };
+        if chaining {
+            rule.backtrack = try!(read_u16_array(&mut cursor));
+            let count = try!(cursor.read_u16::<BigEndian>());
+            for _ in 1..count {
+                rule.input.push(try!(cursor.read_u16::<BigEndian>()));
+            }
+            rule.lookahead = try!(read_u16_array(&mut cursor));
+            rule.lookups = try!(read_lookup_records(&mut cursor));
+        } else {
+            let count = try!(cursor.read_u16::<BigEndian>());
+            let lookup_count = try!(cursor.read_u16::<BigEndian>());
+            for _ in 1..count {
+                rule.input.push(try!(cursor.read_u16::<BigEndian>()));
+            }
+            rule.lookups = try!(read_lookup_records_n(&mut cursor, lookup_count));
+        }
+
+        Ok(rule)
+    }
+
+    fn matches<B, I, L>(&self, glyphs: &[usize], i: usize, backtrack: B, input: I, lookahead: L) -> bool
+        where B: Fn(usize, u16) -> bool, I: Fn(usize, u16) -> bool, L: Fn(usize, u16) -> bool
+    {
+        let end = i + 1 + self.input.len();
+        if i < self.backtrack.len() || end + self.lookahead.len() > glyphs.len() {
+            return false;
+        }
+        self.backtrack.iter().enumerate().all(|(k, &value)| backtrack(glyphs[i - 1 - k], value)) &&
+        self.input.iter().enumerate().all(|(k, &value)| input(glyphs[i + 1 + k], value)) &&
+        self.lookahead.iter().enumerate().all(|(k, &value)| lookahead(glyphs[end + k], value))
+    }
+}
+
+/// A context or chaining context subtable.
+#[derive(Debug)]
+pub enum Context {
+    /// Rules of glyph sequences, indexed by coverage indices of the first glyph.
+    Glyphs {
+        coverage: Coverage,
+        rule_sets: Vec<Vec<ContextRule>>,
+    },
+    /// Rules of class sequences, indexed by the class of the first glyph.
+    Classes {
+        coverage: Coverage,
+        backtrack_classes: ClassDef,
+        input_classes: ClassDef,
+        lookahead_classes: ClassDef,
+        rule_sets: Vec<Vec<ContextRule>>,
+    },
+    /// A single rule of coverage sequences.
+    Coverages {
+        backtrack: Vec<Coverage>,
+        input: Vec<Coverage>,
+        lookahead: Vec<Coverage>,
+        lookups: Vec<(u16, u16)>,
+    },
+}
+
+impl Context {
+    /// Reads a context subtable at `offset`, or a chaining context subtable
+    /// if `chaining` is `true`.
+    pub fn from_data(data: &[u8], offset: usize, chaining: bool) -> Result<Context> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        match try!(cursor.read_u16::<BigEndian>()) {
+            1 => {
+                let coverage = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                Ok(Context::Glyphs {
+                    coverage: try!(Coverage::from_data(data, coverage)),
+                    rule_sets: try!(read_rule_sets(data, offset, &mut cursor, chaining)),
+                })
+            },
+            2 => {
+                let coverage = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                let coverage = try!(Coverage::from_data(data, coverage));
+                let (backtrack_classes, input_classes, lookahead_classes) = if chaining {
+                    let backtrack = try!(read_optional_class_def(data, offset, &mut cursor));
+                    let input = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                    let input = try!(ClassDef::from_data(data, input));
+                    let lookahead = try!(read_optional_class_def(data, offset, &mut cursor));
+                    (backtrack, input, lookahead)
+                } else {
+                    // Context rules have neither backtrack nor lookahead values.
+                    let input = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                    (ClassDef::Array(0, vec![]),
+                     try!(ClassDef::from_data(data, input)),
+                     ClassDef::Array(0, vec![]))
+                };
+                Ok(Context::Classes {
+                    coverage: coverage,
+                    backtrack_classes: backtrack_classes,
+                    input_classes: input_classes,
+                    lookahead_classes: lookahead_classes,
+                    rule_sets: try!(read_rule_sets(data, offset, &mut cursor, chaining)),
+                })
+            },
+            3 => {
+                if chaining {
+                    let backtrack = try!(read_coverages(data, offset, &mut cursor));
+                    let input = try!(read_coverages(data, offset, &mut cursor));
+                    let lookahead = try!(read_coverages(data, offset, &mut cursor));
+                    Ok(Context::Coverages {
+                        backtrack: backtrack,
+                        input: input,
+                        lookahead: lookahead,
+                        lookups: try!(read_lookup_records(&mut cursor)),
+                    })
+                } else {
+                    let count = try!(cursor.read_u16::<BigEndian>());
+                    let lookup_count = try!(cursor.read_u16::<BigEndian>());
+                    let mut input = Vec::with_capacity(count as usize);
+                    for _ in 0..count {
+                        let coverage = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+                        input.push(try!(Coverage::from_data(data, coverage)));
+                    }
+                    Ok(Context::Coverages {
+                        backtrack: vec![],
+                        input: input,
+                        lookahead: vec![],
+                        lookups: try!(read_lookup_records_n(&mut cursor, lookup_count)),
+                    })
+                }
+            },
+            _ => Err(Error::Malformed),
+        }
+    }
+
+    /// Matches the subtable against `glyphs` with the input sequence
+    /// starting at `i`.
+    ///
+    /// Returns the length of the input sequence and lookup records of
+    /// the first matching rule.
+    pub fn matches(&self, glyphs: &[usize], i: usize) -> Option<(usize, &[(u16, u16)])> {
+        match *self {
+            Context::Glyphs { ref coverage, ref rule_sets } => {
+                let same = |glyph: usize, value: u16| glyph == value as usize;
+                coverage.index(glyphs[i]).and_then(|index| rule_sets.get(index)).and_then(|rules| {
+                    rules.iter().find(|rule| rule.matches(glyphs, i, same, same, same))
+                }).map(|rule| (rule.input.len() + 1, &rule.lookups[..]))
+            },
+            Context::Classes {
+                ref coverage, ref backtrack_classes, ref input_classes, ref lookahead_classes, ref rule_sets
+            } => {
+                if !coverage.contains(glyphs[i]) {
+                    return None;
+                }
+                rule_sets.get(input_classes.class(glyphs[i]) as usize).and_then(|rules| {
+                    rules.iter().find(|rule| {
+                        rule.matches(glyphs, i,
+                                     |glyph, class| backtrack_classes.class(glyph) == class,
+                                     |glyph, class| input_classes.class(glyph) == class,
+                                     |glyph, class| lookahead_classes.class(glyph) == class)
+                    })
+                }).map(|rule| (rule.input.len() + 1, &rule.lookups[..]))
+            },
+            Context::Coverages { ref backtrack, ref input, ref lookahead, ref lookups } => {
+                let end = i + input.len();
+                if input.is_empty() || i < backtrack.len() || end + lookahead.len() > glyphs.len() {
+                    return None;
+                }
+                let matched =
+                    backtrack.iter().enumerate().all(|(k, coverage)| coverage.contains(glyphs[i - 1 - k])) &&
+                    input.iter().enumerate().all(|(k, coverage)| coverage.contains(glyphs[i + k])) &&
+                    lookahead.iter().enumerate().all(|(k, coverage)| coverage.contains(glyphs[end + k]));
+                if matched {
+                    Some((input.len(), &lookups[..]))
+                } else {
+                    None
+                }
+            },
+        }
+    }
+}
+
+/// Reads offsets of rule sets and their rules, null offsets are read as
+/// empty rule sets.
+fn read_rule_sets(data: &[u8], offset: usize, cursor: &mut Cursor<&[u8]>,
+                  chaining: bool) -> Result<Vec<Vec<ContextRule>>> {
+    let count = try!(cursor.read_u16::<BigEndian>());
+    let mut rule_sets = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let rule_set = try!(cursor.read_u16::<BigEndian>()) as usize;
+        let mut rules = vec![];
+        if rule_set != 0 {
+            let rule_set = offset + rule_set;
+            if rule_set >= data.len() {
+                return Err(Error::Malformed);
+            }
+            let mut rule_cursor = Cursor::new(&data[rule_set..]);
+            let rule_count = try!(rule_cursor.read_u16::<BigEndian>());
+            for _ in 0..rule_count {
+                let rule = rule_set + try!(rule_cursor.read_u16::<BigEndian>()) as usize;
+                rules.push(try!(ContextRule::from_data(data, rule, chaining)));
+            }
+        }
+        rule_sets.push(rules);
+    }
+    Ok(rule_sets)
+}
+
+/// Reads an offset of a class definition and the class definition, a null
+/// offset is read as an empty class definition.
+fn read_optional_class_def(data: &[u8], offset: usize, cursor: &mut Cursor<&[u8]>) -> Result<ClassDef> {
+    match try!(cursor.read_u16::<BigEndian>()) as usize {
+        0 => Ok(ClassDef::Array(0, vec![])),
+        class_def => ClassDef::from_data(data, offset + class_def),
+    }
+}
+
+/// Reads a counted array of coverage offsets and their coverage tables.
+fn read_coverages(data: &[u8], offset: usize, cursor: &mut Cursor<&[u8]>) -> Result<Vec<Coverage>> {
+    let count = try!(cursor.read_u16::<BigEndian>());
+    let mut coverages = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let coverage = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+        coverages.push(try!(Coverage::from_data(data, coverage)));
+    }
+    Ok(coverages)
+}
+
+/// Reads a counted array of sequence and lookup indices.
+fn read_lookup_records(cursor: &mut Cursor<&[u8]>) -> Result<Vec<(u16, u16)>> {
+    let count = try!(cursor.read_u16::<BigEndian>());
+    read_lookup_records_n(cursor, count)
+}
+
+/// Reads `count` sequence and lookup indices.
+fn read_lookup_records_n(cursor: &mut Cursor<&[u8]>, count: u16) -> Result<Vec<(u16, u16)>> {
+    let mut records = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let sequence_index = try!(cursor.read_u16::<BigEndian>());
+        let lookup_index = try!(cursor.read_u16::<BigEndian>());
+        records.push((sequence_index, lookup_index));
+    }
+    Ok(records)
+}
+
+/// Reads a counted array of 16-bit values.
+pub fn read_u16_array(cursor: &mut Cursor<&[u8]>) -> Result<Vec<u16>> {
+    let count = try!(cursor.read_u16::<BigEndian>());
+    let mut values = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        values.push(try!(cursor.read_u16::<BigEndian>()));
+    }
+    Ok(values)
+}
+
+/// Reads a four byte tag.
+pub fn read_tag(cursor: &mut Cursor<&[u8]>) -> Result<Tag> {
+    let mut tag = [0; 4];
+    try!(read_bytes(cursor, &mut tag));
+    Ok(tag)
+}
+
+/// Helpers to write layout structures in tests.
+#[cfg(test)]
+pub mod tests {
+    use byteorder::{BigEndian, WriteBytesExt};
+
+    /// Writes a table header, a script list with the default script and
+    /// a feature list with `features`, each feature uses the lookups.
+    /// Returns the data and the offset of the lookup list to write next.
+    pub fn header(features: &[(&[u8; 4], &[u16])]) -> Vec<u8> {
+        let mut data = vec![];
+        data.write_u16::<BigEndian>(1).unwrap();
+        data.write_u16::<BigEndian>(0).unwrap();
+        // Offsets of the script, feature and lookup lists.
+        data.write_u16::<BigEndian>(10).unwrap();
+        let features_size: usize = features.iter().map(|&(_, l)| 6 + 4 + l.len() * 2).sum();
+        // Script list: one 'DFLT' script with the default language system
+        // which uses all features.
+        let script_list_size = 2 + 6 + 4 + 6 + features.len() * 2;
+        data.write_u16::<BigEndian>((10 + script_list_size) as u16).unwrap();
+        data.write_u16::<BigEndian>((10 + script_list_size + 2 + features_size) as u16).unwrap();
+
+        data.write_u16::<BigEndian>(1).unwrap();
+        data.extend_from_slice(b"DFLT");
+        data.write_u16::<BigEndian>(8).unwrap();
+        data.write_u16::<BigEndian>(4).unwrap();
+        data.write_u16::<BigEndian>(0).unwrap();
+        data.write_u16::<BigEndian>(0).unwrap();
+        data.write_u16::<BigEndian>(0xffff).unwrap();
+        data.write_u16::<BigEndian>(features.len() as u16).unwrap();
+        for i in 0..features.len() {
+            data.write_u16::<BigEndian>(i as u16).unwrap();
+        }
+
+        data.write_u16::<BigEndian>(features.len() as u16).unwrap();
+        let mut offset = 2 + features.len() * 6;
+        for &(tag, lookups) in features {
+            data.extend_from_slice(tag);
+            data.write_u16::<BigEndian>(offset as u16).unwrap();
+            offset += 4 + lookups.len() * 2;
+        }
+        for &(_, lookups) in features {
+            data.write_u16::<BigEndian>(0).unwrap();
+            data.write_u16::<BigEndian>(lookups.len() as u16).unwrap();
+            for &lookup in lookups {
+                data.write_u16::<BigEndian>(lookup).unwrap();
+            }
+        }
+        data
+    }
+
+    /// Appends a lookup list with lookups of `(type, subtables)` to `data`.
+    pub fn lookup_list(data: &mut Vec<u8>, lookups: &[(u16, &[&[u8]])]) {
+        data.write_u16::<BigEndian>(lookups.len() as u16).unwrap();
+        let mut offset = 2 + lookups.len() * 2;
+        for &(_, subtables) in lookups {
+            data.write_u16::<BigEndian>(offset as u16).unwrap();
+            offset += 6 + subtables.len() * 2 + subtables.iter().map(|s| s.len()).sum::<usize>();
+        }
+        for &(lookup_type, subtables) in lookups {
+            data.write_u16::<BigEndian>(lookup_type).unwrap();
+            data.write_u16::<BigEndian>(0).unwrap();
+            data.write_u16::<BigEndian>(subtables.len() as u16).unwrap();
+            let mut offset = 6 + subtables.len() * 2;
+            for subtable in subtables {
+                data.write_u16::<BigEndian>(offset as u16).unwrap();
+                offset += subtable.len();
+            }
+            for subtable in subtables {
+                data.extend_from_slice(subtable);
+            }
+        }
+    }
+
+    /// Returns a coverage table of the format 1.
+    pub fn coverage(glyphs: &[u16]) -> Vec<u8> {
+        let mut data = vec![];
+        data.write_u16::<BigEndian>(1).unwrap();
+        data.write_u16::<BigEndian>(glyphs.len() as u16).unwrap();
+        for &glyph in glyphs {
+            data.write_u16::<BigEndian>(glyph).unwrap();
+        }
+        data
+    }
+
+    /// Returns a class definition table of the format 2.
+    pub fn class_def(ranges: &[(u16, u16, u16)]) -> Vec<u8> {
+        let mut data = vec![];
+        data.write_u16::<BigEndian>(2).unwrap();
+        data.write_u16::<BigEndian>(ranges.len() as u16).unwrap();
+        for &(start, end, class) in ranges {
+            data.write_u16::<BigEndian>(start).unwrap();
+            data.write_u16::<BigEndian>(end).unwrap();
+            data.write_u16::<BigEndian>(class).unwrap();
+        }
+        data
+    }
+}
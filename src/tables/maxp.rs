@@ -8,11 +8,25 @@ use byteorder::{BigEndian, ReadBytesExt};
 /// A maximum profile.
 ///
 /// The 'maxp' table establishes the memory requirements for a font.
-/// TODO: implement parsing of 1.0 version of the table.
+/// Version 1.0 of the table also holds limits for TrueType instructions,
+/// which are zero for version 0.5.
 #[derive(Debug, Default)]
 pub struct MAXP {
     version: Fixed,
     num_glyphs: u16,
+    max_points: u16,
+    max_contours: u16,
+    max_composite_points: u16,
+    max_composite_contours: u16,
+    max_zones: u16,
+    max_twilight_points: u16,
+    max_storage: u16,
+    max_function_defs: u16,
+    max_instruction_defs: u16,
+    max_stack_elements: u16,
+    max_size_of_instructions: u16,
+    max_component_elements: u16,
+    max_component_depth: u16,
 }
 
 impl MAXP {
@@ -35,6 +49,21 @@ impl MAXP {
                 let mut maxp = MAXP::default();
                 maxp.version = version;
                 maxp.num_glyphs = try!(cursor.read_u16::<BigEndian>());
+                if version == Fixed(0x00010000) {
+                    maxp.max_points = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_contours = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_composite_points = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_composite_contours = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_zones = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_twilight_points = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_storage = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_function_defs = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_instruction_defs = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_stack_elements = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_size_of_instructions = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_component_elements = try!(cursor.read_u16::<BigEndian>());
+                    maxp.max_component_depth = try!(cursor.read_u16::<BigEndian>());
+                }
                 Ok(maxp)
             },
             _ => Err(Error::MAXPVersionIsNotSupported),
@@ -48,6 +77,15 @@ impl MAXP {
         let mut data = vec![];
         data.write_i32::<BigEndian>(self.version.0).unwrap();
         data.write_u16::<BigEndian>(self.num_glyphs).unwrap();
+        if self.version == Fixed(0x00010000) {
+            for &value in &[self.max_points, self.max_contours, self.max_composite_points,
+                            self.max_composite_contours, self.max_zones, self.max_twilight_points,
+                            self.max_storage, self.max_function_defs, self.max_instruction_defs,
+                            self.max_stack_elements, self.max_size_of_instructions,
+                            self.max_component_elements, self.max_component_depth] {
+                data.write_u16::<BigEndian>(value).unwrap();
+            }
+        }
         data
     }
 
@@ -55,6 +93,27 @@ impl MAXP {
     pub fn num_glyphs(&self) -> u32 {
         self.num_glyphs as u32
     }
+
+    /// Returns the number of points in the twilight zone of TrueType
+    /// instructions.
+    pub fn max_twilight_points(&self) -> usize {
+        self.max_twilight_points as usize
+    }
+
+    /// Returns the number of storage locations of TrueType instructions.
+    pub fn max_storage(&self) -> usize {
+        self.max_storage as usize
+    }
+
+    /// Returns the number of functions defined by TrueType instructions.
+    pub fn max_function_defs(&self) -> usize {
+        self.max_function_defs as usize
+    }
+
+    /// Returns the maximum depth of the stack of TrueType instructions.
+    pub fn max_stack_elements(&self) -> usize {
+        self.max_stack_elements as usize
+    }
 }
 
 #[cfg(test)]
@@ -63,7 +122,7 @@ mod tests {
     use Error::*;
     use expectest::prelude::*;
 
-    const SIZE: usize = 4 + 2;
+    const SIZE: usize = 4 + 2 + 13 * 2;
 
     #[test]
     fn smoke() {
@@ -3,15 +3,58 @@ mod hhea;
 mod head;
 mod maxp;
 mod hmtx;
+mod vhea;
+mod vmtx;
 mod loca;
 mod cmap;
 mod glyf;
+mod cvt;
+mod fpgm;
+mod prep;
+mod cff;
+mod colr;
+mod cpal;
+mod eblc;
+mod ebdt;
+mod sbix;
+mod svg;
+mod fvar;
+mod avar;
+mod gvar;
+mod name;
+mod os2;
+mod post;
+mod kern;
+mod layout;
+mod gpos;
+mod gsub;
 
 pub use self::hhea::HHEA;
 pub use self::head::HEAD;
 pub use self::maxp::MAXP;
 pub use self::hmtx::{HMTX, LongHorizontalMetric};
+pub use self::vhea::VHEA;
+pub use self::vmtx::{VMTX, LongVerticalMetric};
 pub use self::loca::LOCA;
 pub use self::cmap::CMAP;
-pub use self::glyf::{GLYF, GlyphData};
+pub use self::glyf::{GLYF, GlyphData, Component, ComponentOffset};
+pub use self::cvt::CVT;
+pub use self::fpgm::FPGM;
+pub use self::prep::PREP;
+pub use self::cff::CFF;
+pub use self::colr::{COLR, LayerRecord, Paint, ColorLine, ColorStop, Extend, CompositeMode};
+pub use self::cpal::CPAL;
+pub use self::eblc::{EBLC, BitmapLocation, BitmapMetrics};
+pub use self::ebdt::EBDT;
+pub use self::sbix::SBIX;
+pub use self::svg::SVG;
+pub use self::fvar::{FVAR, VariationAxis, NamedInstance};
+pub use self::avar::AVAR;
+pub use self::gvar::GVAR;
 
+pub use self::name::{NAME, NameRecord};
+pub use self::os2::OS2;
+pub use self::post::POST;
+pub use self::kern::KERN;
+pub use self::gpos::{GPOS, ValueRecord};
+pub use self::gsub::GSUB;
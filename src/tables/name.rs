@@ -0,0 +1,365 @@
+
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+
+const COPYRIGHT: u16 = 0;
+const FAMILY: u16 = 1;
+const SUBFAMILY: u16 = 2;
+const UNIQUE_ID: u16 = 3;
+const FULL_NAME: u16 = 4;
+const VERSION: u16 = 5;
+const POST_SCRIPT_NAME: u16 = 6;
+const LICENSE: u16 = 13;
+const TYPOGRAPHIC_FAMILY: u16 = 16;
+const TYPOGRAPHIC_SUBFAMILY: u16 = 17;
+
+const PLATFORM_UNICODE: u16 = 0;
+const PLATFORM_MACINTOSH: u16 = 1;
+const PLATFORM_MICROSOFT: u16 = 3;
+
+/// A naming table.
+///
+/// The `name` table contains human readable strings such as the name of
+/// the font family, its style, version and license. Each string can be
+/// present in several encodings and languages, accessors prefer English
+/// strings in Unicode encodings.
+#[derive(Debug, Default)]
+pub struct NAME {
+    records: Vec<NameRecord>,
+    language_tags: Vec<String>,
+}
+
+impl NAME {
+    /// Returns `name` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or format of
+    /// the `name` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<NAME> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let format = try!(cursor.read_u16::<BigEndian>());
+        if format > 1 {
+            return Err(Error::NAMEFormatIsNotSupported);
+        }
+        let count = try!(cursor.read_u16::<BigEndian>());
+        let storage = offset + try!(cursor.read_u16::<BigEndian>()) as usize;
+
+        let mut name = NAME {
+            records: Vec::with_capacity(count as usize),
+            language_tags: vec![],
+        };
+
+        for _ in 0..count {
+            let platform_id = try!(cursor.read_u16::<BigEndian>());
+            let encoding_id = try!(cursor.read_u16::<BigEndian>());
+            let language_id = try!(cursor.read_u16::<BigEndian>());
+            let name_id = try!(cursor.read_u16::<BigEndian>());
+            let bytes = try!(read_string(&mut cursor, data, storage));
+            name.records.push(NameRecord {
+                platform_id: platform_id,
+                encoding_id: encoding_id,
+                language_id: language_id,
+                name_id: name_id,
+                bytes: bytes.to_owned(),
+            });
+        }
+
+        if format == 1 {
+            let count = try!(cursor.read_u16::<BigEndian>());
+            for _ in 0..count {
+                let bytes = try!(read_string(&mut cursor, data, storage));
+                name.language_tags.push(decode_utf16_be(bytes));
+            }
+        }
+
+        Ok(name)
+    }
+
+    /// Returns all name records of the table.
+    pub fn records(&self) -> &[NameRecord] {
+        &self.records
+    }
+
+    /// Returns the language tag for a `language_id` of a Unicode name record.
+    ///
+    /// Language tags are present only in the format 1 of the table, they are
+    /// referenced by language ids starting from 0x8000.
+    pub fn language_tag(&self, language_id: u16) -> Option<&str> {
+        if language_id < 0x8000 {
+            return None;
+        }
+        self.language_tags.get((language_id - 0x8000) as usize).map(|tag| &tag[..])
+    }
+
+    /// Returns the decoded string with the `name_id`.
+    ///
+    /// When there are several records with the `name_id`, the string
+    /// is selected in the following order: Windows English (United States),
+    /// Unicode, any Windows, Macintosh English and finally any record
+    /// that can be decoded.
+    pub fn name(&self, name_id: u16) -> Option<String> {
+        self.records.iter()
+            .filter(|r| r.name_id == name_id && r.is_decodable())
+            .min_by_key(|r| r.order())
+            .and_then(|r| r.decode())
+    }
+
+    /// Returns the copyright notice.
+    pub fn copyright(&self) -> Option<String> {
+        self.name(COPYRIGHT)
+    }
+
+    /// Returns the font family name, e.g. "Tuffy".
+    ///
+    /// This name is limited to four styles of a family, use
+    /// `typographic_family_name` to get the name shared by all styles.
+    pub fn family_name(&self) -> Option<String> {
+        self.name(FAMILY)
+    }
+
+    /// Returns the font subfamily name, e.g. "Bold".
+    pub fn subfamily_name(&self) -> Option<String> {
+        self.name(SUBFAMILY)
+    }
+
+    /// Returns the unique font identifier.
+    pub fn unique_id(&self) -> Option<String> {
+        self.name(UNIQUE_ID)
+    }
+
+    /// Returns the full font name, e.g. "Tuffy Bold".
+    pub fn full_name(&self) -> Option<String> {
+        self.name(FULL_NAME)
+    }
+
+    /// Returns the version string, e.g. "Version 1.000".
+    pub fn version(&self) -> Option<String> {
+        self.name(VERSION)
+    }
+
+    /// Returns the PostScript name of the font, e.g. "Tuffy-Bold".
+    pub fn post_script_name(&self) -> Option<String> {
+        self.name(POST_SCRIPT_NAME)
+    }
+
+    /// Returns the description of the license.
+    pub fn license(&self) -> Option<String> {
+        self.name(LICENSE)
+    }
+
+    /// Returns the typographic family name.
+    ///
+    /// Falls back to `family_name` if the font doesn't contain
+    /// the typographic family name.
+    pub fn typographic_family_name(&self) -> Option<String> {
+        self.name(TYPOGRAPHIC_FAMILY).or_else(|| self.family_name())
+    }
+
+    /// Returns the typographic subfamily name.
+    ///
+    /// Falls back to `subfamily_name` if the font doesn't contain
+    /// the typographic subfamily name.
+    pub fn typographic_subfamily_name(&self) -> Option<String> {
+        self.name(TYPOGRAPHIC_SUBFAMILY).or_else(|| self.subfamily_name())
+    }
+
+    #[cfg(test)]
+    fn bytes(&self) -> Vec<u8> {
+        use byteorder::WriteBytesExt;
+
+        let format = if self.language_tags.is_empty() { 0 } else { 1 };
+        let mut storage = vec![];
+        let mut data = vec![];
+        data.write_u16::<BigEndian>(format).unwrap();
+        data.write_u16::<BigEndian>(self.records.len() as u16).unwrap();
+        let size = 6 + self.records.len() * 12 + if format == 1 {
+            2 + self.language_tags.len() * 4
+        } else {
+            0
+        };
+        data.write_u16::<BigEndian>(size as u16).unwrap();
+        for record in &self.records {
+            data.write_u16::<BigEndian>(record.platform_id).unwrap();
+            data.write_u16::<BigEndian>(record.encoding_id).unwrap();
+            data.write_u16::<BigEndian>(record.language_id).unwrap();
+            data.write_u16::<BigEndian>(record.name_id).unwrap();
+            data.write_u16::<BigEndian>(record.bytes.len() as u16).unwrap();
+            data.write_u16::<BigEndian>(storage.len() as u16).unwrap();
+            storage.extend_from_slice(&record.bytes);
+        }
+        if format == 1 {
+            data.write_u16::<BigEndian>(self.language_tags.len() as u16).unwrap();
+            for tag in &self.language_tags {
+                let units: Vec<u16> = tag.encode_utf16().collect();
+                data.write_u16::<BigEndian>(units.len() as u16 * 2).unwrap();
+                data.write_u16::<BigEndian>(storage.len() as u16).unwrap();
+                for unit in units {
+                    storage.write_u16::<BigEndian>(unit).unwrap();
+                }
+            }
+        }
+        data.extend_from_slice(&storage);
+        data
+    }
+}
+
+/// A record of the naming table.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NameRecord {
+    /// The platform identifier: 0 is Unicode, 1 is Macintosh and 3 is Windows.
+    pub platform_id: u16,
+    /// The platform-specific encoding identifier.
+    pub encoding_id: u16,
+    /// The language identifier.
+    pub language_id: u16,
+    /// The name identifier, e.g. 1 is the font family name.
+    pub name_id: u16,
+    bytes: Vec<u8>,
+}
+
+impl NameRecord {
+    /// Returns the raw encoded bytes of the string.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns `true` if the string is encoded in UTF-16BE or Mac Roman.
+    pub fn is_decodable(&self) -> bool {
+        match self.platform_id {
+            PLATFORM_UNICODE => true,
+            PLATFORM_MACINTOSH => self.encoding_id == 0,
+            PLATFORM_MICROSOFT => self.encoding_id <= 1 || self.encoding_id == 10,
+            _ => false,
+        }
+    }
+
+    /// Returns the decoded string.
+    ///
+    /// Returns `None` if the encoding of the string is not supported.
+    pub fn decode(&self) -> Option<String> {
+        if !self.is_decodable() {
+            return None;
+        }
+        if self.platform_id == PLATFORM_MACINTOSH {
+            Some(decode_mac_roman(&self.bytes))
+        } else {
+            Some(decode_utf16_be(&self.bytes))
+        }
+    }
+
+    /// Defines an order in which the records with the same name id
+    /// should be selected.
+    fn order(&self) -> u32 {
+        match (self.platform_id, self.language_id) {
+            (PLATFORM_MICROSOFT, 0x0409) => 0,
+            (PLATFORM_UNICODE, _) => 1,
+            (PLATFORM_MICROSOFT, _) => 2,
+            (PLATFORM_MACINTOSH, 0) => 3,
+            _ => 10,
+        }
+    }
+}
+
+/// Reads the length and the offset of a string and returns its bytes.
+fn read_string<'a>(cursor: &mut Cursor<&[u8]>, data: &'a [u8], storage: usize) -> Result<&'a [u8]> {
+    let length = try!(cursor.read_u16::<BigEndian>()) as usize;
+    let start = storage + try!(cursor.read_u16::<BigEndian>()) as usize;
+    if start + length > data.len() {
+        return Err(Error::Malformed);
+    }
+    Ok(&data[start..start + length])
+}
+
+fn decode_utf16_be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks(2)
+        .filter(|c| c.len() == 2)
+        .map(|c| (c[0] as u16) << 8 | c[1] as u16)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn decode_mac_roman(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| {
+        if b < 0x80 { b as char } else { MAC_ROMAN[b as usize - 0x80] }
+    }).collect()
+}
+
+/// The upper half of the Mac OS Roman character set.
+const MAC_ROMAN: [char; 128] = [
+    '\u{00C4}', '\u{00C5}', '\u{00C7}', '\u{00C9}', '\u{00D1}', '\u{00D6}', '\u{00DC}', '\u{00E1}',
+    '\u{00E0}', '\u{00E2}', '\u{00E4}', '\u{00E3}', '\u{00E5}', '\u{00E7}', '\u{00E9}', '\u{00E8}',
+    '\u{00EA}', '\u{00EB}', '\u{00ED}', '\u{00EC}', '\u{00EE}', '\u{00EF}', '\u{00F1}', '\u{00F3}',
+    '\u{00F2}', '\u{00F4}', '\u{00F6}', '\u{00F5}', '\u{00FA}', '\u{00F9}', '\u{00FB}', '\u{00FC}',
+    '\u{2020}', '\u{00B0}', '\u{00A2}', '\u{00A3}', '\u{00A7}', '\u{2022}', '\u{00B6}', '\u{00DF}',
+    '\u{00AE}', '\u{00A9}', '\u{2122}', '\u{00B4}', '\u{00A8}', '\u{2260}', '\u{00C6}', '\u{00D8}',
+    '\u{221E}', '\u{00B1}', '\u{2264}', '\u{2265}', '\u{00A5}', '\u{00B5}', '\u{2202}', '\u{2211}',
+    '\u{220F}', '\u{03C0}', '\u{222B}', '\u{00AA}', '\u{00BA}', '\u{03A9}', '\u{00E6}', '\u{00F8}',
+    '\u{00BF}', '\u{00A1}', '\u{00AC}', '\u{221A}', '\u{0192}', '\u{2248}', '\u{2206}', '\u{00AB}',
+    '\u{00BB}', '\u{2026}', '\u{00A0}', '\u{00C0}', '\u{00C3}', '\u{00D5}', '\u{0152}', '\u{0153}',
+    '\u{2013}', '\u{2014}', '\u{201C}', '\u{201D}', '\u{2018}', '\u{2019}', '\u{00F7}', '\u{25CA}',
+    '\u{00FF}', '\u{0178}', '\u{2044}', '\u{20AC}', '\u{2039}', '\u{203A}', '\u{FB01}', '\u{FB02}',
+    '\u{2021}', '\u{00B7}', '\u{201A}', '\u{201E}', '\u{2030}', '\u{00C2}', '\u{00CA}', '\u{00C1}',
+    '\u{00CB}', '\u{00C8}', '\u{00CD}', '\u{00CE}', '\u{00CF}', '\u{00CC}', '\u{00D3}', '\u{00D4}',
+    '\u{F8FF}', '\u{00D2}', '\u{00DA}', '\u{00DB}', '\u{00D9}', '\u{0131}', '\u{02C6}', '\u{02DC}',
+    '\u{00AF}', '\u{02D8}', '\u{02D9}', '\u{02DA}', '\u{00B8}', '\u{02DD}', '\u{02DB}', '\u{02C7}',
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        let data = ::utils::read_file("tests/Tuffy_Bold.ttf");
+        let offset = ::utils::find_table_offset(&data, 0, b"name").unwrap().unwrap();
+
+        let name = NAME::from_data(&data, offset).unwrap();
+        expect!(name.records().len()).to(be_equal_to(42));
+        expect!(name.family_name()).to(be_some().value("Tuffy".to_owned()));
+        expect!(name.subfamily_name()).to(be_some().value("Bold".to_owned()));
+        expect!(name.full_name()).to(be_some().value("Tuffy Bold".to_owned()));
+        expect!(name.post_script_name()).to(be_some().value("Tuffy-Bold".to_owned()));
+        expect!(name.typographic_family_name()).to(be_some().value("Tuffy".to_owned()));
+        expect!(name.version()).to(be_some().value("Version 001.280 ".to_owned()));
+        expect!(name.license()).to(be_some().value("Public Domain\n".to_owned()));
+
+        let german = name.records().iter()
+            .find(|r| r.platform_id == 1 && r.language_id == 2 && r.name_id == 256).unwrap();
+        expect!(german.decode()).to(be_some().value("Alle typografischen Möglichkeiten".to_owned()));
+
+        let name = NAME::from_data(&name.bytes(), 0).unwrap();
+        expect!(name.full_name()).to(be_some().value("Tuffy Bold".to_owned()));
+
+        expect!(NAME::from_data(&[0, 2, 0, 0, 0, 6], 0)).to(be_err().value(NAMEFormatIsNotSupported));
+        expect!(NAME::from_data(&data, data.len())).to(be_err().value(Malformed));
+    }
+
+    #[test]
+    fn language_tags() {
+        let name = NAME {
+            records: vec![NameRecord {
+                platform_id: 0,
+                encoding_id: 4,
+                language_id: 0x8000,
+                name_id: 1,
+                bytes: vec![0, 0x46, 0, 0x6f, 0, 0x6f],
+            }],
+            language_tags: vec!["en-GB".to_owned()],
+        };
+
+        let name = NAME::from_data(&name.bytes(), 0).unwrap();
+        expect!(name.family_name()).to(be_some().value("Foo".to_owned()));
+        expect!(name.language_tag(0x8000)).to(be_some().value("en-GB"));
+        expect!(name.language_tag(0x409)).to(be_none());
+    }
+}
@@ -0,0 +1,434 @@
+
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+use utils::read_bytes;
+
+/// Bits of the `fs_selection` field.
+const ITALIC: u16 = 1 << 0;
+const BOLD: u16 = 1 << 5;
+const REGULAR: u16 = 1 << 6;
+const USE_TYPO_METRICS: u16 = 1 << 7;
+const OBLIQUE: u16 = 1 << 9;
+
+/// An OS/2 and Windows specific metrics table.
+///
+/// The table contains the weight and the width class of the font, its style,
+/// typographic and Windows line metrics, Unicode ranges covered by the font
+/// and other properties. Metrics are expressed in unscaled coordinates.
+///
+/// Fields added by later versions of the table are `None` for fonts with
+/// earlier versions.
+#[derive(Debug, Default)]
+pub struct OS2 {
+    version: u16,
+    x_avg_char_width: i16,
+    us_weight_class: u16,
+    us_width_class: u16,
+    fs_type: u16,
+    y_subscript_x_size: i16,
+    y_subscript_y_size: i16,
+    y_subscript_x_offset: i16,
+    y_subscript_y_offset: i16,
+    y_superscript_x_size: i16,
+    y_superscript_y_size: i16,
+    y_superscript_x_offset: i16,
+    y_superscript_y_offset: i16,
+    y_strikeout_size: i16,
+    y_strikeout_position: i16,
+    s_family_class: i16,
+    panose: [u8; 10],
+    ul_unicode_range: [u32; 4],
+    ach_vend_id: [u8; 4],
+    fs_selection: u16,
+    us_first_char_index: u16,
+    us_last_char_index: u16,
+    s_typo_ascender: i16,
+    s_typo_descender: i16,
+    s_typo_line_gap: i16,
+    us_win_ascent: u16,
+    us_win_descent: u16,
+    // Version 1.
+    ul_code_page_range: Option<[u32; 2]>,
+    // Version 2.
+    sx_height: Option<i16>,
+    s_cap_height: Option<i16>,
+    us_default_char: Option<u16>,
+    us_break_char: Option<u16>,
+    us_max_context: Option<u16>,
+    // Version 5.
+    us_lower_optical_point_size: Option<u16>,
+    us_upper_optical_point_size: Option<u16>,
+}
+
+impl OS2 {
+    /// Returns `OS/2` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `OS/2` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<OS2> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let version = try!(cursor.read_u16::<BigEndian>());
+        if version > 5 {
+            return Err(Error::OS2VersionIsNotSupported);
+        }
+
+        let mut os2 = OS2::default();
+        os2.version = version;
+        os2.x_avg_char_width = try!(cursor.read_i16::<BigEndian>());
+        os2.us_weight_class = try!(cursor.read_u16::<BigEndian>());
+        os2.us_width_class = try!(cursor.read_u16::<BigEndian>());
+        os2.fs_type = try!(cursor.read_u16::<BigEndian>());
+        os2.y_subscript_x_size = try!(cursor.read_i16::<BigEndian>());
+        os2.y_subscript_y_size = try!(cursor.read_i16::<BigEndian>());
+        os2.y_subscript_x_offset = try!(cursor.read_i16::<BigEndian>());
+        os2.y_subscript_y_offset = try!(cursor.read_i16::<BigEndian>());
+        os2.y_superscript_x_size = try!(cursor.read_i16::<BigEndian>());
+        os2.y_superscript_y_size = try!(cursor.read_i16::<BigEndian>());
+        os2.y_superscript_x_offset = try!(cursor.read_i16::<BigEndian>());
+        os2.y_superscript_y_offset = try!(cursor.read_i16::<BigEndian>());
+        os2.y_strikeout_size = try!(cursor.read_i16::<BigEndian>());
+        os2.y_strikeout_position = try!(cursor.read_i16::<BigEndian>());
+        os2.s_family_class = try!(cursor.read_i16::<BigEndian>());
+        try!(read_bytes(&mut cursor, &mut os2.panose));
+        for range in &mut os2.ul_unicode_range {
+            *range = try!(cursor.read_u32::<BigEndian>());
+        }
+        try!(read_bytes(&mut cursor, &mut os2.ach_vend_id));
+        os2.fs_selection = try!(cursor.read_u16::<BigEndian>());
+        os2.us_first_char_index = try!(cursor.read_u16::<BigEndian>());
+        os2.us_last_char_index = try!(cursor.read_u16::<BigEndian>());
+        os2.s_typo_ascender = try!(cursor.read_i16::<BigEndian>());
+        os2.s_typo_descender = try!(cursor.read_i16::<BigEndian>());
+        os2.s_typo_line_gap = try!(cursor.read_i16::<BigEndian>());
+        os2.us_win_ascent = try!(cursor.read_u16::<BigEndian>());
+        os2.us_win_descent = try!(cursor.read_u16::<BigEndian>());
+
+        if version >= 1 {
+            os2.ul_code_page_range = Some([try!(cursor.read_u32::<BigEndian>()),
+                                           try!(cursor.read_u32::<BigEndian>())]);
+        }
+
+        if version >= 2 {
+            os2.sx_height = Some(try!(cursor.read_i16::<BigEndian>()));
+            os2.s_cap_height = Some(try!(cursor.read_i16::<BigEndian>()));
+            os2.us_default_char = Some(try!(cursor.read_u16::<BigEndian>()));
+            os2.us_break_char = Some(try!(cursor.read_u16::<BigEndian>()));
+            os2.us_max_context = Some(try!(cursor.read_u16::<BigEndian>()));
+        }
+
+        if version >= 5 {
+            os2.us_lower_optical_point_size = Some(try!(cursor.read_u16::<BigEndian>()));
+            os2.us_upper_optical_point_size = Some(try!(cursor.read_u16::<BigEndian>()));
+        }
+
+        Ok(os2)
+    }
+
+    #[cfg(test)]
+    fn bytes(&self) -> Vec<u8> {
+        use byteorder::WriteBytesExt;
+
+        let mut data = vec![];
+        data.write_u16::<BigEndian>(self.version).unwrap();
+        data.write_i16::<BigEndian>(self.x_avg_char_width).unwrap();
+        data.write_u16::<BigEndian>(self.us_weight_class).unwrap();
+        data.write_u16::<BigEndian>(self.us_width_class).unwrap();
+        data.write_u16::<BigEndian>(self.fs_type).unwrap();
+        data.write_i16::<BigEndian>(self.y_subscript_x_size).unwrap();
+        data.write_i16::<BigEndian>(self.y_subscript_y_size).unwrap();
+        data.write_i16::<BigEndian>(self.y_subscript_x_offset).unwrap();
+        data.write_i16::<BigEndian>(self.y_subscript_y_offset).unwrap();
+        data.write_i16::<BigEndian>(self.y_superscript_x_size).unwrap();
+        data.write_i16::<BigEndian>(self.y_superscript_y_size).unwrap();
+        data.write_i16::<BigEndian>(self.y_superscript_x_offset).unwrap();
+        data.write_i16::<BigEndian>(self.y_superscript_y_offset).unwrap();
+        data.write_i16::<BigEndian>(self.y_strikeout_size).unwrap();
+        data.write_i16::<BigEndian>(self.y_strikeout_position).unwrap();
+        data.write_i16::<BigEndian>(self.s_family_class).unwrap();
+        data.extend_from_slice(&self.panose);
+        for &range in &self.ul_unicode_range {
+            data.write_u32::<BigEndian>(range).unwrap();
+        }
+        data.extend_from_slice(&self.ach_vend_id);
+        data.write_u16::<BigEndian>(self.fs_selection).unwrap();
+        data.write_u16::<BigEndian>(self.us_first_char_index).unwrap();
+        data.write_u16::<BigEndian>(self.us_last_char_index).unwrap();
+        data.write_i16::<BigEndian>(self.s_typo_ascender).unwrap();
+        data.write_i16::<BigEndian>(self.s_typo_descender).unwrap();
+        data.write_i16::<BigEndian>(self.s_typo_line_gap).unwrap();
+        data.write_u16::<BigEndian>(self.us_win_ascent).unwrap();
+        data.write_u16::<BigEndian>(self.us_win_descent).unwrap();
+        if self.version >= 1 {
+            let ranges = self.ul_code_page_range.unwrap_or_default();
+            data.write_u32::<BigEndian>(ranges[0]).unwrap();
+            data.write_u32::<BigEndian>(ranges[1]).unwrap();
+        }
+        if self.version >= 2 {
+            data.write_i16::<BigEndian>(self.sx_height.unwrap_or(0)).unwrap();
+            data.write_i16::<BigEndian>(self.s_cap_height.unwrap_or(0)).unwrap();
+            data.write_u16::<BigEndian>(self.us_default_char.unwrap_or(0)).unwrap();
+            data.write_u16::<BigEndian>(self.us_break_char.unwrap_or(0)).unwrap();
+            data.write_u16::<BigEndian>(self.us_max_context.unwrap_or(0)).unwrap();
+        }
+        if self.version >= 5 {
+            data.write_u16::<BigEndian>(self.us_lower_optical_point_size.unwrap_or(0)).unwrap();
+            data.write_u16::<BigEndian>(self.us_upper_optical_point_size.unwrap_or(0)).unwrap();
+        }
+        data
+    }
+
+    /// The version of the table.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// The average width of all non-zero width glyphs in the font.
+    pub fn average_char_width(&self) -> i32 {
+        self.x_avg_char_width as i32
+    }
+
+    /// The visual weight of the font, from 100 (thin) to 900 (black),
+    /// 400 is normal and 700 is bold.
+    pub fn weight_class(&self) -> u16 {
+        self.us_weight_class
+    }
+
+    /// The relative change from the normal aspect ratio, from 1 (ultra-condensed)
+    /// to 9 (ultra-expanded), 5 is normal.
+    pub fn width_class(&self) -> u16 {
+        self.us_width_class
+    }
+
+    /// The embedding licensing rights for the font.
+    pub fn fs_type(&self) -> u16 {
+        self.fs_type
+    }
+
+    /// The horizontal and vertical sizes of subscript glyphs.
+    pub fn subscript_size(&self) -> (i32, i32) {
+        (self.y_subscript_x_size as i32, self.y_subscript_y_size as i32)
+    }
+
+    /// The horizontal and vertical offsets of subscript glyphs, the vertical
+    /// offset is measured downwards from the baseline.
+    pub fn subscript_offset(&self) -> (i32, i32) {
+        (self.y_subscript_x_offset as i32, self.y_subscript_y_offset as i32)
+    }
+
+    /// The horizontal and vertical sizes of superscript glyphs.
+    pub fn superscript_size(&self) -> (i32, i32) {
+        (self.y_superscript_x_size as i32, self.y_superscript_y_size as i32)
+    }
+
+    /// The horizontal and vertical offsets of superscript glyphs.
+    pub fn superscript_offset(&self) -> (i32, i32) {
+        (self.y_superscript_x_offset as i32, self.y_superscript_y_offset as i32)
+    }
+
+    /// The thickness of the strikeout stroke.
+    pub fn strikeout_size(&self) -> i32 {
+        self.y_strikeout_size as i32
+    }
+
+    /// The position of the top of the strikeout stroke relative to the baseline.
+    pub fn strikeout_position(&self) -> i32 {
+        self.y_strikeout_position as i32
+    }
+
+    /// The font family class and subclass.
+    pub fn family_class(&self) -> i16 {
+        self.s_family_class
+    }
+
+    /// The PANOSE classification of the font.
+    pub fn panose(&self) -> [u8; 10] {
+        self.panose
+    }
+
+    /// The Unicode blocks supported by the font as a 128 bit field.
+    pub fn unicode_range(&self) -> [u32; 4] {
+        self.ul_unicode_range
+    }
+
+    /// Returns `true` if the Unicode range `bit` is set.
+    ///
+    /// Bits are numbered from 0 to 127 according to the OpenType
+    /// specification, e.g. bit 9 is Cyrillic.
+    pub fn has_unicode_range(&self, bit: usize) -> bool {
+        bit < 128 && self.ul_unicode_range[bit / 32] & (1 << (bit % 32)) != 0
+    }
+
+    /// The identifier of the font vendor.
+    pub fn vendor_id(&self) -> [u8; 4] {
+        self.ach_vend_id
+    }
+
+    /// The font selection flags.
+    pub fn fs_selection(&self) -> u16 {
+        self.fs_selection
+    }
+
+    /// Returns `true` if the font is italic.
+    pub fn is_italic(&self) -> bool {
+        self.fs_selection & ITALIC != 0
+    }
+
+    /// Returns `true` if the font is bold.
+    pub fn is_bold(&self) -> bool {
+        self.fs_selection & BOLD != 0
+    }
+
+    /// Returns `true` if the font is regular, i.e. neither italic nor bold.
+    pub fn is_regular(&self) -> bool {
+        self.fs_selection & REGULAR != 0
+    }
+
+    /// Returns `true` if the font is oblique.
+    ///
+    /// The flag is defined since the version 4 of the table.
+    pub fn is_oblique(&self) -> bool {
+        self.version >= 4 && self.fs_selection & OBLIQUE != 0
+    }
+
+    /// Returns `true` if typographic metrics should be used for line metrics
+    /// instead of `hhea` or Windows metrics.
+    ///
+    /// The flag is defined since the version 4 of the table.
+    pub fn use_typo_metrics(&self) -> bool {
+        self.version >= 4 && self.fs_selection & USE_TYPO_METRICS != 0
+    }
+
+    /// The minimum and maximum Unicode code points in the font, clamped
+    /// to 0xFFFF.
+    pub fn char_index_range(&self) -> (u16, u16) {
+        (self.us_first_char_index, self.us_last_char_index)
+    }
+
+    /// The typographic ascender.
+    pub fn typo_ascender(&self) -> i32 {
+        self.s_typo_ascender as i32
+    }
+
+    /// The typographic descender (i.e. it is typically negative).
+    pub fn typo_descender(&self) -> i32 {
+        self.s_typo_descender as i32
+    }
+
+    /// The typographic line gap.
+    pub fn typo_line_gap(&self) -> i32 {
+        self.s_typo_line_gap as i32
+    }
+
+    /// The ascent used for clipping on Windows.
+    pub fn win_ascent(&self) -> i32 {
+        self.us_win_ascent as i32
+    }
+
+    /// The descent used for clipping on Windows (i.e. it is positive
+    /// for descents below the baseline).
+    pub fn win_descent(&self) -> i32 {
+        self.us_win_descent as i32
+    }
+
+    /// The code pages supported by the font as a 64 bit field.
+    pub fn code_page_range(&self) -> Option<[u32; 2]> {
+        self.ul_code_page_range
+    }
+
+    /// The height of lowercase letters above the baseline.
+    pub fn x_height(&self) -> Option<i32> {
+        self.sx_height.map(|h| h as i32)
+    }
+
+    /// The height of uppercase letters above the baseline.
+    pub fn cap_height(&self) -> Option<i32> {
+        self.s_cap_height.map(|h| h as i32)
+    }
+
+    /// The character to use for characters missing in the font.
+    pub fn default_char(&self) -> Option<u16> {
+        self.us_default_char
+    }
+
+    /// The character used to break words.
+    pub fn break_char(&self) -> Option<u16> {
+        self.us_break_char
+    }
+
+    /// The maximum length of a context of any feature of the font.
+    pub fn max_context(&self) -> Option<u16> {
+        self.us_max_context
+    }
+
+    /// The range of point sizes the font is designed for, in twentieths
+    /// of a point.
+    pub fn optical_point_size_range(&self) -> Option<(u16, u16)> {
+        match (self.us_lower_optical_point_size, self.us_upper_optical_point_size) {
+            (Some(lower), Some(upper)) => Some((lower, upper)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        let data = ::utils::read_file("tests/Tuffy_Bold.ttf");
+        let offset = ::utils::find_table_offset(&data, 0, b"OS/2").unwrap().unwrap();
+
+        let os2 = OS2::from_data(&data, offset).unwrap();
+        assert_eq!(os2.bytes(), &data[offset..offset + 96]);
+        expect!(os2.version()).to(be_equal_to(4));
+        expect!(os2.weight_class()).to(be_equal_to(700));
+        expect!(os2.width_class()).to(be_equal_to(5));
+        expect!(os2.is_bold()).to(be_true());
+        expect!(os2.is_italic()).to(be_false());
+        expect!(os2.use_typo_metrics()).to(be_true());
+        expect!(os2.has_unicode_range(9)).to(be_true());
+        expect!(os2.x_height()).to(be_some());
+        expect!(os2.optical_point_size_range()).to(be_none());
+
+        let mut os2 = OS2::default();
+        os2.version = 6;
+        expect!(OS2::from_data(&os2.bytes(), 0)).to(be_err().value(OS2VersionIsNotSupported));
+
+        os2.version = 0;
+        expect!(OS2::from_data(&os2.bytes()[..60], 0)).to(be_err().value(Malformed));
+        expect!(OS2::from_data(&data, data.len())).to(be_err().value(Malformed));
+    }
+
+    #[test]
+    fn version_5() {
+        let mut os2 = OS2::default();
+        os2.version = 5;
+        os2.fs_selection = ITALIC | OBLIQUE;
+        os2.ul_code_page_range = Some([1, 0]);
+        os2.sx_height = Some(500);
+        os2.s_cap_height = Some(700);
+        os2.us_default_char = Some(0);
+        os2.us_break_char = Some(32);
+        os2.us_max_context = Some(2);
+        os2.us_lower_optical_point_size = Some(160);
+        os2.us_upper_optical_point_size = Some(480);
+
+        let os2 = OS2::from_data(&os2.bytes(), 0).unwrap();
+        expect!(os2.is_italic()).to(be_true());
+        expect!(os2.is_oblique()).to(be_true());
+        expect!(os2.cap_height()).to(be_some().value(700));
+        expect!(os2.optical_point_size_range()).to(be_some().value((160, 480)));
+    }
+}
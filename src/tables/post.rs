@@ -0,0 +1,260 @@
+
+use types::Fixed;
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+use utils::read_bytes;
+
+/// A PostScript table.
+///
+/// The `post` table contains information needed to use the font on
+/// PostScript printers: the italic angle, underline metrics, whether
+/// the font is monospaced and the PostScript names of glyphs.
+#[derive(Debug, Default)]
+pub struct POST {
+    version: Fixed,
+    italic_angle: Fixed,
+    underline_position: i16,
+    underline_thickness: i16,
+    is_fixed_pitch: u32,
+    min_mem_type42: u32,
+    max_mem_type42: u32,
+    min_mem_type1: u32,
+    max_mem_type1: u32,
+    // Indices of glyph names, the first 258 indices refer to the standard
+    // Macintosh names and the rest to `names`.
+    name_indices: Vec<u16>,
+    names: Vec<String>,
+}
+
+impl POST {
+    /// Returns `post` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `post` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<POST> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let mut post = POST::default();
+        post.version = Fixed(try!(cursor.read_i32::<BigEndian>()));
+        post.italic_angle = Fixed(try!(cursor.read_i32::<BigEndian>()));
+        post.underline_position = try!(cursor.read_i16::<BigEndian>());
+        post.underline_thickness = try!(cursor.read_i16::<BigEndian>());
+        post.is_fixed_pitch = try!(cursor.read_u32::<BigEndian>());
+        post.min_mem_type42 = try!(cursor.read_u32::<BigEndian>());
+        post.max_mem_type42 = try!(cursor.read_u32::<BigEndian>());
+        post.min_mem_type1 = try!(cursor.read_u32::<BigEndian>());
+        post.max_mem_type1 = try!(cursor.read_u32::<BigEndian>());
+
+        match post.version {
+            Fixed(0x00010000) => {
+                post.name_indices = (0..STANDARD_NAMES.len() as u16).collect();
+            },
+            Fixed(0x00020000) => {
+                let num_glyphs = try!(cursor.read_u16::<BigEndian>());
+                let mut custom_names = 0;
+                for _ in 0..num_glyphs {
+                    let index = try!(cursor.read_u16::<BigEndian>());
+                    if index as usize >= STANDARD_NAMES.len() {
+                        custom_names = ::std::cmp::max(custom_names, index as usize - STANDARD_NAMES.len() + 1);
+                    }
+                    post.name_indices.push(index);
+                }
+                for _ in 0..custom_names {
+                    let length = try!(cursor.read_u8());
+                    let mut name = vec![0; length as usize];
+                    try!(read_bytes(&mut cursor, &mut name));
+                    post.names.push(String::from_utf8_lossy(&name).into_owned());
+                }
+            },
+            Fixed(0x00025000) => {
+                let num_glyphs = try!(cursor.read_u16::<BigEndian>());
+                for i in 0..num_glyphs {
+                    let index = i as i32 + try!(cursor.read_i8()) as i32;
+                    if index < 0 || index as usize >= STANDARD_NAMES.len() {
+                        return Err(Error::Malformed);
+                    }
+                    post.name_indices.push(index as u16);
+                }
+            },
+            Fixed(0x00030000) => {},
+            _ => return Err(Error::POSTVersionIsNotSupported),
+        }
+
+        Ok(post)
+    }
+
+    #[cfg(test)]
+    fn bytes(&self) -> Vec<u8> {
+        use byteorder::WriteBytesExt;
+
+        let mut data = vec![];
+        data.write_i32::<BigEndian>(self.version.0).unwrap();
+        data.write_i32::<BigEndian>(self.italic_angle.0).unwrap();
+        data.write_i16::<BigEndian>(self.underline_position).unwrap();
+        data.write_i16::<BigEndian>(self.underline_thickness).unwrap();
+        data.write_u32::<BigEndian>(self.is_fixed_pitch).unwrap();
+        data.write_u32::<BigEndian>(self.min_mem_type42).unwrap();
+        data.write_u32::<BigEndian>(self.max_mem_type42).unwrap();
+        data.write_u32::<BigEndian>(self.min_mem_type1).unwrap();
+        data.write_u32::<BigEndian>(self.max_mem_type1).unwrap();
+        match self.version {
+            Fixed(0x00020000) => {
+                data.write_u16::<BigEndian>(self.name_indices.len() as u16).unwrap();
+                for &index in &self.name_indices {
+                    data.write_u16::<BigEndian>(index).unwrap();
+                }
+                for name in &self.names {
+                    data.write_u8(name.len() as u8).unwrap();
+                    data.extend_from_slice(name.as_bytes());
+                }
+            },
+            Fixed(0x00025000) => {
+                data.write_u16::<BigEndian>(self.name_indices.len() as u16).unwrap();
+                for (i, &index) in self.name_indices.iter().enumerate() {
+                    data.write_i8((index as i32 - i as i32) as i8).unwrap();
+                }
+            },
+            _ => {},
+        }
+        data
+    }
+
+    /// The italic angle in counter-clockwise degrees from the vertical,
+    /// it is negative for fonts that slant to the right.
+    pub fn italic_angle(&self) -> f32 {
+        self.italic_angle.0 as f32 / 65536.0
+    }
+
+    /// The position of the top of the underline relative to the baseline.
+    pub fn underline_position(&self) -> i32 {
+        self.underline_position as i32
+    }
+
+    /// The thickness of the underline.
+    pub fn underline_thickness(&self) -> i32 {
+        self.underline_thickness as i32
+    }
+
+    /// Returns `true` if the font is monospaced.
+    pub fn is_fixed_pitch(&self) -> bool {
+        self.is_fixed_pitch != 0
+    }
+
+    /// Returns the PostScript name of the glyph at index `i`.
+    ///
+    /// Returns `None` if `i` is out of bounds or the table doesn't contain
+    /// glyph names (version 3.0).
+    pub fn glyph_name(&self, i: usize) -> Option<&str> {
+        self.name_indices.get(i).and_then(|&index| {
+            let index = index as usize;
+            if index < STANDARD_NAMES.len() {
+                Some(STANDARD_NAMES[index])
+            } else {
+                self.names.get(index - STANDARD_NAMES.len()).map(|name| &name[..])
+            }
+        })
+    }
+
+    /// Returns the index of the first glyph with the PostScript `name`.
+    pub fn glyph_index_for_name(&self, name: &str) -> Option<usize> {
+        (0..self.name_indices.len()).find(|&i| self.glyph_name(i) == Some(name))
+    }
+}
+
+/// The standard Macintosh glyph names.
+const STANDARD_NAMES: [&str; 258] = [
+    ".notdef", ".null", "nonmarkingreturn", "space", "exclam", "quotedbl", "numbersign",
+    "dollar", "percent", "ampersand", "quotesingle", "parenleft", "parenright", "asterisk",
+    "plus", "comma", "hyphen", "period", "slash", "zero", "one", "two", "three", "four", "five",
+    "six", "seven", "eight", "nine", "colon", "semicolon", "less", "equal", "greater",
+    "question", "at", "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O",
+    "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z", "bracketleft", "backslash",
+    "bracketright", "asciicircum", "underscore", "grave", "a", "b", "c", "d", "e", "f", "g",
+    "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y",
+    "z", "braceleft", "bar", "braceright", "asciitilde", "Adieresis", "Aring", "Ccedilla",
+    "Eacute", "Ntilde", "Odieresis", "Udieresis", "aacute", "agrave", "acircumflex",
+    "adieresis", "atilde", "aring", "ccedilla", "eacute", "egrave", "ecircumflex", "edieresis",
+    "iacute", "igrave", "icircumflex", "idieresis", "ntilde", "oacute", "ograve", "ocircumflex",
+    "odieresis", "otilde", "uacute", "ugrave", "ucircumflex", "udieresis", "dagger", "degree",
+    "cent", "sterling", "section", "bullet", "paragraph", "germandbls", "registered",
+    "copyright", "trademark", "acute", "dieresis", "notequal", "AE", "Oslash", "infinity",
+    "plusminus", "lessequal", "greaterequal", "yen", "mu", "partialdiff", "summation",
+    "product", "pi", "integral", "ordfeminine", "ordmasculine", "Omega", "ae", "oslash",
+    "questiondown", "exclamdown", "logicalnot", "radical", "florin", "approxequal", "Delta",
+    "guillemotleft", "guillemotright", "ellipsis", "nonbreakingspace", "Agrave", "Atilde",
+    "Otilde", "OE", "oe", "endash", "emdash", "quotedblleft", "quotedblright", "quoteleft",
+    "quoteright", "divide", "lozenge", "ydieresis", "Ydieresis", "fraction", "currency",
+    "guilsinglleft", "guilsinglright", "fi", "fl", "daggerdbl", "periodcentered",
+    "quotesinglbase", "quotedblbase", "perthousand", "Acircumflex", "Ecircumflex", "Aacute",
+    "Edieresis", "Egrave", "Iacute", "Icircumflex", "Idieresis", "Igrave", "Oacute",
+    "Ocircumflex", "apple", "Ograve", "Uacute", "Ucircumflex", "Ugrave", "dotlessi",
+    "circumflex", "tilde", "macron", "breve", "dotaccent", "ring", "cedilla", "hungarumlaut",
+    "ogonek", "caron", "Lslash", "lslash", "Scaron", "scaron", "Zcaron", "zcaron", "brokenbar",
+    "Eth", "eth", "Yacute", "yacute", "Thorn", "thorn", "minus", "multiply", "onesuperior",
+    "twosuperior", "threesuperior", "onehalf", "onequarter", "threequarters", "franc", "Gbreve",
+    "gbreve", "Idotaccent", "Scedilla", "scedilla", "Cacute", "cacute", "Ccaron", "ccaron",
+    "dcroat",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Fixed;
+    use Error::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        let data = ::utils::read_file("tests/Tuffy_Bold.ttf");
+        let offset = ::utils::find_table_offset(&data, 0, b"post").unwrap().unwrap();
+
+        let post = POST::from_data(&data, offset).unwrap();
+        let bytes = post.bytes();
+        assert_eq!(bytes, &data[offset..offset + bytes.len()]);
+
+        expect!(post.is_fixed_pitch()).to(be_false());
+        expect!(post.italic_angle()).to(be_equal_to(0.0));
+        expect!(post.glyph_name(0)).to(be_some().value(".notdef"));
+        expect!(post.glyph_name(68)).to(be_some().value("a"));
+        expect!(post.glyph_name(487)).to(be_some().value("afii10077"));
+        expect!(post.glyph_name(890)).to(be_none());
+        expect!(post.glyph_index_for_name("afii10077")).to(be_some().value(487));
+        expect!(post.glyph_index_for_name("unknown")).to(be_none());
+
+        let post = POST::default();
+        expect!(POST::from_data(&post.bytes(), 0)).to(be_err().value(POSTVersionIsNotSupported));
+
+        expect!(POST::from_data(&data, data.len())).to(be_err().value(Malformed));
+    }
+
+    #[test]
+    fn versions() {
+        let mut post = POST::default();
+        post.version = Fixed(0x00010000);
+        post.italic_angle = Fixed(-12 << 16);
+        let post = POST::from_data(&post.bytes(), 0).unwrap();
+        expect!(post.italic_angle()).to(be_equal_to(-12.0));
+        expect!(post.glyph_name(257)).to(be_some().value("dcroat"));
+        expect!(post.glyph_index_for_name("space")).to(be_some().value(3));
+
+        let mut post = POST::default();
+        post.version = Fixed(0x00025000);
+        post.name_indices = vec![0, 36, 68];
+        let post = POST::from_data(&post.bytes(), 0).unwrap();
+        expect!(post.glyph_name(1)).to(be_some().value("A"));
+        expect!(post.glyph_name(2)).to(be_some().value("a"));
+
+        let mut post = POST::default();
+        post.version = Fixed(0x00030000);
+        let post = POST::from_data(&post.bytes(), 0).unwrap();
+        expect!(post.glyph_name(0)).to(be_none());
+    }
+}
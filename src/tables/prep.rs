@@ -0,0 +1,51 @@
+use Error;
+use Result;
+
+/// A control value program.
+///
+/// The 'prep' table holds TrueType instructions that are executed each time
+/// the size of the font changes, before any glyph is hinted. It usually
+/// adjusts control values for the size.
+#[derive(Debug, Default)]
+pub struct PREP {
+    instructions: Vec<u8>,
+}
+
+impl PREP {
+    /// Returns `prep` font table.
+    ///
+    /// Attempts to read `size` bytes of `data` starting from `offset`
+    /// position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read.
+    pub fn from_data(data: &[u8], offset: usize, size: usize) -> Result<PREP> {
+        if offset >= data.len() || offset + size > data.len() {
+            return Err(Error::Malformed);
+        }
+
+        Ok(PREP { instructions: data[offset..offset + size].to_owned() })
+    }
+
+    /// Returns the instructions of the program.
+    pub fn instructions(&self) -> &[u8] {
+        &self.instructions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        let data = [0, 0, 0xb0, 0x01, 0x21];
+        let prep = PREP::from_data(&data, 2, 3).unwrap();
+        expect!(prep.instructions()).to(be_equal_to(&[0xb0, 0x01, 0x21][..]));
+
+        expect!(PREP::from_data(&data, 2, 4)).to(be_err().value(Malformed));
+        expect!(PREP::from_data(&data, data.len(), 0)).to(be_err().value(Malformed));
+    }
+}
@@ -0,0 +1,166 @@
+use Error;
+use Result;
+use std::borrow::Cow;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+use bitmap::{EmbeddedImage, ImageFormat};
+
+/// A standard bitmap graphics table.
+///
+/// The `sbix` table contains images of glyphs, usually PNG files, grouped
+/// into strikes for sizes in pixels per em.
+#[derive(Debug)]
+pub struct SBIX {
+    bytes: Vec<u8>,
+    /// Size in pixels per em and offset of each strike.
+    strikes: Vec<(u16, usize)>,
+    num_glyphs: usize,
+}
+
+impl SBIX {
+    /// Returns `sbix` font table.
+    ///
+    /// Attempts to read `size` bytes of `data` starting from `offset`
+    /// position. `num_glyphs` is a number of glyphs in the font.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `sbix` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize, size: usize, num_glyphs: usize) -> Result<SBIX> {
+        if offset >= data.len() || offset + size > data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let bytes = &data[offset..offset + size];
+        let mut cursor = Cursor::new(bytes);
+        let version = try!(cursor.read_u16::<BigEndian>());
+        if version != 1 {
+            return Err(Error::SBIXVersionIsNotSupported);
+        }
+        let _flags = try!(cursor.read_u16::<BigEndian>());
+        let mut strikes = vec![];
+        for _ in 0..try!(cursor.read_u32::<BigEndian>()) {
+            let strike = try!(cursor.read_u32::<BigEndian>()) as usize;
+            // Size, resolution and offsets of glyphs with the end of the last one.
+            if strike + 4 + (num_glyphs + 1) * 4 > size {
+                return Err(Error::Malformed);
+            }
+            let ppem = try!(Cursor::new(&bytes[strike..]).read_u16::<BigEndian>());
+            strikes.push((ppem, strike));
+        }
+
+        Ok(SBIX { bytes: bytes.to_owned(), strikes: strikes, num_glyphs: num_glyphs })
+    }
+
+    /// Returns sizes in pixels per em of strikes.
+    pub fn ppems(&self) -> Vec<u16> {
+        self.strikes.iter().map(|strike| strike.0).collect()
+    }
+
+    /// Returns the image of `glyph` in the strike at index `strike`, or
+    /// `None` if there is no image or its format is not supported.
+    ///
+    /// The size of PNG images is read from their header, the size of other
+    /// images is unknown.
+    ///
+    /// # Errors
+    /// Returns error if the image data is malformed.
+    pub fn image(&self, strike: usize, glyph: u16) -> Result<Option<EmbeddedImage<'_>>> {
+        self.image_or_duplicate(strike, glyph, true)
+    }
+
+    fn image_or_duplicate(&self, strike: usize, glyph: u16, follow_duplicates: bool) -> Result<Option<EmbeddedImage<'_>>> {
+        let (ppem, offset) = match self.strikes.get(strike) {
+            Some(&strike) => strike,
+            None => return Ok(None),
+        };
+        if glyph as usize >= self.num_glyphs {
+            return Ok(None);
+        }
+
+        let mut cursor = Cursor::new(&self.bytes[offset + 4 + glyph as usize * 4..]);
+        let start = offset + try!(cursor.read_u32::<BigEndian>()) as usize;
+        let end = offset + try!(cursor.read_u32::<BigEndian>()) as usize;
+        // Glyphs without images have no data.
+        if end <= start {
+            return Ok(None);
+        }
+        if end > self.bytes.len() || end - start < 8 {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&self.bytes[start..end]);
+        let origin_x = try!(cursor.read_i16::<BigEndian>()) as i32;
+        let origin_y = try!(cursor.read_i16::<BigEndian>()) as i32;
+        let data = &self.bytes[start + 8..end];
+        let format = match &self.bytes[start + 4..start + 8] {
+            b"png " => ImageFormat::Png,
+            b"jpg " => ImageFormat::Jpeg,
+            b"tiff" => ImageFormat::Tiff,
+            b"dupe" if follow_duplicates => {
+                let glyph = try!(Cursor::new(data).read_u16::<BigEndian>());
+                return self.image_or_duplicate(strike, glyph, false);
+            },
+            _ => return Ok(None),
+        };
+
+        // The width and the height follow the signature, the length and
+        // the type of the first chunk.
+        let (width, height) = if format == ImageFormat::Png && data.len() >= 24 && &data[12..16] == b"IHDR" {
+            let mut header = Cursor::new(&data[16..24]);
+            (try!(header.read_u32::<BigEndian>()), try!(header.read_u32::<BigEndian>()))
+        } else {
+            (0, 0)
+        };
+
+        // The origin is the offset to the bottom left corner of the image.
+        Ok(Some(EmbeddedImage {
+            format: format,
+            data: Cow::Borrowed(data),
+            ppem: ppem,
+            width: width,
+            height: height,
+            bearing_x: origin_x,
+            bearing_y: origin_y + height as i32,
+            advance: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        let png: &[u8] = &[0x89, b'P', b'N', b'G', 13, 10, 26, 10, 0, 0, 0, 13, b'I', b'H', b'D', b'R',
+                           0, 0, 0, 20, 0, 0, 0, 30];
+        // Two strikes of three glyphs, glyph 1 of the second strike is
+        // a duplicate of glyph 2.
+        let mut data = vec![0, 1, 0, 1, 0, 0, 0, 2, 0, 0, 0, 16, 0, 0, 0, 36];
+        data.extend_from_slice(&[0, 20, 0, 72, 0, 0, 0, 20, 0, 0, 0, 20, 0, 0, 0, 20, 0, 0, 0, 20]);
+        data.extend_from_slice(&[0, 40, 0, 72, 0, 0, 0, 20, 0, 0, 0, 20, 0, 0, 0, 30, 0, 0, 0, 62]);
+        data.extend_from_slice(&[0, 0, 0, 0, b'd', b'u', b'p', b'e', 0, 2]);
+        data.extend_from_slice(&[0, 1, 0xff, 0xfe, b'p', b'n', b'g', b' ']);
+        data.extend_from_slice(png);
+
+        let sbix = SBIX::from_data(&data, 0, data.len(), 3).unwrap();
+        expect!(sbix.ppems()).to(be_equal_to(vec![20, 40]));
+        expect!(sbix.image(0, 1)).to(be_ok().value(None));
+        expect!(sbix.image(1, 0)).to(be_ok().value(None));
+        expect!(sbix.image(1, 3)).to(be_ok().value(None));
+        expect!(sbix.image(2, 2)).to(be_ok().value(None));
+
+        let image = sbix.image(1, 2).unwrap().unwrap();
+        expect!(image.format).to(be_equal_to(ImageFormat::Png));
+        expect!(&image.data[..]).to(be_equal_to(png));
+        expect!((image.width, image.height, image.ppem)).to(be_equal_to((20, 30, 40)));
+        expect!((image.bearing_x, image.bearing_y, image.advance)).to(be_equal_to((1, 28, None)));
+        expect!(sbix.image(1, 1)).to(be_ok().value(Some(image)));
+
+        expect!(SBIX::from_data(&data, 0, data.len(), 20)).to(be_err().value(Malformed));
+        expect!(SBIX::from_data(&[0, 2, 0, 0], 0, 4, 1)).to(be_err().value(SBIXVersionIsNotSupported));
+    }
+}
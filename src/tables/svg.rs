@@ -0,0 +1,154 @@
+use Error;
+use Result;
+use std::borrow::Cow;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+use inflate::gunzip;
+
+/// An SVG table.
+///
+/// The `SVG ` table contains SVG documents of glyphs. A document may
+/// describe a range of glyphs, each by an element with the `glyphN` id.
+#[derive(Debug, Default)]
+pub struct SVG {
+    bytes: Vec<u8>,
+    records: Vec<DocumentRecord>,
+}
+
+/// A range of glyphs and the position of their document in the table.
+#[derive(Debug, Default, Clone, Copy)]
+struct DocumentRecord {
+    start_glyph: u16,
+    end_glyph: u16,
+    offset: usize,
+    length: usize,
+}
+
+impl SVG {
+    /// Returns `SVG ` font table.
+    ///
+    /// Attempts to read `size` bytes of `data` starting from `offset`
+    /// position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `SVG ` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize, size: usize) -> Result<SVG> {
+        if offset >= data.len() || offset + size > data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let bytes = &data[offset..offset + size];
+        let mut cursor = Cursor::new(bytes);
+        let version = try!(cursor.read_u16::<BigEndian>());
+        if version != 0 {
+            return Err(Error::SVGVersionIsNotSupported);
+        }
+        let list = try!(cursor.read_u32::<BigEndian>()) as usize;
+        if list >= bytes.len() {
+            return Err(Error::Malformed);
+        }
+
+        // Offsets of documents are from the start of the document list.
+        let mut cursor = Cursor::new(&bytes[list..]);
+        let mut records = vec![];
+        for _ in 0..try!(cursor.read_u16::<BigEndian>()) {
+            let record = DocumentRecord {
+                start_glyph: try!(cursor.read_u16::<BigEndian>()),
+                end_glyph: try!(cursor.read_u16::<BigEndian>()),
+                offset: list + try!(cursor.read_u32::<BigEndian>()) as usize,
+                length: try!(cursor.read_u32::<BigEndian>()) as usize,
+            };
+            if record.end_glyph < record.start_glyph || record.offset + record.length > bytes.len() {
+                return Err(Error::Malformed);
+            }
+            records.push(record);
+        }
+        // The lookup relies on records being sorted, which fonts may not follow.
+        records.sort_by_key(|record| record.start_glyph);
+
+        Ok(SVG { bytes: bytes.to_owned(), records: records })
+    }
+
+    /// Returns the number of documents.
+    pub fn document_count(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns the first and the last glyph described by the document of
+    /// `glyph`, or `None` if the glyph has no document.
+    pub fn glyph_range(&self, glyph: u16) -> Option<(u16, u16)> {
+        self.record(glyph).map(|record| (record.start_glyph, record.end_glyph))
+    }
+
+    /// Returns the SVG document of `glyph`, or `None` if the glyph has no
+    /// document. Documents compressed with gzip are decompressed.
+    ///
+    /// # Errors
+    /// Returns error if a compressed document is malformed.
+    pub fn document(&self, glyph: u16) -> Result<Option<Cow<'_, [u8]>>> {
+        let record = match self.record(glyph) {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        let document = &self.bytes[record.offset..record.offset + record.length];
+        if document.starts_with(&[0x1f, 0x8b]) {
+            Ok(Some(Cow::Owned(try!(gunzip(document)))))
+        } else {
+            Ok(Some(Cow::Borrowed(document)))
+        }
+    }
+
+    fn record(&self, glyph: u16) -> Option<&DocumentRecord> {
+        // The last record starting at or before the glyph.
+        let index = match self.records.binary_search_by_key(&glyph, |record| record.start_glyph) {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let record = &self.records[index];
+        if glyph <= record.end_glyph {
+            Some(record)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        // Documents of glyphs 5 and 2 to 3, the second compressed.
+        let mut data = vec![0, 0, 0, 0, 0, 10, 0, 0, 0, 0];
+        data.extend_from_slice(&[0, 2, 0, 5, 0, 5, 0, 0, 0, 26, 0, 0, 0, 6]);
+        data.extend_from_slice(&[0, 2, 0, 3, 0, 0, 0, 32, 0, 0, 0, 26]);
+        data.extend_from_slice(b"<svg/>");
+        data.extend_from_slice(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 2, 3, 0xb3, 0x29, 0x2e, 0x4b, 0xd7, 0xb7,
+                                 0x03, 0x00, 0x49, 0xfb, 0xb9, 0xac, 6, 0, 0, 0]);
+        data.push(0);
+
+        let svg = SVG::from_data(&data, 0, data.len()).unwrap();
+        expect!(svg.document_count()).to(be_equal_to(2));
+        expect!(svg.glyph_range(3)).to(be_some().value((2, 3)));
+        expect!(svg.glyph_range(4)).to(be_none());
+        expect!(svg.document(1)).to(be_ok().value(None));
+        expect!(svg.document(6)).to(be_ok().value(None));
+        expect!(svg.document(5).unwrap().unwrap().into_owned()).to(be_equal_to(b"<svg/>".to_vec()));
+        let document = svg.document(2).unwrap().unwrap();
+        expect!(document.clone().into_owned()).to(be_equal_to(b"<svg/>".to_vec()));
+        expect!(svg.document(3).unwrap().unwrap()).to(be_equal_to(document));
+
+        // Breaks the checksum.
+        data[60] ^= 1;
+        let svg = SVG::from_data(&data, 0, data.len()).unwrap();
+        expect!(svg.document(2)).to(be_err().value(Malformed));
+
+        expect!(SVG::from_data(&data, 0, 50)).to(be_err().value(Malformed));
+        expect!(SVG::from_data(&[0, 1, 0, 0, 0, 6, 0, 0], 0, 8)).to(be_err().value(SVGVersionIsNotSupported));
+    }
+}
@@ -0,0 +1,158 @@
+
+use types::Fixed;
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+
+/// A vertical header.
+///
+/// This table contains information needed to layout fonts whose characters
+/// are written vertically, that is, either top to bottom or bottom to top.
+///
+/// The table provides such properties as: `ascent`, `descent` and `line_gap`,
+/// these are distances from the vertical center line expressed in unscaled
+/// coordinates, so you must multiply by the scale factor for a given size.
+/// You can advance the horizontal position by `ascent - descent + line_gap`.
+#[derive(Debug, Default)]
+pub struct VHEA {
+    version: Fixed,
+    ascent: i16,
+    descent: i16,
+    line_gap: i16,
+    advance_height_max: u16,
+    min_top_side_bearing: i16,
+    min_bottom_side_bearing: i16,
+    y_max_extent: i16,
+    caret_slope_rise: i16,
+    caret_slope_run: i16,
+    caret_offset: i16,
+    reserved1: i16,
+    reserved2: i16,
+    reserved3: i16,
+    reserved4: i16,
+    metric_data_format: i16,
+    num_of_long_ver_metrics: u16,
+}
+
+impl VHEA {
+    /// Returns `vhea` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or version of
+    /// the `vhea` font table is not supported.
+    pub fn from_data(data: &[u8], offset: usize) -> Result<VHEA> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        let version = Fixed(try!(cursor.read_i32::<BigEndian>()));
+        if version != Fixed(0x00010000) && version != Fixed(0x00011000) {
+            return Err(Error::VHEAVersionIsNotSupported);
+        }
+
+        let mut vhea = VHEA::default();
+        vhea.version = version;
+        vhea.ascent = try!(cursor.read_i16::<BigEndian>());
+        vhea.descent = try!(cursor.read_i16::<BigEndian>());
+        vhea.line_gap = try!(cursor.read_i16::<BigEndian>());
+        vhea.advance_height_max = try!(cursor.read_u16::<BigEndian>());
+        vhea.min_top_side_bearing = try!(cursor.read_i16::<BigEndian>());
+        vhea.min_bottom_side_bearing = try!(cursor.read_i16::<BigEndian>());
+        vhea.y_max_extent = try!(cursor.read_i16::<BigEndian>());
+        vhea.caret_slope_rise = try!(cursor.read_i16::<BigEndian>());
+        vhea.caret_slope_run = try!(cursor.read_i16::<BigEndian>());
+        vhea.caret_offset = try!(cursor.read_i16::<BigEndian>());
+        vhea.reserved1 = try!(cursor.read_i16::<BigEndian>());
+        vhea.reserved2 = try!(cursor.read_i16::<BigEndian>());
+        vhea.reserved3 = try!(cursor.read_i16::<BigEndian>());
+        vhea.reserved4 = try!(cursor.read_i16::<BigEndian>());
+        vhea.metric_data_format = try!(cursor.read_i16::<BigEndian>());
+        vhea.num_of_long_ver_metrics = try!(cursor.read_u16::<BigEndian>());
+
+        Ok(vhea)
+    }
+
+    #[cfg(test)]
+    fn bytes(&self) -> Vec<u8> {
+        use byteorder::WriteBytesExt;
+
+        let mut data = vec![];
+        data.write_i32::<BigEndian>(self.version.0).unwrap();
+        data.write_i16::<BigEndian>(self.ascent).unwrap();
+        data.write_i16::<BigEndian>(self.descent).unwrap();
+        data.write_i16::<BigEndian>(self.line_gap).unwrap();
+        data.write_u16::<BigEndian>(self.advance_height_max).unwrap();
+        data.write_i16::<BigEndian>(self.min_top_side_bearing).unwrap();
+        data.write_i16::<BigEndian>(self.min_bottom_side_bearing).unwrap();
+        data.write_i16::<BigEndian>(self.y_max_extent).unwrap();
+        data.write_i16::<BigEndian>(self.caret_slope_rise).unwrap();
+        data.write_i16::<BigEndian>(self.caret_slope_run).unwrap();
+        data.write_i16::<BigEndian>(self.caret_offset).unwrap();
+        data.write_i16::<BigEndian>(self.reserved1).unwrap();
+        data.write_i16::<BigEndian>(self.reserved2).unwrap();
+        data.write_i16::<BigEndian>(self.reserved3).unwrap();
+        data.write_i16::<BigEndian>(self.reserved4).unwrap();
+        data.write_i16::<BigEndian>(self.metric_data_format).unwrap();
+        data.write_u16::<BigEndian>(self.num_of_long_ver_metrics).unwrap();
+        data
+    }
+
+    /// Distance from the center line to the right of the line.
+    pub fn ascent(&self) -> i32 {
+        self.ascent as i32
+    }
+
+    /// Distance from the center line to the left of the line (i.e. it is
+    /// typically negative).
+    pub fn descent(&self) -> i32 {
+        self.descent as i32
+    }
+
+    /// The spacing between one column's descent and the next column's ascent.
+    pub fn line_gap(&self) -> i32 {
+        self.line_gap as i32
+    }
+
+    /// The number of advance heights in metrics table.
+    pub fn num_of_long_ver_metrics(&self) -> u32 {
+        self.num_of_long_ver_metrics as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use types::Fixed;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        let vhea = VHEA {
+            version: Fixed(0x00011000),
+            ascent: 500,
+            descent: -500,
+            line_gap: 100,
+            num_of_long_ver_metrics: 3,
+            ..VHEA::default()
+        };
+        let data = vhea.bytes();
+        expect!(data.len()).to(be_equal_to(36));
+
+        let vhea = VHEA::from_data(&data, 0).unwrap();
+        assert_eq!(vhea.bytes(), data);
+        expect!(vhea.ascent()).to(be_equal_to(500));
+        expect!(vhea.descent()).to(be_equal_to(-500));
+        expect!(vhea.line_gap()).to(be_equal_to(100));
+        expect!(vhea.num_of_long_ver_metrics()).to(be_equal_to(3));
+
+        let vhea = VHEA::default();
+        expect!(VHEA::from_data(&vhea.bytes(), 0)).to(be_err().value(VHEAVersionIsNotSupported));
+
+        expect!(VHEA::from_data(&data, data.len())).to(be_err().value(Malformed));
+    }
+}
@@ -0,0 +1,125 @@
+
+use Error;
+use Result;
+use std::io::Cursor;
+use byteorder::{BigEndian, ReadBytesExt};
+
+/// A record of vertical metrics.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LongVerticalMetric {
+    /// The offset from the current vertical position to the next vertical
+    /// position.
+    pub advance_height: u16,
+    /// The offset from the current vertical position to the top edge
+    /// of the character.
+    pub top_side_bearing: i16,
+}
+
+/// A table of vertical metrics.
+///
+/// The 'vmtx' table contains metric information for the vertical layout
+/// each of the glyphs in the font.
+#[derive(Debug, Default)]
+pub struct VMTX {
+    metrics: Vec<LongVerticalMetric>,
+    top_side_bearings: Vec<i16>,
+}
+
+impl VMTX {
+    /// Returns `vmtx` font table.
+    ///
+    /// Attempts to read `data` starting from `offset` position.
+    /// `metrics` is a number of long vertical metrics taken from `vhea`
+    /// font table.
+    /// `glyphs` is a number of glyphs in the font.
+    ///
+    /// # Errors
+    /// Returns error if there is not enough data to read or the number of
+    /// `metrics` is zero or greater than the number of `glyphs`.
+    pub fn from_data(data: &[u8], offset: usize, metrics: u32, glyphs: u32) -> Result<VMTX> {
+        if offset >= data.len() {
+            return Err(Error::Malformed);
+        }
+        if metrics == 0 || metrics > glyphs {
+            return Err(Error::Malformed);
+        }
+        let bearings = glyphs - metrics;
+
+        let mut vmtx = VMTX {
+            metrics: Vec::with_capacity(metrics as usize),
+            top_side_bearings: Vec::with_capacity(bearings as usize),
+        };
+
+        let mut cursor = Cursor::new(&data[offset..]);
+        for _ in 0..metrics {
+            let w = try!(cursor.read_u16::<BigEndian>());
+            let b = try!(cursor.read_i16::<BigEndian>());
+            vmtx.metrics.push(LongVerticalMetric { advance_height: w, top_side_bearing: b });
+        }
+
+        for _ in 0..bearings {
+            vmtx.top_side_bearings.push(try!(cursor.read_i16::<BigEndian>()));
+        }
+
+        Ok(vmtx)
+    }
+
+    #[cfg(test)]
+    fn bytes(&self) -> Vec<u8> {
+        use byteorder::WriteBytesExt;
+
+        let mut data = vec![];
+        for metric in &self.metrics {
+            data.write_u16::<BigEndian>(metric.advance_height).unwrap();
+            data.write_i16::<BigEndian>(metric.top_side_bearing).unwrap();
+        }
+        for &bearing in &self.top_side_bearings {
+            data.write_i16::<BigEndian>(bearing).unwrap();
+        }
+        data
+    }
+
+    /// Returns a vertical metric for a glyph at a given index.
+    pub fn vmetric_for_glyph_at_index(&self, i: usize) -> LongVerticalMetric {
+        if let Some(&metric) = self.metrics.get(i) {
+            metric
+        } else {
+            // It's safe to `unwrap` here, since the table is read with
+            // at least one entry of vertical metrics.
+            let mut metric = *self.metrics.last().unwrap();
+            if let Some(&tsb) = self.top_side_bearings.get(i - self.metrics.len()) {
+                metric.top_side_bearing = tsb;
+            }
+            metric
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error::*;
+    use byteorder::WriteBytesExt;
+    use expectest::prelude::*;
+
+    #[test]
+    fn smoke() {
+        let mut data = vec![];
+        for &(height, bearing) in &[(1000, 100), (900, -50)] {
+            data.write_u16::<BigEndian>(height).unwrap();
+            data.write_i16::<BigEndian>(bearing).unwrap();
+        }
+        data.write_i16::<BigEndian>(70).unwrap();
+
+        let vmtx = VMTX::from_data(&data, 0, 2, 3).unwrap();
+        assert_eq!(vmtx.bytes(), data);
+        expect!(vmtx.vmetric_for_glyph_at_index(0))
+            .to(be_equal_to(LongVerticalMetric { advance_height: 1000, top_side_bearing: 100 }));
+        expect!(vmtx.vmetric_for_glyph_at_index(2))
+            .to(be_equal_to(LongVerticalMetric { advance_height: 900, top_side_bearing: 70 }));
+
+        expect!(VMTX::from_data(&data, data.len(), 2, 3)).to(be_err().value(Malformed));
+        expect!(VMTX::from_data(&data, 0, 1, 0)).to(be_err().value(Malformed));
+        expect!(VMTX::from_data(&data, 0, 0, 3)).to(be_err().value(Malformed));
+    }
+}
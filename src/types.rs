@@ -8,6 +8,44 @@ pub struct BBox {
     pub y1: i32,
 }
 
+/// Metrics for laying out lines of text.
+///
+/// Metrics are expressed in unscaled coordinates, so you must multiply by
+/// the scale factor for a given size. You can advance the vertical position
+/// by `ascent - descent + line_gap`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct LineMetrics {
+    /// Distance from baseline of highest ascender.
+    pub ascent: i32,
+    /// Distance from baseline of lowest descender (i.e. it is typically negative).
+    pub descent: i32,
+    /// The spacing between one row's descent and the next row's ascent.
+    pub line_gap: i32,
+}
+
+/// Metrics of a glyph for vertical layout.
+///
+/// Metrics are expressed in unscaled coordinates.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct VerticalMetrics {
+    /// The offset from the current vertical position to the next vertical
+    /// position.
+    pub advance_height: i32,
+    /// The offset from the current vertical position to the top edge
+    /// of the glyph.
+    pub top_side_bearing: i32,
+}
+
+/// A color with straight (not premultiplied) alpha.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    /// Opacity of the color, 0 is transparent and 255 is opaque.
+    pub alpha: u8,
+}
+
 #[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
 pub struct Fixed(pub i32);
 
@@ -1,23 +1,30 @@
 
 use Error;
 use Result;
-use byteorder::{BigEndian, ByteOrder};
+use std::io::{Cursor, Read};
 
 /// Attempts to find the table offset in `data` for a font table `tag`
 /// starting from a `fontstart` offset.
 pub fn find_table_offset(data: &[u8], fontstart: usize, tag: &[u8; 4]) -> Result<Option<usize>> {
+    Ok(try!(find_table_range(data, fontstart, tag)).map(|(offset, _)| offset))
+}
+
+/// Attempts to find the offset and the length of the table in `data` for
+/// a font table `tag` starting from a `fontstart` offset.
+pub fn find_table_range(data: &[u8], fontstart: usize, tag: &[u8; 4]) -> Result<Option<(usize, usize)>> {
     let tabledir = fontstart + 12;
     if tabledir >= data.len() {
         return Err(Error::Malformed);
     }
 
-    let num_tables = BigEndian::read_u16(&data[fontstart + 4..]) as usize;
+    let num_tables = read_u16(&data[fontstart + 4..]) as usize;
     for table_chunk in data[tabledir..].chunks(16).take(num_tables) {
         if table_chunk.len()==16 && prefix_is_tag(table_chunk, tag) {
-            return Ok(Some(BigEndian::read_u32(&table_chunk[8..12]) as usize));
+            return Ok(Some((read_u32(&table_chunk[8..12]) as usize,
+                            read_u32(&table_chunk[12..16]) as usize)));
         }
     }
-    return Ok(None);
+    Ok(None)
 }
 
 /// Attempts to find the table offset in `data` for a required font table `tag`
@@ -40,9 +47,37 @@ pub fn prefix_is_tag(bs: &[u8], tag: &[u8; 4]) -> bool {
     bs.len()>=4 && bs[0]==tag[0] && bs[1]==tag[1] && bs[2]==tag[2] && bs[3]==tag[3]
 }
 
+/// Fills `buf` with bytes read from `cursor`.
+pub fn read_bytes(cursor: &mut Cursor<&[u8]>, buf: &mut [u8]) -> Result<()> {
+    match cursor.read(buf) {
+        Ok(n) if n == buf.len() => Ok(()),
+        _ => Err(Error::Malformed),
+    }
+}
+
+/// Reads a big endian `u16` at the start of `data`, which may be unaligned.
+pub fn read_u16(data: &[u8]) -> u16 {
+    (data[0] as u16) << 8 | data[1] as u16
+}
+
+/// Reads a big endian `i16` at the start of `data`, which may be unaligned.
+pub fn read_i16(data: &[u8]) -> i16 {
+    read_u16(data) as i16
+}
+
+/// Reads a big endian `u32` at the start of `data`, which may be unaligned.
+pub fn read_u32(data: &[u8]) -> u32 {
+    (read_u16(data) as u32) << 16 | read_u16(&data[2..]) as u32
+}
+
+/// Reads a big endian `i32` at the start of `data`, which may be unaligned.
+pub fn read_i32(data: &[u8]) -> i32 {
+    read_u32(data) as i32
+}
+
 pub fn read_u16_from_raw_data(data: &[u8], index: usize) -> Option<u16> {
     if index * 2 < data.len() {
-        Some(BigEndian::read_u16(&data[index * 2..]))
+        Some(read_u16(&data[index * 2..]))
     } else {
         None
     }
@@ -50,7 +85,7 @@ pub fn read_u16_from_raw_data(data: &[u8], index: usize) -> Option<u16> {
 
 pub fn read_i16_from_raw_data(data: &[u8], index: usize) -> Option<i16> {
     if index * 2 < data.len() {
-        Some(BigEndian::read_i16(&data[index * 2..]))
+        Some(read_i16(&data[index * 2..]))
     } else {
         None
     }
@@ -89,4 +124,13 @@ mod tests {
         expect!(read_u16_from_raw_data(data, 1)).to(be_some().value(3));
         expect!(read_u16_from_raw_data(data, 2)).to(be_none());
     }
+
+    #[test]
+    fn test_read_unaligned() {
+        let data: &[u8] = &[0, 0x80, 1, 0xff, 0xfe];
+        expect!(read_u16(&data[1..])).to(be_equal_to(0x8001));
+        expect!(read_i16(&data[3..])).to(be_equal_to(-2));
+        expect!(read_u32(&data[1..])).to(be_equal_to(0x8001fffe));
+        expect!(read_i32(&data[1..])).to(be_equal_to(-0x7ffe0002));
+    }
 }
@@ -1,8 +1,13 @@
 extern crate piston_truetype;
 
+use std::cmp;
 use std::ptr::{null_mut};
 use piston_truetype::*;
 
+fn font_data() -> &'static [u8] {
+    include_bytes!("Tuffy_Bold.ttf")
+}
+
 fn expect_glyph(letter: char, expected: String) {
     unsafe {
         let bs = include_bytes!("Tuffy_Bold.ttf");
@@ -69,3 +74,922 @@ fn draw_capital_g() {
         "   o@@@@:  \n" +
         "     .     \n" );
 }
+
+
+#[test]
+fn glyph_outline() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+
+    let outline = font.glyph_outline(font.glyph_index_for_code('A' as usize)).unwrap();
+    assert!(!outline.is_empty());
+    let mut contours = 0;
+    let mut start = None;
+    let mut current = None;
+    for segment in &outline {
+        match *segment {
+            Segment::MoveTo { x, y } => {
+                assert_eq!(start, current);
+                contours += 1;
+                start = Some((x, y));
+                current = start;
+            },
+            Segment::LineTo { x, y } | Segment::QuadTo { x, y, .. } |
+            Segment::CurveTo { x, y, .. } => current = Some((x, y)),
+        }
+    }
+    assert_eq!(start, current);
+    assert_eq!(contours, 2);
+
+    let space = font.glyph_outline(font.glyph_index_for_code(' ' as usize)).unwrap();
+    assert!(space.is_empty());
+
+    // 'Á' is a composite glyph of 'A' and an acute accent.
+    let composite = font.glyph_outline(font.glyph_index_for_code('Á' as usize)).unwrap();
+    assert!(composite.len() > outline.len());
+}
+
+struct ContourCounter {
+    contours: usize,
+    closed: usize,
+    segments: usize,
+}
+
+impl OutlineBuilder for ContourCounter {
+    fn move_to(&mut self, _: f32, _: f32) {
+        assert_eq!(self.contours, self.closed);
+        self.contours += 1;
+    }
+
+    fn line_to(&mut self, _: f32, _: f32) {
+        self.segments += 1;
+    }
+
+    fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {
+        self.segments += 1;
+    }
+
+    fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {
+        self.segments += 1;
+    }
+
+    fn close(&mut self) {
+        self.closed += 1;
+    }
+}
+
+#[test]
+fn build_glyph_outline() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+
+    for &c in &['A', 'G', 'Á', ' '] {
+        let glyph = font.glyph_index_for_code(c as usize);
+        let mut counter = ContourCounter { contours: 0, closed: 0, segments: 0 };
+        font.build_glyph_outline(glyph, &mut counter).unwrap();
+        assert_eq!(counter.contours, counter.closed);
+
+        let outline = font.glyph_outline(glyph).unwrap();
+        assert_eq!(counter.contours + counter.segments, outline.len());
+    }
+}
+
+#[test]
+fn render_glyph() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let scale = font.scale_for_pixel_height(20.0);
+
+    for &c in &['A', 'G', 'Á'] {
+        let glyph = font.glyph_index_for_code(c as usize);
+        let bitmap = font.render_glyph(glyph, 0.0, scale, 0.3, 0.0).unwrap();
+
+        let (mut w, mut h, mut x, mut y) = (0, 0, 0, 0);
+        unsafe {
+            let pixels = get_glyph_bitmap_subpixel(&font, 0.0, scale, 0.3, 0.0, glyph as isize,
+                                                   &mut w, &mut h, &mut x, &mut y);
+            assert_eq!((bitmap.width, bitmap.height), (w as usize, h as usize));
+            assert_eq!((bitmap.x_offset, bitmap.y_offset), (x as i32, y as i32));
+            assert_eq!(&bitmap.pixels[..], std::slice::from_raw_parts(pixels, (w * h) as usize));
+            free_bitmap(pixels);
+        }
+    }
+
+    let space = font.render_glyph(font.glyph_index_for_code(' ' as usize), scale, scale, 0.0, 0.0);
+    assert!(space.unwrap().is_empty());
+}
+
+#[test]
+fn render_glyph_into() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let scale = font.scale_for_pixel_height(20.0);
+    let glyph = font.glyph_index_for_code('G' as usize);
+    let expected = font.render_glyph(glyph, scale, scale, 0.0, 0.0).unwrap();
+
+    let (width, height, stride) = (16, 12, 20);
+    // Fully inside, clipped at the top left and clipped at the bottom right.
+    for &(x, y) in &[(2, 16), (-3, 9), (10, 20)] {
+        let mut buffer = vec![7u8; stride * height];
+        font.render_glyph_into(glyph, scale, scale, 0.0, 0.0,
+                               &mut buffer, width, height, stride, x, y).unwrap();
+
+        for row in 0..height {
+            for column in 0..stride {
+                let gx = column as i32 - x - expected.x_offset;
+                let gy = row as i32 - y - expected.y_offset;
+                let inside = column < width && gx >= 0 && gy >= 0 &&
+                    (gx as usize) < expected.width && (gy as usize) < expected.height;
+                let pixel = if inside { expected.pixel(gx as usize, gy as usize) } else { 7 };
+                assert_eq!(buffer[row * stride + column], pixel, "at {}, {}", column, row);
+            }
+        }
+    }
+}
+
+#[test]
+fn render_glyph_sdf() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let scale = font.scale_for_pixel_height(40.0);
+
+    for &c in &['O', 'A'] {
+        let glyph = font.glyph_index_for_code(c as usize);
+        let coverage = font.render_glyph(glyph, scale, scale, 0.0, 0.0).unwrap();
+        let sdf = font.render_glyph_sdf(glyph, scale, 3, 128, 20.0).unwrap();
+        assert_eq!((sdf.width, sdf.height), (coverage.width + 6, coverage.height + 6));
+        assert_eq!((sdf.x_offset, sdf.y_offset), (coverage.x_offset - 3, coverage.y_offset - 3));
+
+        // Fully covered pixels are inside and pixels without coverage,
+        // including the hole of 'O', are outside.
+        for y in 0..coverage.height {
+            for x in 0..coverage.width {
+                let distance = sdf.pixel(x + 3, y + 3);
+                match coverage.pixel(x, y) {
+                    255 => assert!(distance > 128, "{} at {}, {}", c, x, y),
+                    0 => assert!(distance < 128, "{} at {}, {}", c, x, y),
+                    _ => {},
+                }
+            }
+        }
+        assert!(sdf.row(0).iter().all(|&distance| distance < 128));
+    }
+
+    let space = font.glyph_index_for_code(' ' as usize);
+    assert!(font.render_glyph_sdf(space, scale, 3, 128, 20.0).unwrap().is_empty());
+}
+
+#[test]
+fn render_glyph_msdf() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let scale = font.scale_for_pixel_height(40.0);
+    let median = |p: [u8; 3]| p[0].max(p[1]).min(p[0].min(p[1]).max(p[2]));
+
+    for &c in &['O', 'A'] {
+        let glyph = font.glyph_index_for_code(c as usize);
+        let coverage = font.render_glyph(glyph, scale, scale, 0.0, 0.0).unwrap();
+        let msdf = font.render_glyph_msdf(glyph, scale, 2, 4.0).unwrap();
+        assert_eq!((msdf.width, msdf.height), (coverage.width + 4, coverage.height + 4));
+        assert_eq!((msdf.x_offset, msdf.y_offset), (coverage.x_offset - 2, coverage.y_offset - 2));
+
+        for y in 0..coverage.height {
+            for x in 0..coverage.width {
+                let distance = median(msdf.pixel(x + 2, y + 2));
+                match coverage.pixel(x, y) {
+                    255 => assert!(distance > 127, "{} at {}, {}", c, x, y),
+                    0 => assert!(distance < 128, "{} at {}, {}", c, x, y),
+                    _ => {},
+                }
+            }
+        }
+    }
+
+    let space = font.glyph_index_for_code(' ' as usize);
+    assert!(font.render_glyph_msdf(space, scale, 2, 4.0).unwrap().is_empty());
+}
+
+#[test]
+fn render_glyph_lcd() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let scale = font.scale_for_pixel_height(20.0);
+    let glyph = font.glyph_index_for_code('A' as usize);
+
+    // Without filtering channels are coverage at three times the resolution.
+    let coverage = font.render_glyph(glyph, scale * 3.0, scale, 0.75, 0.0).unwrap();
+    let subpixel = |x: i32, y: i32| {
+        let (x, y) = (x - coverage.x_offset, y - coverage.y_offset);
+        if x < 0 || y < 0 || x >= coverage.width as i32 || y >= coverage.height as i32 {
+            0
+        } else {
+            coverage.pixel(x as usize, y as usize)
+        }
+    };
+    let options = LcdOptions { filter: [0, 0, 1, 0, 0], ..LcdOptions::default() };
+    let lcd = font.render_glyph_lcd(glyph, scale, scale, 0.25, 0.0, &options).unwrap();
+    for y in 0..lcd.height {
+        for x in 0..lcd.width {
+            let (px, py) = (3 * (lcd.x_offset + x as i32), lcd.y_offset + y as i32);
+            assert_eq!(lcd.pixel(x, y), [subpixel(px, py), subpixel(px + 1, py), subpixel(px + 2, py)]);
+        }
+    }
+
+    // Filtering keeps the total coverage, BGR swaps the channels.
+    let rgb = font.render_glyph_lcd(glyph, scale, scale, 0.25, 0.0, &LcdOptions::default()).unwrap();
+    let total = |pixels: &[u8]| pixels.iter().map(|&p| p as i32).sum::<i32>();
+    assert!((total(&rgb.pixels) - total(&lcd.pixels)).abs() < total(&lcd.pixels) / 100);
+    let options = LcdOptions { order: SubpixelOrder::Bgr, ..LcdOptions::default() };
+    let bgr = font.render_glyph_lcd(glyph, scale, scale, 0.25, 0.0, &options).unwrap();
+    for (a, b) in rgb.pixels.chunks(3).zip(bgr.pixels.chunks(3)) {
+        assert_eq!((a[0], a[1], a[2]), (b[2], b[1], b[0]));
+    }
+
+    let options = LcdOptions { layout: SubpixelLayout::Vertical, ..LcdOptions::default() };
+    let vertical = font.render_glyph_lcd(glyph, scale, scale, 0.0, 0.0, &options).unwrap();
+    let coverage = font.render_glyph(glyph, scale, scale, 0.0, 0.0).unwrap();
+    assert_eq!(vertical.width, coverage.width);
+    assert!(vertical.height > coverage.height);
+}
+
+#[test]
+fn render_hinted_glyph() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let hinter = Hinter::new(&font, 16).unwrap();
+    assert_eq!(hinter.ppem(), 16);
+
+    // Glyphs without instructions are scaled, only phantom points are
+    // placed at whole pixels.
+    let glyph = font.glyph_index_for_code('H' as usize);
+    let hinted = hinter.hint_glyph(&font, glyph).unwrap();
+    let scale = font.scale_for_mapping_em_to_pixels(16.0);
+    assert!(hinted.advance_width > 0.0);
+    assert_eq!(hinted.advance_width, hinted.advance_width.round());
+    assert_eq!(hinted.outline.len(), font.glyph_outline(glyph).unwrap().len());
+
+    let bitmap = font.render_hinted_glyph(&hinter, glyph, 0.0, 0.0).unwrap();
+    let unhinted = font.render_glyph(glyph, scale, scale, 0.0, 0.0).unwrap();
+    assert!(!bitmap.is_empty());
+    assert!((bitmap.width as i32 - unhinted.width as i32).abs() <= 1);
+    assert_eq!(bitmap.height, unhinted.height);
+
+    let space = font.glyph_index_for_code(' ' as usize);
+    assert!(font.render_hinted_glyph(&hinter, space, 0.0, 0.0).unwrap().is_empty());
+}
+
+#[test]
+fn render_autohinted_glyph() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let hinter = AutoHinter::new(&font);
+    let zone = |kind| hinter.blue_zones().iter().find(|zone| zone.kind == kind).cloned().unwrap();
+    assert_eq!(zone(BlueZoneKind::Baseline).reference, 0);
+    assert!(zone(BlueZoneKind::Baseline).overshoot <= 0);
+    let x_height = zone(BlueZoneKind::XHeight);
+    assert!(x_height.reference > 0 && x_height.overshoot >= x_height.reference);
+    assert!(zone(BlueZoneKind::CapHeight).reference > x_height.reference);
+
+    // Horizontal bars of E are sharper than without hinting.
+    let scale = font.scale_for_pixel_height(11.0);
+    let glyph = font.glyph_index_for_code('E' as usize);
+    let hinted = font.render_autohinted_glyph(&hinter, glyph, scale, 0.0, 0.0).unwrap();
+    let unhinted = font.render_glyph(glyph, scale, scale, 0.0, 0.0).unwrap();
+    let blur = |bitmap: &GlyphBitmap| {
+        bitmap.pixels.iter().map(|&p| cmp::min(p, 255 - p) as i32).sum::<i32>()
+    };
+    assert!(blur(&hinted) < blur(&unhinted));
+    assert_eq!(hinted.width, unhinted.width);
+    // The bottom stays on the baseline and the top is at the cap height.
+    let cap_height = zone(BlueZoneKind::CapHeight).reference as f32 * scale;
+    assert_eq!(hinted.y_offset + hinted.height as i32, 0);
+    assert_eq!(hinted.y_offset, -cap_height.round() as i32);
+}
+
+#[test]
+fn render_stroked_and_emboldened_glyphs() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let scale = font.scale_for_pixel_height(40.0);
+    let glyph = font.glyph_index_for_code('O' as usize);
+    let outline = font.glyph_outline(glyph).unwrap();
+    let total = |bitmap: &GlyphBitmap| bitmap.pixels.iter().map(|&p| p as i32).sum::<i32>();
+
+    // Outlines are rendered like glyphs.
+    let plain = render_outline(&outline, scale, scale, 0.0, 0.0);
+    assert_eq!(plain, font.render_glyph(glyph, scale, scale, 0.0, 0.0).unwrap());
+
+    // A thin stroke covers less than the glyph and leaves the counter empty.
+    let style = StrokeStyle { width: 40.0, join: LineJoin::Round, ..StrokeStyle::default() };
+    let stroked = render_outline(&stroke_outline(&outline, &style), scale, scale, 0.0, 0.0);
+    assert!(total(&stroked) < total(&plain));
+    assert!(stroked.width >= plain.width);
+    assert_eq!(stroked.pixel(stroked.width / 2, stroked.height / 2), 0);
+
+    let emboldened = render_outline(&embolden_outline(&outline, 40.0), scale, scale, 0.0, 0.0);
+    assert!(total(&emboldened) > total(&plain));
+    assert!(emboldened.width >= plain.width + 1);
+}
+
+#[test]
+fn render_glyph_transformed() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let scale = font.scale_for_pixel_height(30.0);
+    let glyph = font.glyph_index_for_code('F' as usize);
+    let close = |a: &GlyphBitmap, b: &GlyphBitmap| {
+        a.pixels.iter().zip(&b.pixels).all(|(&p, &q)| (p as i32 - q as i32).abs() <= 8)
+    };
+
+    // A scaling renders like `render_glyph`.
+    let plain = font.render_glyph(glyph, scale, scale, 0.25, 0.5).unwrap();
+    let transform = Transform([scale, 0.0, 0.0, scale, 0.25, -0.5]);
+    let transformed = font.render_glyph_transformed(glyph, &transform).unwrap();
+    assert_eq!((transformed.width, transformed.height), (plain.width, plain.height));
+    assert_eq!((transformed.x_offset, transformed.y_offset), (plain.x_offset, plain.y_offset));
+    assert!(close(&transformed, &plain));
+    let bbox = font.glyph_bitmap_box_transformed(glyph, &transform).unwrap();
+    assert_eq!((bbox.x0, bbox.y0), (plain.x_offset, plain.y_offset));
+    assert_eq!((bbox.x1 - bbox.x0, bbox.y1 - bbox.y0), (plain.width as i32, plain.height as i32));
+
+    // Mirrored glyphs are flipped bitmaps.
+    let plain = font.render_glyph(glyph, scale, scale, 0.0, 0.0).unwrap();
+    let mirrored = font.render_glyph_transformed(glyph, &Transform::scale(-scale, scale)).unwrap();
+    assert_eq!((mirrored.width, mirrored.height), (plain.width, plain.height));
+    assert_eq!(mirrored.x_offset, -plain.x_offset - plain.width as i32);
+    let flipped = GlyphBitmap {
+        pixels: plain.pixels.chunks(plain.width).flat_map(|row| row.iter().rev().cloned()).collect(),
+        ..mirrored.clone()
+    };
+    assert!(close(&mirrored, &flipped));
+
+    // Rotation by a right angle swaps the sides of the box.
+    let rotation = Transform::rotate(std::f32::consts::PI / 2.0).combine(&Transform::scale(scale, scale));
+    let rotated = font.render_glyph_transformed(glyph, &rotation).unwrap();
+    assert!((rotated.width as i32 - plain.height as i32).abs() <= 1);
+    assert!((rotated.height as i32 - plain.width as i32).abs() <= 1);
+
+    // A synthetic oblique leans right.
+    let oblique = Transform::shear(0.2, 0.0).combine(&Transform::scale(scale, scale));
+    let box_oblique = font.glyph_bitmap_box_transformed(glyph, &oblique).unwrap();
+    assert!(box_oblique.x1 > plain.x_offset + plain.width as i32);
+    assert_eq!(box_oblique.y0, plain.y_offset);
+    assert!(font.glyph_bitmap_box_transformed(font.glyph_index_for_code(' ' as usize), &oblique).is_none());
+}
+
+#[test]
+fn render_color_glyph() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let glyph = |c| font.glyph_index_for_code(c as usize);
+    let (b, c, i, o) = (glyph('B'), glyph('C'), glyph('I'), glyph('O'));
+
+    // 'B' is a red 'O' under an 'I' of the text color, 'C' is an 'O' filled
+    // with a gradient from red on the left to blue on the right.
+    let mut colr = vec![0, 1, 0, 1, 0, 0, 0, 34, 0, 0, 0, 40, 0, 2, 0, 0, 0, 48,
+                        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    for &value in &[b, 0, 2, o, 0, i, 0xffff, 0, 1, c, 0, 10] {
+        push_u16(&mut colr, value);
+    }
+    colr.extend_from_slice(&[10, 0, 0, 6]);
+    push_u16(&mut colr, o);
+    colr.extend_from_slice(&[4, 0, 0, 16, 0, 0, 0, 0, 0x03, 0xe8, 0, 0, 0, 0, 0x03, 0xe8]);
+    colr.extend_from_slice(&[0, 0, 2, 0, 0, 0, 0, 0x40, 0, 0x40, 0, 0, 1, 0x40, 0]);
+    let cpal = vec![0, 0, 0, 2, 0, 1, 0, 2, 0, 0, 0, 14, 0, 0, 0, 0, 255, 255, 255, 0, 0, 255];
+    let bs = rebuild_font_data(&bs[..4], &[], vec![(b"COLR".to_vec(), colr), (b"CPAL".to_vec(), cpal)]);
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    assert_eq!(font.colr().unwrap().version(), 1);
+    assert_eq!(font.cpal().unwrap().palette_count(), 1);
+
+    let scale = font.scale_for_pixel_height(40.0);
+    let green = Color { red: 0, green: 128, blue: 0, alpha: 255 };
+    assert_eq!(font.render_color_glyph(glyph('A'), scale, scale, 0.0, 0.0, 0, green).unwrap(), None);
+
+    // Layers are rasterized like plain glyphs.
+    let transform = Transform::scale(scale, scale);
+    let layer = |glyph| font.render_glyph_transformed(glyph, &transform).unwrap();
+    let (ring, stem) = (layer(o), layer(i));
+    let coverage = |bitmap: &GlyphBitmap, x: i32, y: i32| {
+        let (x, y) = (x - bitmap.x_offset, y - bitmap.y_offset);
+        if x < 0 || y < 0 || x >= bitmap.width as i32 || y >= bitmap.height as i32 {
+            0
+        } else {
+            bitmap.pixel(x as usize, y as usize)
+        }
+    };
+
+    let bitmap = font.render_color_glyph(b, scale, scale, 0.0, 0.0, 0, green).unwrap().unwrap();
+    assert_eq!(bitmap.x_offset, cmp::min(ring.x_offset, stem.x_offset));
+    assert_eq!(bitmap.y_offset, cmp::min(ring.y_offset, stem.y_offset));
+    assert_eq!(bitmap.x_offset + bitmap.width as i32, ring.x_offset + ring.width as i32);
+    let (mut red_pixels, mut green_pixels) = (0, 0);
+    for y in 0..bitmap.height {
+        for x in 0..bitmap.width {
+            let (ax, ay) = (x as i32 + bitmap.x_offset, y as i32 + bitmap.y_offset);
+            let pixel = bitmap.pixel(x, y);
+            match (coverage(&ring, ax, ay), coverage(&stem, ax, ay)) {
+                (_, 255) => { assert_eq!(pixel, [0, 128, 0, 255]); green_pixels += 1; },
+                (255, 0) => { assert_eq!(pixel, [255, 0, 0, 255]); red_pixels += 1; },
+                (0, 0) => assert_eq!(pixel, [0, 0, 0, 0]),
+                _ => {},
+            }
+        }
+    }
+    assert!(red_pixels > 50 && green_pixels > 50);
+
+    let bitmap = font.render_color_glyph(c, scale, scale, 0.0, 0.0, 0, green).unwrap().unwrap();
+    assert_eq!((bitmap.x_offset, bitmap.y_offset), (ring.x_offset, ring.y_offset));
+    assert_eq!((bitmap.width, bitmap.height), (ring.width, ring.height));
+    let row = bitmap.height / 2;
+    let left = (0..bitmap.width).find(|&x| ring.pixel(x, row) == 255).unwrap();
+    let right = (0..bitmap.width).rev().find(|&x| ring.pixel(x, row) == 255).unwrap();
+    let (left, right) = (bitmap.pixel(left, row), bitmap.pixel(right, row));
+    assert!(left[0] > 200 && left[2] < 55 && left[3] == 255);
+    assert!(right[0] < 55 && right[2] > 200 && right[3] == 255);
+}
+
+#[test]
+fn embedded_image() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let glyph = |c| font.glyph_index_for_code(c as usize);
+    let (a, b) = (glyph('A'), glyph('B'));
+    let num_glyphs = num_glyphs(&bs);
+    // PNG images of a size in pixels per em with the size in the header.
+    let png = |size: usize| {
+        let mut png = vec![0x89, b'P', b'N', b'G', 13, 10, 26, 10, 0, 0, 0, 13, b'I', b'H', b'D', b'R'];
+        push_u32(&mut png, size);
+        push_u32(&mut png, size);
+        png
+    };
+
+    // Strikes of 20 and 40 pixels per em with images of 'A' in PNG.
+    let mut cblc = vec![0, 3, 0, 0, 0, 0, 0, 2];
+    for (i, &size) in [20, 40].iter().enumerate() {
+        push_u32(&mut cblc, 104 + i * 24);
+        cblc.extend_from_slice(&[0, 0, 0, 24, 0, 0, 0, 1]);
+        cblc.extend_from_slice(&[0; 28]);
+        push_u16(&mut cblc, a);
+        push_u16(&mut cblc, a);
+        cblc.extend_from_slice(&[size, size, 32, 1]);
+    }
+    let mut cbdt = vec![0, 3, 0, 0];
+    for &size in &[20, 40] {
+        push_u16(&mut cblc, a);
+        push_u16(&mut cblc, a);
+        cblc.extend_from_slice(&[0, 0, 0, 8, 0, 1, 0, 17]);
+        push_u32(&mut cblc, cbdt.len());
+        cblc.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 33]);
+        cbdt.extend_from_slice(&[size, size, 0, size, size, 0, 0, 0, 24]);
+        cbdt.extend_from_slice(&png(size as usize));
+    }
+
+    // A strike of 32 pixels per em with images of 'A' and 'B'.
+    let mut sbix = vec![0, 1, 0, 1, 0, 0, 0, 1, 0, 0, 0, 12, 0, 32, 0, 72];
+    let start = 4 + (num_glyphs + 1) * 4;
+    for i in 0..num_glyphs + 1 {
+        let images = (i > a) as usize + (i > b) as usize;
+        push_u32(&mut sbix, start + images * 32);
+    }
+    for _ in 0..2 {
+        sbix.extend_from_slice(&[0, 0, 0, 0, b'p', b'n', b'g', b' ']);
+        sbix.extend_from_slice(&png(32));
+    }
+
+    let bs = rebuild_font_data(&bs[..4], &[], vec![(b"CBLC".to_vec(), cblc), (b"CBDT".to_vec(), cbdt),
+                                                  (b"sbix".to_vec(), sbix)]);
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    assert_eq!(font.cblc().unwrap().ppems(), vec![20, 40]);
+    assert_eq!(font.sbix().unwrap().ppems(), vec![32]);
+    assert!(font.eblc().is_none() && font.ebdt().is_none());
+
+    let ppem = |glyph, size| font.embedded_image(glyph, size).unwrap().map(|image| image.ppem);
+    assert_eq!(ppem(a, 10), Some(20));
+    assert_eq!(ppem(a, 26), Some(32));
+    assert_eq!(ppem(a, 36), Some(40));
+    assert_eq!(ppem(a, 100), Some(40));
+    assert_eq!(ppem(b, 40), Some(32));
+    assert_eq!(ppem(glyph('C'), 40), None);
+
+    let image = font.embedded_image(a, 18).unwrap().unwrap();
+    assert_eq!(image.format, ImageFormat::Png);
+    assert_eq!(&image.data[..], &png(20)[..]);
+    assert_eq!((image.width, image.height, image.bearing_x, image.bearing_y), (20, 20, 0, 20));
+    assert_eq!(image.advance, Some(20));
+}
+
+#[test]
+fn svg_document() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    assert!(font.svg().is_none());
+    let glyph = |c| font.glyph_index_for_code(c as usize);
+    let (a, b) = (glyph('A'), glyph('B'));
+
+    // A plain document of 'A' and a compressed one of 'B'.
+    let document = format!("<svg><path id=\"glyph{}\" d=\"M0 0h10v10z\"/></svg>", a).into_bytes();
+    let compressed = [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 2, 3, 0xb3, 0x29, 0x2e, 0x4b, 0xd7, 0xb7,
+                      0x03, 0x00, 0x49, 0xfb, 0xb9, 0xac, 6, 0, 0, 0];
+    let mut svg = vec![0, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0, 2];
+    let mut records = vec![(a, 26, document.len()), (b, 26 + document.len(), compressed.len())];
+    records.sort();
+    for (glyph, offset, length) in records {
+        push_u16(&mut svg, glyph);
+        push_u16(&mut svg, glyph);
+        push_u32(&mut svg, offset);
+        push_u32(&mut svg, length);
+    }
+    svg.extend_from_slice(&document);
+    svg.extend_from_slice(&compressed);
+
+    let bs = rebuild_font_data(&bs[..4], &[], vec![(b"SVG ".to_vec(), svg)]);
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    assert_eq!(font.svg().unwrap().document_count(), 2);
+    assert_eq!(font.svg_document(a).unwrap().unwrap().into_owned(), document);
+    assert_eq!(font.svg_document(b).unwrap().unwrap().into_owned(), b"<svg/>".to_vec());
+    assert_eq!(font.svg_document(glyph('C')).unwrap(), None);
+    assert_eq!(font.svg_document(0x10000).unwrap(), None);
+}
+
+#[test]
+fn variation_coordinates() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let glyph = |c| font.glyph_index_for_code(c as usize);
+    let (i, a_acute) = (glyph('I'), glyph('Á'));
+    let (i_outline, a_acute_outline) = (font.glyph_outline(i).unwrap(), font.glyph_outline(a_acute).unwrap());
+    let a_acute_box = font.glyph_bounding_box(a_acute).unwrap();
+    let i_points = font.glyph_data_for_glyph_at_index(i).number_of_points() + 4;
+    assert!(font.fvar().is_none());
+
+    // The weight axis from 100 to 900, with 650 mapped to 837.5.
+    let fvar = vec![0, 1, 0, 0, 0, 16, 0, 2, 0, 1, 0, 20, 0, 0, 0, 8,
+                    b'w', b'g', b'h', b't', 0, 100, 0, 0, 1, 144, 0, 0, 3, 132, 0, 0, 0, 0, 1, 0];
+    let avar = vec![0, 1, 0, 0, 0, 0, 0, 1, 0, 4, 0xc0, 0, 0xc0, 0, 0, 0, 0, 0,
+                    0x20, 0, 0x30, 0, 0x40, 0, 0x40, 0];
+
+    // At the maximum weight 'I' moves right by 100 units and the accent
+    // of 'Á' moves up by 100 units.
+    let packed = |count: usize, control: u8, value: u8| {
+        let mut data = vec![];
+        for run in (0..count).collect::<Vec<_>>().chunks(64) {
+            data.push(control | (run.len() - 1) as u8);
+            if control == 0 {
+                data.extend(run.iter().map(|_| value));
+            }
+        }
+        data
+    };
+    let mut i_deltas = vec![0];
+    i_deltas.extend(packed(i_points, 0, 100));
+    i_deltas.extend(packed(i_points, 0x80, 0));
+    let a_acute_deltas = vec![1, 0, 1, 0, 0, 0, 100];
+    let variation_data = |deltas: &[u8]| {
+        let mut data = vec![0, 1, 0, 10];
+        push_u16(&mut data, deltas.len());
+        data.extend_from_slice(&[0xa0, 0, 0x40, 0]);
+        data.extend_from_slice(deltas);
+        data
+    };
+
+    let num_glyphs = num_glyphs(&bs);
+    let mut gvar = vec![0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+    push_u16(&mut gvar, num_glyphs);
+    push_u16(&mut gvar, 1);
+    push_u32(&mut gvar, 20 + (num_glyphs + 1) * 4);
+    let mut glyph_data = vec![];
+    for glyph in 0..num_glyphs + 1 {
+        push_u32(&mut gvar, glyph_data.len());
+        if glyph == i {
+            glyph_data.extend(variation_data(&i_deltas));
+        } else if glyph == a_acute {
+            glyph_data.extend(variation_data(&a_acute_deltas));
+        }
+    }
+    gvar.extend(glyph_data);
+
+    let bs = rebuild_font_data(&bs[..4], &[], vec![(b"fvar".to_vec(), fvar), (b"avar".to_vec(), avar),
+                                                  (b"gvar".to_vec(), gvar)]);
+    let mut font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    assert_eq!(font.fvar().unwrap().axes()[0].tag, *b"wght");
+    assert_eq!(font.variation_coordinates(), &[0.0]);
+    assert_eq!(font.glyph_outline(i).unwrap(), i_outline);
+
+    font.set_variation_coordinates(&[(*b"wght", 1000.0), (*b"wdth", 50.0)]);
+    assert_eq!(font.variation_coordinates(), &[1.0]);
+    assert_eq!(font.glyph_outline(i).unwrap(), Transform::translate(100.0, 0.0).apply_to_outline(&i_outline));
+    let outline = font.glyph_outline(a_acute).unwrap();
+    assert_eq!(outline.len(), a_acute_outline.len());
+    let bbox = font.glyph_bounding_box(a_acute).unwrap();
+    assert_eq!(bbox, BBox { y1: a_acute_box.y1 + 100, ..a_acute_box });
+
+    font.set_variation_coordinates(&[(*b"wght", 650.0)]);
+    assert_eq!(font.variation_coordinates(), &[0.75]);
+    assert_eq!(font.glyph_outline(i).unwrap(), Transform::translate(75.0, 0.0).apply_to_outline(&i_outline));
+
+    font.set_variation_coordinates(&[]);
+    assert_eq!(font.glyph_outline(a_acute).unwrap(), a_acute_outline);
+}
+
+#[test]
+fn pack_msdf() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let (width, height) = (128, 64);
+    let mut pixels = vec![0xff; width * height * 3];
+    unsafe {
+        let mut context: PackContext = std::mem::zeroed();
+        let mut chars: [PackedChar; 3] = std::mem::zeroed();
+        assert_eq!(pack_begin_msdf(&mut context, pixels.as_mut_ptr(), width as isize,
+            height as isize, 0, 1, 4.0, null_mut()), 1);
+        assert_eq!(pack_font_range(&mut context, &bs, 0, 20.0, 'A' as isize, 3, chars.as_mut_ptr()).unwrap(), 1);
+        pack_end(&mut context);
+    }
+
+    // The area inside of packed glyphs matches their coverage.
+    let inside = pixels.chunks(3).filter(|p| p[0].max(p[1]).min(p[0].min(p[1]).max(p[2])) > 127).count();
+    let scale = font.scale_for_pixel_height(20.0);
+    let covered: f32 = "ABC".chars().map(|c| {
+        let glyph = font.glyph_index_for_code(c as usize);
+        let bitmap = font.render_glyph(glyph, scale, scale, 0.0, 0.0).unwrap();
+        bitmap.pixels.iter().map(|&p| p as f32 / 255.0).sum::<f32>()
+    }).sum();
+    assert!((inside as f32 - covered).abs() < covered * 0.1, "{} {}", inside, covered);
+}
+
+#[test]
+fn font_tables() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+
+    let name = font.name().unwrap();
+    assert_eq!(name.family_name(), Some("Tuffy".to_owned()));
+    assert_eq!(name.subfamily_name(), Some("Bold".to_owned()));
+
+    let os2 = font.os2().unwrap();
+    assert_eq!(os2.weight_class(), 700);
+    assert!(os2.use_typo_metrics());
+
+    let metrics = font.line_metrics();
+    assert_eq!(metrics.ascent, os2.typo_ascender());
+    assert_eq!(metrics.descent, os2.typo_descender());
+    assert_eq!(metrics.line_gap, os2.typo_line_gap());
+}
+
+#[test]
+fn unsupported_optional_tables() {
+    // `OS/2` version 6 and `post` version 4.0 don't exist yet.
+    let bs = font_data();
+    let mut os2 = table_data(&bs, b"OS/2");
+    os2[..2].copy_from_slice(&[0, 6]);
+    let mut post = table_data(&bs, b"post");
+    post[..4].copy_from_slice(&[0, 4, 0, 0]);
+    let bs = rebuild_font_data(&bs[..4], &[b"OS/2", b"post"], vec![(b"OS/2".to_vec(), os2), (b"post".to_vec(), post)]);
+
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    assert!(font.os2().is_none());
+    assert!(font.post().is_none());
+    assert!(font.name().is_some());
+    let a = font.glyph_index_for_code('A' as usize);
+    assert!(font.glyph_outline(a).unwrap().len() > 0);
+}
+
+#[test]
+fn glyph_names() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+
+    let glyph = font.glyph_index_for_code('л' as usize);
+    assert_eq!(font.glyph_name(glyph), Some("afii10077"));
+    assert_eq!(font.glyph_index_for_name("afii10077"), Some(glyph));
+    assert_eq!(font.glyph_index_for_name("A"), Some(font.glyph_index_for_code('A' as usize)));
+    assert!(font.post().unwrap().underline_thickness() > 0);
+}
+
+#[test]
+fn kerning() {
+    let bs = font_data();
+    let mut font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    // The font has no `GPOS` table, so the `kern` table is used.
+    assert!(font.gpos().is_none());
+
+    let a = font.glyph_index_for_code('A' as usize);
+    let v = font.glyph_index_for_code('V' as usize);
+    assert_eq!(font.kerning(a, v), -213);
+    assert_eq!(font.kerning(v, a), -244);
+    assert_eq!(font.kerning(a, a), 0);
+    unsafe {
+        assert_eq!(get_codepoint_kern_advance(&mut font, 'A' as isize, 'V' as isize), -213);
+        assert_eq!(get_codepoint_kern_advance(&mut font, -1, 'V' as isize), 0);
+        assert_eq!(get_glyph_kern_advance(&mut font, a as isize, -1), 0);
+    }
+}
+
+#[test]
+fn gpos_kerning() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let a = font.glyph_index_for_code('A' as usize);
+    let v = font.glyph_index_for_code('V' as usize);
+
+    // Header, the 'DFLT' script with the 'kern' feature at 10, the feature
+    // list at 30 and the lookup list at 44 with a pair adjustment of 'A'
+    // and 'V' by -50 at 56.
+    let mut gpos = vec![0, 1, 0, 0, 0, 10, 0, 30, 0, 44];
+    gpos.extend_from_slice(&[0, 1, b'D', b'F', b'L', b'T', 0, 8, 0, 4, 0, 0, 0, 0, 0xff, 0xff, 0, 1, 0, 0]);
+    gpos.extend_from_slice(&[0, 1, b'k', b'e', b'r', b'n', 0, 8, 0, 0, 0, 1, 0, 0]);
+    gpos.extend_from_slice(&[0, 1, 0, 4, 0, 2, 0, 0, 0, 1, 0, 8]);
+    gpos.extend_from_slice(&[0, 1, 0, 18, 0, 4, 0, 0, 0, 1, 0, 12, 0, 1]);
+    push_u16(&mut gpos, v);
+    push_u16(&mut gpos, -50i16 as u16 as usize);
+    gpos.extend_from_slice(&[0, 1, 0, 1]);
+    push_u16(&mut gpos, a);
+    let bs = rebuild_font_data(&bs[..4], &[], vec![(b"GPOS".to_vec(), gpos)]);
+    let mut font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    assert!(font.gpos().unwrap().has_kerning());
+
+    // Pairs of the `kern` table are not used when `GPOS` has kerning.
+    assert_eq!(font.kerning(a, v), -50);
+    assert_eq!(font.kerning(v, a), 0);
+    unsafe {
+        assert_eq!(get_codepoint_kern_advance(&mut font, 'A' as isize, 'V' as isize), -50);
+    }
+}
+
+#[test]
+fn shape_text() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let scale = font.scale_for_pixel_height(32.0);
+
+    let glyphs = shape(&font, "AVлA", 32.0);
+    let a = font.glyph_index_for_code('A' as usize);
+    let v = font.glyph_index_for_code('V' as usize);
+    assert_eq!(glyphs.iter().map(|g| g.glyph).collect::<Vec<_>>(),
+               vec![a, v, font.glyph_index_for_code('л' as usize), a]);
+    assert_eq!(glyphs.iter().map(|g| g.cluster).collect::<Vec<_>>(), vec![0, 1, 2, 4]);
+    assert_eq!(glyphs[0].x, 0.0);
+    for pair in glyphs.windows(2) {
+        assert_eq!(pair[1].x, pair[0].x + pair[0].advance);
+        assert_eq!(pair[1].y, 0.0);
+    }
+
+    // Kerning of 'A' and 'V' shortens the advance of 'A'.
+    let unkerned = shape(&font, "AA", 32.0);
+    assert!((unkerned[0].advance - glyphs[0].advance - 213.0 * scale).abs() < 1e-4);
+    assert!(shape(&font, "", 32.0).is_empty());
+}
+
+#[test]
+fn vertical_metrics() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    // The font has no vertical tables, so metrics are derived from `OS/2`.
+    assert!(font.vhea().is_none() && font.vmtx().is_none());
+
+    let os2 = font.os2().unwrap();
+    let a = font.glyph_index_for_code('A' as usize);
+    let top = font.glyph_data_for_glyph_at_index(a).bounding_box().unwrap().y1;
+    assert_eq!(font.vertical_metrics(a), VerticalMetrics {
+        advance_height: os2.typo_ascender() - os2.typo_descender(),
+        top_side_bearing: os2.typo_ascender() - top,
+    });
+    let space = font.glyph_index_for_code(' ' as usize);
+    assert_eq!(font.vertical_metrics(space).top_side_bearing, os2.typo_ascender());
+}
+
+fn push_u16(data: &mut Vec<u8>, value: usize) {
+    data.extend_from_slice(&[(value >> 8) as u8, value as u8]);
+}
+
+fn push_u32(data: &mut Vec<u8>, value: usize) {
+    push_u16(data, value >> 16);
+    push_u16(data, value & 0xffff);
+}
+
+fn read_u32(data: &[u8], offset: usize) -> usize {
+    data[offset..offset + 4].iter().fold(0, |value, &b| value << 8 | b as usize)
+}
+
+// Returns the data of the table with the `tag` of the font.
+fn table_data(data: &[u8], tag: &[u8]) -> Vec<u8> {
+    (0..(data[4] as usize) << 8 | data[5] as usize)
+        .map(|i| &data[12 + i * 16..])
+        .find(|entry| &entry[..4] == tag)
+        .map(|entry| (read_u32(entry, 8), read_u32(entry, 12)))
+        .map(|(offset, length)| data[offset..offset + length].to_vec())
+        .unwrap()
+}
+
+// Returns the number of glyphs from the `maxp` table of the font.
+fn num_glyphs(data: &[u8]) -> usize {
+    let maxp = table_data(data, b"maxp");
+    (maxp[4] as usize) << 8 | maxp[5] as usize
+}
+
+// Rebuilds the font without tables with the `excluded` tags and with
+// the `extra` tables.
+fn rebuild_font_data(version: &[u8], excluded: &[&[u8]], extra: Vec<(Vec<u8>, Vec<u8>)>) -> Vec<u8> {
+    let ttf = font_data();
+    let mut tables: Vec<(Vec<u8>, Vec<u8>)> = vec![];
+    for i in 0..(ttf[4] as usize) << 8 | ttf[5] as usize {
+        let entry = &ttf[12 + i * 16..];
+        let tag = entry[..4].to_vec();
+        if !excluded.contains(&&tag[..]) {
+            let (offset, length) = (read_u32(entry, 8), read_u32(entry, 12));
+            tables.push((tag, ttf[offset..offset + length].to_vec()));
+        }
+    }
+    tables.extend(extra);
+
+    let mut data = version.to_vec();
+    push_u16(&mut data, tables.len());
+    data.extend_from_slice(&[0; 6]);
+    let mut offset = 12 + tables.len() * 16;
+    for &(ref tag, ref table) in &tables {
+        data.extend_from_slice(tag);
+        push_u32(&mut data, 0);
+        push_u32(&mut data, offset);
+        push_u32(&mut data, table.len());
+        offset += (table.len() + 3) & !3;
+    }
+    for &(_, ref table) in &tables {
+        data.extend_from_slice(table);
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+    }
+    data
+}
+
+// Rebuilds the font with a `CFF ` table instead of `glyf` and `loca`. Glyphs
+// are empty, except for 'A' which is a square from 100, 100 to 600, 700.
+fn cff_font_data(a: usize, num_glyphs: usize) -> Vec<u8> {
+    let square: &[u8] = &[239, 239, 21, 248, 136, 6, 248, 236, 7, 252, 136, 6, 14];
+    cff_font_data_with_char_strings(&[(a, square)], num_glyphs)
+}
+
+// Rebuilds the font with a `CFF ` table instead of `glyf` and `loca`. Glyphs
+// are empty, except for the given charstrings.
+fn cff_font_data_with_char_strings(char_strings: &[(usize, &[u8])], num_glyphs: usize) -> Vec<u8> {
+    // Header, names, top dict with CharStrings at 29, strings and global subroutines.
+    let mut cff = vec![1, 0, 4, 2, 0, 1, 2, 0, 1, 0, 2, b'T',
+                       0, 1, 2, 0, 1, 0, 7, 29, 0, 0, 0, 29, 17, 0, 0, 0, 0];
+    let char_string = |glyph| char_strings.iter().find(|c| c.0 == glyph).map_or(&[14][..], |c| c.1);
+    push_u16(&mut cff, num_glyphs);
+    cff.push(2);
+    let mut offset = 1;
+    push_u16(&mut cff, offset);
+    for glyph in 0..num_glyphs {
+        offset += char_string(glyph).len();
+        push_u16(&mut cff, offset);
+    }
+    for glyph in 0..num_glyphs {
+        cff.extend_from_slice(char_string(glyph));
+    }
+    rebuild_font_data(b"OTTO", &[b"glyf", b"loca"], vec![(b"CFF ".to_vec(), cff)])
+}
+
+#[test]
+fn cff_outlines() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let a = font.glyph_index_for_code('A' as usize);
+    let maxp = (0..bs[5] as usize).map(|i| &bs[12 + i * 16..]).find(|entry| &entry[..4] == b"maxp").unwrap();
+    let maxp = read_u32(maxp, 8);
+    let num_glyphs = (bs[maxp + 4] as usize) << 8 | bs[maxp + 5] as usize;
+    let bs = cff_font_data(a, num_glyphs);
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    assert_eq!(font.cff().unwrap().glyph_count(), num_glyphs);
+
+    let outline = font.glyph_outline(a).unwrap();
+    assert_eq!(outline.len(), 5);
+    assert_eq!(font.glyph_bounding_box(a), Some(BBox { x0: 100, y0: 100, x1: 600, y1: 700 }));
+    assert!(font.glyph_outline(font.glyph_index_for_code('V' as usize)).unwrap().is_empty());
+
+    // The square covers whole pixels at this scale.
+    let bitmap = font.render_glyph(a, 0.02, 0.02, 0.0, 0.0).unwrap();
+    assert_eq!((bitmap.width, bitmap.height), (10, 12));
+    assert_eq!((bitmap.x_offset, bitmap.y_offset), (2, -14));
+    assert!(bitmap.pixels.iter().all(|&pixel| pixel == 255));
+}
+
+#[test]
+fn render_cubic_curves() {
+    let bs = font_data();
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    let d = font.glyph_index_for_code('D' as usize);
+    let num_glyphs = num_glyphs(&bs);
+    // 'D' is a half disc above the line from 100, 100 to 600, 100 drawn
+    // with a cubic.
+    let half_disc: &[u8] = &[239, 239, 21, 248, 136, 6,
+                             139, 247, 192, 252, 136, 139, 139, 251, 192, 8, 14];
+    let bs = cff_font_data_with_char_strings(&[(d, half_disc)], num_glyphs);
+    let font = FontInfo::new_with_offset(&bs, 0).ok().expect("Failed to load font");
+    assert_eq!(font.glyph_bounding_box(d), Some(BBox { x0: 100, y0: 100, x1: 600, y1: 325 }));
+
+    // The half disc covers 90000 square units, 900 pixels at this scale.
+    let bitmap = font.render_glyph(d, 0.1, 0.1, 0.0, 0.0).unwrap();
+    let coverage = bitmap.pixels.iter().map(|&pixel| pixel as f32 / 255.0).sum::<f32>();
+    assert!((coverage - 900.0).abs() < 9.0, "coverage is {}", coverage);
+}